@@ -0,0 +1,325 @@
+//! The macros of cder, re-exported by `cder` with its `macros` feature. Use them through `cder`
+//! (e.g. `#[cder::fixtures(...)]`) rather than depending on this crate directly.
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Data, DeriveInput, Fields, FnArg, Ident, ItemFn, LitStr, Pat, Token, Type,
+};
+
+/// the directory the files are read from by default, relative to the crate of the test
+const DEFAULT_DIR: &str = "tests/fixtures";
+
+/// Loads the seed files, and injects their records into the arguments of the test, each
+/// deserialized into the type of the argument. The records are looked up by the names of the
+/// arguments, ignoring the cases and the underscores (e.g. `alice` for `Alice`, `order_1` for
+/// `Order1`).
+///
+/// The files are read from `tests/fixtures` of the crate (or `dir`), and populated in the given
+/// order without a database: the ids are assigned sequentially from 1 in each file, so that REFs
+/// are resolved as usual (see `cder::testing::Fixtures`). The attribute has to be placed above
+/// the test attribute, e.g. `#[test]` or `#[tokio::test]`.
+///
+/// ```rust,ignore
+/// #[cder::fixtures("customers.yml", "orders.yml")]
+/// #[test]
+/// fn test_order(alice: Customer, order_1: Order) {
+///     assert_eq!(alice.name, "Alice");
+///     assert_eq!(order_1.customer_id, 1);
+/// }
+///
+/// #[cder::fixtures("items.yml", dir = "tests/seeds")]
+/// #[tokio::test]
+/// async fn test_item(melon: Item) {
+///     assert_eq!(melon.price, 500.0);
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn fixtures(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as FixturesArgs);
+    let item = parse_macro_input!(item as ItemFn);
+
+    expand_fixtures(args, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct FixturesArgs {
+    files: Vec<LitStr>,
+    dir: Option<LitStr>,
+}
+
+enum FixturesArg {
+    File(LitStr),
+    Dir(LitStr),
+}
+
+impl Parse for FixturesArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            return input.parse().map(FixturesArg::File);
+        }
+
+        let name = input.parse::<Ident>()?;
+        if name != "dir" {
+            return Err(syn::Error::new(
+                name.span(),
+                "expected the seed files, or `dir = \"...\"`",
+            ));
+        }
+        input.parse::<Token![=]>()?;
+        input.parse().map(FixturesArg::Dir)
+    }
+}
+
+impl Parse for FixturesArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut files = Vec::new();
+        let mut dir = None;
+        for arg in Punctuated::<FixturesArg, Token![,]>::parse_terminated(input)? {
+            match arg {
+                FixturesArg::File(file) => files.push(file),
+                FixturesArg::Dir(value) if dir.is_some() => {
+                    return Err(syn::Error::new(value.span(), "`dir` is given twice"));
+                }
+                FixturesArg::Dir(value) => dir = Some(value),
+            }
+        }
+        if files.is_empty() {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "at least one seed file has to be given, e.g. #[cder::fixtures(\"customers.yml\")]",
+            ));
+        }
+        Ok(Self { files, dir })
+    }
+}
+
+fn expand_fixtures(args: FixturesArgs, mut item: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    let dir = args
+        .dir
+        .unwrap_or_else(|| LitStr::new(DEFAULT_DIR, Span::call_site()));
+    let files = args.files;
+
+    let mut bindings = Vec::new();
+    for input in std::mem::take(&mut item.sig.inputs) {
+        let FnArg::Typed(arg) = input else {
+            return Err(syn::Error::new_spanned(
+                input,
+                "fixtures cannot be injected into methods",
+            ));
+        };
+        let Pat::Ident(pat_ident) = arg.pat.as_ref() else {
+            return Err(syn::Error::new_spanned(
+                &arg.pat,
+                "the arguments have to be named after the labels, e.g. `alice: Customer`",
+            ));
+        };
+        let name = pat_ident.ident.to_string();
+        let name = name.strip_prefix("r#").unwrap_or(&name);
+        let pat = &arg.pat;
+        let ty = &arg.ty;
+        bindings.push(quote! {
+            let #pat: #ty = __cder_fixtures.find(#name).unwrap_or_else(|err| {
+                panic!("failed to inject the fixture `{}`: {:?}", #name, err)
+            });
+        });
+    }
+
+    let block = &item.block;
+    item.block = syn::parse_quote!({
+        let __cder_fixtures = ::cder::testing::Fixtures::load(
+            &::std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join(#dir)
+                .to_string_lossy(),
+            &[#(#files),*],
+        )
+        .unwrap_or_else(|err| panic!("failed to load the fixtures: {:?}", err));
+        #(#bindings)*
+        #block
+    });
+
+    Ok(quote!(#item))
+}
+
+/// Derives `cder::SqlxSeedable` from the struct (available with the `sqlx` feature as well), so
+/// that its records are inserted by `DatabaseSeeder::populate_model` (or `populate_sqlx`) without
+/// writing the impl by hand. The fields are bound to the columns of the same names, in the order
+/// of the fields.
+///
+/// The struct accepts `#[seedable(...)]` with:
+/// - `table = "..."`: the table (default: the name of the struct in snake case, followed by `s`,
+///   e.g. `order_items` for `OrderItem`)
+/// - `id = <type>`: the type of the primary key (default: `i64`)
+/// - `id_column = "..."`: the primary key column returned by the INSERT statement (default: `id`)
+///
+/// and the fields accept `#[seedable(column = "...")]` to be bound to another column, or
+/// `#[seedable(skip)]` to be left out (e.g. the columns with defaults).
+///
+/// ```rust,ignore
+/// #[derive(Deserialize, cder::Seedable)]
+/// #[seedable(table = "users", id = i32, id_column = "user_id")]
+/// struct User {
+///     name: String,
+///     #[seedable(column = "email_address")]
+///     email: String,
+///     #[seedable(skip)]
+///     created_at: Option<String>,
+/// }
+/// ```
+#[proc_macro_derive(Seedable, attributes(seedable))]
+pub fn derive_seedable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_seedable(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// the values of `#[seedable(...)]` of the struct
+#[derive(Default)]
+struct SeedableAttrs {
+    table: Option<LitStr>,
+    id: Option<Type>,
+    id_column: Option<LitStr>,
+}
+
+/// the values of `#[seedable(...)]` of a field
+#[derive(Default)]
+struct SeedableFieldAttrs {
+    column: Option<LitStr>,
+    skip: bool,
+}
+
+fn parse_seedable_attrs(attrs: &[syn::Attribute]) -> syn::Result<SeedableAttrs> {
+    let mut parsed = SeedableAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("seedable")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                parsed.table = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("id") {
+                parsed.id = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("id_column") {
+                parsed.id_column = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("expected `table`, `id` or `id_column`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(parsed)
+}
+
+fn parse_seedable_field_attrs(attrs: &[syn::Attribute]) -> syn::Result<SeedableFieldAttrs> {
+    let mut parsed = SeedableFieldAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("seedable")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("column") {
+                parsed.column = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("skip") {
+                parsed.skip = true;
+            } else {
+                return Err(meta.error("expected `column` or `skip`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(parsed)
+}
+
+/// `OrderItem` as the default table name, i.e. `order_items`
+fn default_table(name: &str) -> String {
+    let mut table = String::with_capacity(name.len() + 4);
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            table.push('_');
+        }
+        table.extend(c.to_lowercase());
+    }
+    table.push('s');
+    table
+}
+
+fn expand_seedable(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Seedable can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Seedable can only be derived for structs with named fields",
+        ));
+    };
+
+    let attrs = parse_seedable_attrs(&input.attrs)?;
+    let table = attrs
+        .table
+        .map(|table| table.value())
+        .unwrap_or_else(|| default_table(&input.ident.to_string()));
+    let id = attrs.id.unwrap_or_else(|| syn::parse_quote!(i64));
+    let id_column = attrs.id_column.map(|id_column| {
+        quote! {
+            fn id_column() -> &'static str {
+                #id_column
+            }
+        }
+    });
+
+    let mut columns = Vec::new();
+    let mut idents = Vec::new();
+    for field in &fields.named {
+        let field_attrs = parse_seedable_field_attrs(&field.attrs)?;
+        if field_attrs.skip {
+            continue;
+        }
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let name = ident.to_string();
+        let name = name.strip_prefix("r#").unwrap_or(&name).to_string();
+        columns.push(
+            field_attrs
+                .column
+                .map(|column| column.value())
+                .unwrap_or(name),
+        );
+        idents.push(ident);
+    }
+    if columns.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Seedable needs at least one field to be inserted",
+        ));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::cder::SqlxSeedable for #name #ty_generics #where_clause {
+            type Id = #id;
+
+            fn table() -> &'static str {
+                #table
+            }
+
+            fn columns() -> &'static [&'static str] {
+                &[#(#columns),*]
+            }
+
+            #id_column
+
+            fn bind(
+                self,
+                query: ::sqlx::query::Query<'_, ::sqlx::Postgres, ::sqlx::postgres::PgArguments>,
+            ) -> ::sqlx::query::Query<'_, ::sqlx::Postgres, ::sqlx::postgres::PgArguments> {
+                query #(.bind(self.#idents))*
+            }
+        }
+    })
+}
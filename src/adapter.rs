@@ -0,0 +1,153 @@
+use anyhow::Result;
+use std::future::Future;
+
+use crate::{RefValue, ToRefValue};
+
+/// SeedAdapter abstracts the insertion target of the records of type `T` (a database table, an
+/// HTTP API, a mock etc.), so that DatabaseSeeder can drive it with `populate_with`,
+/// `populate_many_with`, `teardown_with`, `verify` and `diff`.
+/// Only `insert` is required; the other capabilities fall back to reasonable defaults.
+///
+/// # Examples
+/// ```rust
+/// use anyhow::Result;
+/// use cder::SeedAdapter;
+/// # use serde::Deserialize;
+/// #
+/// # #[derive(Deserialize)]
+/// # struct User {
+/// #   name: String,
+/// #   email: String,
+/// # }
+///
+/// struct UsersTable {
+///   // connection pool etc.
+/// }
+///
+/// impl SeedAdapter<User> for UsersTable {
+///     type Id = i64;
+///
+///     async fn insert(&mut self, record: User) -> Result<i64> {
+///         // INSERT INTO users ... RETURNING id
+///         Ok(1)
+///     }
+///
+///     async fn exists(&mut self, record: &User) -> Result<Option<i64>> {
+///         // SELECT id FROM users WHERE email = ...
+///         Ok(None)
+///     }
+/// }
+/// ```
+pub trait SeedAdapter<T>: Send
+where
+    T: Send,
+{
+    type Id: ToRefValue + Send;
+
+    /// inserts the record, and returns its id
+    fn insert(&mut self, record: T) -> impl Future<Output = Result<Self::Id>> + Send;
+
+    /// inserts the records at once, and returns their ids in the same order.
+    /// inserts them one by one by default
+    fn insert_many(
+        &mut self,
+        records: Vec<T>,
+    ) -> impl Future<Output = Result<Vec<Self::Id>>> + Send {
+        async move {
+            let mut ids = Vec::new();
+            for record in records {
+                ids.push(self.insert(record).await?);
+            }
+            Ok(ids)
+        }
+    }
+
+    /// inserts the record along with its label (e.g. to be the key of a message), and returns its
+    /// id. called by `DatabaseSeeder::populate_with`, and calls `insert` by default
+    fn insert_labeled(
+        &mut self,
+        _label: &str,
+        record: T,
+    ) -> impl Future<Output = Result<Self::Id>> + Send {
+        self.insert(record)
+    }
+
+    /// inserts the records along with their labels at once, and returns their ids in the same
+    /// order. called by `DatabaseSeeder::populate_many_with`, and calls `insert_many` by default
+    fn insert_many_labeled(
+        &mut self,
+        records: Vec<(String, T)>,
+    ) -> impl Future<Output = Result<Vec<Self::Id>>> + Send {
+        self.insert_many(records.into_iter().map(|(_, record)| record).collect())
+    }
+
+    /// returns the id of the record if it already exists, in which case the record is not
+    /// inserted. regards every record as new by default
+    fn exists(&mut self, _record: &T) -> impl Future<Output = Result<Option<Self::Id>>> + Send {
+        async { Ok(None) }
+    }
+
+    /// empties the table. not supported by default
+    fn truncate(&mut self, table: &str) -> impl Future<Output = Result<()>> + Send {
+        let table = table.to_string();
+        async move {
+            Err(anyhow::anyhow!(
+                "the adapter does not support truncation (table: {})",
+                table
+            ))
+        }
+    }
+
+    /// deletes the record referred by the id. not supported by default
+    fn delete(&mut self, id: &RefValue) -> impl Future<Output = Result<()>> + Send {
+        let id = id.clone();
+        async move {
+            Err(anyhow::anyhow!(
+                "the adapter does not support deletion (id: {})",
+                id
+            ))
+        }
+    }
+
+    /// fetches the record referred by the id, or returns None when it does not exist.
+    /// used by `DatabaseSeeder::verify`. not supported by default
+    fn fetch(&mut self, id: &RefValue) -> impl Future<Output = Result<Option<T>>> + Send {
+        let id = id.clone();
+        async move {
+            Err(anyhow::anyhow!(
+                "the adapter does not support fetching (id: {})",
+                id
+            ))
+        }
+    }
+
+    /// returns the ids of all the records in the table, used by `DatabaseSeeder::diff` to find
+    /// the records that are not defined in the fixtures. not supported by default
+    fn ids(&mut self) -> impl Future<Output = Result<Vec<RefValue>>> + Send {
+        async {
+            Err(anyhow::anyhow!(
+                "the adapter does not support listing the ids"
+            ))
+        }
+    }
+
+    /// returns the checksum the seed file has been recorded with by `record_applied`, or None
+    /// if it has not been applied yet. used with `DatabaseSeeder::track_versions`.
+    /// regards no file as applied by default
+    fn applied_checksum(
+        &mut self,
+        _filename: &str,
+    ) -> impl Future<Output = Result<Option<String>>> + Send {
+        async { Ok(None) }
+    }
+
+    /// records that the seed file has been applied, along with the checksum of its contents.
+    /// used with `DatabaseSeeder::track_versions`. records nothing by default
+    fn record_applied(
+        &mut self,
+        _filename: &str,
+        _checksum: &str,
+    ) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+}
@@ -0,0 +1,340 @@
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use regex::Regex;
+use serde_yaml::{Mapping, Value};
+use std::{fs, path::Path};
+
+#[derive(Args)]
+pub struct ConvertArgs {
+    /// the fixture file to convert
+    file: String,
+    /// the format to convert the file into
+    #[arg(long)]
+    to: Format,
+    /// the format of the file (default: guessed from its extension)
+    #[arg(long)]
+    from: Option<Format>,
+    /// the file to write into (default: stdout)
+    #[arg(long, short)]
+    output: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, ValueEnum)]
+enum Format {
+    Yaml,
+    Json,
+    Toml,
+    Csv,
+}
+
+impl Format {
+    fn from_extension(filename: &str) -> Result<Self> {
+        match Path::new(filename)
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("yml" | "yaml") => Ok(Format::Yaml),
+            Some("json") => Ok(Format::Json),
+            Some("toml") => Ok(Format::Toml),
+            Some("csv") => Ok(Format::Csv),
+            _ => Err(anyhow::anyhow!(
+                "the format cannot be guessed from the file: {} (specify --from)",
+                filename
+            )),
+        }
+    }
+}
+
+pub fn run(args: ConvertArgs) -> Result<()> {
+    let from = match args.from {
+        Some(from) => from,
+        None => Format::from_extension(&args.file)?,
+    };
+    let raw_text = fs::read_to_string(&args.file)
+        .map_err(|err| anyhow::anyhow!("Can't open the file: {}\n   err: {}", args.file, err))?;
+
+    let converted = convert(&raw_text, from, args.to)
+        .map_err(|err| anyhow::anyhow!("failed to convert: {}\n   err: {}", args.file, err))?;
+    match &args.output {
+        Some(output) => fs::write(output, converted)?,
+        None => print!("{}", converted),
+    }
+    Ok(())
+}
+
+/// an embedded tag, replaced with a placeholder while the document is converted
+struct MaskedTag {
+    tag: String,
+    placeholder: String,
+    /// the quote the tag is surrounded with in the source, if any
+    quote: Option<char>,
+}
+
+impl MaskedTag {
+    /// the tags that are not quoted are replaced with their values as they are, e.g.
+    /// `company_id: ${{ REF(Acme) }}` with `company_id: 1`. they are written unquoted into the
+    /// formats that quote strings as well, except for ENV and SECRET tags whose values are not
+    /// quoted
+    fn is_bare(&self, format: Format) -> bool {
+        let directive = self.tag[3..].trim_start();
+        self.quote.is_none()
+            && (format == Format::Yaml
+                || !(directive.starts_with("ENV") || directive.starts_with("SECRET")))
+    }
+}
+
+/// converts the labeled records between the formats, keeping the order of the records and
+/// their fields, and the embedded tags untouched
+fn convert(raw_text: &str, from: Format, to: Format) -> Result<String> {
+    let (masked_text, tags) = mask_tags(raw_text, from);
+    let records = read_records(&masked_text, from)?;
+    let text = write_records(&records, to)?;
+    Ok(unmask_tags(text, &tags, &records, to))
+}
+
+fn mask_tags(raw_text: &str, from: Format) -> (String, Vec<MaskedTag>) {
+    // matches anything that looks like a tag, see `DatabaseSeeder::validate_files` for the syntax
+    let re = Regex::new(r"\$\{\{[^}\n]*\}\}").unwrap();
+    let mut tags = Vec::new();
+
+    let masked_text = re
+        .replace_all(raw_text, |captures: &regex::Captures| {
+            let matched = captures.get(0).unwrap();
+            let before = raw_text[..matched.start()].chars().next_back();
+            let after = raw_text[matched.end()..].chars().next();
+            let quote = match (before, after) {
+                (Some(before), Some(after)) if before == after && "\"'".contains(before) => {
+                    Some(before)
+                }
+                _ => None,
+            };
+            let placeholder = format!("__cder_tag_{}__", tags.len());
+            tags.push(MaskedTag {
+                tag: matched.as_str().to_string(),
+                placeholder: placeholder.clone(),
+                quote,
+            });
+            // the bare values are quoted to be parsed, e.g. `"company_id": ${{ REF(Acme) }}`
+            let is_bare_value = raw_text[..matched.start()]
+                .trim_end_matches([' ', '\t'])
+                .ends_with([':', '=', ',', '['])
+                && raw_text[matched.end()..]
+                    .trim_start_matches([' ', '\t'])
+                    .chars()
+                    .next()
+                    .is_none_or(|next| ",}]\r\n".contains(next));
+            match from {
+                Format::Json | Format::Toml if is_bare_value => format!("\"{}\"", placeholder),
+                _ => placeholder,
+            }
+        })
+        .into_owned();
+
+    (masked_text, tags)
+}
+
+fn unmask_tags(mut text: String, tags: &[MaskedTag], records: &Mapping, to: Format) -> String {
+    for tag in tags {
+        let whole_value = is_whole_value(records, &tag.placeholder);
+        text = match to {
+            Format::Csv => text.replace(&tag.placeholder, &tag.tag),
+            Format::Yaml => match (whole_value, tag.quote) {
+                (true, Some(quote)) => {
+                    text.replace(&tag.placeholder, &format!("{}{}{}", quote, tag.tag, quote))
+                }
+                _ => text.replace(&tag.placeholder, &tag.tag),
+            },
+            Format::Json | Format::Toml => {
+                if whole_value && tag.is_bare(to) {
+                    text = text.replace(&format!("\"{}\"", tag.placeholder), &tag.tag);
+                }
+                // escapes the quotes of the default values within the strings
+                let escaped = serde_json::to_string(&tag.tag).unwrap();
+                text.replace(&tag.placeholder, &escaped[1..escaped.len() - 1])
+            }
+        };
+    }
+    text
+}
+
+/// returns true when the placeholder is the whole value of a field rather than a part of a text
+fn is_whole_value(records: &Mapping, placeholder: &str) -> bool {
+    fn contains(value: &Value, placeholder: &str) -> bool {
+        match value {
+            Value::String(text) => text == placeholder,
+            Value::Sequence(values) => values.iter().any(|value| contains(value, placeholder)),
+            Value::Mapping(mapping) => mapping.values().any(|value| contains(value, placeholder)),
+            Value::Tagged(tagged) => contains(&tagged.value, placeholder),
+            _ => false,
+        }
+    }
+    records.values().any(|record| contains(record, placeholder))
+}
+
+fn read_records(text: &str, format: Format) -> Result<Mapping> {
+    let mut records: Mapping = match format {
+        Format::Yaml => return Ok(serde_yaml::from_str(text)?),
+        // json is read as yaml, so that the order of the fields is kept
+        Format::Json => serde_yaml::from_str(text)?,
+        Format::Toml => {
+            let table: toml::Table = toml::from_str(text)?;
+            serde_yaml::from_value(serde_yaml::to_value(table)?)?
+        }
+        Format::Csv => read_csv(text)?,
+    };
+    records.values_mut().for_each(restore_yaml_tags);
+    Ok(records)
+}
+
+/// turns the yaml tags written as single-entry mappings (e.g. `{"!Premium": null}`, as yaml
+/// tags are serialized into the other formats) back into yaml tags (`!Premium`)
+fn restore_yaml_tags(value: &mut Value) {
+    match value {
+        Value::Sequence(values) => values.iter_mut().for_each(restore_yaml_tags),
+        Value::Mapping(mapping) => {
+            mapping.values_mut().for_each(restore_yaml_tags);
+            if mapping.len() != 1 {
+                return;
+            }
+            let Some((Value::String(key), tagged)) = mapping.iter().next() else {
+                return;
+            };
+            if let Some(tag) = key.strip_prefix('!').filter(|tag| !tag.is_empty()) {
+                *value = Value::Tagged(Box::new(serde_yaml::value::TaggedValue {
+                    tag: serde_yaml::value::Tag::new(tag),
+                    value: tagged.clone(),
+                }));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn write_records(records: &Mapping, format: Format) -> Result<String> {
+    match format {
+        Format::Yaml => Ok(serde_yaml::to_string(records)?),
+        Format::Json => Ok(serde_json::to_string_pretty(records)? + "\n"),
+        Format::Toml => Ok(toml::to_string(records)?),
+        Format::Csv => write_csv(records),
+    }
+}
+
+/// reads the rows whose first column is the label, and the others the fields. the cells are read
+/// as yaml scalars (e.g. `500` as a number), and the empty ones are left out
+fn read_csv(text: &str) -> Result<Mapping> {
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    let headers = reader.headers()?.clone();
+    let mut records = Mapping::new();
+
+    for row in reader.records() {
+        let row = row?;
+        let mut cells = row.iter();
+        let label = cells
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("the row has no label: {:?}", row))?;
+
+        let mut record = Mapping::new();
+        for (field, cell) in headers.iter().skip(1).zip(cells) {
+            if cell.is_empty() {
+                continue;
+            }
+            let value = serde_yaml::from_str(cell).unwrap_or_else(|_| Value::from(cell));
+            record.insert(Value::from(field), value);
+        }
+        records.insert(Value::from(label), Value::Mapping(record));
+    }
+    Ok(records)
+}
+
+/// writes the records as rows of the label and the fields, which have to be scalars
+fn write_csv(records: &Mapping) -> Result<String> {
+    let mut fields = Vec::<&Value>::new();
+    for record in records.values() {
+        let record = record
+            .as_mapping()
+            .ok_or_else(|| anyhow::anyhow!("the records must be mappings to be written in csv"))?;
+        for field in record.keys() {
+            if !fields.contains(&field) {
+                fields.push(field);
+            }
+        }
+    }
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(
+        std::iter::once("label".to_string()).chain(fields.iter().map(|field| cell(field))),
+    )?;
+    for (label, record) in records {
+        let mut row = vec![cell(label)];
+        for field in &fields {
+            row.push(match record.get(field) {
+                Some(Value::Sequence(_) | Value::Mapping(_) | Value::Tagged(_)) => {
+                    return Err(anyhow::anyhow!(
+                        "the field cannot be written in csv: {}.{}",
+                        cell(label),
+                        cell(field)
+                    ))
+                }
+                Some(value) => cell(value),
+                None => String::new(),
+            });
+        }
+        writer.write_record(row)?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        value => serde_yaml::to_string(value)
+            .map(|text| text.trim_end().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USERS: &str = "Alice:\n  name: Alice\n  email: ${{ ENV(ALICE_EMAIL) }}\n  company_id: ${{ REF(Acme) }}\n  note: \"joined ${{ CTX(date) }}\"\nBob:\n  name: Bob\n  company_id: 2\n";
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(
+            convert(USERS, Format::Yaml, Format::Json).unwrap(),
+            "{\n  \"Alice\": {\n    \"name\": \"Alice\",\n    \"email\": \"${{ ENV(ALICE_EMAIL) }}\",\n    \"company_id\": ${{ REF(Acme) }},\n    \"note\": \"joined ${{ CTX(date) }}\"\n  },\n  \"Bob\": {\n    \"name\": \"Bob\",\n    \"company_id\": 2\n  }\n}\n"
+        );
+        assert_eq!(
+            convert(USERS, Format::Yaml, Format::Csv).unwrap(),
+            "label,name,email,company_id,note\nAlice,Alice,${{ ENV(ALICE_EMAIL) }},${{ REF(Acme) }},joined ${{ CTX(date) }}\nBob,Bob,,2,\n"
+        );
+
+        // the records, their fields and the tags are kept through the formats
+        for format in [Format::Json, Format::Toml, Format::Csv] {
+            let converted = convert(USERS, Format::Yaml, format).unwrap();
+            let email = match format {
+                // ENV tags are quoted in json and toml
+                Format::Json | Format::Toml => "\"${{ ENV(ALICE_EMAIL) }}\"",
+                _ => "${{ ENV(ALICE_EMAIL) }}",
+            };
+            assert_eq!(
+                convert(&converted, format, Format::Yaml).unwrap(),
+                format!("Alice:\n  name: Alice\n  email: {}\n  company_id: ${{{{ REF(Acme) }}}}\n  note: joined ${{{{ CTX(date) }}}}\nBob:\n  name: Bob\n  company_id: 2\n", email),
+                "{:?}",
+                format
+            );
+        }
+
+        // yaml tags are written as single-entry mappings
+        let customers =
+            "Alice:\n  plan: !Premium\n  contact: !Email\n    email: alice@example.com\n";
+        let converted = convert(customers, Format::Yaml, Format::Json).unwrap();
+        assert!(converted.contains("\"!Premium\": null"));
+        assert_eq!(
+            convert(&converted, Format::Json, Format::Yaml).unwrap(),
+            "Alice:\n  plan: !Premium null\n  contact: !Email\n    email: alice@example.com\n"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use anyhow::Result;
+use cder::{DatabaseSeeder, SqlxTable};
+use clap::Args;
+use sqlx::postgres::PgPool;
+
+use crate::files::load_refs;
+
+#[derive(Args)]
+pub struct DiffArgs {
+    /// the seed files to compare, each with the table of its file stem
+    #[arg(required = true)]
+    files: Vec<String>,
+    /// the directory the seed files are read from
+    #[arg(long)]
+    dir: Option<String>,
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: String,
+    /// the ids of the records populated earlier, saved with `cder seed --save-state`. the
+    /// records whose ids are not known are regarded as added
+    #[arg(long)]
+    state: Option<String>,
+}
+
+pub async fn run(args: DiffArgs) -> Result<()> {
+    let mut builder = DatabaseSeeder::builder();
+    if let Some(dir) = &args.dir {
+        builder = builder.base_dir(dir);
+    }
+    let mut seeder = builder.build()?;
+    if let Some(state) = &args.state {
+        seeder.restore_state(load_refs(state)?);
+    }
+
+    let pool = PgPool::connect(&args.database_url).await?;
+    let mut changed_files = 0;
+    for file in &args.files {
+        let mut table = SqlxTable::new(&pool, file_stem(file));
+        let diff = seeder
+            .diff::<serde_json::Value, _>(file, &mut table)
+            .await?;
+        println!("{}", diff);
+        if !diff.is_empty() {
+            changed_files += 1;
+        }
+    }
+
+    // fails like `diff`, so that the changes can be detected in CI
+    if changed_files > 0 {
+        return Err(anyhow::anyhow!(
+            "{} file(s) differ from the database",
+            changed_files
+        ));
+    }
+    Ok(())
+}
+
+fn file_stem(filename: &str) -> &str {
+    std::path::Path::new(filename)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(filename)
+}
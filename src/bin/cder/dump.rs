@@ -0,0 +1,64 @@
+use anyhow::Result;
+use cder::{FixtureDumper, SeedAdapter, SqlxTable};
+use clap::Args;
+use sqlx::postgres::PgPool;
+
+#[derive(Args)]
+pub struct DumpArgs {
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: String,
+    /// the table to dump
+    #[arg(long)]
+    table: String,
+    /// the condition of the rows to dump, e.g. `--where "plan = 'Premium'"` (all the rows by
+    /// default)
+    #[arg(long = "where")]
+    condition: Option<String>,
+    /// the primary key column, which is left out of the fixtures
+    #[arg(long, default_value = "id")]
+    id_column: String,
+}
+
+/// prints the rows of the table as a seed file. the foreign keys are replaced with REF tags to
+/// the labels the referred rows get when their tables are dumped as a whole (e.g. `plans_2` for
+/// the row of the second smallest id in `plans`), so dump the referred tables into their own
+/// files too
+pub async fn run(args: DumpArgs) -> Result<()> {
+    let pool = PgPool::connect(&args.database_url).await?;
+    let table = SqlxTable::new(&pool, &args.table).id_column(&args.id_column);
+
+    let mut dumper = FixtureDumper::new();
+    dumper.id_column = args.id_column.clone();
+
+    let mut references = Vec::new();
+    for (column, referred_table) in table.foreign_keys().await? {
+        // REFs within the same file cannot be resolved
+        if referred_table == args.table {
+            eprintln!(
+                "{}.{} refers to the same table, left as ids",
+                args.table, column
+            );
+            continue;
+        }
+        let ids = SqlxTable::new(&pool, &referred_table)
+            .id_column(&args.id_column)
+            .ids()
+            .await?;
+        for (index, id) in ids.into_iter().enumerate() {
+            dumper.set_label(
+                &referred_table,
+                id,
+                &format!("{}_{}", referred_table, index + 1),
+            );
+        }
+        references.push((column, referred_table));
+    }
+    let references = references
+        .iter()
+        .map(|(column, referred_table)| (column.as_str(), referred_table.as_str()))
+        .collect::<Vec<(&str, &str)>>();
+
+    let rows = table.rows(args.condition.as_deref()).await?;
+    print!("{}", dumper.dump(&args.table, &rows, &references)?);
+    Ok(())
+}
@@ -0,0 +1,319 @@
+use anyhow::Result;
+use clap::Args;
+use fake::{
+    faker::{
+        address::en::{CityName, CountryName, StateName, StreetName, ZipCode},
+        company::en::CompanyName,
+        internet::en::{DomainSuffix, IPv4, SafeEmail, Username},
+        lorem::en::{Sentence, Word, Words},
+        name::en::{FirstName, LastName, Name},
+        phone_number::en::PhoneNumber,
+    },
+    Fake,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde_json::Value as JsonValue;
+use serde_yaml::{Mapping, Value};
+use std::{fs, path::Path};
+
+#[derive(Args)]
+pub struct FakeArgs {
+    /// the JSON schema of the records, e.g. `users.schema.json` (as used by `cder validate`)
+    #[arg(long)]
+    schema: String,
+    /// the number of the records to generate
+    #[arg(long, default_value_t = 10)]
+    count: usize,
+    /// the prefix of the labels, numbered from 1 (the title of the schema, or the name of the
+    /// schema file by default, e.g. `User1` for `user.schema.json`)
+    #[arg(long)]
+    label: Option<String>,
+    /// the seed of the random values, to generate the same records every time
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+pub fn run(args: FakeArgs) -> Result<()> {
+    let raw_text = fs::read_to_string(&args.schema)
+        .map_err(|err| anyhow::anyhow!("Can't open the file: {}\n   err: {}", args.schema, err))?;
+    let schema = serde_json::from_str::<JsonValue>(&raw_text).map_err(|err| {
+        anyhow::anyhow!(
+            "failed to parse the schema: {}\n   err: {}",
+            args.schema,
+            err
+        )
+    })?;
+
+    let label = match &args.label {
+        Some(label) => label.clone(),
+        None => default_label(&args.schema, &schema),
+    };
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+
+    print!("{}", fake_records(&schema, &label, args.count, &mut rng)?);
+    Ok(())
+}
+
+/// generates the labeled records conforming to the schema, as a yaml seed file
+fn fake_records(schema: &JsonValue, label: &str, count: usize, rng: &mut StdRng) -> Result<String> {
+    let mut records = Mapping::new();
+    for index in 0..count {
+        let record = fake_value(schema, "", index, rng);
+        records.insert(Value::from(format!("{}{}", label, index + 1)), record);
+    }
+    Ok(serde_yaml::to_string(&records)?)
+}
+
+/// the title of the schema, or else the capitalized name of the file, e.g. `User` for
+/// `schemas/user.schema.json`
+fn default_label(path: &str, schema: &JsonValue) -> String {
+    if let Some(title) = schema.get("title").and_then(JsonValue::as_str) {
+        return title.split_whitespace().collect();
+    }
+    let name = Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.split('.').next())
+        .unwrap_or("Record");
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => "Record".to_string(),
+    }
+}
+
+/// generates a value conforming to the schema: `const`, `enum`, `type`, `format`, the ranges
+/// (`minimum`, `maxLength`, `minItems`, ...) and the properties of the objects are taken into
+/// account, and the strings are made up after the names of the fields (e.g. `email`, `city`)
+fn fake_value(schema: &JsonValue, field: &str, index: usize, rng: &mut StdRng) -> Value {
+    if let Some(value) = schema.get("const") {
+        return to_yaml(value);
+    }
+    if let Some(values) = schema.get("enum").and_then(JsonValue::as_array) {
+        if !values.is_empty() {
+            return to_yaml(&values[rng.random_range(0..values.len())]);
+        }
+    }
+    if let Some(value) = schema.get("default") {
+        return to_yaml(value);
+    }
+    let schema = match ["oneOf", "anyOf"]
+        .iter()
+        .find_map(|key| schema.get(*key).and_then(JsonValue::as_array))
+    {
+        // the first choice that is not null
+        Some(choices) => choices
+            .iter()
+            .find(|choice| schema_type(choice) != Some("null"))
+            .unwrap_or(schema),
+        None => schema,
+    };
+
+    let number = |key: &str| schema.get(key).and_then(JsonValue::as_f64);
+    let size = |key: &str, default: usize| {
+        schema
+            .get(key)
+            .and_then(JsonValue::as_u64)
+            .map_or(default, |size| size as usize)
+    };
+
+    match schema_type(schema) {
+        Some("object") => {
+            let mut record = Mapping::new();
+            if let Some(properties) = schema.get("properties").and_then(JsonValue::as_object) {
+                for (name, property) in properties {
+                    record.insert(
+                        Value::from(name.as_str()),
+                        fake_value(property, name, index, rng),
+                    );
+                }
+            }
+            Value::Mapping(record)
+        }
+        Some("array") => {
+            let min_items = size("minItems", 1);
+            let max_items = size("maxItems", min_items.max(3)).max(min_items);
+            let items = schema.get("items").unwrap_or(&JsonValue::Null);
+            (0..rng.random_range(min_items..=max_items))
+                .map(|_| fake_value(items, field, index, rng))
+                .collect()
+        }
+        Some("integer") => {
+            // the ids are numbered in the order of the records
+            if field == "id" {
+                return Value::from(index as i64 + 1);
+            }
+            let minimum = number("minimum").map_or(1, |minimum| minimum.ceil() as i64);
+            let maximum = number("maximum").map_or(minimum.max(1000), |maximum| maximum as i64);
+            Value::from(rng.random_range(minimum..=maximum.max(minimum)))
+        }
+        Some("number") => {
+            let minimum = number("minimum").unwrap_or(0.0);
+            let maximum = number("maximum")
+                .unwrap_or(minimum.max(1000.0))
+                .max(minimum);
+            let value = rng.random_range(minimum..=maximum);
+            Value::from((value * 100.0).round() / 100.0)
+        }
+        Some("boolean") => Value::from(rng.random_bool(0.5)),
+        Some("null") => Value::Null,
+        // strings, or the values of unknown types
+        _ => {
+            let mut value = fake_string(schema, field, rng);
+            let min_length = size("minLength", 0);
+            let max_length = size("maxLength", usize::MAX).max(min_length);
+            while value.chars().count() < min_length {
+                value.push_str(&Word().fake_with_rng::<String, _>(rng));
+            }
+            Value::from(value.chars().take(max_length).collect::<String>())
+        }
+    }
+}
+
+/// the type of the schema, the first one other than null when more than one is allowed
+fn schema_type(schema: &JsonValue) -> Option<&str> {
+    match schema.get("type")? {
+        JsonValue::String(schema_type) => Some(schema_type),
+        JsonValue::Array(types) => types
+            .iter()
+            .filter_map(JsonValue::as_str)
+            .find(|schema_type| *schema_type != "null")
+            .or(Some("null")),
+        _ => None,
+    }
+}
+
+fn fake_string(schema: &JsonValue, field: &str, rng: &mut StdRng) -> String {
+    match schema.get("format").and_then(JsonValue::as_str) {
+        Some("email") => return SafeEmail().fake_with_rng(rng),
+        Some("uuid") => {
+            return uuid::Builder::from_random_bytes(rng.random())
+                .into_uuid()
+                .to_string()
+        }
+        Some("date") => return fake_date(rng),
+        Some("date-time") => {
+            return format!(
+                "{}T{:02}:{:02}:{:02}Z",
+                fake_date(rng),
+                rng.random_range(0..24),
+                rng.random_range(0..60),
+                rng.random_range(0..60)
+            )
+        }
+        Some("uri") => return fake_url(rng),
+        Some("ipv4") => return IPv4().fake_with_rng(rng),
+        _ => {}
+    }
+
+    let field = field.to_lowercase();
+    let is = |names: &[&str]| {
+        names
+            .iter()
+            .any(|name| field == *name || field.ends_with(&format!("_{}", name)))
+    };
+    if is(&["email", "mail"]) {
+        SafeEmail().fake_with_rng(rng)
+    } else if is(&["first_name", "given_name"]) {
+        FirstName().fake_with_rng(rng)
+    } else if is(&["last_name", "family_name", "surname"]) {
+        LastName().fake_with_rng(rng)
+    } else if is(&["username", "login", "nickname", "handle"]) {
+        Username().fake_with_rng(rng)
+    } else if is(&["company", "company_name", "organization"]) {
+        CompanyName().fake_with_rng(rng)
+    } else if is(&["name", "full_name"]) {
+        Name().fake_with_rng(rng)
+    } else if is(&["phone", "phone_number", "tel", "mobile"]) {
+        PhoneNumber().fake_with_rng(rng)
+    } else if is(&["city"]) {
+        CityName().fake_with_rng(rng)
+    } else if is(&["state", "prefecture", "region"]) {
+        StateName().fake_with_rng(rng)
+    } else if is(&["country"]) {
+        CountryName().fake_with_rng(rng)
+    } else if is(&["street", "address"]) {
+        StreetName().fake_with_rng(rng)
+    } else if is(&["zip", "zip_code", "postal_code", "postcode"]) {
+        ZipCode().fake_with_rng(rng)
+    } else if is(&["url", "website", "homepage"]) {
+        fake_url(rng)
+    } else if is(&["description", "bio", "body", "comment", "note", "summary"]) {
+        Sentence(3..10).fake_with_rng(rng)
+    } else if is(&["title", "subject"]) {
+        Words(2..5).fake_with_rng::<Vec<String>, _>(rng).join(" ")
+    } else if is(&["date", "birthday"]) || field.ends_with("_on") {
+        fake_date(rng)
+    } else {
+        Word().fake_with_rng(rng)
+    }
+}
+
+fn fake_url(rng: &mut StdRng) -> String {
+    format!(
+        "https://{}.{}",
+        Word().fake_with_rng::<String, _>(rng),
+        DomainSuffix().fake_with_rng::<String, _>(rng)
+    )
+}
+
+fn fake_date(rng: &mut StdRng) -> String {
+    format!(
+        "{}-{:02}-{:02}",
+        rng.random_range(2000..=2025),
+        rng.random_range(1..=12),
+        rng.random_range(1..=28)
+    )
+}
+
+fn to_yaml(value: &JsonValue) -> Value {
+    serde_yaml::to_value(value).unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fake_records() {
+        let schema = serde_json::json!({
+            "title": "User",
+            "type": "object",
+            "properties": {
+                "id": { "type": "integer" },
+                "email": { "type": "string", "format": "email" },
+                "role": { "enum": ["admin", "member"] },
+                "age": { "type": ["integer", "null"], "minimum": 20, "maximum": 30 },
+                "tags": { "type": "array", "items": { "type": "string" }, "maxItems": 2 },
+                "active": { "const": true }
+            }
+        });
+        assert_eq!(default_label("schemas/users.schema.json", &schema), "User");
+        assert_eq!(
+            default_label("schemas/user.schema.json", &JsonValue::Null),
+            "User"
+        );
+
+        let text = fake_records(&schema, "User", 3, &mut StdRng::seed_from_u64(1)).unwrap();
+        // the same seed generates the same records
+        assert_eq!(
+            text,
+            fake_records(&schema, "User", 3, &mut StdRng::seed_from_u64(1)).unwrap()
+        );
+
+        let records = serde_yaml::from_str::<Mapping>(&text).unwrap();
+        assert_eq!(records.len(), 3);
+        for (index, (label, record)) in records.iter().enumerate() {
+            assert_eq!(label, &Value::from(format!("User{}", index + 1)));
+            assert_eq!(record["id"], Value::from(index + 1));
+            assert!(record["email"].as_str().unwrap().contains('@'));
+            assert!(["admin", "member"].contains(&record["role"].as_str().unwrap()));
+            assert!((20..=30).contains(&record["age"].as_i64().unwrap()));
+            assert!((1..=2).contains(&record["tags"].as_sequence().unwrap().len()));
+            assert_eq!(record["active"], Value::from(true));
+        }
+    }
+}
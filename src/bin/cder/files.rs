@@ -0,0 +1,82 @@
+use anyhow::Result;
+use cder::{DatabaseSeeder, ManifestEntry, RefValue, SeedState};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// the seed files in the order they are populated: the files given explicitly or the yaml files
+/// in the directory, sorted by their dependencies, or else the entries of the manifest
+pub fn seed_entries(
+    seeder: &mut DatabaseSeeder,
+    from_manifest: bool,
+    files: &[String],
+) -> Result<Vec<ManifestEntry>> {
+    if files.is_empty() && from_manifest {
+        let mut entries = Vec::new();
+        seeder.populate_manifest(None, |_, entry| {
+            entries.push(entry.clone());
+            Ok(())
+        })?;
+        return Ok(entries);
+    }
+
+    let filenames = match files.is_empty() {
+        true => yaml_files(&seeder.base_dir)?,
+        false => files.to_vec(),
+    };
+    let filenames = filenames.iter().map(String::as_str).collect::<Vec<&str>>();
+    let entries = seeder
+        .sort_by_dependencies(&filenames)?
+        .into_iter()
+        .map(|file| ManifestEntry {
+            file,
+            model: None,
+            table: None,
+            profiles: Vec::new(),
+        })
+        .collect();
+    Ok(entries)
+}
+
+/// the yaml files in the directory, except for the profile overlays (e.g. `plans.staging.yml`)
+pub fn yaml_files(dir: &str) -> Result<Vec<String>> {
+    let dir = match dir {
+        "" => ".",
+        dir => dir,
+    };
+    let mut filenames = Vec::new();
+    for entry in fs::read_dir(dir)
+        .map_err(|err| anyhow::anyhow!("Can't read the directory: {}\n   err: {}", dir, err))?
+    {
+        let path = entry?.path();
+        let is_yaml = matches!(
+            path.extension().and_then(|extension| extension.to_str()),
+            Some("yml" | "yaml")
+        );
+        let is_overlay = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|stem| stem.contains('.'));
+        if !is_yaml || is_overlay {
+            continue;
+        }
+        if let Some(filename) = Path::new(&path).file_name().and_then(|name| name.to_str()) {
+            filenames.push(filename.to_string());
+        }
+    }
+    filenames.sort();
+    Ok(filenames)
+}
+
+/// reads the saved state, or else a json object of the labels and their ids
+pub fn load_refs(path: &str) -> Result<SeedState> {
+    if let Ok(state) = SeedState::load(path) {
+        return Ok(state);
+    }
+    let raw_text = fs::read_to_string(path)
+        .map_err(|err| anyhow::anyhow!("Can't open the file: {}\n   err: {}", path, err))?;
+    let ids: BTreeMap<String, RefValue> = serde_json::from_str(&raw_text)
+        .map_err(|err| anyhow::anyhow!("failed to parse the refs: {}\n   err: {}", path, err))?;
+    Ok(SeedState {
+        files: Vec::new(),
+        ids,
+    })
+}
@@ -0,0 +1,51 @@
+use anyhow::Result;
+use cder::DatabaseSeeder;
+use clap::{Args, ValueEnum};
+
+use crate::files::seed_entries;
+
+#[derive(Args)]
+pub struct GraphArgs {
+    /// the directory the seed files are read from
+    dir: Option<String>,
+    /// the manifest that lists the seed files in the order they are populated
+    #[arg(long)]
+    manifest: Option<String>,
+    #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+    format: GraphFormat,
+    /// the seed files to draw (all the yaml files in the directory by default)
+    files: Vec<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GraphFormat {
+    /// the DOT language of Graphviz, e.g. `cder graph fixtures | dot -Tsvg > seeds.svg`
+    Dot,
+    Mermaid,
+    Json,
+}
+
+pub fn run(args: GraphArgs) -> Result<()> {
+    let mut builder = DatabaseSeeder::builder();
+    if let Some(dir) = &args.dir {
+        builder = builder.base_dir(dir);
+    }
+    if let Some(manifest) = &args.manifest {
+        builder = builder.manifest(manifest);
+    }
+    let mut seeder = builder.build()?;
+
+    let filenames = seed_entries(&mut seeder, args.manifest.is_some(), &args.files)?
+        .into_iter()
+        .map(|entry| entry.file)
+        .collect::<Vec<String>>();
+    let filenames = filenames.iter().map(String::as_str).collect::<Vec<&str>>();
+    let graph = seeder.dependency_graph(&filenames)?;
+
+    match args.format {
+        GraphFormat::Dot => print!("{}", graph.to_dot()),
+        GraphFormat::Mermaid => print!("{}", graph.to_mermaid()),
+        GraphFormat::Json => println!("{}", serde_json::to_string_pretty(&graph)?),
+    }
+    Ok(())
+}
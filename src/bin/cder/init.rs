@@ -0,0 +1,62 @@
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use std::{fs, path::Path};
+
+#[derive(Args)]
+pub struct InitArgs {
+    /// the directory the seed files are created in
+    #[arg(long, default_value = "fixtures")]
+    dir: String,
+    /// the database crate the sample seeding binary is written with
+    #[arg(long, value_enum, default_value_t = DbCrate::Sqlx)]
+    db: DbCrate,
+    /// the path of the sample seeding binary
+    #[arg(long, default_value = "src/bin/seed.rs")]
+    seed_file: String,
+    /// overwrites the files that already exist
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DbCrate {
+    Sqlx,
+    Diesel,
+}
+
+const MANIFEST: &str = include_str!("templates/seeds.yml");
+const COMPANIES: &str = include_str!("templates/companies.yml");
+const USERS: &str = include_str!("templates/users.yml");
+const SEED_SQLX: &str = include_str!("templates/seed_sqlx.rs");
+const SEED_DIESEL: &str = include_str!("templates/seed_diesel.rs");
+
+pub fn run(args: InitArgs) -> Result<()> {
+    let dir = Path::new(&args.dir);
+    let seed_template = match args.db {
+        DbCrate::Sqlx => SEED_SQLX,
+        DbCrate::Diesel => SEED_DIESEL,
+    };
+    let files = [
+        (dir.join("seeds.yml"), MANIFEST),
+        (dir.join("companies.yml"), COMPANIES),
+        (dir.join("users.yml"), USERS),
+        (Path::new(&args.seed_file).to_path_buf(), seed_template),
+    ];
+
+    for (path, template) in files {
+        if path.exists() && !args.force {
+            println!("skipped {} (already exists)", path.display());
+            continue;
+        }
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, template.replace("{{dir}}", &args.dir))
+            .map_err(|err| anyhow::anyhow!("Can't write the file: {:?}\n   err: {}", path, err))?;
+        println!("created {}", path.display());
+    }
+    Ok(())
+}
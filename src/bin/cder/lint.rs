@@ -0,0 +1,85 @@
+use anyhow::Result;
+use cder::{CderConfig, DatabaseSeeder};
+use clap::{Args, ValueEnum};
+
+use crate::files::seed_entries;
+
+#[derive(Args)]
+pub struct LintArgs {
+    /// the directory the seed files are read from
+    dir: Option<String>,
+    /// the manifest that lists the seed files in the order they are populated
+    #[arg(long)]
+    manifest: Option<String>,
+    /// the regex every label has to match, e.g. `^[A-Z][A-Za-z0-9]*$`
+    #[arg(long)]
+    label_pattern: Option<String>,
+    /// the maximum number of the fields of a record
+    #[arg(long)]
+    max_fields: Option<usize>,
+    /// the maximum size of a record in bytes, measured in yaml
+    #[arg(long)]
+    max_record_bytes: Option<usize>,
+    /// the maximum length of a string value in chars
+    #[arg(long)]
+    max_value_len: Option<usize>,
+    /// turns the rule off, e.g. `--allow unused-labels`
+    #[arg(long, value_enum)]
+    allow: Vec<Rule>,
+}
+
+/// the rules that are checked by default
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Rule {
+    UnusedLabels,
+    EnvDefaults,
+}
+
+/// the rules are taken from the `[lint]` table of `.cder.toml`, and then overridden by the options
+pub fn run(args: LintArgs) -> Result<()> {
+    let mut rules = CderConfig::load()?.lint.unwrap_or_default();
+    if args.label_pattern.is_some() {
+        rules.label_pattern = args.label_pattern;
+    }
+    if args.max_fields.is_some() {
+        rules.max_fields = args.max_fields;
+    }
+    if args.max_record_bytes.is_some() {
+        rules.max_record_bytes = args.max_record_bytes;
+    }
+    if args.max_value_len.is_some() {
+        rules.max_value_len = args.max_value_len;
+    }
+    rules.unused_labels &= !args.allow.contains(&Rule::UnusedLabels);
+    rules.env_defaults &= !args.allow.contains(&Rule::EnvDefaults);
+
+    let mut builder = DatabaseSeeder::builder();
+    if let Some(dir) = &args.dir {
+        builder = builder.base_dir(dir);
+    }
+    if let Some(manifest) = &args.manifest {
+        builder = builder.manifest(manifest);
+    }
+    let mut seeder = builder.build()?;
+
+    let filenames = seed_entries(&mut seeder, args.manifest.is_some(), &[])?
+        .into_iter()
+        .map(|entry| entry.file)
+        .collect::<Vec<String>>();
+    let filenames = filenames.iter().map(String::as_str).collect::<Vec<&str>>();
+    let warnings = seeder.lint(&filenames, &rules)?;
+
+    if !warnings.is_empty() {
+        let warnings = warnings
+            .iter()
+            .map(|warning| warning.to_string())
+            .collect::<Vec<String>>();
+        return Err(anyhow::anyhow!(
+            "{} warning(s) found:\n   {}",
+            warnings.len(),
+            warnings.join("\n   ")
+        ));
+    }
+    println!("{} file(s) linted", filenames.len());
+    Ok(())
+}
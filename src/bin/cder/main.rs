@@ -0,0 +1,92 @@
+//! `cder` command line tool, available with the `cli` feature.
+//!
+//! ```sh
+//! cargo install cder --features cli
+//! cder init --dir fixtures --db sqlx
+//! cder seed --dir fixtures --manifest fixtures/seeds.yml --database-url postgres://localhost/app
+//! cder seed --dir fixtures --manifest fixtures/seeds.yml --sql seeds.sql
+//! cder validate fixtures --manifest fixtures/seeds.yml
+//! cder lint fixtures --label-pattern '^[A-Z][A-Za-z0-9]*$'
+//! cder render users.yml --dir fixtures --env-file .env --refs refs.json
+//! cder convert fixtures/users.yml --to json
+//! cder fake --schema schemas/users.schema.json --count 1000 > fixtures/users.yml
+//! cder graph fixtures --format dot | dot -Tsvg > seeds.svg
+//! cder dump --database-url postgres://localhost/app --table customers --where "plan = 'Premium'" > customers.yml
+//! cder diff users.yml --dir fixtures --state seed_state.json --database-url postgres://localhost/app
+//! ```
+mod convert;
+mod diff;
+mod dump;
+mod fake;
+mod files;
+mod graph;
+mod init;
+mod lint;
+mod render;
+mod seed;
+mod validate;
+
+use anyhow::Result;
+use cder::CderError;
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(
+    name = "cder",
+    version,
+    about = "database seeding tool based on yaml fixtures"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// populates the seed files into the database
+    Seed(seed::SeedArgs),
+    /// checks the seed files without inserting anything, and reports all the problems found
+    Validate(validate::ValidateArgs),
+    /// prints the seed file with its tags resolved
+    Render(render::RenderArgs),
+    /// compares the seed files with the rows in the database
+    Diff(diff::DiffArgs),
+    /// creates the seed files, a manifest and a seeding binary to start with
+    Init(init::InitArgs),
+    /// converts the fixture file into another format, keeping its labels and tags
+    Convert(convert::ConvertArgs),
+    /// prints the REF dependencies between the seed files and their records
+    Graph(graph::GraphArgs),
+    /// generates the labeled records of the JSON schema with fake data, to start a seed file with
+    Fake(fake::FakeArgs),
+    /// prints the rows of the table as a seed file, with the foreign keys replaced with REF tags
+    Dump(dump::DumpArgs),
+    /// checks the seed files against the conventions, e.g. the naming and the usage of the labels
+    Lint(lint::LintArgs),
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let result = match Cli::parse().command {
+        Command::Seed(args) => seed::run(args).await,
+        Command::Validate(args) => validate::run(args),
+        Command::Render(args) => render::run(args),
+        Command::Diff(args) => diff::run(args).await,
+        Command::Init(args) => init::run(args),
+        Command::Convert(args) => convert::run(args),
+        Command::Graph(args) => graph::run(args),
+        Command::Fake(args) => fake::run(args),
+        Command::Dump(args) => dump::run(args).await,
+        Command::Lint(args) => lint::run(args),
+    };
+
+    // the problems of the seed files are rendered with the snippets of the files
+    match result.map_err(|err| err.downcast::<CderError>()) {
+        Err(Ok(err)) => {
+            eprintln!("{:?}", miette::Report::new(err));
+            std::process::exit(1);
+        }
+        Err(Err(err)) => Err(err),
+        Ok(()) => Ok(()),
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::Result;
+use cder::DatabaseSeeder;
+use clap::Args;
+use std::{env, fs};
+
+use crate::files::load_refs;
+
+#[derive(Args)]
+pub struct RenderArgs {
+    /// the seed file to render
+    file: String,
+    /// the directory the seed file is read from
+    #[arg(long)]
+    dir: Option<String>,
+    /// the file of the environment variables (`KEY=value` per line) that ENV tags refer to.
+    /// the variables that are already set are kept
+    #[arg(long)]
+    env_file: Option<String>,
+    /// the ids that REF tags refer to: the state saved by `DatabaseSeeder::save_state`, or a json
+    /// object of the labels and their ids
+    #[arg(long)]
+    refs: Option<String>,
+    /// the values that CTX tags refer to, e.g. `--context tenant=acme`
+    #[arg(long, value_parser = parse_context)]
+    context: Vec<(String, String)>,
+}
+
+pub fn run(args: RenderArgs) -> Result<()> {
+    if let Some(env_file) = &args.env_file {
+        load_env_file(env_file)?;
+    }
+
+    let mut builder = DatabaseSeeder::builder();
+    if let Some(dir) = &args.dir {
+        builder = builder.base_dir(dir);
+    }
+    let mut seeder = builder.build()?;
+    if let Some(refs) = &args.refs {
+        seeder.restore_state(load_refs(refs)?);
+    }
+    for (key, value) in &args.context {
+        match value.parse::<i64>() {
+            Ok(value) => seeder.set_context(key, value),
+            Err(_) => seeder.set_context(key, value.as_str()),
+        }
+    }
+
+    print!("{}", seeder.render(&args.file)?);
+    Ok(())
+}
+
+fn parse_context(arg: &str) -> Result<(String, String)> {
+    arg.split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| anyhow::anyhow!("the context must be given as key=value: {}", arg))
+}
+
+/// sets the variables of the env file, leaving the ones already set as they are
+fn load_env_file(path: &str) -> Result<()> {
+    let raw_text = fs::read_to_string(path)
+        .map_err(|err| anyhow::anyhow!("Can't open the file: {}\n   err: {}", path, err))?;
+
+    for line in raw_text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line.split_once('=').ok_or_else(|| {
+            anyhow::anyhow!("invalid line in the env file: {}\n   {}", path, line)
+        })?;
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|quote| {
+                value
+                    .strip_prefix(*quote)
+                    .and_then(|value| value.strip_suffix(*quote))
+            })
+            .unwrap_or(value);
+
+        if env::var_os(key.trim()).is_none() {
+            env::set_var(key.trim(), value);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use anyhow::{anyhow, Result};
+use cder::{DatabaseSeeder, ManifestEntry, SqlFormat, SqlWriter, SqlxTable};
+use clap::Args;
+use sqlx::postgres::PgPool;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use crate::files::seed_entries;
+
+#[derive(Args)]
+pub struct SeedArgs {
+    /// the directory the seed files are read from
+    #[arg(long)]
+    dir: Option<String>,
+    /// the manifest that lists the seed files in the order they are populated
+    #[arg(long)]
+    manifest: Option<String>,
+    /// populates only the manifest entries of the profile
+    #[arg(long)]
+    profile: Option<String>,
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: Option<String>,
+    /// writes the records as SQL statements into the file (`-` for stdout) in a transaction,
+    /// instead of inserting them. the ids are assigned from 1 for each table
+    #[arg(long, value_name = "FILE", conflicts_with = "dry_run")]
+    sql: Option<String>,
+    /// writes COPY statements (of up to 1000 rows) rather than INSERT ones, with `--sql`
+    #[arg(long, requires = "sql")]
+    copy: bool,
+    /// fails when a label has already been populated (e.g. by another file), rather than
+    /// overwriting the id REFs resolve to
+    #[arg(long)]
+    strict: bool,
+    /// resolves and deserializes the seed files without inserting anything
+    #[arg(long)]
+    dry_run: bool,
+    /// skips the files that have been applied, see `DatabaseSeeder::track_versions`
+    #[arg(long)]
+    track_versions: bool,
+    /// saves the ids of the records populated, e.g. for `cder render` and `cder diff`
+    #[arg(long)]
+    save_state: Option<String>,
+    /// the seed files to populate (default: the files in the manifest, or every yaml file in
+    /// the directory, sorted by their dependencies). each of them is inserted into the table of
+    /// its file stem
+    files: Vec<String>,
+}
+
+pub async fn run(args: SeedArgs) -> Result<()> {
+    let mut builder = DatabaseSeeder::builder()
+        .dry_run(args.dry_run)
+        .track_versions(args.track_versions);
+    if let Some(dir) = &args.dir {
+        builder = builder.base_dir(dir);
+    }
+    if let Some(manifest) = &args.manifest {
+        builder = builder.manifest(manifest);
+    }
+    if let Some(profile) = &args.profile {
+        builder = builder.profile(profile);
+    }
+    if args.strict {
+        builder = builder.strict(true);
+    }
+    let mut seeder = builder.build()?;
+    let entries = seed_entries(&mut seeder, args.manifest.is_some(), &args.files)?;
+
+    match &args.sql {
+        Some(path) => write_sql(&mut seeder, &entries, path, args.copy).await?,
+        None => {
+            let database_url = args.database_url.as_deref().ok_or_else(|| {
+                anyhow!("--database-url (or DATABASE_URL) is required unless --sql is given")
+            })?;
+            // connects on the first query, so that dry runs do not need the database
+            let pool = PgPool::connect_lazy(database_url)?;
+            for entry in &entries {
+                let mut table = SqlxTable::new(&pool, entry.table());
+                seeder
+                    .populate_with::<serde_json::Value, _>(&entry.file, &mut table)
+                    .await?;
+            }
+        }
+    }
+
+    if let Some(path) = &args.save_state {
+        seeder.save_state(path)?;
+    }
+    for warning in seeder.warnings() {
+        eprintln!("warning: {}", warning);
+    }
+    match (args.dry_run, &args.sql) {
+        (true, _) => println!("{}", seeder.dry_run_report()),
+        // the statements may be written to stdout
+        (false, Some(_)) => eprintln!("{}", seeder.report()),
+        (false, None) => println!("{}", seeder.report()),
+    }
+    Ok(())
+}
+
+/// writes the entries as SQL statements, continuing the ids of the tables populated by more than
+/// one entry, and advancing the sequences of the tables past the ids assigned
+async fn write_sql(
+    seeder: &mut DatabaseSeeder,
+    entries: &[ManifestEntry],
+    path: &str,
+    copy: bool,
+) -> Result<()> {
+    let mut output: Box<dyn Write + Send> = match path {
+        "-" => Box::new(io::stdout()),
+        path => Box::new(BufWriter::new(File::create(path).map_err(|err| {
+            anyhow!("Can't create the file: {}\n   err: {}", path, err)
+        })?)),
+    };
+    let format = match copy {
+        true => SqlFormat::Copy,
+        false => SqlFormat::Insert,
+    };
+
+    let mut next_ids = HashMap::<String, i64>::new();
+    let mut tables = Vec::<String>::new();
+    writeln!(output, "BEGIN;")?;
+    for entry in entries {
+        let table = entry.table().to_string();
+        let next_id = next_ids.entry(table.clone()).or_insert(1);
+        let mut writer = SqlWriter::new(&mut output, &table)
+            .format(format)
+            .starting_at(*next_id);
+        match format {
+            SqlFormat::Insert => {
+                seeder
+                    .populate_with::<serde_json::Value, _>(&entry.file, &mut writer)
+                    .await?;
+            }
+            // a COPY block for each chunk rather than each record
+            SqlFormat::Copy => {
+                seeder
+                    .populate_many_with::<serde_json::Value, _>(&entry.file, 1000, &mut writer)
+                    .await?;
+            }
+        }
+        *next_id = writer.next_id();
+        if !tables.contains(&table) {
+            tables.push(table);
+        }
+    }
+    for table in tables {
+        let last_id = next_ids[&table] - 1;
+        if last_id > 0 {
+            writeln!(
+                output,
+                "SELECT setval(pg_get_serial_sequence('{}', 'id'), {});",
+                table, last_id
+            )?;
+        }
+    }
+    writeln!(output, "COMMIT;")?;
+    output.flush()?;
+    Ok(())
+}
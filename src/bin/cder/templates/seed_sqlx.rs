@@ -0,0 +1,60 @@
+//! populates the seed files in `{{dir}}` into the database of `DATABASE_URL`:
+//!
+//! cargo run --bin seed
+//!
+//! requires: anyhow, cder, serde (derive), sqlx (postgres, runtime-tokio), tokio (macros)
+use anyhow::Result;
+use cder::DatabaseSeeder;
+use serde::Deserialize;
+use sqlx::postgres::PgPool;
+
+#[derive(Deserialize)]
+struct Company {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct User {
+    name: String,
+    email: String,
+    company_id: i64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL")?).await?;
+    let mut seeder = DatabaseSeeder::from_manifest("{{dir}}/seeds.yml")?;
+
+    seeder
+        .populate_async("companies.yml", |company: Company| {
+            let pool = pool.clone();
+            async move {
+                let id: i64 =
+                    sqlx::query_scalar("INSERT INTO companies (name) VALUES ($1) RETURNING id")
+                        .bind(company.name)
+                        .fetch_one(&pool)
+                        .await?;
+                Ok(id)
+            }
+        })
+        .await?;
+    seeder
+        .populate_async("users.yml", |user: User| {
+            let pool = pool.clone();
+            async move {
+                let id: i64 = sqlx::query_scalar(
+                    "INSERT INTO users (name, email, company_id) VALUES ($1, $2, $3) RETURNING id",
+                )
+                .bind(user.name)
+                .bind(user.email)
+                .bind(user.company_id)
+                .fetch_one(&pool)
+                .await?;
+                Ok(id)
+            }
+        })
+        .await?;
+
+    println!("{}", seeder.report());
+    Ok(())
+}
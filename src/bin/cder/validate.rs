@@ -0,0 +1,131 @@
+use anyhow::Result;
+use cder::DatabaseSeeder;
+use clap::Args;
+use jsonschema::Validator;
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::files::{seed_entries, yaml_files};
+
+#[derive(Args)]
+pub struct ValidateArgs {
+    /// the directory the seed files are read from
+    dir: Option<String>,
+    /// the manifest that lists the seed files in the order they are populated
+    #[arg(long)]
+    manifest: Option<String>,
+    /// validates only the manifest entries of the profile
+    #[arg(long)]
+    profile: Option<String>,
+    /// reports the labels defined by more than one file
+    #[arg(long)]
+    strict: bool,
+    /// the directory of the JSON schemas the records are checked against, named after the seed
+    /// files (e.g. `users.schema.json` for `users.yml`)
+    #[arg(long)]
+    schema_dir: Option<String>,
+}
+
+/// the schema of the file being validated, and the records that do not conform to it
+#[derive(Default)]
+struct SchemaCheck {
+    filename: String,
+    validator: Option<Validator>,
+    problems: Vec<String>,
+}
+
+pub fn run(args: ValidateArgs) -> Result<()> {
+    let mut builder = DatabaseSeeder::builder().dry_run(true);
+    if let Some(dir) = &args.dir {
+        builder = builder.base_dir(dir);
+    }
+    if let Some(manifest) = &args.manifest {
+        builder = builder.manifest(manifest);
+    }
+    if let Some(profile) = &args.profile {
+        builder = builder.profile(profile);
+    }
+    if args.strict {
+        builder = builder.strict(true);
+    }
+    let mut seeder = builder.build()?;
+
+    // the files that cannot be sorted are checked in the order of their names, so that every
+    // problem of them is reported
+    let filenames = match seed_entries(&mut seeder, args.manifest.is_some(), &[]) {
+        Ok(entries) => entries.into_iter().map(|entry| entry.file).collect(),
+        Err(_) if args.manifest.is_none() => yaml_files(&seeder.base_dir)?,
+        Err(err) => return Err(err),
+    };
+    let filenames = filenames.iter().map(String::as_str).collect::<Vec<&str>>();
+    seeder.validate_files(&filenames)?;
+
+    // resolves the tags and deserializes every record as they would be populated
+    let schema_check = Arc::new(Mutex::new(SchemaCheck::default()));
+    let hook_check = schema_check.clone();
+    seeder.before_insert(move |label, record| {
+        let mut check = hook_check.lock().unwrap();
+        if let Some(validator) = &check.validator {
+            let instance = serde_json::to_value(&*record)?;
+            let problems = validator
+                .iter_errors(&instance)
+                .map(|err| {
+                    format!(
+                        "{}: {}{}: {}",
+                        check.filename, label, err.instance_path, err
+                    )
+                })
+                .collect::<Vec<String>>();
+            check.problems.extend(problems);
+        }
+        Ok(())
+    });
+
+    let mut problems = Vec::new();
+    for filename in &filenames {
+        {
+            let mut check = schema_check.lock().unwrap();
+            check.filename = filename.to_string();
+            check.validator = match &args.schema_dir {
+                Some(schema_dir) => load_schema(schema_dir, filename)?,
+                None => None,
+            };
+        }
+        if let Err(err) = seeder.populate(filename, |_: serde_yaml::Value| Ok(0)) {
+            problems.push(format!("{}: {}", filename, err));
+        }
+    }
+    problems.append(&mut schema_check.lock().unwrap().problems);
+
+    if !problems.is_empty() {
+        return Err(anyhow::anyhow!(
+            "{} problem(s) found:\n   {}",
+            problems.len(),
+            problems.join("\n   ")
+        ));
+    }
+    println!("{} file(s) validated", filenames.len());
+    Ok(())
+}
+
+/// the schema of the seed file, e.g. `users.schema.json` for `users.yml`, if any
+fn load_schema(schema_dir: &str, filename: &str) -> Result<Option<Validator>> {
+    let stem = Path::new(filename)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(filename);
+    let path = Path::new(schema_dir).join(format!("{}.schema.json", stem));
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let schema = serde_json::from_str(&fs::read_to_string(&path)?).map_err(|err| {
+        anyhow::anyhow!("failed to parse the schema: {:?}\n   err: {}", path, err)
+    })?;
+    let validator = jsonschema::validator_for(&schema)
+        .map_err(|err| anyhow::anyhow!("invalid schema: {:?}\n   err: {}", path, err))?;
+    Ok(Some(validator))
+}
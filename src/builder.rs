@@ -0,0 +1,242 @@
+use anyhow::Result;
+
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    database_seeder::SharedObserver, CderConfig, DatabaseSeeder, LintRules, RetryPolicy,
+    SeedObserver,
+};
+
+/// DatabaseSeederBuilder configures a DatabaseSeeder at once, rather than calling its setters one
+/// by one. Create it with `DatabaseSeeder::builder`.
+/// The base_dir, profile and strict mode that are not given fall back to the ones of the global
+/// configuration, see `CderConfig`.
+///
+/// # Examples
+/// ```rust
+/// use cder::DatabaseSeeder;
+/// # use anyhow::Result;
+/// #
+/// # fn main() -> Result<()> {
+/// let seeder = DatabaseSeeder::builder()
+///     .base_dir("fixtures")
+///     .profile("development")
+///     .continue_on_error(true)
+///     .build()?;
+///
+/// assert_eq!(seeder.base_dir, "fixtures");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct DatabaseSeederBuilder {
+    base_dir: Option<String>,
+    manifest: Option<String>,
+    profile: Option<String>,
+    strict: Option<bool>,
+    concurrency: Option<usize>,
+    retry_policy: Option<RetryPolicy>,
+    continue_on_error: bool,
+    dry_run: bool,
+    scale: Option<usize>,
+    rate_limit: Option<u32>,
+    namespace_by_file: bool,
+    track_versions: bool,
+    rails_fixtures: bool,
+    django_fixtures: bool,
+    #[cfg(feature = "dbt")]
+    dbt_seeds: Option<crate::DbtSeeds>,
+    #[cfg(feature = "sqlx")]
+    pool: Option<sqlx::PgPool>,
+    observer: Option<SharedObserver>,
+    warning_rules: Option<LintRules>,
+    config: Option<CderConfig>,
+}
+
+impl DatabaseSeederBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// sets the directory the seed files are read from, see `DatabaseSeeder::set_dir`.
+    /// it takes precedence over the base_dir of the manifest
+    pub fn base_dir(mut self, base_dir: &str) -> Self {
+        self.base_dir = Some(base_dir.to_string());
+        self
+    }
+
+    /// loads the manifest on build, see `DatabaseSeeder::from_manifest`
+    pub fn manifest(mut self, path: &str) -> Self {
+        self.manifest = Some(path.to_string());
+        self
+    }
+
+    /// see `DatabaseSeeder::set_profile`
+    pub fn profile(mut self, profile: &str) -> Self {
+        self.profile = Some(profile.to_string());
+        self
+    }
+
+    /// see `DatabaseSeeder::strict`
+    pub fn strict(mut self, enabled: bool) -> Self {
+        self.strict = Some(enabled);
+        self
+    }
+
+    /// see `DatabaseSeeder::set_concurrency`
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = Some(concurrency);
+        self
+    }
+
+    /// see `DatabaseSeeder::set_retry_policy`
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// see `DatabaseSeeder::continue_on_error`
+    pub fn continue_on_error(mut self, enabled: bool) -> Self {
+        self.continue_on_error = enabled;
+        self
+    }
+
+    /// see `DatabaseSeeder::dry_run`
+    pub fn dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// see `DatabaseSeeder::namespace_by_file`
+    pub fn namespace_by_file(mut self, enabled: bool) -> Self {
+        self.namespace_by_file = enabled;
+        self
+    }
+
+    /// see `DatabaseSeeder::track_versions`
+    pub fn track_versions(mut self, enabled: bool) -> Self {
+        self.track_versions = enabled;
+        self
+    }
+
+    /// see `DatabaseSeeder::rails_fixtures`
+    pub fn rails_fixtures(mut self, enabled: bool) -> Self {
+        self.rails_fixtures = enabled;
+        self
+    }
+
+    /// see `DatabaseSeeder::django_fixtures`
+    pub fn django_fixtures(mut self, enabled: bool) -> Self {
+        self.django_fixtures = enabled;
+        self
+    }
+
+    /// see `DatabaseSeeder::dbt_seeds`
+    #[cfg(feature = "dbt")]
+    pub fn dbt_seeds(mut self, seeds: crate::DbtSeeds) -> Self {
+        self.dbt_seeds = Some(seeds);
+        self
+    }
+
+    /// see `DatabaseSeeder::set_pool`
+    #[cfg(feature = "sqlx")]
+    pub fn pool(mut self, pool: &sqlx::PgPool) -> Self {
+        self.pool = Some(pool.clone());
+        self
+    }
+
+    /// see `DatabaseSeeder::set_rate_limit`
+    pub fn rate_limit(mut self, records_per_second: u32) -> Self {
+        self.rate_limit = Some(records_per_second);
+        self
+    }
+
+    /// see `DatabaseSeeder::with_scale`
+    pub fn scale(mut self, scale: usize) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    /// sets the observer that gets notified of the progress, see `SeedObserver`
+    pub fn observer<O>(mut self, observer: O) -> Self
+    where
+        O: SeedObserver + Send + 'static,
+    {
+        self.observer = Some(Arc::new(Mutex::new(observer)));
+        self
+    }
+
+    /// sets the rules the records are checked against while they are populated, see
+    /// `DatabaseSeeder::set_warning_rules`. falls back to the `[lint]` rules of the configuration
+    pub fn warning_rules(mut self, rules: LintRules) -> Self {
+        self.warning_rules = Some(rules);
+        self
+    }
+
+    /// sets the defaults of the base_dir, the profile and strict mode, in place of the ones loaded
+    /// with `CderConfig::load`
+    pub fn config(mut self, config: CderConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// creates the seeder. fails only when the manifest or the global configuration cannot be
+    /// loaded, or when the label_pattern of the warning rules is not a valid regex
+    pub fn build(self) -> Result<DatabaseSeeder> {
+        let config = match self.config {
+            Some(config) => config,
+            None => CderConfig::load()?,
+        };
+        let mut seeder = match &self.manifest {
+            Some(path) => DatabaseSeeder::from_manifest(path)?,
+            None => DatabaseSeeder::new(),
+        };
+
+        // the base_dir of the manifest takes precedence over the global one
+        let base_dir = match &self.manifest {
+            Some(_) => self.base_dir,
+            None => self.base_dir.or(config.base_dir),
+        };
+        if let Some(base_dir) = &base_dir {
+            seeder.set_dir(base_dir);
+        }
+        if let Some(profile) = self.profile.or(config.profile) {
+            seeder.set_profile(&profile);
+        }
+        if let Some(concurrency) = self.concurrency {
+            seeder.set_concurrency(concurrency);
+        }
+        if let Some(retry_policy) = self.retry_policy {
+            seeder.set_retry_policy(retry_policy);
+        }
+        if let Some(records_per_second) = self.rate_limit {
+            seeder.set_rate_limit(records_per_second);
+        }
+        if let Some(scale) = self.scale {
+            seeder.with_scale(scale);
+        }
+        if let Some(observer) = self.observer {
+            seeder.observer = Some(observer);
+        }
+        if let Some(rules) = self.warning_rules.or(config.lint) {
+            seeder.set_warning_rules(rules)?;
+        }
+        seeder.strict(self.strict.or(config.strict).unwrap_or_default());
+        seeder.namespace_by_file(self.namespace_by_file);
+        seeder.track_versions(self.track_versions);
+        seeder.rails_fixtures(self.rails_fixtures);
+        seeder.django_fixtures(self.django_fixtures);
+        #[cfg(feature = "dbt")]
+        if let Some(seeds) = self.dbt_seeds {
+            seeder.dbt_seeds(seeds);
+        }
+        #[cfg(feature = "sqlx")]
+        if let Some(pool) = self.pool {
+            seeder.set_pool(&pool);
+        }
+        seeder.continue_on_error(self.continue_on_error);
+        seeder.dry_run(self.dry_run);
+
+        Ok(seeder)
+    }
+}
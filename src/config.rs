@@ -0,0 +1,162 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::LintRules;
+
+/// the config file looked up from the crate directory up to the root, e.g. at the workspace root
+const CONFIG_FILENAME: &str = ".cder.toml";
+
+/// CderConfig holds the defaults shared by the binaries of a workspace, so that each of them does
+/// not have to repeat the same setup. `DatabaseSeederBuilder::build` falls back to it for the
+/// settings that are not given explicitly, and `StructLoader::with_config` takes its base_dir.
+/// `cder lint` takes the rules in its `[lint]` table (see `LintRules`).
+///
+/// The defaults are read from `.cder.toml`, looked up from the crate directory
+/// (`CARGO_MANIFEST_DIR`, or the current directory) towards the root, and then overridden by the
+/// environment variables `CDER_BASE_DIR`, `CDER_PROFILE` and `CDER_STRICT`.
+///
+/// ```toml
+/// # .cder.toml
+/// # relative to the directory the config file is placed in
+/// base_dir = "fixtures"
+/// profile = "development"
+/// strict = true
+///
+/// [lint]
+/// label_pattern = "^[A-Z][A-Za-z0-9]*$"
+/// ```
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CderConfig {
+    pub base_dir: Option<String>,
+    pub profile: Option<String>,
+    pub strict: Option<bool>,
+    pub lint: Option<LintRules>,
+}
+
+impl CderConfig {
+    /// reads `.cder.toml` (if any), then applies the environment variables on top of it
+    pub fn load() -> Result<Self> {
+        let config = match find_config_file() {
+            Some(path) => Self::from_file(&path.to_string_lossy())?,
+            None => Self::default(),
+        };
+        Ok(config.overridden_by(Self::from_env()?))
+    }
+
+    /// reads the config file. the relative base_dir is resolved against the directory of the file
+    pub fn from_file(path: &str) -> Result<Self> {
+        let raw_text = fs::read_to_string(path)
+            .map_err(|err| anyhow::anyhow!("Can't open the file: {}\n   err: {}", path, err))?;
+        let mut config: CderConfig = toml::from_str(&raw_text).map_err(|err| {
+            anyhow::anyhow!("failed to parse the config: {}\n   err: {}", path, err)
+        })?;
+
+        if let (Some(base_dir), Some(parent)) = (&config.base_dir, Path::new(path).parent()) {
+            config.base_dir = Some(parent.join(base_dir).to_string_lossy().into_owned());
+        }
+        Ok(config)
+    }
+
+    /// reads `CDER_BASE_DIR`, `CDER_PROFILE` and `CDER_STRICT`
+    pub fn from_env() -> Result<Self> {
+        from_vars(env::vars())
+    }
+
+    /// the settings of the other config take precedence over the ones of this config
+    fn overridden_by(self, other: Self) -> Self {
+        Self {
+            base_dir: other.base_dir.or(self.base_dir),
+            profile: other.profile.or(self.profile),
+            strict: other.strict.or(self.strict),
+            lint: other.lint.or(self.lint),
+        }
+    }
+}
+
+fn find_config_file() -> Option<PathBuf> {
+    let start = env::var("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .or_else(|_| env::current_dir())
+        .ok()?;
+
+    start
+        .ancestors()
+        .map(|dir| dir.join(CONFIG_FILENAME))
+        .find(|path| path.is_file())
+}
+
+fn from_vars<I>(vars: I) -> Result<CderConfig>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut config = CderConfig::default();
+    for (key, value) in vars {
+        match key.as_str() {
+            "CDER_BASE_DIR" => config.base_dir = Some(value),
+            "CDER_PROFILE" => config.profile = Some(value),
+            "CDER_STRICT" => config.strict = Some(parse_bool(&key, &value)?),
+            _ => {}
+        }
+    }
+    Ok(config)
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "" | "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(anyhow::anyhow!(
+            "{} must be either true or false: {}",
+            key,
+            value
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::*;
+
+    #[test]
+    fn test_from_vars() {
+        let vars = [
+            ("CDER_BASE_DIR".to_string(), "fixtures".to_string()),
+            ("CDER_STRICT".to_string(), "1".to_string()),
+            ("PATH".to_string(), "/usr/bin".to_string()),
+        ];
+        let config = from_vars(vars).unwrap();
+        assert_eq!(
+            config,
+            CderConfig {
+                base_dir: Some("fixtures".to_string()),
+                profile: None,
+                strict: Some(true),
+                lint: None,
+            }
+        );
+
+        let vars = [("CDER_STRICT".to_string(), "maybe".to_string())];
+        assert!(from_vars(vars).is_err());
+
+        let file_config = CderConfig {
+            base_dir: Some("seeds".to_string()),
+            profile: Some("staging".to_string()),
+            strict: None,
+            lint: None,
+        };
+        assert_eq!(
+            file_config.overridden_by(config),
+            CderConfig {
+                base_dir: Some("fixtures".to_string()),
+                profile: Some("staging".to_string()),
+                strict: Some(true),
+                lint: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::{json, Map, Value as JsonValue};
+use std::future::Future;
+
+use crate::{RefValue, SeedAdapter};
+
+/// a parameterized Cypher query CypherAdapter runs, with the rows of the records as `$rows`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CypherQuery {
+    pub query: String,
+    pub params: Map<String, JsonValue>,
+}
+
+/// CypherExecutor runs the queries of CypherAdapter, so that any driver (e.g. neo4rs) can be
+/// used, or the queries can be written out to be run later.
+///
+/// # Examples
+/// ```rust,ignore
+/// use cder::{CypherExecutor, CypherQuery};
+///
+/// struct Neo4j(neo4rs::Graph);
+///
+/// impl CypherExecutor for Neo4j {
+///     async fn run(&self, query: CypherQuery) -> anyhow::Result<()> {
+///         let mut cypher = neo4rs::query(&query.query);
+///         for (name, value) in query.params {
+///             cypher = cypher.param(&name, neo4rs::BoltType::try_from(value)?);
+///         }
+///         self.0.run(cypher).await?;
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait CypherExecutor: Send + Sync {
+    fn run(&self, query: CypherQuery) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// the relationship of a field to the nodes its values (the ids the REFs resolved to) refer to
+#[derive(Debug, Clone)]
+struct Relationship {
+    field: String,
+    rel_type: String,
+    node_label: String,
+    key_property: String,
+}
+
+/// CypherAdapter is an adapter (see `SeedAdapter`) that merges the records into a graph
+/// database (e.g. Neo4j or Memgraph) as the nodes of the label, with their fields as the
+/// properties, and the fields given by `relationship` as the relationships to the nodes their
+/// REFs refer to, so that the graph is seeded from the same fixtures as the primary database.
+/// The nodes are merged by their keys, i.e. the labels of the records (as the `label` property)
+/// or the values of the field given by `id_field`, and the REFs to them resolve to the keys.
+/// The queries are parameterized, and run for each chunk of
+/// `DatabaseSeeder::populate_many_with` by the CypherExecutor. Available with the `cypher`
+/// feature.
+///
+/// # Examples
+/// ```rust,no_run
+/// use cder::{CypherAdapter, CypherExecutor, DatabaseSeeder};
+/// # use anyhow::Result;
+///
+/// async fn populate_seeds<E: CypherExecutor + Clone>(executor: E) -> Result<()> {
+///     let mut seeder = DatabaseSeeder::new();
+///     let mut customers = CypherAdapter::new(executor.clone(), "Customer");
+///     let mut items = CypherAdapter::new(executor.clone(), "Item");
+///     // `customer_id: ${{ REF(Alice) }}` becomes `(:Order)-[:PLACED_BY]->(:Customer)`
+///     let mut orders = CypherAdapter::new(executor, "Order")
+///         .id_field("id")
+///         .relationship("customer_id", "PLACED_BY", "Customer")
+///         .relationship("item_id", "CONTAINS", "Item");
+///
+///     seeder
+///         .populate_many_with::<serde_json::Value, _>("customers.yml", 500, &mut customers)
+///         .await?;
+///     seeder
+///         .populate_many_with::<serde_json::Value, _>("items.yml", 500, &mut items)
+///         .await?;
+///     seeder
+///         .populate_many_with::<serde_json::Value, _>("orders.yml", 500, &mut orders)
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+pub struct CypherAdapter<E> {
+    executor: E,
+    node_label: String,
+    id_field: Option<String>,
+    relationships: Vec<Relationship>,
+}
+
+impl<E> CypherAdapter<E>
+where
+    E: CypherExecutor,
+{
+    pub fn new(executor: E, node_label: &str) -> Self {
+        Self {
+            executor,
+            node_label: node_label.to_string(),
+            id_field: None,
+            relationships: Vec::new(),
+        }
+    }
+
+    /// merges the nodes by the field (as their key property), rather than by their labels
+    pub fn id_field(mut self, id_field: &str) -> Self {
+        self.id_field = Some(id_field.to_string());
+        self
+    }
+
+    /// turns the field into the relationship of the type to the nodes its values refer to,
+    /// rather than a property. the target is the label of the nodes, followed by their key
+    /// property (default: `label`), e.g. `Customer` or `Customer.id`. the field may have a list
+    /// of the values, for a relationship to each of them
+    pub fn relationship(mut self, field: &str, rel_type: &str, target: &str) -> Self {
+        let (node_label, key_property) = target.split_once('.').unwrap_or((target, "label"));
+        self.relationships.push(Relationship {
+            field: field.to_string(),
+            rel_type: rel_type.to_string(),
+            node_label: node_label.to_string(),
+            key_property: key_property.to_string(),
+        });
+        self
+    }
+
+    pub fn node_label(&self) -> &str {
+        &self.node_label
+    }
+
+    pub fn executor(&self) -> &E {
+        &self.executor
+    }
+
+    fn key_property(&self) -> &str {
+        self.id_field.as_deref().unwrap_or("label")
+    }
+
+    /// the queries merging the rows: the nodes first, and then their relationships
+    fn queries(&self) -> Vec<String> {
+        let node = format!(
+            "n:{} {{{}: row.key}}",
+            escape(&self.node_label),
+            escape(self.key_property())
+        );
+        let mut queries = vec![format!(
+            "UNWIND $rows AS row\nMERGE ({})\nSET n += row.properties",
+            node
+        )];
+        for (index, relationship) in self.relationships.iter().enumerate() {
+            queries.push(format!(
+                "UNWIND $rows AS row\nMATCH ({})\nUNWIND row.targets[{}] AS target\nMATCH (m:{} {{{}: target}})\nMERGE (n)-[:{}]->(m)",
+                node,
+                index,
+                escape(&relationship.node_label),
+                escape(&relationship.key_property),
+                escape(&relationship.rel_type)
+            ));
+        }
+        queries
+    }
+
+    /// the row of the record, i.e. its key, properties and targets of the relationships, and
+    /// the id the REFs to it resolve to
+    fn row<T>(&self, label: Option<&str>, record: &T) -> Result<(RefValue, JsonValue)>
+    where
+        T: Serialize,
+    {
+        let JsonValue::Object(mut properties) = serde_json::to_value(record)? else {
+            return Err(anyhow!(
+                "the records must be structs or mappings to be merged as nodes (label: {})",
+                self.node_label
+            ));
+        };
+        let (id, key) = match (&self.id_field, label) {
+            (Some(id_field), _) => {
+                let key = properties.get(id_field).cloned().ok_or_else(|| {
+                    anyhow!(
+                        "the record has no `{}` to be its key (label: {})",
+                        id_field,
+                        self.node_label
+                    )
+                })?;
+                let id = RefValue::from_json(&key).ok_or_else(|| {
+                    anyhow!(
+                        "`{}` is not a valid key: {} (label: {})",
+                        id_field,
+                        key,
+                        self.node_label
+                    )
+                })?;
+                (id, key)
+            }
+            (None, Some(label)) => {
+                let key = JsonValue::String(label.to_string());
+                properties.insert("label".to_string(), key.clone());
+                (RefValue::String(label.to_string()), key)
+            }
+            (None, None) => {
+                return Err(anyhow!(
+                    "the record has neither its label nor `id_field` to be its key (label: {})",
+                    self.node_label
+                ))
+            }
+        };
+
+        let targets = self
+            .relationships
+            .iter()
+            .map(
+                |relationship| match properties.remove(&relationship.field) {
+                    Some(JsonValue::Null) | None => JsonValue::Array(Vec::new()),
+                    Some(JsonValue::Array(targets)) => JsonValue::Array(targets),
+                    Some(target) => JsonValue::Array(vec![target]),
+                },
+            )
+            .collect::<Vec<JsonValue>>();
+        let properties = properties
+            .into_iter()
+            .map(|(name, value)| (name, property_value(value)))
+            .collect::<Map<String, JsonValue>>();
+        Ok((
+            id,
+            json!({ "key": key, "properties": properties, "targets": targets }),
+        ))
+    }
+
+    async fn merge<T>(&self, records: Vec<(Option<String>, T)>) -> Result<Vec<RefValue>>
+    where
+        T: Serialize,
+    {
+        let mut ids = Vec::with_capacity(records.len());
+        let mut rows = Vec::with_capacity(records.len());
+        for (label, record) in records {
+            let (id, row) = self.row(label.as_deref(), &record)?;
+            ids.push(id);
+            rows.push(row);
+        }
+        if rows.is_empty() {
+            return Ok(ids);
+        }
+
+        let rows = JsonValue::Array(rows);
+        for query in self.queries() {
+            let params = Map::from_iter([("rows".to_string(), rows.clone())]);
+            self.executor.run(CypherQuery { query, params }).await?;
+        }
+        Ok(ids)
+    }
+}
+
+impl<T, E> SeedAdapter<T> for CypherAdapter<E>
+where
+    T: Serialize + Send,
+    E: CypherExecutor,
+{
+    type Id = RefValue;
+
+    async fn insert(&mut self, record: T) -> Result<RefValue> {
+        let mut ids = self.merge(vec![(None, record)]).await?;
+        Ok(ids.remove(0))
+    }
+
+    async fn insert_labeled(&mut self, label: &str, record: T) -> Result<RefValue> {
+        let mut ids = self.merge(vec![(Some(label.to_string()), record)]).await?;
+        Ok(ids.remove(0))
+    }
+
+    async fn insert_many(&mut self, records: Vec<T>) -> Result<Vec<RefValue>> {
+        let records = records.into_iter().map(|record| (None, record)).collect();
+        self.merge(records).await
+    }
+
+    async fn insert_many_labeled(&mut self, records: Vec<(String, T)>) -> Result<Vec<RefValue>> {
+        let records = records
+            .into_iter()
+            .map(|(label, record)| (Some(label), record))
+            .collect();
+        self.merge(records).await
+    }
+}
+
+/// the name as an identifier of Cypher, e.g. `` `Order Item` ``
+fn escape(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+/// the value as a property, which may only be a scalar or a list of scalars. the other values
+/// (e.g. nested records and enums) are stored as JSON
+fn property_value(value: JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Object(_) => JsonValue::String(value.to_string()),
+        JsonValue::Array(ref values)
+            if values
+                .iter()
+                .any(|value| value.is_object() || value.is_array() || value.is_null()) =>
+        {
+            JsonValue::String(value.to_string())
+        }
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cypher::*;
+
+    #[test]
+    fn test_property_value() {
+        assert_eq!(escape("Order`Item"), "`Order``Item`");
+        assert_eq!(property_value(json!([1, 2])), json!([1, 2]));
+        assert_eq!(
+            property_value(json!({ "Premium": { "rank": 1 } })),
+            json!(r#"{"Premium":{"rank":1}}"#)
+        );
+        assert_eq!(
+            property_value(json!([{ "name": "a" }])),
+            json!(r#"[{"name":"a"}]"#)
+        );
+        assert_eq!(property_value(json!(null)), json!(null));
+    }
+}
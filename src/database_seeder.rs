@@ -1,13 +1,56 @@
-use crate::{load_named_records, Dict};
+use crate::{
+    dependency::{sort_by_dependencies, sort_seeded_files, sort_with_dependencies, validate_refs},
+    deserialize_resolved_records,
+    dry_run::{fill_placeholders, placeholder_kinds, placeholder_marker},
+    error::{aggregate, mask_error, record_error},
+    label::intern,
+    lint::{
+        defaults_used_warnings, label_pattern, lint_files, record_warnings, unused_label_warning,
+    },
+    load_named_records,
+    logging::log_event,
+    nested::{deserialize_child, split_nested_records, NestedRecord},
+    prefetch::{from_values, PrefetchedFile},
+    profile::{apply_profile, overlay_filename},
+    reader::{checksum, file_exists, read_file, read_seed_file, ReadOptions, SeedText},
+    registry::RegisteredFile,
+    resolve_text,
+    resolver::{ResolvedText, TagScope},
+    throttle::{wait_for, Throttle},
+    validation::{validate_files, ValidationReport},
+    verify::compare_fields,
+    AsyncLoader, CderError, Checkpoint, DatabaseSeederBuilder, DependencyGraph, Dict, DryRunFile,
+    DryRunReport, Factory, FileReport, FixtureDiff, Label, LabelMap, LintRules, LintWarning,
+    ManifestEntry, NamedRecords, RefValue, RetryPolicy, SeedAdapter, SeedHistoryEntry,
+    SeedManifest, SeedObserver, SeedReport, SeedState, SeedStatus, ToRefValue, Transaction,
+    VerificationReport,
+};
 use anyhow::Result;
-use serde::de::DeserializeOwned;
-use std::future::Future;
+use futures::{
+    future::{self, BoxFuture},
+    stream::{self, Stream, StreamExt},
+};
+use regex::Regex;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashSet,
+    fmt,
+    future::Future,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    time::{Instant, SystemTime},
+};
 /// DatabaseSeeder persists data deserialized from specified file.
 /// Internally it keeps record label mapped against its id on insertion. The mapping can be reused
 /// later process to resolve embedded tags.
 ///
 /// NOTE: record names must be unique, otherwise the ealier records will be overwritten by the latter.
 ///
+/// The records are inserted in the order they appear in the seed file.
+///
 /// # Examples
 /// ```rust
 /// use serde::Deserialize;
@@ -51,11 +94,150 @@ use std::future::Future;
 ///     Ok(())
 /// }
 /// ```
-
 pub struct DatabaseSeeder {
     pub filenames: Vec<String>,
     pub base_dir: String,
-    name_resolver: Dict<String>,
+    name_resolver: LabelMap<RefValue>,
+    seeded_records: Vec<SeededRecord>,
+    pub(crate) dry_run: bool,
+    dry_run_report: DryRunReport,
+    /// the labels given placeholder ids in dry-run mode, along with the ids they had before
+    dry_run_placeholders: Vec<(Label, Option<RefValue>)>,
+    manifest: Option<SeedManifest>,
+    pub(crate) observer: Option<SharedObserver>,
+    retry_policy: Option<RetryPolicy>,
+    continue_on_error: bool,
+    failures: Vec<SeedFailure>,
+    registered_files: Mutex<Vec<RegisteredFile>>,
+    resumed_files: HashSet<String>,
+    before_insert_hooks: Vec<BeforeInsertHook>,
+    after_insert_hooks: Vec<AfterInsertHook>,
+    report: SeedReport,
+    file_started_at: Option<Instant>,
+    // the number of the seeded records when the current file has started
+    file_seeded_from: usize,
+    history: Vec<SeedHistoryEntry>,
+    tag_scope: TagScope,
+    scale: usize,
+    strict: bool,
+    concurrency: usize,
+    profile: Option<String>,
+    throttle: Option<Throttle>,
+    namespace_by_file: bool,
+    track_versions: bool,
+    read_options: ReadOptions,
+    // the profile the file being populated is read with, see `populate_with_profile`
+    file_profile: Option<String>,
+    // the names of the parts of the composite keys, by file
+    key_columns: Dict<Vec<String>>,
+    warning_rules: LintRules,
+    warning_label_pattern: Option<Regex>,
+    warnings: Mutex<Vec<LintWarning>>,
+    // the REF keys resolved so far, to tell the unused labels
+    referred_keys: Mutex<HashSet<String>>,
+    // the REF keys resolved by each file, to tell the order the files are torn down in
+    file_refs: Mutex<Dict<HashSet<String>>>,
+    // the values of the ENV and SECRET tags to be masked in the errors and the logs
+    secrets: Mutex<HashSet<String>>,
+    // the files parsed ahead of their seeding, see `populate_all`
+    prefetched: Mutex<Dict<PrefetchedFile>>,
+    // the pool the models are inserted with, see `populate_model`
+    #[cfg(feature = "sqlx")]
+    pub(crate) pool: Option<sqlx::PgPool>,
+}
+
+// the hooks and the observer are shared with the forks of the seeder, see `SharedSeeder`
+type BeforeInsertHook = Arc<Mutex<dyn FnMut(&str, &mut serde_yaml::Value) -> Result<()> + Send>>;
+type AfterInsertHook = Arc<Mutex<dyn FnMut(&str, &RefValue) + Send>>;
+pub(crate) type SharedObserver = Arc<Mutex<dyn SeedObserver + Send>>;
+
+/// the id returned by the loader along with the model, see `populate_async_returning`
+struct Returned<U, M>(U, M);
+
+impl<U, M> ToRefValue for Returned<U, M>
+where
+    U: ToRefValue,
+{
+    fn to_ref_value(&self) -> RefValue {
+        self.0.to_ref_value()
+    }
+
+    fn captures(&self) -> Vec<(String, RefValue)> {
+        self.0.captures()
+    }
+}
+
+/// locks the mutex, ignoring the poisoning: a panicking hook does not leave anything half-updated
+pub(crate) fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// a record persisted by DatabaseSeeder, in the order of insertion
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeededRecord {
+    /// shared by the records of the file
+    pub filename: Arc<str>,
+    pub label: Label,
+    pub id: RefValue,
+}
+
+impl SeededRecord {
+    /// the table the record belongs to, which is assumed to be the file stem of the seed file
+    /// e.g. `fixtures/users.yml` => `users`
+    pub fn table(&self) -> &str {
+        file_table(&self.filename)
+    }
+}
+
+/// the table of the seed file, which is assumed to be its file stem
+pub(crate) fn file_table(filename: &str) -> &str {
+    Path::new(filename)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(filename)
+}
+
+/// the result of an idempotent insertion, see `DatabaseSeeder::populate_idempotent`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SeedOutcome<U> {
+    /// the record has been inserted
+    Inserted(U),
+    /// the record already exists in the database; its id is still available to resolve REFs
+    Exists(U),
+    /// the record has been skipped; REFs to the record cannot be resolved
+    Skipped,
+}
+
+impl<U> SeedOutcome<U> {
+    /// returns the id of the record, unless it has been skipped
+    pub fn id(&self) -> Option<&U> {
+        match self {
+            SeedOutcome::Inserted(id) | SeedOutcome::Exists(id) => Some(id),
+            SeedOutcome::Skipped => None,
+        }
+    }
+
+    /// converts the outcome into the id of the record, unless it has been skipped
+    pub fn into_id(self) -> Option<U> {
+        match self {
+            SeedOutcome::Inserted(id) | SeedOutcome::Exists(id) => Some(id),
+            SeedOutcome::Skipped => None,
+        }
+    }
+}
+
+/// a record that failed to be inserted in continue-on-error mode
+#[derive(Debug)]
+pub struct SeedFailure {
+    pub filename: String,
+    pub label: Label,
+    pub error: anyhow::Error,
+}
+
+impl fmt::Display for SeedFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {:#}", self.label, self.filename, self.error)
+    }
 }
 
 impl Default for DatabaseSeeder {
@@ -69,127 +251,3635 @@ impl DatabaseSeeder {
         Self {
             filenames: Vec::new(),
             base_dir: String::new(),
-            name_resolver: Dict::<String>::new(),
+            name_resolver: LabelMap::<RefValue>::new(),
+            seeded_records: Vec::new(),
+            dry_run: false,
+            dry_run_report: DryRunReport::default(),
+            dry_run_placeholders: Vec::new(),
+            manifest: None,
+            observer: None,
+            retry_policy: None,
+            continue_on_error: false,
+            failures: Vec::new(),
+            registered_files: Mutex::new(Vec::new()),
+            resumed_files: HashSet::new(),
+            before_insert_hooks: Vec::new(),
+            after_insert_hooks: Vec::new(),
+            report: SeedReport::default(),
+            file_started_at: None,
+            file_seeded_from: 0,
+            history: Vec::new(),
+            tag_scope: TagScope::default(),
+            scale: 1,
+            strict: false,
+            concurrency: 1,
+            profile: None,
+            throttle: None,
+            namespace_by_file: false,
+            track_versions: false,
+            read_options: ReadOptions::default(),
+            file_profile: None,
+            key_columns: Dict::new(),
+            warning_rules: LintRules::default(),
+            warning_label_pattern: None,
+            warnings: Mutex::new(Vec::new()),
+            referred_keys: Mutex::new(HashSet::new()),
+            file_refs: Mutex::new(Dict::new()),
+            secrets: Mutex::new(HashSet::new()),
+            prefetched: Mutex::new(Dict::new()),
+            #[cfg(feature = "sqlx")]
+            pool: None,
         }
     }
 
-    pub fn set_dir(&mut self, base_dir: &str) {
-        self.base_dir = base_dir.to_string();
+    /// Creates a builder to configure the seeder at once, see `DatabaseSeederBuilder`.
+    ///
+    /// ```rust
+    /// use cder::{DatabaseSeeder, RetryPolicy};
+    /// # use anyhow::Result;
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let seeder = DatabaseSeeder::builder()
+    ///     .base_dir("fixtures")
+    ///     .strict(true)
+    ///     .concurrency(8)
+    ///     .retry_policy(RetryPolicy::new(3))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder() -> DatabaseSeederBuilder {
+        DatabaseSeederBuilder::new()
+    }
+
+    /// Creates a seeder from the manifest file (see `SeedManifest`), which can be executed
+    /// later with `populate_manifest`.
+    pub fn from_manifest(path: &str) -> Result<Self> {
+        let manifest = SeedManifest::load(path)?;
+
+        let mut seeder = Self::new();
+        seeder.set_dir(manifest.base_dir.as_deref().unwrap_or_default());
+        seeder.manifest = Some(manifest);
+        Ok(seeder)
+    }
+
+    /// Creates a seeder from the state saved by `save_state`, so that an interrupted seeding run
+    /// can continue where it stopped: the ids of the records are preloaded to resolve REFs, and
+    /// the files that have already been processed are skipped by the populate methods (which
+    /// return no ids for them).
+    ///
+    /// NOTE: the records inserted before resuming are not subject to `teardown`.
+    ///
+    /// ```rust,no_run
+    /// use cder::DatabaseSeeder;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut seeder = DatabaseSeeder::resume_from("seed_state.json")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resume_from(path: &str) -> Result<Self> {
+        let state = SeedState::load(path)?;
+
+        let mut seeder = Self::new();
+        seeder.restore_state(state);
+        Ok(seeder)
+    }
+
+    /// preloads the ids and the processed files from the saved state, see `resume_from`
+    pub fn restore_state(&mut self, state: SeedState) {
+        self.name_resolver
+            .extend(state.ids.into_iter().map(|(label, id)| (label.into(), id)));
+        self.resumed_files.extend(state.files.iter().cloned());
+        self.filenames.extend(state.files);
+    }
+
+    /// Merges what another seeder has populated (the ids, the seeded records, the processed files,
+    /// the failures and the report) into this seeder, so that REFs resolve across seeders owned by
+    /// independent modules. The merged records are torn down by this seeder afterwards.
+    /// Labels populated by both seeders are taken from the other one, unless strict mode is
+    /// enabled (see `strict`), in which case nothing is merged and an error is returned.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn populate_users() -> Result<DatabaseSeeder> { Ok(DatabaseSeeder::new()) }
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut seeder = DatabaseSeeder::new();
+    ///
+    /// // the users module populates its seeds on its own
+    /// seeder.merge(populate_users()?)?;
+    ///
+    /// // REFs to the users can be resolved from now on
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn merge(&mut self, other: DatabaseSeeder) -> Result<()> {
+        if self.strict {
+            let mut duplicates = other
+                .name_resolver
+                .keys()
+                .filter(|label| self.name_resolver.contains_key(*label))
+                .map(|label| label.as_str())
+                .collect::<Vec<&str>>();
+            if !duplicates.is_empty() {
+                duplicates.sort_unstable();
+                return Err(anyhow::anyhow!(
+                    "labels populated by both seeders: {}",
+                    duplicates.join(", ")
+                ));
+            }
+        }
+
+        self.name_resolver.extend(other.name_resolver);
+        self.seeded_records.extend(other.seeded_records);
+        self.filenames.extend(other.filenames);
+        self.failures.extend(other.failures);
+        lock(&self.warnings).extend(std::mem::take(&mut *lock(&other.warnings)));
+        lock(&self.referred_keys).extend(std::mem::take(&mut *lock(&other.referred_keys)));
+        {
+            let mut file_refs = lock(&self.file_refs);
+            for (filename, refs) in std::mem::take(&mut *lock(&other.file_refs)) {
+                file_refs.entry(filename).or_default().extend(refs);
+            }
+        }
+        lock(&self.secrets).extend(std::mem::take(&mut *lock(&other.secrets)));
+        self.report.files.extend(other.report.files);
+        self.dry_run_report.files.extend(other.dry_run_report.files);
+        self.dry_run_placeholders.extend(other.dry_run_placeholders);
+        self.history.extend(other.history);
+        Ok(())
+    }
+
+    /// creates a seeder with the same configuration and ids, which populates files on its own and
+    /// is joined later, see `SharedSeeder`
+    pub(crate) fn fork(&self) -> Self {
+        Self {
+            base_dir: self.base_dir.clone(),
+            name_resolver: self.name_resolver.clone(),
+            dry_run: self.dry_run,
+            observer: self.observer.clone(),
+            retry_policy: self.retry_policy.clone(),
+            continue_on_error: self.continue_on_error,
+            resumed_files: self.resumed_files.clone(),
+            before_insert_hooks: self.before_insert_hooks.clone(),
+            after_insert_hooks: self.after_insert_hooks.clone(),
+            tag_scope: self.tag_scope.clone(),
+            scale: self.scale,
+            strict: self.strict,
+            concurrency: self.concurrency,
+            profile: self.profile.clone(),
+            throttle: self.throttle.clone(),
+            namespace_by_file: self.namespace_by_file,
+            track_versions: self.track_versions,
+            read_options: self.read_options.clone(),
+            file_profile: self.file_profile.clone(),
+            key_columns: self.key_columns.clone(),
+            warning_rules: self.warning_rules.clone(),
+            warning_label_pattern: self.warning_label_pattern.clone(),
+            #[cfg(feature = "sqlx")]
+            pool: self.pool.clone(),
+            ..Self::new()
+        }
+    }
+
+    /// merges the fork, except for the ids it has inherited and kept as they are
+    pub(crate) fn join(&mut self, mut fork: Self) -> Result<()> {
+        fork.name_resolver
+            .retain(|label, id| self.name_resolver.get(label) != Some(id));
+        self.merge(fork)
+    }
+
+    /// returns the manifest the seeder has been created from
+    pub fn manifest(&self) -> Option<&SeedManifest> {
+        self.manifest.as_ref()
+    }
+
+    /// returns whether the labels of the file are referred to by the other seed files, i.e. the
+    /// entries of the manifest, or else the yaml files in the base directory
+    #[cfg(feature = "sqlx")]
+    pub(crate) fn is_referenced(&self, filename: &str) -> Result<bool> {
+        let others = match &self.manifest {
+            Some(manifest) => manifest
+                .entries_with(self.profile.as_deref(), &self.read_options)?
+                .into_iter()
+                .map(|entry| entry.file)
+                .collect(),
+            None => crate::dependency::yaml_files(&self.base_dir)?,
+        };
+        let others = others
+            .into_iter()
+            .filter(|other| other != filename)
+            .collect::<Vec<String>>();
+        crate::dependency::is_referenced(filename, &others, &self.base_dir, &self.read_options)
     }
 
+    /// Populates the files listed in the manifest that are active with the given profile, in the
+    /// order specified by the manifest. The closure receives each entry and is responsible for
+    /// populating the file with the corresponding model.
+    ///
     /// ```rust
     /// use cder::DatabaseSeeder;
     /// # use serde::Deserialize;
     /// # use anyhow::Result;
     /// #
-    /// # #[derive(Deserialize)] // add this derive macro
+    /// # #[derive(Deserialize)]
     /// # struct User {
     /// #   name: String,
-    /// #   email: String,
     /// # }
-    /// #
-    /// # impl User {
-    /// #   fn insert(input: &User) -> Result<(i64)> {
-    /// #     //
-    /// #     // this function inserts a corresponding User record into table,
-    /// #     // and returns its id when succeeded
-    /// #     //
-    /// #     Ok(1)
-    /// #   }
+    /// # #[derive(Deserialize)]
+    /// # struct Post {
+    /// #   title: String,
     /// # }
     /// #
+    /// # fn insert_user(input: &User) -> Result<i64> { Ok(1) }
+    /// # fn insert_post(input: &Post) -> Result<i64> { Ok(1) }
+    /// #
     /// # fn main() {
     /// #     populate_seeds();
     /// # }
     ///
-    /// async fn populate_seeds() -> Result<()> {
-    ///     let mut seeder = DatabaseSeeder::new();
-    ///
-    /// seeder
-    ///     .populate("fixtures/users.yml", |input| {
-    ///         // this block can contain any non-async functions
-    ///         // but it has to return Result<i64> in the end
-    ///         User::insert(&input)
-    ///     });
+    /// fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::from_manifest("fixtures/seeds.yml")?;
     ///
-    ///     Ok(())
+    ///     seeder.populate_manifest(Some("development"), |seeder, entry| {
+    ///         match entry.model.as_deref() {
+    ///             Some("User") => seeder.populate(&entry.file, |input| insert_user(&input))?,
+    ///             Some("Post") => seeder.populate(&entry.file, |input| insert_post(&input))?,
+    ///             _ => return Err(anyhow::anyhow!("unknown model: {:?}", entry.model)),
+    ///         };
+    ///         Ok(())
+    ///     })
     /// }
     /// ```
-    pub fn populate<F, T, U>(&mut self, filename: &str, mut loader: F) -> Result<Vec<U>>
+    pub fn populate_manifest<F>(&mut self, profile: Option<&str>, mut f: F) -> Result<()>
     where
-        F: FnMut(T) -> Result<U>,
-        T: DeserializeOwned,
-        U: ToString,
+        F: FnMut(&mut Self, &ManifestEntry) -> Result<()>,
     {
-        let named_records = load_named_records::<T>(filename, &self.base_dir, &self.name_resolver)?;
-        let mut ids = Vec::new();
+        let manifest = self
+            .manifest
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("the seeder has not been created from a manifest"))?;
 
-        for (name, record) in named_records {
-            let id = loader(record)?;
-            self.name_resolver.insert(name.clone(), id.to_string());
-            ids.push(id);
+        let profile = profile.or(self.profile.as_deref());
+        for entry in manifest.entries_with(profile, &self.read_options)? {
+            f(self, &entry)?;
         }
-        Ok(ids)
+        Ok(())
+    }
+
+    pub fn set_dir(&mut self, base_dir: &str) {
+        self.base_dir = base_dir.to_string();
     }
 
+    /// Lets the values of the environment variables appear in the errors and the logs. The values
+    /// of `${{ ENV(key) }}` tags (of 4 chars or longer) are otherwise masked with `***`, so that
+    /// e.g. an API key is not dumped into CI logs by a failed parse, while the ones of
+    /// `${{ SECRET(key) }}` tags are masked anyway.
+    ///
     /// ```rust
     /// use cder::DatabaseSeeder;
-    /// # use serde::Deserialize;
+    ///
+    /// let mut seeder = DatabaseSeeder::new();
+    /// seeder.set_unmasked_env(&["DATABASE_HOST", "APP_ENV"]);
+    /// ```
+    pub fn set_unmasked_env(&mut self, keys: &[&str]) {
+        self.tag_scope
+            .unmasked_env
+            .extend(keys.iter().map(|key| key.to_string()));
+    }
+
+    /// Sets the value that `${{ ENV(key) }}` (and `${{ SECRET(key) }}`) tags are replaced with,
+    /// in preference to the environment variable. Unlike `std::env::set_var`, the value is only
+    /// seen by this seeder, so that the tests running in parallel do not affect each other.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    ///
+    /// let mut seeder = DatabaseSeeder::new();
+    /// seeder.set_dir("tests/fixtures");
+    /// seeder.set_env("DEV_EMAIL", "dev@example.org");
+    ///
+    /// let text = seeder.render("customers.yml").unwrap();
+    /// assert!(text.contains("emails: [dev@example.org]"));
+    /// ```
+    pub fn set_env(&mut self, key: &str, value: &str) {
+        self.tag_scope
+            .env
+            .insert(key.to_string(), value.to_string());
+    }
+
+    /// sets the value that `${{ CTX(key) }}` tags are replaced with
+    pub fn set_context<V: ToRefValue>(&mut self, key: &str, value: V) {
+        self.tag_scope
+            .context
+            .insert(key.to_string(), value.to_ref_value());
+    }
+
+    /// Names the parts of the composite keys returned by the loader for the file, e.g. with
+    /// `&["tenant_id", "user_id"]`, a label `Alice` populated with the id `(1, 2)` can be referred
+    /// either as a whole (`${{ REF(Alice) }}` => `[1, 2]`) or part by part
+    /// (`${{ REF(Alice.tenant_id) }}` => `1`, `${{ REF(Alice.user_id) }}` => `2`).
+    /// The parts are named in order; the ones without names are left out.
+    ///
+    /// ```rust
+    /// use cder::{DatabaseSeeder, RefValue};
     /// # use anyhow::Result;
     /// #
-    /// # #[derive(Deserialize)] // add this derive macro
-    /// # struct User {
-    /// #   name: String,
-    /// #   email: String,
-    /// # }
-    /// #
-    /// # impl User {
-    /// #   async fn insert(input: &User) -> Result<(i64)> {
-    /// #     //
-    /// #     // this function inserts a corresponding User record into table,
-    /// #     // and returns its id when succeeded
-    /// #     //
-    /// #     Ok(1)
-    /// #   }
-    /// # }
-    /// #
-    /// # fn main() {
-    /// #     populate_seeds();
-    /// # }
+    /// # fn main() -> Result<()> {
+    /// let mut seeder = DatabaseSeeder::new();
+    /// seeder.set_key_columns("memberships.yml", &["tenant_id", "user_id"]);
+    /// // populates memberships.yml, returning (tenant_id, user_id) from the loader
+    /// // ...
     ///
-    /// async fn populate_seeds() -> Result<()> {
-    ///     let mut seeder = DatabaseSeeder::new();
+    /// // then the memberships can be referred from other files
+    /// // tenant_id: ${{ REF(Alice.tenant_id) }}
+    /// // user_id: ${{ REF(Alice.user_id) }}
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_key_columns(&mut self, filename: &str, columns: &[&str]) {
+        self.key_columns.insert(
+            filename.to_string(),
+            columns.iter().map(|column| column.to_string()).collect(),
+        );
+    }
+
+    /// Inserts every record `scale` times, e.g. to generate volumes of data for load testing.
+    /// `${{ CTX(index) }}` tags are replaced with the index of the copy (starting from 0), so that
+    /// the copies can vary, e.g. `email: user${{ CTX(index) }}@example.com`.
+    /// The base copies (index 0) keep their labels, and REFs refer to them; the others are
+    /// labeled `<label>#<index>`. A scale smaller than 1 is regarded as 1.
+    pub fn with_scale(&mut self, scale: usize) {
+        self.scale = scale.max(1);
+    }
+
+    /// In strict mode, a file fails to be populated when any of its labels has already been
+    /// populated (e.g. by another file), rather than silently overwriting the id REFs resolve to.
+    pub fn strict(&mut self, enabled: bool) {
+        self.strict = enabled;
+    }
+
+    /// When enabled, the labels are prefixed with the file stem of their seed file, e.g. `Alice` in
+    /// `customers.yml` is registered as `customers/Alice`, so that the files can reuse labels such
+    /// as `Default` or `Admin` without overwriting each other's ids. REFs have to be qualified
+    /// accordingly: `${{ REF(customers/Alice) }}`.
+    pub fn namespace_by_file(&mut self, enabled: bool) {
+        self.namespace_by_file = enabled;
+    }
+
+    /// When enabled, the seed files are read as the fixtures of Rails: the ERB expressions reading
+    /// the environment variables (e.g. `<%= ENV['API_KEY'] %>`) are replaced with ENV tags,
+    /// `DEFAULTS` and the merge keys are applied, `$LABEL` is replaced with the label, and an
+    /// association given by label (e.g. `customer: alice`) is replaced with the REF to its id
+    /// (`customer_id: ${{ REF(alice) }}`) when the fixture of the table (`customers.yml`) is found
+    /// next to the file. Any other ERB (e.g. loops) is rejected. The positions of the errors are
+    /// the ones in the translated text, see `render`.
+    pub fn rails_fixtures(&mut self, enabled: bool) {
+        self.read_options.rails = enabled;
+    }
+
+    /// When enabled, the seed files that are JSON arrays are read as the fixtures of Django, i.e.
+    /// `[{"model": "shop.customer", "pk": 1, "fields": {...}}]`: each record is labeled with its
+    /// pk (e.g. `${{ REF(1) }}`), and deserialized from its fields. The other files are read as
+    /// they are. The foreign keys of the fields are not translated, as their pks are not the ids
+    /// of the records populated; REF tags can be written in the fields instead. The positions of
+    /// the errors are the ones in the translated text, see `render`.
+    pub fn django_fixtures(&mut self, enabled: bool) {
+        self.read_options.django = enabled;
+    }
+
+    /// Reads the `.csv` files as the seeds of dbt, each row labeled with the value of the key
+    /// column, see `DbtSeeds`. The other files are read as they are. Available with the `dbt`
+    /// feature.
+    #[cfg(feature = "dbt")]
+    pub fn dbt_seeds(&mut self, seeds: crate::DbtSeeds) {
+        self.read_options.dbt = Some(seeds);
+    }
+
+    /// When enabled, `populate_with` and `populate_many_with` keep track of the seed files applied
+    /// with the adapter, like schema migrations: once a file has been populated without failures,
+    /// it is recorded along with the checksum of its contents (see `SeedAdapter::record_applied`),
+    /// and skipped on the following runs. A file changed since it was applied results in an
+    /// error, rather than being populated twice.
+    /// Note that the ids of the skipped records are not registered: restore them with
+    /// `restore_state` if the other files refer to them.
+    pub fn track_versions(&mut self, enabled: bool) {
+        self.track_versions = enabled;
+    }
+
+    /// Sets how many records `populate_async` inserts at once (1 by default). With more than one,
+    /// `populate_async` works the same as `populate_async_concurrent`, retrying each insertion
+    /// with the retry policy (if any) while the others are in flight.
+    /// A concurrency smaller than 1 is regarded as 1.
+    pub fn set_concurrency(&mut self, concurrency: usize) {
+        self.concurrency = concurrency.max(1);
+    }
+
+    /// Limits the number of records `populate_async` and `populate_async_concurrent` insert per
+    /// second, e.g. for seeding through rate-limited APIs. The insertions are spaced out evenly.
+    /// To limit the number of insertions in flight instead, see `set_concurrency`.
+    /// 0 removes the limit.
+    pub fn set_rate_limit(&mut self, records_per_second: u32) {
+        self.throttle = match records_per_second {
+            0 => None,
+            records_per_second => Some(Throttle::new(records_per_second)),
+        };
+    }
+
+    /// sets the profile used by `populate_manifest` and `clean_manifest` when none is given
+    pub fn set_profile(&mut self, profile: &str) {
+        self.profile = Some(profile.to_string());
+    }
+
+    /// sets the observer that gets notified of the progress, see `SeedObserver`
+    pub fn set_observer<O>(&mut self, observer: O)
+    where
+        O: SeedObserver + Send + 'static,
+    {
+        self.observer = Some(Arc::new(Mutex::new(observer)));
+    }
+
+    /// Registers a hook that is called with the label and the (tag-resolved) value of every record
+    /// before it is deserialized and passed to the loader, so that the records can be modified or
+    /// validated in one place. The file fails to be populated when the hook returns an error.
     ///
-    ///     seeder
-    ///         .populate_async("fixtures/users.yml", |input| {
-    ///             async move { User::insert(&input).await }
-    ///         })
-    ///         .await?;
+    /// ```rust
+    /// use cder::DatabaseSeeder;
     ///
+    /// let mut seeder = DatabaseSeeder::new();
+    /// seeder.before_insert(|label, record| {
+    ///     if let Some(email) = record.get_mut("email") {
+    ///         *email = format!("{}@example.com", label.to_lowercase()).into();
+    ///     }
     ///     Ok(())
-    /// }
+    /// });
     /// ```
-    pub async fn populate_async<Fut, F, T, U>(
-        &mut self,
-        filename: &str,
-        mut loader: F,
-    ) -> Result<Vec<U>>
+    pub fn before_insert<F>(&mut self, hook: F)
     where
-        Fut: Future<Output = Result<U>>,
-        F: FnMut(T) -> Fut,
-        T: DeserializeOwned,
-        U: ToString,
+        F: FnMut(&str, &mut serde_yaml::Value) -> Result<()> + Send + 'static,
     {
-        let named_records = load_named_records::<T>(filename, &self.base_dir, &self.name_resolver)?;
-        self.filenames.push(filename.to_string());
+        self.before_insert_hooks.push(Arc::new(Mutex::new(hook)));
+    }
 
-        let mut ids = Vec::new();
+    /// Registers a provider of the fields computed at insert time, such as a tenant id, the user
+    /// seeding the records or a trace id, which are specific to the run and cannot be written in
+    /// the fixtures. The provider is called with the label and the (tag-resolved) value of every
+    /// record, and the fields it returns are added to the record before it is deserialized and
+    /// passed to the loader or the adapter. The fields defined in the fixture are kept as they are.
+    ///
+    /// ```rust
+    /// use cder::{DatabaseSeeder, ToRefValue};
+    ///
+    /// let trace_id = "2c1e6ad7".to_string();
+    ///
+    /// let mut seeder = DatabaseSeeder::new();
+    /// seeder.computed_fields(move |_label, _record| {
+    ///     vec![
+    ///         ("created_by".to_string(), "seeder".to_ref_value()),
+    ///         ("trace_id".to_string(), trace_id.to_ref_value()),
+    ///     ]
+    /// });
+    /// ```
+    pub fn computed_fields<F>(&mut self, mut provider: F)
+    where
+        F: FnMut(&str, &serde_yaml::Value) -> Vec<(String, RefValue)> + Send + 'static,
+    {
+        self.before_insert(move |label, record| {
+            let fields = provider(label, record);
+            let mapping = record.as_mapping_mut().ok_or_else(|| {
+                anyhow::anyhow!("fields cannot be computed for a record other than a mapping")
+            })?;
+            for (field, value) in fields {
+                if !mapping.contains_key(field.as_str()) {
+                    mapping.insert(field.into(), serde_yaml::to_value(value)?);
+                }
+            }
+            Ok(())
+        });
+    }
 
-        for (name, record) in named_records {
-            let id = loader(record).await?;
-            self.name_resolver.insert(name.clone(), id.to_string());
-            ids.push(id);
+    /// Registers a hook that is called with the label and the id of every record that has been
+    /// inserted, e.g. for audit logging.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    ///
+    /// let mut seeder = DatabaseSeeder::new();
+    /// seeder.after_insert(|label, id| println!("inserted {}: {}", label, id));
+    /// ```
+    pub fn after_insert<F>(&mut self, hook: F)
+    where
+        F: FnMut(&str, &RefValue) + Send + 'static,
+    {
+        self.after_insert_hooks.push(Arc::new(Mutex::new(hook)));
+    }
+
+    /// sets the policy to retry failed insertions in `populate` and `populate_async`,
+    /// see `RetryPolicy`
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = Some(retry_policy);
+    }
+
+    /// When enabled, a record that fails to be inserted does not abort the seeding; the failure
+    /// is collected instead, and can be inspected with `failures` (or `check_failures`) at the end.
+    /// Note that the files referring to the failed records still fail to resolve their REFs.
+    pub fn continue_on_error(&mut self, enabled: bool) {
+        self.continue_on_error = enabled;
+    }
+
+    /// returns the failures collected in continue-on-error mode
+    pub fn failures(&self) -> &[SeedFailure] {
+        &self.failures
+    }
+
+    /// returns an error summarizing the failures collected in continue-on-error mode, if any,
+    /// e.g. "999 succeeded, 3 failed: ..."
+    pub fn check_failures(&self) -> Result<()> {
+        if self.failures.is_empty() {
+            return Ok(());
+        }
+
+        let failures = self
+            .failures
+            .iter()
+            .map(|failure| format!("  {}", failure))
+            .collect::<Vec<String>>();
+        Err(anyhow::anyhow!(
+            "{} succeeded, {} failed:\n{}",
+            self.seeded_records.len(),
+            self.failures.len(),
+            failures.join("\n")
+        ))
+    }
+
+    /// Sets the rules the records are checked against while they are populated, see `warnings`.
+    /// By default, only the unused labels are reported (see `LintRules`). Fails when the
+    /// label_pattern is not a valid regex.
+    pub fn set_warning_rules(&mut self, rules: LintRules) -> Result<()> {
+        self.warning_label_pattern = label_pattern(&rules)?;
+        self.warning_rules = rules;
+        Ok(())
+    }
+
+    /// Returns the warnings on the files populated so far, which do not fail the seeding:
+    /// - `defaults_used`: the ENV and CTX tags that have fallen back to their default values
+    /// - `unused_labels`: the labels no REF has referred to, within the files some of whose labels
+    ///   have been referred to
+    /// - `label_pattern`, `max_fields`, `max_record_bytes` and `max_value_len`: the records that
+    ///   break the rules given with `set_warning_rules`
+    ///
+    /// ```rust
+    /// use cder::{DatabaseSeeder, LintRules};
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)]
+    /// # struct Item {
+    /// #   name: String,
+    /// #   price: f64,
+    /// # }
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut seeder = DatabaseSeeder::new();
+    /// seeder.set_dir("tests/fixtures");
+    /// seeder.set_warning_rules(LintRules {
+    ///     max_value_len: Some(5),
+    ///     ..LintRules::default()
+    /// })?;
+    ///
+    /// seeder.populate("items.yml", |_: Item| Ok(1))?;
+    ///
+    /// let warnings = seeder.warnings();
+    /// assert_eq!(
+    ///     warnings[0].to_string(),
+    ///     "items.yml: Orange: the value of `name` is 6 chars long, exceeding the limit of 5 [max_value_len]"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn warnings(&self) -> Vec<LintWarning> {
+        let mut warnings = lock(&self.warnings).clone();
+        if !self.warning_rules.unused_labels {
+            return warnings;
+        }
+
+        let referred_keys = lock(&self.referred_keys);
+        // the captured columns (`<label>.<column>`) refer to the label
+        let is_referred = |label: &str| {
+            referred_keys
+                .iter()
+                .any(|key| key.split('.').next() == Some(label))
+        };
+        let mut files = Vec::<(&str, Vec<&str>)>::new();
+        for record in &self.seeded_records {
+            // the copies of the scaled records are not meant to be referred to
+            if record.label.contains('#') {
+                continue;
+            }
+            match files
+                .iter_mut()
+                .find(|(filename, _)| *filename == &*record.filename)
+            {
+                Some((_, labels)) => labels.push(&record.label),
+                None => files.push((&record.filename, vec![&record.label])),
+            }
+        }
+        for (filename, labels) in files {
+            if labels.iter().any(|label| is_referred(label)) {
+                warnings.extend(
+                    labels
+                        .iter()
+                        .filter(|label| !is_referred(label))
+                        .map(|label| unused_label_warning(filename, label)),
+                );
+            }
+        }
+        warnings
+    }
+
+    /// In dry-run mode, the seeder resolves tags and deserializes every file, but does not invoke
+    /// the loaders. Each record is given a sequential placeholder id (1, 2, 3...) instead, so that
+    /// the REFs from the subsequent files can still be checked. The REFs to the placeholders are
+    /// typed by the fields they appear in: integers, or uuids (`00000000-...-000000000001`) for
+    /// the fields that take uuids. The placeholders are removed when dry-run mode is turned off.
+    /// What would have been inserted is available from `dry_run_report`.
+    pub fn dry_run(&mut self, enabled: bool) {
+        self.dry_run = enabled;
+        if !enabled {
+            while let Some((label, id)) = self.dry_run_placeholders.pop() {
+                match id {
+                    Some(id) => self.name_resolver.insert(label, id),
+                    None => self.name_resolver.remove(&label),
+                };
+            }
         }
-        Ok(ids)
     }
+
+    /// returns the summary of the files processed in dry-run mode
+    pub fn dry_run_report(&self) -> &DryRunReport {
+        &self.dry_run_report
+    }
+
+    /// Returns the statistics of the files populated so far (records parsed, inserted, skipped,
+    /// failed, and the wall time), which can be printed or serialized.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    ///
+    /// let seeder = DatabaseSeeder::new();
+    /// // populates the seeds
+    /// // ...
+    ///
+    /// println!("{}", seeder.report());
+    /// ```
+    pub fn report(&self) -> &SeedReport {
+        &self.report
+    }
+
+    /// Returns the files processed so far in the order they have finished, each with the number
+    /// of the records inserted, the time it has finished, and its status (see `SeedStatus`).
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    ///
+    /// let seeder = DatabaseSeeder::new();
+    /// // populates the seeds
+    /// // ...
+    ///
+    /// for entry in seeder.history() {
+    ///     println!("{}", entry); // e.g. "users.yml: completed (2 records)"
+    /// }
+    /// ```
+    pub fn history(&self) -> &[SeedHistoryEntry] {
+        &self.history
+    }
+
+    /// returns the id of the record that has been persisted under the given label
+    pub fn get_id(&self, label: &str) -> Option<&RefValue> {
+        self.name_resolver.get(label)
+    }
+
+    /// returns all the ids of the persisted records, mapped against their labels
+    pub fn get_ids(&self) -> &LabelMap<RefValue> {
+        &self.name_resolver
+    }
+
+    /// returns the files processed so far along with the ids of the persisted records. The files
+    /// some of whose records have failed (in continue-on-error mode) are left out, so that they are
+    /// populated again when resumed (see `resume_from`), as they are not recorded as applied either
+    pub fn state(&self) -> SeedState {
+        SeedState {
+            files: self
+                .filenames
+                .iter()
+                .filter(|filename| {
+                    self.history
+                        .iter()
+                        .rfind(|entry| entry.filename == **filename)
+                        .is_none_or(|entry| entry.status != SeedStatus::CompletedWithFailures)
+                })
+                .cloned()
+                .collect(),
+            ids: self
+                .name_resolver
+                .iter()
+                .map(|(label, id)| (label.to_string(), id.clone()))
+                .collect(),
+        }
+    }
+
+    /// Writes the state of the seeder (the processed files and the label→id mappings) into the
+    /// specified json file, so that the ids outlive the seeding process.
+    ///
+    /// ```rust,no_run
+    /// use cder::DatabaseSeeder;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn main() -> Result<()> {
+    /// let mut seeder = DatabaseSeeder::new();
+    /// // populates the seeds
+    /// // ...
+    ///
+    /// seeder.save_state("seed_state.json")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn save_state(&self, path: &str) -> Result<()> {
+        self.state().save(path)
+    }
+
+    /// Sorts the given files so that each file comes after the files that define the records it
+    /// refers to with REF tags, e.g. `["orders.yml", "items.yml"]` => `["items.yml", "orders.yml"]`.
+    /// Returns an error when the files refer to each other circularly.
+    pub fn sort_by_dependencies(&self, filenames: &[&str]) -> Result<Vec<String>> {
+        let filenames = filenames
+            .iter()
+            .map(|filename| filename.to_string())
+            .collect::<Vec<String>>();
+        sort_by_dependencies(&filenames, &self.base_dir, &self.read_options)
+    }
+
+    /// Returns the graph of the records in the given files and the records they refer to with REF
+    /// tags. The files are sorted in the order they are populated (see `sort_by_dependencies`).
+    pub fn dependency_graph(&self, filenames: &[&str]) -> Result<DependencyGraph> {
+        let filenames = self.sort_by_dependencies(filenames)?;
+        DependencyGraph::read(&filenames, &self.base_dir, &self.read_options)
+    }
+
+    /// Checks the given files against the conventions of the rules, e.g. the naming of the labels
+    /// or the labels never referred to, and returns the warnings. Unlike `validate_files`, the
+    /// files breaking the rules can still be populated.
+    ///
+    /// ```rust
+    /// use cder::{DatabaseSeeder, LintRules};
+    ///
+    /// let mut seeder = DatabaseSeeder::new();
+    /// seeder.set_dir("tests/fixtures");
+    ///
+    /// let rules = LintRules {
+    ///     label_pattern: Some("^[A-Z][a-z]+$".to_string()),
+    ///     ..LintRules::default()
+    /// };
+    /// let warnings = seeder.lint(&["customers.yml", "orders.yml"], &rules).unwrap();
+    /// // Order1, ... do not match the pattern
+    /// assert!(warnings
+    ///     .iter()
+    ///     .any(|warning| warning.to_string() == "orders.yml: Order1: the label does not match `^[A-Z][a-z]+$` [label_pattern]"));
+    /// ```
+    pub fn lint(&self, filenames: &[&str], rules: &LintRules) -> Result<Vec<LintWarning>> {
+        let filenames = filenames
+            .iter()
+            .map(|filename| filename.to_string())
+            .collect::<Vec<String>>();
+        lint_files(&filenames, &self.base_dir, &self.read_options, rules)
+    }
+
+    /// Checks, without inserting anything, that every REF in the given files refers either to a
+    /// record defined by the files populated earlier (see `sort_by_dependencies`) or to a record
+    /// the seeder has already populated. Returns an error listing all the unresolved references,
+    /// e.g. `users.yml: REF(Compnay1)`.
+    pub fn validate_refs(&self, filenames: &[&str]) -> Result<()> {
+        let filenames = filenames
+            .iter()
+            .map(|filename| filename.to_string())
+            .collect::<Vec<String>>();
+        self.validate_ref_keys(&filenames)
+    }
+
+    fn validate_ref_keys(&self, filenames: &[String]) -> Result<()> {
+        validate_refs(filenames, &self.base_dir, &self.read_options, |key| {
+            self.is_populated(key)
+        })
+    }
+
+    /// returns true when the REF key refers to a record populated so far
+    fn is_populated(&self, key: &str) -> bool {
+        self.name_resolver.contains_key(key)
+            || self.tag_scope.namespace.as_ref().is_some_and(|namespace| {
+                self.name_resolver
+                    .contains_key(format!("{}/{}", namespace, key).as_str())
+            })
+    }
+
+    /// Creates a factory (see `Factory`) from the record of the label in the seed file, with the
+    /// tags resolved as when the file is populated. The fields of the record can be overridden
+    /// before it is inserted, e.g. to insert variants of a record defined in the file.
+    pub fn factory<T>(&self, filename: &str, label: &str) -> Result<Factory<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let mut scope = self.tag_scope.clone();
+        scope.context.insert("index".to_string(), RefValue::Int(0));
+        let record = load_named_records::<T, _>(
+            filename,
+            &self.base_dir,
+            &self.read_options,
+            &self.name_resolver,
+            &scope,
+        )
+        .map_err(|err| self.mask_error(err))?
+        .into_iter()
+        .find_map(|(record_label, record)| (record_label == label).then_some(record))
+        .ok_or_else(|| {
+            anyhow::anyhow!("no record is labeled `{}` in the file: {}", label, filename)
+        })?;
+        Ok(Factory::new(filename, label, record))
+    }
+
+    /// Returns the text of the seed file with its tags resolved, i.e. the document that is
+    /// deserialized when the file is populated: REFs are replaced with the ids of the records
+    /// populated so far (or restored with `restore_state`), and CTX tags with the context values.
+    /// The overlay of the profile, if any, is not applied.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    ///
+    /// let mut seeder = DatabaseSeeder::new();
+    /// seeder.set_dir("tests/fixtures");
+    ///
+    /// let text = seeder.render("items.yml").unwrap();
+    /// assert!(text.contains("name: melon"));
+    /// ```
+    pub fn render(&self, filename: &str) -> Result<String> {
+        let raw_text = read_seed_file(filename, &self.base_dir, &self.read_options)?;
+        let mut scope = self.tag_scope.clone();
+        scope.context.insert("index".to_string(), RefValue::Int(0));
+        resolve_text(filename, &raw_text, &self.name_resolver, &scope)
+            .map(|resolved| resolved.text.into_owned())
+    }
+
+    /// Checks the given files without inserting anything, in the order they would be populated
+    /// (e.g. the order of the manifest, or the one of `sort_by_dependencies`). Returns an error
+    /// listing all the problems found at once: the files that cannot be read or parsed, the
+    /// malformed tags, the labels defined twice in a file, and the REFs to the records that are
+    /// neither defined by the earlier files nor populated by the seeder beforehand.
+    /// In strict mode (see `strict`), the labels that have already been defined are reported too.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    ///
+    /// let mut seeder = DatabaseSeeder::new();
+    /// seeder.set_dir("tests/fixtures");
+    ///
+    /// assert!(seeder.validate_files(&["items.yml", "customers.yml", "orders.yml"]).is_ok());
+    /// // orders refer to the customers
+    /// assert!(seeder.validate_files(&["orders.yml", "customers.yml"]).is_err());
+    /// ```
+    pub fn validate_files(&self, filenames: &[&str]) -> Result<()> {
+        let filenames = filenames
+            .iter()
+            .map(|filename| filename.to_string())
+            .collect::<Vec<String>>();
+        let problems = validate_files(
+            &filenames,
+            &self.base_dir,
+            &self.read_options,
+            self.strict,
+            |label| self.is_populated(label),
+        )
+        .into_iter()
+        .flat_map(|file| {
+            file.errors
+                .into_iter()
+                .map(move |error| format!("{}: {}", file.filename, error.message))
+        })
+        .collect::<Vec<String>>();
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+        Err(anyhow::anyhow!(
+            "{} problem(s) found:\n   {}",
+            problems.len(),
+            problems.join("\n   ")
+        ))
+    }
+
+    /// Checks the given files like `validate_files`, and returns the problems file by file, along
+    /// with the warnings of the warning rules (see `set_warning_rules`) and the statistics of the
+    /// files. The warnings are checked only when no file has errors.
+    /// The report can be serialized into json, e.g. for CI dashboards.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    ///
+    /// let mut seeder = DatabaseSeeder::new();
+    /// seeder.set_dir("tests/fixtures");
+    ///
+    /// let report = seeder.validate(&["orders.yml", "customers.yml"]);
+    /// assert!(!report.is_valid());
+    /// // orders refer to the customers
+    /// assert_eq!(report.files[0].errors[0].code, Some("CDER001"));
+    /// assert_eq!(report.files[1].stats.records, 3);
+    ///
+    /// let json = serde_json::to_string(&report).unwrap();
+    /// assert!(json.contains(r#""filename":"orders.yml""#));
+    /// ```
+    pub fn validate(&self, filenames: &[&str]) -> ValidationReport {
+        let filenames = filenames
+            .iter()
+            .map(|filename| filename.to_string())
+            .collect::<Vec<String>>();
+        let mut report = ValidationReport {
+            files: validate_files(
+                &filenames,
+                &self.base_dir,
+                &self.read_options,
+                self.strict,
+                |label| self.is_populated(label),
+            ),
+        };
+
+        if report.is_valid() {
+            if let Ok(warnings) = lint_files(
+                &filenames,
+                &self.base_dir,
+                &self.read_options,
+                &self.warning_rules,
+            ) {
+                for warning in warnings {
+                    if let Some(file) = report
+                        .files
+                        .iter_mut()
+                        .find(|file| file.filename == warning.filename)
+                    {
+                        file.warnings.push(warning);
+                    }
+                }
+            }
+        }
+        report
+    }
+
+    /// returns all the records persisted so far, in the order of insertion
+    pub fn seeded_records(&self) -> &[SeededRecord] {
+        &self.seeded_records
+    }
+
+    /// Deletes all the persisted records, so that the records are removed before the ones they
+    /// depend on: the files are torn down in the reverse order of their REF dependencies, and the
+    /// records of each file in the reverse order of insertion. Therefore the order holds even when
+    /// the records have been inserted in another order, e.g. by the seeders combined with `merge`.
+    /// The dependencies are the REFs recorded when the files were populated, so the seed files are
+    /// not read again. The deleter receives the table name and the id of each record: the table is
+    /// the one specified in the manifest (see `ManifestEntry::table`) when the seeder has been
+    /// created from one, or else the file stem (see `SeededRecord::table`).
+    /// The records that have been deleted are forgotten by the seeder; when the deleter fails,
+    /// the remaining records are kept so that teardown can be retried.
+    ///
+    /// ```rust
+    /// use cder::{DatabaseSeeder, RefValue};
+    /// # use anyhow::Result;
+    /// #
+    /// # fn delete_row(table: &str, id: &RefValue) -> Result<()> {
+    /// #     // DELETE FROM {table} WHERE id = {id}
+    /// #     Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     cleanup();
+    /// # }
+    ///
+    /// fn cleanup() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///     // populates the seeds
+    ///     // ...
+    ///
+    ///     seeder.teardown(|table, id| delete_row(table, id))
+    /// }
+    /// ```
+    pub fn teardown<F>(&mut self, mut deleter: F) -> Result<()>
+    where
+        F: FnMut(&str, &RefValue) -> Result<()>,
+    {
+        for filename in self.teardown_order()? {
+            while let Some(index) = self.last_record_of(&filename) {
+                let record = &self.seeded_records[index];
+                deleter(self.record_table(record), &record.id)?;
+                self.forget_record(index);
+            }
+        }
+        Ok(())
+    }
+
+    /// works the same as `teardown`, but with an async deleter
+    ///
+    /// ```rust
+    /// use cder::{DatabaseSeeder, RefValue};
+    /// # use anyhow::Result;
+    /// #
+    /// # async fn delete_row(table: String, id: RefValue) -> Result<()> {
+    /// #     // DELETE FROM {table} WHERE id = {id}
+    /// #     Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     cleanup();
+    /// # }
+    ///
+    /// async fn cleanup() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///     // populates the seeds
+    ///     // ...
+    ///
+    ///     seeder
+    ///         .teardown_async(|table, id| async move { delete_row(table, id).await })
+    ///         .await
+    /// }
+    /// ```
+    pub async fn teardown_async<Fut, F>(&mut self, mut deleter: F) -> Result<()>
+    where
+        Fut: Future<Output = Result<()>>,
+        F: FnMut(String, RefValue) -> Fut,
+    {
+        for filename in self.teardown_order()? {
+            while let Some(index) = self.last_record_of(&filename) {
+                let record = &self.seeded_records[index];
+                let table = self.record_table(record).to_string();
+                deleter(table, record.id.clone()).await?;
+                self.forget_record(index);
+            }
+        }
+        Ok(())
+    }
+
+    /// Empties the tables of the given files before seeding, in the reverse order of their REF
+    /// dependencies, so that the referencing tables are emptied before the referenced ones.
+    /// The table of a file is assumed to be its file stem, e.g. `fixtures/users.yml` => `users`.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn truncate_table(table: &str) -> Result<()> {
+    /// #     // TRUNCATE TABLE {table}
+    /// #     Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     clean();
+    /// # }
+    ///
+    /// fn clean() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///     seeder.set_dir("fixtures");
+    ///
+    ///     // truncates orders first, as it refers to users
+    ///     seeder.clean(&["users.yml", "orders.yml"], |table| truncate_table(table))
+    /// }
+    /// ```
+    pub fn clean<F>(&self, filenames: &[&str], mut truncate: F) -> Result<()>
+    where
+        F: FnMut(&str) -> Result<()>,
+    {
+        for filename in self.sort_by_dependencies(filenames)?.iter().rev() {
+            truncate(file_table(filename))?;
+        }
+        Ok(())
+    }
+
+    /// works the same as `clean`, but with an async truncate function
+    pub async fn clean_async<Fut, F>(&self, filenames: &[&str], mut truncate: F) -> Result<()>
+    where
+        Fut: Future<Output = Result<()>>,
+        F: FnMut(String) -> Fut,
+    {
+        for filename in self.sort_by_dependencies(filenames)?.iter().rev() {
+            truncate(file_table(filename).to_string()).await?;
+        }
+        Ok(())
+    }
+
+    /// Empties the tables listed in the manifest that are active with the given profile, in the
+    /// reverse order they are populated in. The tables are the ones specified in the manifest, or
+    /// the file stems of the seed files.
+    pub fn clean_manifest<F>(&self, profile: Option<&str>, mut truncate: F) -> Result<()>
+    where
+        F: FnMut(&str) -> Result<()>,
+    {
+        let manifest = self
+            .manifest
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("the seeder has not been created from a manifest"))?;
+
+        let profile = profile.or(self.profile.as_deref());
+        for entry in manifest
+            .entries_with(profile, &self.read_options)?
+            .iter()
+            .rev()
+        {
+            truncate(entry.table())?;
+        }
+        Ok(())
+    }
+
+    /// Discards all the label→id mappings, so that REFs to the records populated so far fail to
+    /// resolve. The records are still tracked, and can be torn down with `teardown`.
+    pub fn clear_refs(&mut self) {
+        self.name_resolver.clear();
+    }
+
+    /// Runs the given closure (that usually populates one or more files), then discards the
+    /// label→id mappings registered (or overwritten) inside it, e.g. so that the labels seeded by
+    /// one test case do not mask missing REFs in the next one. The records are still tracked, and
+    /// can be torn down with `teardown`.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)]
+    /// # struct User {
+    /// #   name: String,
+    /// # }
+    /// #
+    /// # fn insert_user(input: User) -> Result<i64> { Ok(1) }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///
+    ///     seeder.scope(|seeder| -> Result<()> {
+    ///         seeder.populate("fixtures/users.yml", insert_user)?;
+    ///         // REFs to the users can be resolved here
+    ///         Ok(())
+    ///     })?;
+    ///
+    ///     // but not here
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn scope<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        let name_resolver = self.name_resolver.clone();
+        let result = f(self);
+        self.name_resolver = name_resolver;
+        result
+    }
+
+    /// works the same as `scope`, but with an async closure, e.g.
+    /// `seeder.scope_async(|seeder| Box::pin(async move { ... }))`
+    pub async fn scope_async<F, R>(&mut self, f: F) -> R
+    where
+        F: for<'a> FnOnce(&'a mut Self) -> BoxFuture<'a, R>,
+    {
+        let name_resolver = self.name_resolver.clone();
+        let result = f(self).await;
+        self.name_resolver = name_resolver;
+        result
+    }
+
+    /// Saves the current state of the seeder (populated files and their ids), which can be
+    /// restored with `rollback_to` when the records inserted afterwards have been rolled back.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)] // add this derive macro
+    /// # struct User {
+    /// #   name: String,
+    /// #   email: String,
+    /// # }
+    /// #
+    /// # // a (cheaply clonable) handle of the transaction
+    /// # #[derive(Clone)]
+    /// # struct Tx;
+    /// # impl Tx {
+    /// #   async fn begin() -> Result<Tx> { Ok(Tx) }
+    /// #   async fn insert(&self, input: &User) -> Result<i64> { Ok(1) }
+    /// #   async fn commit(self) -> Result<()> { Ok(()) }
+    /// #   async fn rollback(self) -> Result<()> { Ok(()) }
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// async fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///     let tx = Tx::begin().await?;
+    ///     let checkpoint = seeder.checkpoint();
+    ///
+    ///     let result = seeder
+    ///         .populate_async("fixtures/users.yml", |input| {
+    ///             let tx = tx.clone();
+    ///             async move { tx.insert(&input).await }
+    ///         })
+    ///         .await;
+    ///
+    ///     match result {
+    ///         Ok(_) => tx.commit().await,
+    ///         Err(err) => {
+    ///             tx.rollback().await?;
+    ///             seeder.rollback_to(checkpoint);
+    ///             Err(err)
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            filenames_len: self.filenames.len(),
+            seeded_records_len: self.seeded_records.len(),
+            failures_len: self.failures.len(),
+            report_len: self.report.files.len(),
+            history_len: self.history.len(),
+            warnings_len: lock(&self.warnings).len(),
+            dry_run_files_len: self.dry_run_report.files.len(),
+            dry_run_placeholders_len: self.dry_run_placeholders.len(),
+            name_resolver: self.name_resolver.clone(),
+            referred_keys: lock(&self.referred_keys).clone(),
+            file_refs: lock(&self.file_refs).clone(),
+            secrets: lock(&self.secrets).clone(),
+        }
+    }
+
+    /// Restores the state of the seeder saved by `checkpoint`. The files populated since are
+    /// removed from the report, the history, the warnings and the dry-run report as well, and
+    /// their REFs and secrets are forgotten.
+    pub fn rollback_to(&mut self, checkpoint: Checkpoint) {
+        self.filenames.truncate(checkpoint.filenames_len);
+        self.seeded_records.truncate(checkpoint.seeded_records_len);
+        self.failures.truncate(checkpoint.failures_len);
+        self.report.files.truncate(checkpoint.report_len);
+        self.history.truncate(checkpoint.history_len);
+        lock(&self.warnings).truncate(checkpoint.warnings_len);
+        self.dry_run_report
+            .files
+            .truncate(checkpoint.dry_run_files_len);
+        self.dry_run_placeholders
+            .truncate(checkpoint.dry_run_placeholders_len);
+        self.name_resolver = checkpoint.name_resolver;
+        *lock(&self.referred_keys) = checkpoint.referred_keys;
+        *lock(&self.file_refs) = checkpoint.file_refs;
+        *lock(&self.secrets) = checkpoint.secrets;
+    }
+
+    /// Runs the given closure (that usually populates one or more files) within the transaction.
+    /// The transaction is committed when the closure succeeds, otherwise it is rolled back and the
+    /// seeder forgets all the records populated inside the closure.
+    ///
+    /// ```rust
+    /// use cder::{DatabaseSeeder, Transaction};
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)] // add this derive macro
+    /// # struct User {
+    /// #   name: String,
+    /// #   email: String,
+    /// # }
+    /// #
+    /// # struct Tx;
+    /// # impl Tx {
+    /// #   fn begin() -> Result<Tx> { Ok(Tx) }
+    /// #   fn insert(&mut self, input: &User) -> Result<i64> { Ok(1) }
+    /// # }
+    /// # impl Transaction for Tx {
+    /// #   fn commit(self) -> Result<()> { Ok(()) }
+    /// #   fn rollback(self) -> Result<()> { Ok(()) }
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///
+    ///     seeder.populate_in_transaction(Tx::begin()?, |seeder, tx| {
+    ///         seeder.populate("fixtures/users.yml", |input| tx.insert(&input))?;
+    ///         seeder.populate("fixtures/admins.yml", |input| tx.insert(&input))?;
+    ///         Ok(())
+    ///     })
+    /// }
+    /// ```
+    pub fn populate_in_transaction<X, F, R>(&mut self, mut tx: X, f: F) -> Result<R>
+    where
+        X: Transaction,
+        F: FnOnce(&mut Self, &mut X) -> Result<R>,
+    {
+        let checkpoint = self.checkpoint();
+
+        match f(self, &mut tx) {
+            Ok(result) => {
+                tx.commit()?;
+                Ok(result)
+            }
+            Err(err) => {
+                self.rollback_to(checkpoint);
+                tx.rollback().map_err(|rollback_err| {
+                    anyhow::anyhow!(
+                        "failed to rollback the transaction: {}\n   original err: {}",
+                        rollback_err,
+                        err
+                    )
+                })?;
+                Err(err)
+            }
+        }
+    }
+
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)] // add this derive macro
+    /// # struct User {
+    /// #   name: String,
+    /// #   email: String,
+    /// # }
+    /// #
+    /// # impl User {
+    /// #   fn insert(input: &User) -> Result<(i64)> {
+    /// #     //
+    /// #     // this function inserts a corresponding User record into table,
+    /// #     // and returns its id when succeeded
+    /// #     //
+    /// #     Ok(1)
+    /// #   }
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// async fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///
+    /// seeder
+    ///     .populate("fixtures/users.yml", |input| {
+    ///         // this block can contain any non-async functions
+    ///         // but it has to return Result<i64> in the end
+    ///         User::insert(&input)
+    ///     });
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn populate<F, T, U>(&mut self, filename: &str, mut loader: F) -> Result<Vec<U>>
+    where
+        F: FnMut(T) -> Result<U>,
+        T: DeserializeOwned,
+        U: ToRefValue,
+    {
+        if self.skip_resumed(filename) {
+            return Ok(Vec::new());
+        }
+        if self.dry_run {
+            self.populate_dry_run::<T>(filename)?;
+            return Ok(Vec::new());
+        }
+
+        let (named_records, named_values) = self.load_retryable_records::<T>(filename)?;
+        let total = named_records.len();
+        self.notify_file_start(filename, total);
+        let retry_policy = self.retry_policy.clone();
+        let mut ids = Vec::with_capacity(total);
+
+        for (name, record) in named_records {
+            let value = named_values.as_ref().and_then(|values| values.get(&name));
+            let result = match (&retry_policy, value) {
+                (Some(retry_policy), Some(value)) => retry_policy.run(record, value, &mut loader),
+                _ => loader(record),
+            };
+            match result {
+                Ok(id) => {
+                    self.register_id(filename, name, &id);
+                    ids.push(id);
+                }
+                Err(err) => self.handle_failure(filename, name, err)?,
+            }
+        }
+        self.notify_file_done(filename, total, ids.len());
+        Ok(ids)
+    }
+
+    /// Works the same as `populate`, except that the records are read for the profile (e.g.
+    /// `staging`), so that the environments can share most of their seeds:
+    /// - the overlay file of the profile (`plans.staging.yml` for `plans.yml`), if it exists, is
+    ///   applied on top of the file: the records with the same labels have their fields
+    ///   overwritten, and the others are added
+    /// - the records with `_profiles` (a profile or a sequence of them) are populated only for the
+    ///   listed profiles
+    ///
+    /// Note that `populate` and the others do not take the overlay files nor `_profiles` into
+    /// account.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)]
+    /// # struct Plan {
+    /// #   name: String,
+    /// #   price: i64,
+    /// # }
+    /// #
+    /// # fn insert_plan(input: Plan) -> Result<i64> { Ok(1) }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// // # fixtures/plans.yml
+    /// // Basic:
+    /// //   name: basic
+    /// //   price: 1000
+    /// // Trial:
+    /// //   name: trial
+    /// //   price: 0
+    /// //   _profiles: [development, staging]
+    /// //
+    /// // # fixtures/plans.staging.yml
+    /// // Basic:
+    /// //   price: 1
+    ///
+    /// fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///
+    ///     seeder.populate_with_profile("fixtures/plans.yml", "staging", insert_plan)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn populate_with_profile<F, T, U>(
+        &mut self,
+        filename: &str,
+        profile: &str,
+        loader: F,
+    ) -> Result<Vec<U>>
+    where
+        F: FnMut(T) -> Result<U>,
+        T: DeserializeOwned,
+        U: ToRefValue,
+    {
+        let previous = self.file_profile.replace(profile.to_string());
+        let result = self.populate(filename, loader);
+        self.file_profile = previous;
+        result
+    }
+
+    /// async version of `populate_with_profile`
+    pub async fn populate_async_with_profile<Fut, F, T, U>(
+        &mut self,
+        filename: &str,
+        profile: &str,
+        loader: F,
+    ) -> Result<Vec<U>>
+    where
+        Fut: Future<Output = Result<U>>,
+        F: FnMut(T) -> Fut,
+        T: DeserializeOwned,
+        U: ToRefValue,
+    {
+        let previous = self.file_profile.replace(profile.to_string());
+        let result = self.populate_async(filename, loader).await;
+        self.file_profile = previous;
+        result
+    }
+
+    /// Works the same as `populate`, except that the loader returns the inserted model along with
+    /// its id, e.g. with the defaults or the timestamps generated by the database, so that the
+    /// created rows are handed back without being fetched again.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)]
+    /// # struct User {
+    /// #   name: String,
+    /// # }
+    /// #
+    /// # struct UserRow {
+    /// #   id: i64,
+    /// #   name: String,
+    /// # }
+    /// #
+    /// # fn insert_returning(input: User) -> Result<UserRow> {
+    /// #     Ok(UserRow { id: 1, name: input.name })
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///
+    ///     // INSERT INTO users ... RETURNING *
+    ///     let users = seeder.populate_returning("fixtures/users.yml", |input: User| {
+    ///         let row = insert_returning(input)?;
+    ///         Ok((row.id, row))
+    ///     })?;
+    ///
+    ///     for (id, row) in users {
+    ///         println!("{}: {}", id, row.name);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn populate_returning<F, T, U, M>(
+        &mut self,
+        filename: &str,
+        mut loader: F,
+    ) -> Result<Vec<(U, M)>>
+    where
+        F: FnMut(T) -> Result<(U, M)>,
+        T: DeserializeOwned,
+        U: ToRefValue,
+    {
+        let mut models = Vec::new();
+        let ids = self.populate(filename, |record| {
+            let (id, model) = loader(record)?;
+            models.push(model);
+            Ok(id)
+        })?;
+        Ok(ids.into_iter().zip(models).collect())
+    }
+
+    /// Populates the records along with the child records nested under them (e.g. a customer with
+    /// its `orders`): each parent is inserted first, then its children are inserted with the fresh
+    /// id of the parent in place of `${{ PARENT }}` tags. Returns the ids of the parents, each
+    /// paired with the ids of its children.
+    /// The children are either a sequence, labeled `<parent>/<key>/<index>`, or a mapping,
+    /// labeled `<parent>/<key>/<label>`, so that other files can REF them, e.g.
+    /// `${{ REF(Alice/orders/0) }}`.
+    ///
+    /// ```yaml
+    /// Alice:
+    ///   name: Alice
+    ///   orders:
+    ///     - item: apple
+    ///       customer_id: ${{ PARENT }}
+    ///     - item: melon
+    ///       customer_id: ${{ PARENT }}
+    /// ```
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)]
+    /// # struct Customer {
+    /// #   name: String,
+    /// # }
+    /// # #[derive(Deserialize)]
+    /// # struct Order {
+    /// #   item: String,
+    /// #   customer_id: i64,
+    /// # }
+    /// #
+    /// # fn insert_customer(input: Customer) -> Result<i64> { Ok(1) }
+    /// # fn insert_order(input: Order) -> Result<i64> { Ok(1) }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///
+    ///     seeder.populate_nested(
+    ///         "fixtures/customers.yml",
+    ///         "orders",
+    ///         |input: Customer| insert_customer(input),
+    ///         |input: Order| insert_order(input),
+    ///     )?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn populate_nested<F, G, T, C, U, V>(
+        &mut self,
+        filename: &str,
+        children_key: &str,
+        mut loader: F,
+        mut child_loader: G,
+    ) -> Result<Vec<(U, Vec<V>)>>
+    where
+        F: FnMut(T) -> Result<U>,
+        G: FnMut(C) -> Result<V>,
+        T: DeserializeOwned,
+        C: DeserializeOwned,
+        U: ToRefValue,
+        V: ToRefValue,
+    {
+        if self.skip_resumed(filename) {
+            return Ok(Vec::new());
+        }
+        if self.dry_run {
+            self.populate_dry_run::<serde_yaml::Value>(filename)?;
+            return Ok(Vec::new());
+        }
+
+        let nested_records = self.load_nested_records::<T>(filename, children_key)?;
+        let total = count_nested_records(&nested_records);
+        self.notify_file_start(filename, total);
+
+        let mut inserted = 0;
+        let mut ids = Vec::with_capacity(nested_records.len());
+
+        for nested in nested_records {
+            let id = match loader(nested.record) {
+                Ok(id) => id,
+                // the children of the failed parent are skipped
+                Err(err) => {
+                    self.handle_failure(filename, nested.label, err)?;
+                    continue;
+                }
+            };
+            let parent_id = id.to_ref_value();
+            self.register_id(filename, nested.label, &id);
+            inserted += 1;
+
+            let mut child_ids = Vec::new();
+            for (label, value) in nested.children {
+                let result = deserialize_child::<C>(filename, &label, value, &parent_id)
+                    .and_then(&mut child_loader);
+                match result {
+                    Ok(child_id) => {
+                        self.register_id(filename, label, &child_id);
+                        child_ids.push(child_id);
+                        inserted += 1;
+                    }
+                    Err(err) => self.handle_failure(filename, label, err)?,
+                }
+            }
+            ids.push((id, child_ids));
+        }
+        self.notify_file_done(filename, total, inserted);
+        Ok(ids)
+    }
+
+    /// Populates the same file once per tenant. While a tenant is populated:
+    /// - `${{ CTX(tenant) }}` tags are replaced with the tenant
+    /// - the labels are namespaced with the tenant, e.g. `acme/Alice`
+    /// - `${{ REF(label) }}` tags refer to the records of the same tenant first, then fall back to
+    ///   the records that are not namespaced
+    ///
+    /// The loader receives the tenant along with each record.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)]
+    /// # struct User {
+    /// #   name: String,
+    /// # }
+    /// #
+    /// # fn insert_user(tenant: &str, input: &User) -> Result<i64> { Ok(1) }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///
+    ///     seeder.populate_per_tenant(&["acme", "globex"], "fixtures/users.yml", |tenant, input| {
+    ///         insert_user(&tenant.to_string(), &input)
+    ///     })?;
+    ///     assert!(seeder.get_id("acme/User1").is_some());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn populate_per_tenant<V, F, T, U>(
+        &mut self,
+        tenants: &[V],
+        filename: &str,
+        mut loader: F,
+    ) -> Result<Vec<U>>
+    where
+        V: ToRefValue,
+        F: FnMut(&RefValue, T) -> Result<U>,
+        T: DeserializeOwned,
+        U: ToRefValue,
+    {
+        let mut ids = Vec::new();
+
+        for tenant in tenants {
+            let tenant = tenant.to_ref_value();
+            let mut scope = self.tag_scope.clone();
+            scope.namespace = Some(tenant.to_string());
+            scope.context.insert("tenant".to_string(), tenant.clone());
+
+            let outer_scope = std::mem::replace(&mut self.tag_scope, scope);
+            let result = self.populate(filename, |record| loader(&tenant, record));
+            self.tag_scope = outer_scope;
+            ids.extend(result?);
+        }
+        Ok(ids)
+    }
+
+    /// Works the same as `populate`, except that the loader tells whether the record has been
+    /// inserted, already exists, or has been skipped. This allows seeds to be re-run against a
+    /// non-empty database (e.g. with `INSERT ... ON CONFLICT DO NOTHING`).
+    /// The ids of the existing records are registered so that REFs still resolve, but they are
+    /// never deleted by `teardown`.
+    ///
+    /// ```rust
+    /// use cder::{DatabaseSeeder, SeedOutcome};
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)] // add this derive macro
+    /// # struct User {
+    /// #   name: String,
+    /// #   email: String,
+    /// # }
+    /// #
+    /// # impl User {
+    /// #   fn find_by_email(email: &str) -> Result<Option<i64>> {
+    /// #     Ok(None)
+    /// #   }
+    /// #   fn insert(input: &User) -> Result<i64> {
+    /// #     Ok(1)
+    /// #   }
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///
+    ///     seeder.populate_idempotent("fixtures/users.yml", |input: User| {
+    ///         match User::find_by_email(&input.email)? {
+    ///             Some(id) => Ok(SeedOutcome::Exists(id)),
+    ///             None => User::insert(&input).map(SeedOutcome::Inserted),
+    ///         }
+    ///     })?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn populate_idempotent<F, T, U>(
+        &mut self,
+        filename: &str,
+        mut loader: F,
+    ) -> Result<Vec<SeedOutcome<U>>>
+    where
+        F: FnMut(T) -> Result<SeedOutcome<U>>,
+        T: DeserializeOwned,
+        U: ToRefValue,
+    {
+        if self.skip_resumed(filename) {
+            return Ok(Vec::new());
+        }
+        if self.dry_run {
+            self.populate_dry_run::<T>(filename)?;
+            return Ok(Vec::new());
+        }
+
+        let named_records = self.load_records::<T>(filename)?;
+        let total = named_records.len();
+        self.notify_file_start(filename, total);
+
+        let mut outcomes = Vec::with_capacity(total);
+
+        for (name, record) in named_records {
+            match loader(record) {
+                Ok(outcome) => {
+                    self.register_outcome(filename, name, &outcome);
+                    outcomes.push(outcome);
+                }
+                Err(err) => self.handle_failure(filename, name, err)?,
+            }
+        }
+        let inserted = outcomes
+            .iter()
+            .filter(|outcome| matches!(outcome, SeedOutcome::Inserted(_)))
+            .count();
+        self.notify_file_done(filename, total, inserted);
+        Ok(outcomes)
+    }
+
+    /// Persists records in chunks of (at most) `chunk_size`, so that the loader can issue
+    /// multi-row INSERTs (or COPY) instead of a round trip per record.
+    /// The loader must return the ids in the same order as the records it received.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)] // add this derive macro
+    /// # struct User {
+    /// #   name: String,
+    /// #   email: String,
+    /// # }
+    /// #
+    /// # impl User {
+    /// #   fn bulk_insert(input: &[User]) -> Result<Vec<i64>> {
+    /// #     //
+    /// #     // this function inserts corresponding User records into table at once,
+    /// #     // and returns their ids when succeeded
+    /// #     //
+    /// #     Ok((1..=input.len() as i64).collect())
+    /// #   }
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///
+    ///     seeder
+    ///         .populate_batch("fixtures/users.yml", 1000, |inputs| {
+    ///             // inserts up to 1000 users with a single statement
+    ///             User::bulk_insert(&inputs)
+    ///         });
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn populate_batch<F, T, U>(
+        &mut self,
+        filename: &str,
+        chunk_size: usize,
+        mut loader: F,
+    ) -> Result<Vec<U>>
+    where
+        F: FnMut(Vec<T>) -> Result<Vec<U>>,
+        T: DeserializeOwned,
+        U: ToRefValue,
+    {
+        if chunk_size == 0 {
+            return Err(anyhow::anyhow!(
+                "filename : {} chunk_size must be greater than zero",
+                filename,
+            ));
+        }
+
+        if self.skip_resumed(filename) {
+            return Ok(Vec::new());
+        }
+        if self.dry_run {
+            self.populate_dry_run::<T>(filename)?;
+            return Ok(Vec::new());
+        }
+
+        let named_records = self.load_records::<T>(filename)?;
+        let total = named_records.len();
+        self.notify_file_start(filename, total);
+
+        let mut ids = Vec::with_capacity(total);
+        let mut named_records = named_records.into_iter().peekable();
+
+        while named_records.peek().is_some() {
+            let (names, records): (Vec<String>, Vec<T>) =
+                named_records.by_ref().take(chunk_size).unzip();
+            let chunk_ids = match loader(records) {
+                Ok(chunk_ids) => chunk_ids,
+                Err(err) => {
+                    // the whole chunk is regarded as failed
+                    for name in names {
+                        self.handle_failure(filename, name, anyhow::anyhow!("{:#}", err))?;
+                    }
+                    continue;
+                }
+            };
+
+            if chunk_ids.len() != names.len() {
+                return Err(anyhow::anyhow!(
+                    "filename : {} the loader returned {} ids for {} records",
+                    filename,
+                    chunk_ids.len(),
+                    names.len(),
+                ));
+            }
+
+            for (name, id) in names.into_iter().zip(chunk_ids) {
+                self.register_id(filename, name, &id);
+                ids.push(id);
+            }
+        }
+        self.notify_file_done(filename, total, ids.len());
+        Ok(ids)
+    }
+
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)] // add this derive macro
+    /// # struct User {
+    /// #   name: String,
+    /// #   email: String,
+    /// # }
+    /// #
+    /// # impl User {
+    /// #   async fn insert(input: &User) -> Result<(i64)> {
+    /// #     //
+    /// #     // this function inserts a corresponding User record into table,
+    /// #     // and returns its id when succeeded
+    /// #     //
+    /// #     Ok(1)
+    /// #   }
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// async fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///
+    ///     seeder
+    ///         .populate_async("fixtures/users.yml", |input| {
+    ///             async move { User::insert(&input).await }
+    ///         })
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn populate_async<Fut, F, T, U>(
+        &mut self,
+        filename: &str,
+        mut loader: F,
+    ) -> Result<Vec<U>>
+    where
+        Fut: Future<Output = Result<U>>,
+        F: FnMut(T) -> Fut,
+        T: DeserializeOwned,
+        U: ToRefValue,
+    {
+        if self.concurrency > 1 {
+            return self
+                .populate_async_concurrent(filename, self.concurrency, loader)
+                .await;
+        }
+
+        if self.skip_resumed(filename) {
+            return Ok(Vec::new());
+        }
+        if self.dry_run {
+            self.populate_dry_run::<T>(filename)?;
+            return Ok(Vec::new());
+        }
+
+        let (named_records, named_values) = self.load_retryable_records::<T>(filename)?;
+        let total = named_records.len();
+        self.notify_file_start(filename, total);
+        let retry_policy = self.retry_policy.clone();
+        let mut throttle = self.throttle.clone();
+
+        let mut ids = Vec::with_capacity(total);
+
+        for (name, record) in named_records {
+            if let Some(throttle) = throttle.as_mut() {
+                throttle.wait().await;
+            }
+            let value = named_values.as_ref().and_then(|values| values.get(&name));
+            let result = match (&retry_policy, value) {
+                (Some(retry_policy), Some(value)) => {
+                    retry_policy.run_async(record, value, &mut loader).await
+                }
+                _ => loader(record).await,
+            };
+            match result {
+                Ok(id) => {
+                    self.register_id(filename, name, &id);
+                    ids.push(id);
+                }
+                Err(err) => self.handle_failure(filename, name, err)?,
+            }
+        }
+        self.notify_file_done(filename, total, ids.len());
+        Ok(ids)
+    }
+
+    /// works the same as `populate_returning`, but with an async loader
+    pub async fn populate_async_returning<Fut, F, T, U, M>(
+        &mut self,
+        filename: &str,
+        mut loader: F,
+    ) -> Result<Vec<(U, M)>>
+    where
+        Fut: Future<Output = Result<(U, M)>>,
+        F: FnMut(T) -> Fut,
+        T: DeserializeOwned,
+        U: ToRefValue,
+    {
+        // the models are returned along with the ids, so that they stay in the same order even
+        // when inserted concurrently
+        let returned = self
+            .populate_async(filename, |record| {
+                let future = loader(record);
+                async move {
+                    let (id, model) = future.await?;
+                    Ok(Returned(id, model))
+                }
+            })
+            .await?;
+        Ok(returned
+            .into_iter()
+            .map(|Returned(id, model)| (id, model))
+            .collect())
+    }
+
+    /// works the same as `populate_nested`, but with async loaders
+    pub async fn populate_nested_async<FutF, FutG, F, G, T, C, U, V>(
+        &mut self,
+        filename: &str,
+        children_key: &str,
+        mut loader: F,
+        mut child_loader: G,
+    ) -> Result<Vec<(U, Vec<V>)>>
+    where
+        FutF: Future<Output = Result<U>>,
+        FutG: Future<Output = Result<V>>,
+        F: FnMut(T) -> FutF,
+        G: FnMut(C) -> FutG,
+        T: DeserializeOwned,
+        C: DeserializeOwned,
+        U: ToRefValue,
+        V: ToRefValue,
+    {
+        if self.skip_resumed(filename) {
+            return Ok(Vec::new());
+        }
+        if self.dry_run {
+            self.populate_dry_run::<serde_yaml::Value>(filename)?;
+            return Ok(Vec::new());
+        }
+
+        let nested_records = self.load_nested_records::<T>(filename, children_key)?;
+        let total = count_nested_records(&nested_records);
+        self.notify_file_start(filename, total);
+
+        let mut inserted = 0;
+        let mut ids = Vec::with_capacity(nested_records.len());
+
+        for nested in nested_records {
+            let id = match loader(nested.record).await {
+                Ok(id) => id,
+                // the children of the failed parent are skipped
+                Err(err) => {
+                    self.handle_failure(filename, nested.label, err)?;
+                    continue;
+                }
+            };
+            let parent_id = id.to_ref_value();
+            self.register_id(filename, nested.label, &id);
+            inserted += 1;
+
+            let mut child_ids = Vec::new();
+            for (label, value) in nested.children {
+                let result = match deserialize_child::<C>(filename, &label, value, &parent_id) {
+                    Ok(child) => child_loader(child).await,
+                    Err(err) => Err(err),
+                };
+                match result {
+                    Ok(child_id) => {
+                        self.register_id(filename, label, &child_id);
+                        child_ids.push(child_id);
+                        inserted += 1;
+                    }
+                    Err(err) => self.handle_failure(filename, label, err)?,
+                }
+            }
+            ids.push((id, child_ids));
+        }
+        self.notify_file_done(filename, total, inserted);
+        Ok(ids)
+    }
+
+    /// Works the same as `populate_async`, except that up to `concurrency` insertions run at the
+    /// same time. This is useful for network databases, where the round trip dominates.
+    /// Tags are resolved before any insertion takes place, so the records inside the same file
+    /// cannot REF each other; files that depend on each other still have to be populated in order.
+    /// The insertions start in the order of the records in the file, and may complete in any
+    /// order (a slow one does not keep the others from starting), while the ids are registered
+    /// and returned in the order of the file. When an
+    /// insertion fails, no more insertions are started (unless continue_on_error is enabled), and
+    /// the ones already succeeded are registered (and torn down) as usual before the error is
+    /// returned. The failed insertions are retried with the retry policy (if any) before they
+    /// count as failures.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)] // add this derive macro
+    /// # struct User {
+    /// #   name: String,
+    /// #   email: String,
+    /// # }
+    /// #
+    /// # impl User {
+    /// #   async fn insert(input: &User) -> Result<(i64)> {
+    /// #     //
+    /// #     // this function inserts a corresponding User record into table,
+    /// #     // and returns its id when succeeded
+    /// #     //
+    /// #     Ok(1)
+    /// #   }
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// async fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///
+    ///     seeder
+    ///         .populate_async_concurrent("fixtures/users.yml", 8, |input| {
+    ///             async move { User::insert(&input).await }
+    ///         })
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn populate_async_concurrent<Fut, F, T, U>(
+        &mut self,
+        filename: &str,
+        concurrency: usize,
+        loader: F,
+    ) -> Result<Vec<U>>
+    where
+        Fut: Future<Output = Result<U>>,
+        F: FnMut(T) -> Fut,
+        T: DeserializeOwned,
+        U: ToRefValue,
+    {
+        if concurrency == 0 {
+            return Err(anyhow::anyhow!(
+                "filename : {} concurrency must be greater than zero",
+                filename,
+            ));
+        }
+
+        if self.skip_resumed(filename) {
+            return Ok(Vec::new());
+        }
+        if self.dry_run {
+            self.populate_dry_run::<T>(filename)?;
+            return Ok(Vec::new());
+        }
+
+        let (named_records, named_values) = self.load_retryable_records::<T>(filename)?;
+        let total = named_records.len();
+        self.notify_file_start(filename, total);
+
+        let continue_on_error = self.continue_on_error;
+        let retry_policy = self.retry_policy.clone();
+        let mut throttle = self.throttle.clone();
+        // the loader is shared by the insertions in flight, each of which may call it again on
+        // retries. it is locked only while the future is created, not while it is awaited
+        let loader = Mutex::new(loader);
+        // no more insertions are started after a failure, unless continue_on_error is enabled
+        let stopped = AtomicBool::new(false);
+        let mut named_results = stream::iter(named_records.into_iter().enumerate())
+            .take_while(|_| future::ready(!stopped.load(Ordering::Relaxed)))
+            .then(|named_record| {
+                let wait = throttle.as_mut().map(Throttle::reserve).unwrap_or_default();
+                async move {
+                    wait_for(wait).await;
+                    named_record
+                }
+            })
+            .map(|(index, (name, record))| {
+                let loader = &loader;
+                let stopped = &stopped;
+                let retry = retry_policy
+                    .as_ref()
+                    .zip(named_values.as_ref().and_then(|values| values.get(&name)));
+                async move {
+                    let mut call = |record: T| (*lock(loader))(record);
+                    let result = match retry {
+                        Some((retry_policy, value)) => {
+                            retry_policy.run_async(record, value, &mut call).await
+                        }
+                        None => call(record).await,
+                    };
+                    if result.is_err() && !continue_on_error {
+                        stopped.store(true, Ordering::Relaxed);
+                    }
+                    (index, name, result)
+                }
+            })
+            // a slow insertion does not hold back the ones completed after it
+            .buffer_unordered(concurrency)
+            .collect::<Vec<(usize, String, Result<U>)>>()
+            .await;
+        named_results.sort_unstable_by_key(|(index, _, _)| *index);
+
+        // the records inserted before (or while) the others failed are registered all the same,
+        // so that they can be referred to and torn down
+        let mut ids = Vec::with_capacity(total);
+        let mut failures = Vec::new();
+        for (_, name, result) in named_results {
+            match result {
+                Ok(id) => {
+                    self.register_id(filename, name, &id);
+                    ids.push(id);
+                }
+                Err(err) => failures.push((name, err)),
+            }
+        }
+        if !continue_on_error && failures.len() > 1 {
+            let label = failures[0].0.clone();
+            let messages = failures
+                .iter()
+                .map(|(name, err)| format!("  {}: {:#}", name, err))
+                .collect::<Vec<String>>();
+            let error = anyhow::anyhow!(
+                "{} records failed to be inserted:\n{}",
+                failures.len(),
+                messages.join("\n")
+            );
+            failures = vec![(label, error)];
+        }
+        for (name, err) in failures {
+            self.handle_failure(filename, name, err)?;
+        }
+        self.notify_file_done(filename, total, ids.len());
+        Ok(ids)
+    }
+
+    /// Works the same as `populate_async`, except that the labels and the ids of the inserted
+    /// records are yielded as they complete. The records are inserted one by one as the stream is
+    /// polled, so that the caller can process the ids incrementally or apply backpressure.
+    /// The stream ends after the first error, unless continue_on_error is enabled.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    /// use futures::{pin_mut, StreamExt};
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)]
+    /// # struct User {
+    /// #   name: String,
+    /// # }
+    /// #
+    /// # async fn insert_user(input: &User) -> Result<i64> { Ok(1) }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// async fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///
+    ///     let users = seeder.populate_stream("fixtures/users.yml", |input: User| async move {
+    ///         insert_user(&input).await
+    ///     });
+    ///     pin_mut!(users);
+    ///     while let Some(result) = users.next().await {
+    ///         let (label, id) = result?;
+    ///         println!("inserted {}: {}", label, id);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn populate_stream<'a, Fut, F, T, U>(
+        &'a mut self,
+        filename: &'a str,
+        loader: F,
+    ) -> impl Stream<Item = Result<(String, U)>> + 'a
+    where
+        Fut: Future<Output = Result<U>> + 'a,
+        F: FnMut(T) -> Fut + 'a,
+        T: DeserializeOwned + 'a,
+        U: ToRefValue + 'a,
+    {
+        let loaded = if self.skip_resumed(filename) {
+            Ok(None)
+        } else if self.dry_run {
+            self.populate_dry_run::<T>(filename).map(|_| None)
+        } else {
+            self.load_retryable_records::<T>(filename).map(Some)
+        };
+
+        let (load_error, state) = match loaded {
+            Ok(Some((named_records, named_values))) => {
+                let total = named_records.len();
+                self.notify_file_start(filename, total);
+                let state = PopulateStreamState {
+                    retry_policy: self.retry_policy.clone(),
+                    seeder: self,
+                    filename,
+                    loader,
+                    named_records: named_records.into_iter(),
+                    named_values,
+                    total,
+                    inserted: 0,
+                };
+                (None, Some(state))
+            }
+            Ok(None) => (None, None),
+            Err(err) => (Some(Err(err)), None),
+        };
+
+        let results = stream::unfold(state, |state| async move {
+            let mut state = state?;
+            loop {
+                let Some((name, record)) = state.named_records.next() else {
+                    state
+                        .seeder
+                        .notify_file_done(state.filename, state.total, state.inserted);
+                    return None;
+                };
+
+                let value = state
+                    .named_values
+                    .as_ref()
+                    .and_then(|values| values.get(&name));
+                let result = match (&state.retry_policy, value) {
+                    (Some(retry_policy), Some(value)) => {
+                        retry_policy
+                            .run_async(record, value, &mut state.loader)
+                            .await
+                    }
+                    _ => (state.loader)(record).await,
+                };
+                match result {
+                    Ok(id) => {
+                        state.seeder.register_id(state.filename, name.clone(), &id);
+                        state.inserted += 1;
+                        return Some((Ok((name, id)), Some(state)));
+                    }
+                    Err(err) => {
+                        if let Err(err) = state.seeder.handle_failure(state.filename, name, err) {
+                            return Some((Err(err), None));
+                        }
+                    }
+                }
+            }
+        });
+
+        stream::iter(load_error).chain(results)
+    }
+
+    /// works the same as `populate_idempotent`, but with an async loader
+    ///
+    /// ```rust
+    /// use cder::{DatabaseSeeder, SeedOutcome};
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)] // add this derive macro
+    /// # struct User {
+    /// #   name: String,
+    /// #   email: String,
+    /// # }
+    /// #
+    /// # impl User {
+    /// #   async fn insert_or_ignore(input: &User) -> Result<Option<i64>> {
+    /// #     //
+    /// #     // this function inserts a corresponding User record into table unless it exists,
+    /// #     // and returns its id when inserted
+    /// #     //
+    /// #     Ok(Some(1))
+    /// #   }
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// async fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///
+    ///     seeder
+    ///         .populate_async_idempotent("fixtures/users.yml", |input: User| async move {
+    ///             match User::insert_or_ignore(&input).await? {
+    ///                 Some(id) => Ok(SeedOutcome::Inserted(id)),
+    ///                 None => Ok(SeedOutcome::Skipped),
+    ///             }
+    ///         })
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn populate_async_idempotent<Fut, F, T, U>(
+        &mut self,
+        filename: &str,
+        mut loader: F,
+    ) -> Result<Vec<SeedOutcome<U>>>
+    where
+        Fut: Future<Output = Result<SeedOutcome<U>>>,
+        F: FnMut(T) -> Fut,
+        T: DeserializeOwned,
+        U: ToRefValue,
+    {
+        if self.skip_resumed(filename) {
+            return Ok(Vec::new());
+        }
+        if self.dry_run {
+            self.populate_dry_run::<T>(filename)?;
+            return Ok(Vec::new());
+        }
+
+        let named_records = self.load_records::<T>(filename)?;
+        let total = named_records.len();
+        self.notify_file_start(filename, total);
+
+        let mut outcomes = Vec::with_capacity(total);
+
+        for (name, record) in named_records {
+            match loader(record).await {
+                Ok(outcome) => {
+                    self.register_outcome(filename, name, &outcome);
+                    outcomes.push(outcome);
+                }
+                Err(err) => self.handle_failure(filename, name, err)?,
+            }
+        }
+        let inserted = outcomes
+            .iter()
+            .filter(|outcome| matches!(outcome, SeedOutcome::Inserted(_)))
+            .count();
+        self.notify_file_done(filename, total, inserted);
+        Ok(outcomes)
+    }
+
+    /// loads the records, along with their raw values when the retry policy is set, so that the
+    /// records can be deserialized again on retries
+    fn load_retryable_records<T>(
+        &mut self,
+        filename: &str,
+    ) -> Result<(NamedRecords<T>, Option<Dict<serde_yaml::Value>>)>
+    where
+        T: DeserializeOwned,
+    {
+        if self.retry_policy.is_none() {
+            return Ok((self.load_records::<T>(filename)?, None));
+        }
+
+        let named_values = self.load_named_values(filename)?;
+        let named_records = deserialize_named_values::<T>(filename, &named_values)?;
+
+        Ok((named_records, Some(named_values.into_iter().collect())))
+    }
+
+    pub(crate) fn load_records<T>(&mut self, filename: &str) -> Result<NamedRecords<T>>
+    where
+        T: DeserializeOwned,
+    {
+        if self.before_insert_hooks.is_empty() {
+            return self.read_named_records::<T>(filename);
+        }
+
+        let named_values = self.load_named_values(filename)?;
+        deserialize_named_values::<T>(filename, &named_values)
+    }
+
+    /// loads the records as yaml values, applying the before_insert hooks
+    fn load_nested_records<T>(
+        &mut self,
+        filename: &str,
+        children_key: &str,
+    ) -> Result<Vec<NestedRecord<T>>>
+    where
+        T: DeserializeOwned,
+    {
+        let named_values = self.load_records::<serde_yaml::Value>(filename)?;
+        split_nested_records(filename, named_values, children_key)
+    }
+
+    fn load_named_values(&mut self, filename: &str) -> Result<NamedRecords<serde_yaml::Value>> {
+        let mut named_values = self.read_named_records::<serde_yaml::Value>(filename)?;
+
+        for (label, value) in named_values.iter_mut() {
+            self.apply_before_insert_hooks(filename, label, value)?;
+        }
+        Ok(named_values)
+    }
+
+    fn apply_before_insert_hooks(
+        &self,
+        filename: &str,
+        label: &str,
+        value: &mut serde_yaml::Value,
+    ) -> Result<()> {
+        for hook in self.before_insert_hooks.iter() {
+            (*lock(hook))(label, value).map_err(|err| {
+                anyhow::anyhow!(
+                    "before_insert hook failed: {} ({})\n   err: {}",
+                    label,
+                    filename,
+                    err
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Reads the records from the file, resolving the tags. When the scale is set, the records are
+    /// read as many times with `${{ CTX(index) }}` incremented from 0; the copies other than the
+    /// base ones (index 0) are labeled `<label>#<index>`, so that REFs keep referring to the bases.
+    fn read_named_records<T>(&self, filename: &str) -> Result<NamedRecords<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let named_records = self.read_labeled_records(filename, true)?;
+
+        if self.strict {
+            if let Some((label, _)) = named_records
+                .iter()
+                .find(|(label, _)| self.name_resolver.contains_key(label.as_str()))
+            {
+                return Err(CderError::DuplicateLabel {
+                    label: label.clone(),
+                    file: filename.to_string(),
+                }
+                .into());
+            }
+        }
+        Ok(named_records)
+    }
+
+    /// reads the records with the labels they are registered with, see `read_named_records`.
+    /// the warnings are collected for the files to be populated, see `warnings`
+    fn read_labeled_records<T>(
+        &self,
+        filename: &str,
+        collect_warnings: bool,
+    ) -> Result<NamedRecords<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.resolve_labeled_records(filename, collect_warnings)
+            .map_err(|err| self.mask_error(err))
+    }
+
+    fn resolve_labeled_records<T>(
+        &self,
+        filename: &str,
+        collect_warnings: bool,
+    ) -> Result<NamedRecords<T>>
+    where
+        T: DeserializeOwned,
+    {
+        // the prefetched files are read with the index 0 and without profiles
+        let prefetched = match self.scale == 1 && self.file_profile.is_none() {
+            true => lock(&self.prefetched).remove(filename),
+            false => None,
+        };
+        let started_at = Instant::now();
+        let (raw_text, mut prefetched) = match prefetched {
+            Some(file) => (file.raw_text, Some((file.resolved, file.values))),
+            None => (
+                read_seed_file(filename, &self.base_dir, &self.read_options)?,
+                None,
+            ),
+        };
+        let overlay = self.read_overlay(filename)?;
+        log_event!(
+            debug,
+            "read the seed file",
+            file = filename,
+            bytes = raw_text.len(),
+            elapsed_ms = started_at.elapsed().as_millis(),
+        );
+        let mut named_records = Vec::new();
+
+        for index in 0..self.scale {
+            let mut scope = self.tag_scope.clone();
+            scope
+                .context
+                .insert("index".to_string(), RefValue::Int(index as i64));
+            let started_at = Instant::now();
+            let (resolved, values) = match prefetched.take() {
+                Some((resolved, values)) => (
+                    resolved.unwrap_or_else(|| ResolvedText::unchanged(&raw_text)),
+                    Some(values),
+                ),
+                None => (
+                    resolve_text(filename, &raw_text, &self.name_resolver, &scope)?,
+                    None,
+                ),
+            };
+            lock(&self.secrets).extend(resolved.secrets.iter().cloned());
+            log_event!(
+                debug,
+                "resolved the tags",
+                file = filename,
+                tags = resolved.tags(),
+                refs = resolved.refs.len(),
+                elapsed_ms = started_at.elapsed().as_millis(),
+            );
+            if collect_warnings && index == 0 {
+                self.collect_warnings(filename, &raw_text, &resolved);
+            }
+
+            let started_at = Instant::now();
+            let records = match &self.file_profile {
+                Some(profile) => {
+                    let named_values =
+                        deserialize_resolved_records(filename, &raw_text, &resolved)?;
+                    let overlay = match &overlay {
+                        Some((overlay_filename, overlay_text)) => {
+                            let resolved = resolve_text(
+                                overlay_filename,
+                                overlay_text,
+                                &self.name_resolver,
+                                &scope,
+                            )?;
+                            lock(&self.secrets).extend(resolved.secrets.iter().cloned());
+                            deserialize_resolved_records(overlay_filename, overlay_text, &resolved)?
+                        }
+                        None => Vec::new(),
+                    };
+                    let named_values = apply_profile(filename, profile, named_values, overlay)?;
+                    deserialize_named_values::<T>(filename, &named_values)?
+                }
+                None => match values.and_then(from_values::<T>) {
+                    Some(records) => records,
+                    None => deserialize_resolved_records::<T>(filename, &raw_text, &resolved)?,
+                },
+            };
+            log_event!(
+                debug,
+                "parsed the records",
+                file = filename,
+                records = records.len(),
+                elapsed_ms = started_at.elapsed().as_millis(),
+            );
+
+            named_records.extend(records.into_iter().map(|(label, record)| match index {
+                0 => (label, record),
+                _ => (format!("{}#{}", label, index), record),
+            }));
+        }
+        if self.namespace_by_file {
+            let stem = file_table(filename);
+            named_records = named_records
+                .into_iter()
+                .map(|(label, record)| (format!("{}/{}", stem, label), record))
+                .collect();
+        }
+        Ok(self.scope_labels(named_records))
+    }
+
+    /// collects the warnings on the file: the ENV and CTX tags that have fallen back to their
+    /// defaults, and the records that break the warning rules. the REF keys are kept to tell the
+    /// unused labels and the order of teardown afterwards
+    fn collect_warnings(&self, filename: &str, raw_text: &str, resolved: &ResolvedText<'_>) {
+        let mut warnings = defaults_used_warnings(filename, raw_text, resolved);
+
+        let rules = &self.warning_rules;
+        let checks_records = rules.label_pattern.is_some()
+            || rules.max_fields.is_some()
+            || rules.max_record_bytes.is_some()
+            || rules.max_value_len.is_some();
+        // the records that cannot be read are reported as errors anyway
+        if let (true, Ok(records)) = (
+            checks_records,
+            deserialize_resolved_records::<serde_yaml::Value>(filename, raw_text, resolved),
+        ) {
+            for (label, record) in &records {
+                if let Ok(record_warnings) = record_warnings(
+                    filename,
+                    label,
+                    record,
+                    rules,
+                    self.warning_label_pattern.as_ref(),
+                ) {
+                    warnings.extend(record_warnings);
+                }
+            }
+        }
+
+        lock(&self.warnings).extend(warnings);
+        lock(&self.referred_keys).extend(resolved.refs.iter().cloned());
+        lock(&self.file_refs)
+            .entry(filename.to_string())
+            .or_default()
+            .extend(resolved.refs.iter().cloned());
+    }
+
+    /// reads the overlay file of the profile the file is populated with, if any
+    fn read_overlay(&self, filename: &str) -> Result<Option<(String, SeedText)>> {
+        let Some(profile) = &self.file_profile else {
+            return Ok(None);
+        };
+        let overlay_filename = overlay_filename(filename, profile);
+        if !file_exists(&overlay_filename, &self.base_dir) {
+            return Ok(None);
+        }
+        let overlay_text = read_seed_file(&overlay_filename, &self.base_dir, &self.read_options)?;
+        Ok(Some((overlay_filename, overlay_text)))
+    }
+
+    /// prefixes the labels with the namespace of the tag scope (if any), e.g. `acme/Alice`
+    fn scope_labels<T>(&self, named_records: NamedRecords<T>) -> NamedRecords<T> {
+        match &self.tag_scope.namespace {
+            Some(namespace) => named_records
+                .into_iter()
+                .map(|(label, record)| (format!("{}/{}", namespace, label), record))
+                .collect(),
+            None => named_records,
+        }
+    }
+
+    /// Populates the file with the adapter (see `SeedAdapter`): the records that already exist
+    /// are registered without being inserted, and the others are inserted one by one.
+    ///
+    /// ```rust
+    /// use cder::{DatabaseSeeder, SeedAdapter};
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)]
+    /// # struct User {
+    /// #   name: String,
+    /// # }
+    /// #
+    /// # struct UsersTable;
+    /// #
+    /// # impl SeedAdapter<User> for UsersTable {
+    /// #     type Id = i64;
+    /// #     async fn insert(&mut self, record: User) -> Result<i64> { Ok(1) }
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// async fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///     let mut users_table = UsersTable;
+    ///
+    ///     seeder
+    ///         .populate_with("fixtures/users.yml", &mut users_table)
+    ///         .await?;
+    ///
+    ///     // deletes the users inserted above
+    ///     seeder
+    ///         .teardown_with("fixtures/users.yml", &mut users_table)
+    ///         .await
+    /// }
+    /// ```
+    pub async fn populate_with<T, A>(
+        &mut self,
+        filename: &str,
+        adapter: &mut A,
+    ) -> Result<Vec<SeedOutcome<A::Id>>>
+    where
+        T: DeserializeOwned + Send,
+        A: SeedAdapter<T>,
+    {
+        if self.skip_resumed(filename) {
+            return Ok(Vec::new());
+        }
+        if self.dry_run {
+            self.populate_dry_run::<T>(filename)?;
+            return Ok(Vec::new());
+        }
+        let checksum = self.version_checksum(filename)?;
+        if let Some(checksum) = &checksum {
+            if self.skip_applied(filename, checksum, adapter).await? {
+                return Ok(Vec::new());
+            }
+        }
+
+        let named_records = self.load_records::<T>(filename)?;
+        let total = named_records.len();
+        self.notify_file_start(filename, total);
+
+        let mut outcomes = Vec::with_capacity(total);
+
+        for (name, record) in named_records {
+            let result = match adapter.exists(&record).await {
+                Ok(Some(id)) => Ok(SeedOutcome::Exists(id)),
+                Ok(None) => adapter
+                    .insert_labeled(&name, record)
+                    .await
+                    .map(SeedOutcome::Inserted),
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(outcome) => {
+                    self.register_outcome(filename, name, &outcome);
+                    outcomes.push(outcome);
+                }
+                Err(err) => self.handle_failure(filename, name, err)?,
+            }
+        }
+        let inserted = outcomes
+            .iter()
+            .filter(|outcome| matches!(outcome, SeedOutcome::Inserted(_)))
+            .count();
+        self.notify_file_done(filename, total, inserted);
+        self.record_version(filename, checksum, adapter).await?;
+        Ok(outcomes)
+    }
+
+    /// Populates the file with the adapter, inserting the records in chunks of (at most)
+    /// `chunk_size` with `SeedAdapter::insert_many`.
+    pub async fn populate_many_with<T, A>(
+        &mut self,
+        filename: &str,
+        chunk_size: usize,
+        adapter: &mut A,
+    ) -> Result<Vec<A::Id>>
+    where
+        T: DeserializeOwned + Send,
+        A: SeedAdapter<T>,
+    {
+        if chunk_size == 0 {
+            return Err(anyhow::anyhow!(
+                "filename : {} chunk_size must be greater than zero",
+                filename,
+            ));
+        }
+
+        if self.skip_resumed(filename) {
+            return Ok(Vec::new());
+        }
+        if self.dry_run {
+            self.populate_dry_run::<T>(filename)?;
+            return Ok(Vec::new());
+        }
+        let checksum = self.version_checksum(filename)?;
+        if let Some(checksum) = &checksum {
+            if self.skip_applied(filename, checksum, adapter).await? {
+                return Ok(Vec::new());
+            }
+        }
+
+        let named_records = self.load_records::<T>(filename)?;
+        let total = named_records.len();
+        self.notify_file_start(filename, total);
+
+        let mut ids = Vec::with_capacity(total);
+        let mut named_records = named_records.into_iter().peekable();
+
+        while named_records.peek().is_some() {
+            let chunk = named_records.by_ref().take(chunk_size).collect::<Vec<_>>();
+            let names = chunk
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<String>>();
+            let chunk_ids = match adapter.insert_many_labeled(chunk).await {
+                Ok(chunk_ids) => chunk_ids,
+                Err(err) => {
+                    // the whole chunk is regarded as failed
+                    for name in names {
+                        self.handle_failure(filename, name, anyhow::anyhow!("{:#}", err))?;
+                    }
+                    continue;
+                }
+            };
+
+            if chunk_ids.len() != names.len() {
+                return Err(anyhow::anyhow!(
+                    "filename : {} the adapter returned {} ids for {} records",
+                    filename,
+                    chunk_ids.len(),
+                    names.len(),
+                ));
+            }
+
+            for (name, id) in names.into_iter().zip(chunk_ids) {
+                self.register_id(filename, name, &id);
+                ids.push(id);
+            }
+        }
+        self.notify_file_done(filename, total, ids.len());
+        self.record_version(filename, checksum, adapter).await?;
+        Ok(ids)
+    }
+
+    /// Deletes the records populated from the file with `SeedAdapter::delete`, in the reverse
+    /// order of insertion. The records of the other files are left as they are.
+    pub async fn teardown_with<T, A>(&mut self, filename: &str, adapter: &mut A) -> Result<()>
+    where
+        T: Send,
+        A: SeedAdapter<T>,
+    {
+        while let Some(index) = self.last_record_of(filename) {
+            adapter.delete(&self.seeded_records[index].id).await?;
+            self.forget_record(index);
+        }
+        Ok(())
+    }
+
+    /// Empties the table of the file with `SeedAdapter::truncate`, see `clean`.
+    pub async fn truncate_with<T, A>(&self, filename: &str, adapter: &mut A) -> Result<()>
+    where
+        T: Send,
+        A: SeedAdapter<T>,
+    {
+        adapter.truncate(file_table(filename)).await
+    }
+
+    /// Compares the records in the file with the rows in the database, instead of inserting them:
+    /// the rows are fetched with `SeedAdapter::fetch` by the ids of the labels known to the
+    /// seeder (e.g. restored with `restore_state` from the environment populated earlier), and
+    /// the fields defined in the fixtures are compared with the serialized rows.
+    /// The fields the fixtures leave out (e.g. ids or timestamps) are not compared.
+    ///
+    /// ```rust
+    /// use cder::{DatabaseSeeder, SeedAdapter, SeedState};
+    /// # use serde::{Deserialize, Serialize};
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize, Serialize)]
+    /// # struct User {
+    /// #   name: String,
+    /// # }
+    /// #
+    /// # struct UsersTable;
+    /// #
+    /// # impl SeedAdapter<User> for UsersTable {
+    /// #     type Id = i64;
+    /// #     async fn insert(&mut self, record: User) -> Result<i64> { Ok(1) }
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     verify_seeds();
+    /// # }
+    ///
+    /// async fn verify_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///     seeder.restore_state(SeedState::load("seed_state.json")?);
+    ///
+    ///     let report = seeder
+    ///         .verify::<User, _>("fixtures/users.yml", &mut UsersTable)
+    ///         .await?;
+    ///     if !report.is_clean() {
+    ///         println!("{}", report);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn verify<T, A>(&self, filename: &str, adapter: &mut A) -> Result<VerificationReport>
+    where
+        T: Serialize + Send,
+        A: SeedAdapter<T>,
+    {
+        let named_values = self.read_labeled_records::<serde_yaml::Value>(filename, false)?;
+        let mut report = VerificationReport {
+            filename: filename.to_string(),
+            ..VerificationReport::default()
+        };
+
+        for (label, expected) in named_values {
+            let Some(id) = self.name_resolver.get(label.as_str()) else {
+                report.unknown.push(label);
+                continue;
+            };
+            let Some(row) = adapter.fetch(id).await? else {
+                report.missing.push(label);
+                continue;
+            };
+            let actual = serde_yaml::to_value(row)?;
+            report
+                .differences
+                .extend(compare_fields(&label, &expected, &actual));
+            report.verified += 1;
+        }
+        Ok(report)
+    }
+
+    /// Compares the records in the file with the rows in the database like `verify`, and lists
+    /// the changes populating the file would make: the records to be added (whose rows are not
+    /// found, or whose ids are not known), the fields to be changed, and the rows that are not
+    /// defined in the file (listed with `SeedAdapter::ids`) as removed.
+    ///
+    /// ```rust
+    /// use cder::{DatabaseSeeder, SeedAdapter, SeedState};
+    /// # use serde::{Deserialize, Serialize};
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize, Serialize)]
+    /// # struct User {
+    /// #   name: String,
+    /// # }
+    /// #
+    /// # struct UsersTable;
+    /// #
+    /// # impl SeedAdapter<User> for UsersTable {
+    /// #     type Id = i64;
+    /// #     async fn insert(&mut self, record: User) -> Result<i64> { Ok(1) }
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     diff_seeds();
+    /// # }
+    ///
+    /// async fn diff_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///     seeder.restore_state(SeedState::load("seed_state.json")?);
+    ///
+    ///     let diff = seeder
+    ///         .diff::<User, _>("fixtures/users.yml", &mut UsersTable)
+    ///         .await?;
+    ///     println!("{}", diff);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn diff<T, A>(&self, filename: &str, adapter: &mut A) -> Result<FixtureDiff>
+    where
+        T: Serialize + Send,
+        A: SeedAdapter<T>,
+    {
+        let report = self.verify(filename, adapter).await?;
+        let defined = self
+            .read_labeled_records::<serde_yaml::Value>(filename, false)?
+            .iter()
+            .filter_map(|(label, _)| self.name_resolver.get(label.as_str()))
+            .map(|id| id.to_string())
+            .collect::<HashSet<String>>();
+
+        let removed = adapter
+            .ids()
+            .await?
+            .into_iter()
+            .filter(|id| !defined.contains(&id.to_string()))
+            .collect();
+        Ok(FixtureDiff::new(report, removed))
+    }
+
+    /// Registers the file along with the async loader of its records, so that all the registered
+    /// files can be populated at once with `populate_all`. The files can be of different types.
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    /// # use serde::Deserialize;
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)]
+    /// # struct User {
+    /// #   name: String,
+    /// # }
+    /// # #[derive(Deserialize)]
+    /// # struct Post {
+    /// #   title: String,
+    /// #   user_id: i64,
+    /// # }
+    /// #
+    /// # async fn insert_user(input: User) -> Result<i64> { Ok(1) }
+    /// # async fn insert_post(input: Post) -> Result<i64> { Ok(1) }
+    /// #
+    /// # fn main() {
+    /// #     populate_seeds();
+    /// # }
+    ///
+    /// async fn populate_seeds() -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///
+    ///     // the order of registration does not matter
+    ///     seeder.register::<Post>("fixtures/posts.yml", insert_post);
+    ///     seeder.register::<User>("fixtures/users.yml", insert_user);
+    ///
+    ///     seeder.populate_all().await
+    /// }
+    /// ```
+    pub fn register<T>(&mut self, filename: &str, mut loader: impl AsyncLoader<T>)
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let registered_filename = filename.to_string();
+
+        lock(&self.registered_files).push(RegisteredFile {
+            filename: filename.to_string(),
+            populate: Box::new(move |seeder: &mut DatabaseSeeder| {
+                Box::pin(async move {
+                    seeder
+                        .populate_async(&registered_filename, |record: T| loader.call(record))
+                        .await
+                        .map(|_| ())
+                })
+            }),
+        });
+    }
+
+    /// Populates all the registered files (see `register`), sorted so that each file is
+    /// populated after the files it refers to with REF tags.
+    /// Before any insertion, the REFs of all the files are validated (see `validate_refs`), so that
+    /// a typo does not leave the files half-populated.
+    /// The registrations are consumed, regardless of whether the seeding succeeds or not.
+    ///
+    /// With the `rayon` feature, the files whose dependencies have been populated are read and
+    /// parsed in parallel ahead of their seeding, while the records are still inserted in the
+    /// order of the dependencies.
+    pub async fn populate_all(&mut self) -> Result<()> {
+        let mut registered_files = std::mem::take(&mut *lock(&self.registered_files));
+        let filenames = registered_files
+            .iter()
+            .map(|registered| registered.filename.clone())
+            .collect::<Vec<String>>();
+        self.validate_ref_keys(&filenames)?;
+
+        let sorted = sort_with_dependencies(&filenames, &self.base_dir, &self.read_options)?;
+        let mut populated = HashSet::<&String>::new();
+        let result = async {
+            for (filename, _) in &sorted {
+                // the files read with the scale are not prefetched, see `read_labeled_records`
+                if cfg!(feature = "rayon")
+                    && self.scale == 1
+                    && !lock(&self.prefetched).contains_key(filename)
+                {
+                    let ready = sorted
+                        .iter()
+                        .filter(|(filename, dependencies)| {
+                            !populated.contains(filename)
+                                && dependencies.iter().all(|file| populated.contains(file))
+                        })
+                        .map(|(filename, _)| filename)
+                        .collect::<Vec<&String>>();
+                    self.prefetch(&ready);
+                }
+
+                let index = registered_files
+                    .iter()
+                    .position(|registered| &registered.filename == filename)
+                    .ok_or_else(|| anyhow::anyhow!("filename : {} is not registered", filename))?;
+                let registered = registered_files.remove(index);
+
+                (registered.populate)(self).await?;
+                populated.insert(filename);
+            }
+            Ok(())
+        }
+        .await;
+
+        lock(&self.prefetched).clear();
+        result
+    }
+
+    /// reads and parses the files in parallel, see `populate_all`
+    #[cfg(feature = "rayon")]
+    fn prefetch(&self, filenames: &[&String]) {
+        let mut scope = self.tag_scope.clone();
+        scope.context.insert("index".to_string(), RefValue::Int(0));
+        let files = crate::prefetch::prefetch_files(
+            filenames,
+            &self.base_dir,
+            &self.read_options,
+            &self.name_resolver,
+            &scope,
+        );
+        lock(&self.prefetched).extend(files);
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    fn prefetch(&self, _: &[&String]) {}
+
+    fn handle_failure(
+        &mut self,
+        filename: &str,
+        label: String,
+        error: anyhow::Error,
+    ) -> Result<()> {
+        // the loaders may echo the values of the records, e.g. in the messages of the database
+        let error = self.mask_error(error);
+        if let Some(file) = self.report.files.last_mut() {
+            file.failed += 1;
+        }
+        self.update_elapsed();
+        log_event!(
+            warn,
+            "failed to insert the record",
+            file = filename,
+            label = label,
+            error = error,
+        );
+
+        if !self.continue_on_error {
+            let records = self
+                .seeded_records
+                .len()
+                .saturating_sub(self.file_seeded_from);
+            self.push_history(filename, records, SeedStatus::Failed);
+            return Err(error);
+        }
+
+        self.failures.push(SeedFailure {
+            filename: filename.to_string(),
+            label: intern(&self.name_resolver, label),
+            error,
+        });
+        Ok(())
+    }
+
+    /// masks the values of the ENV and SECRET tags resolved so far in the error
+    fn mask_error(&self, error: anyhow::Error) -> anyhow::Error {
+        let secrets = lock(&self.secrets).iter().cloned().collect::<Vec<String>>();
+        mask_error(error, &secrets)
+    }
+
+    /// registers the columns captured along with the id as `<label>.<column>`, see `Captures`
+    fn register_captures<U>(&mut self, filename: &str, label: &str, id: &U)
+    where
+        U: ToRefValue + ?Sized,
+    {
+        if let Some(columns) = self.key_columns.get(filename) {
+            let values = match id.to_ref_value() {
+                RefValue::Composite(values) => values,
+                value => vec![value],
+            };
+            for (column, value) in columns.iter().zip(values) {
+                self.name_resolver
+                    .insert(format!("{}.{}", label, column).into(), value);
+            }
+        }
+        for (column, value) in id.captures() {
+            self.name_resolver
+                .insert(format!("{}.{}", label, column).into(), value);
+        }
+    }
+
+    fn register_outcome<U>(&mut self, filename: &str, label: String, outcome: &SeedOutcome<U>)
+    where
+        U: ToRefValue,
+    {
+        match outcome {
+            SeedOutcome::Inserted(id) => self.register_id(filename, label, id),
+            // existing records are not tracked, so that teardown leaves them as they are
+            SeedOutcome::Exists(id) => {
+                self.register_captures(filename, &label, id);
+                let label = intern(&self.name_resolver, label);
+                self.name_resolver.insert(label, id.to_ref_value());
+            }
+            SeedOutcome::Skipped => {}
+        }
+    }
+
+    pub(crate) fn register_id<U>(&mut self, filename: &str, label: String, id: &U)
+    where
+        U: ToRefValue + ?Sized,
+    {
+        self.register_captures(filename, &label, id);
+        // the label is shared by the ids and the seeded records
+        let label = intern(&self.name_resolver, label);
+        let id = id.to_ref_value();
+        log_event!(
+            trace,
+            "inserted the record",
+            file = filename,
+            label = label,
+            id = id,
+        );
+        if let Some(observer) = self.observer.as_ref() {
+            lock(observer).on_record_inserted(filename, &label, &id);
+        }
+        for hook in self.after_insert_hooks.iter() {
+            (*lock(hook))(&label, &id);
+        }
+        self.name_resolver.insert(label.clone(), id.clone());
+        // the records of a file are inserted in a row
+        let filename = match self.seeded_records.last() {
+            Some(last) if &*last.filename == filename => last.filename.clone(),
+            _ => Arc::from(filename),
+        };
+        self.seeded_records.push(SeededRecord {
+            filename,
+            label,
+            id,
+        });
+    }
+
+    pub(crate) fn notify_file_start(&mut self, filename: &str, total: usize) {
+        self.report.files.push(FileReport {
+            filename: filename.to_string(),
+            parsed: total,
+            ..FileReport::default()
+        });
+        self.file_started_at = Some(Instant::now());
+        self.file_seeded_from = self.seeded_records.len();
+        log_event!(info, "seeding the file", file = filename, records = total);
+        if let Some(observer) = self.observer.as_ref() {
+            lock(observer).on_file_start(filename, total);
+        }
+    }
+
+    fn update_elapsed(&mut self) {
+        if let (Some(file), Some(started_at)) = (self.report.files.last_mut(), self.file_started_at)
+        {
+            file.elapsed = started_at.elapsed();
+        }
+    }
+
+    /// returns whether the file is to be skipped as it has been populated before resuming
+    pub(crate) fn skip_resumed(&mut self, filename: &str) -> bool {
+        if !self.resumed_files.contains(filename) {
+            return false;
+        }
+        self.push_history(filename, 0, SeedStatus::Skipped);
+        true
+    }
+
+    /// returns the checksum of the file when the versions are tracked
+    pub(crate) fn version_checksum(&self, filename: &str) -> Result<Option<String>> {
+        if !self.track_versions {
+            return Ok(None);
+        }
+        let raw_text = read_file(filename, &self.base_dir)?;
+        Ok(Some(checksum(&raw_text)))
+    }
+
+    /// returns true when the file has been applied with the same contents
+    pub(crate) async fn skip_applied<T, A>(
+        &mut self,
+        filename: &str,
+        checksum: &str,
+        adapter: &mut A,
+    ) -> Result<bool>
+    where
+        T: Send,
+        A: SeedAdapter<T>,
+    {
+        match adapter.applied_checksum(filename).await? {
+            None => Ok(false),
+            Some(applied) if applied == checksum => {
+                self.push_history(filename, 0, SeedStatus::Skipped);
+                Ok(true)
+            }
+            Some(applied) => Err(anyhow::anyhow!(
+                "the seed file has been changed since it was applied: {}\n   applied checksum: {}\n   current checksum: {}",
+                filename,
+                applied,
+                checksum
+            )),
+        }
+    }
+
+    /// records the file as applied, unless some of its records have failed
+    pub(crate) async fn record_version<T, A>(
+        &self,
+        filename: &str,
+        checksum: Option<String>,
+        adapter: &mut A,
+    ) -> Result<()>
+    where
+        T: Send,
+        A: SeedAdapter<T>,
+    {
+        let completed = self
+            .history
+            .last()
+            .is_some_and(|entry| entry.status == SeedStatus::Completed);
+        match checksum {
+            Some(checksum) if completed => adapter.record_applied(filename, &checksum).await,
+            _ => Ok(()),
+        }
+    }
+
+    fn push_history(&mut self, filename: &str, records: usize, status: SeedStatus) {
+        self.history.push(SeedHistoryEntry {
+            filename: filename.to_string(),
+            records,
+            finished_at: SystemTime::now(),
+            status,
+        });
+    }
+
+    pub(crate) fn notify_file_done(&mut self, filename: &str, total: usize, inserted: usize) {
+        let mut status = SeedStatus::Completed;
+        if let Some(file) = self.report.files.last_mut() {
+            file.inserted = inserted;
+            file.skipped = total.saturating_sub(inserted + file.failed);
+            if file.failed > 0 {
+                status = SeedStatus::CompletedWithFailures;
+            }
+        }
+        self.update_elapsed();
+        if let Some(file) = self.report.files.last() {
+            log_event!(
+                info,
+                "seeded the file",
+                file = filename,
+                inserted = file.inserted,
+                skipped = file.skipped,
+                failed = file.failed,
+                elapsed_ms = file.elapsed.as_millis(),
+            );
+        }
+        self.filenames.push(filename.to_string());
+        self.push_history(filename, inserted, status);
+        if let Some(observer) = self.observer.as_ref() {
+            lock(observer).on_file_done(filename, total, inserted);
+        }
+    }
+
+    /// the files of the seeded records, in the order they are torn down: the referencing files
+    /// come before the files they refer to, as told by the REF keys recorded when the files were
+    /// populated. REFs never refer to the records in the same file, so the records inside each
+    /// file only have to be deleted in the reverse order of insertion
+    fn teardown_order(&self) -> Result<Vec<String>> {
+        let mut files = Vec::<(String, Vec<String>, Vec<String>)>::new();
+        for record in self.seeded_records.iter().rev() {
+            let label = record.label.as_str().to_string();
+            match files
+                .iter_mut()
+                .find(|(filename, _, _)| **filename == *record.filename)
+            {
+                Some((_, labels, _)) => labels.push(label),
+                None => files.push((record.filename.to_string(), vec![label], Vec::new())),
+            }
+        }
+        let file_refs = lock(&self.file_refs);
+        for (filename, _, refs) in files.iter_mut() {
+            if let Some(keys) = file_refs.get(filename.as_str()) {
+                refs.extend(keys.iter().cloned());
+            }
+        }
+        drop(file_refs);
+
+        // the files later inserted come first, unless they are referred by the earlier ones
+        files.reverse();
+        let mut filenames = sort_seeded_files(files)?;
+        filenames.reverse();
+        Ok(filenames)
+    }
+
+    /// the table of the record, which is the one of the manifest entry listing its file if any,
+    /// so that teardown deletes from the same tables as `clean_manifest` empties
+    fn record_table<'a>(&'a self, record: &'a SeededRecord) -> &'a str {
+        self.manifest
+            .as_ref()
+            .and_then(|manifest| {
+                manifest
+                    .seeds
+                    .iter()
+                    .find(|entry| entry.file == *record.filename)
+            })
+            .map_or_else(|| record.table(), |entry| entry.table())
+    }
+
+    fn last_record_of(&self, filename: &str) -> Option<usize> {
+        self.seeded_records
+            .iter()
+            .rposition(|record| &*record.filename == filename)
+    }
+
+    fn forget_record(&mut self, index: usize) {
+        let record = self.seeded_records.remove(index);
+        // the label may have been overwritten by a later record
+        if self.name_resolver.get(&record.label) == Some(&record.id) {
+            self.name_resolver.remove(&record.label);
+        }
+    }
+
+    pub(crate) fn populate_dry_run<T>(&mut self, filename: &str) -> Result<()>
+    where
+        T: DeserializeOwned,
+    {
+        // the REFs to the placeholders are read as markers, to be typed by the records
+        let placeholders = self
+            .dry_run_placeholders
+            .iter()
+            .filter_map(|(label, _)| Some((label.clone(), self.name_resolver.get(label)?.clone())))
+            .collect::<Vec<(Label, RefValue)>>();
+        for (label, id) in &placeholders {
+            self.name_resolver
+                .insert(label.clone(), placeholder_marker(id));
+        }
+        let named_values = self.read_named_records::<serde_yaml::Value>(filename);
+        self.name_resolver.extend(placeholders);
+
+        let mut records = Vec::new();
+        let mut errors = Vec::new();
+        // makes sure that every record can be deserialized into T, reporting all the failures
+        for (label, value) in named_values? {
+            match self.fill_dry_run_record::<T>(filename, &label, value)? {
+                Ok(record) => records.push((label, record)),
+                Err(err) => errors.push((Some(label.clone()), record_error(filename, &label, err))),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(aggregate(filename, errors).into());
+        }
+
+        let labels = records
+            .iter()
+            .map(|(label, _)| label.clone())
+            .collect::<Vec<String>>();
+        let example = records.into_iter().next();
+
+        let offset = self.dry_run_report.record_count();
+        for (index, label) in labels.iter().enumerate() {
+            let id = offset + index + 1;
+            let label = Label::from(label.as_str());
+            let previous = self
+                .name_resolver
+                .insert(label.clone(), RefValue::Int(id as i64));
+            self.dry_run_placeholders.push((label, previous));
+        }
+        self.filenames.push(filename.to_string());
+        self.dry_run_report.files.push(DryRunFile {
+            filename: filename.to_string(),
+            labels,
+            example,
+        });
+
+        Ok(())
+    }
+
+    /// fills the placeholders in the record by the types of the fields of T, then applies the
+    /// before_insert hooks once, as they would be applied to the record in a real run
+    fn fill_dry_run_record<T>(
+        &self,
+        filename: &str,
+        label: &str,
+        mut record: serde_yaml::Value,
+    ) -> Result<std::result::Result<serde_yaml::Value, serde_yaml::Error>>
+    where
+        T: DeserializeOwned,
+    {
+        let kinds = placeholder_kinds::<T>(&record);
+        fill_placeholders(&mut record, &kinds, &mut 0);
+        self.apply_before_insert_hooks(filename, label, &mut record)?;
+        Ok(serde_yaml::from_value::<T>(record.clone()).map(|_| record))
+    }
+}
+
+/// the number of the parent and the child records
+fn count_nested_records<T>(nested_records: &[NestedRecord<T>]) -> usize {
+    nested_records
+        .iter()
+        .map(|nested| 1 + nested.children.len())
+        .sum()
+}
+
+/// deserializes the records from their yaml values
+fn deserialize_named_values<T>(
+    filename: &str,
+    named_values: &NamedRecords<serde_yaml::Value>,
+) -> Result<NamedRecords<T>>
+where
+    T: DeserializeOwned,
+{
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    // every record is tried, so that all the failures are reported at once
+    for (label, value) in named_values {
+        match serde_yaml::from_value::<T>(value.clone()) {
+            Ok(record) => records.push((label.clone(), record)),
+            Err(err) => errors.push((Some(label.clone()), record_error(filename, label, err))),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(aggregate(filename, errors).into());
+    }
+    Ok(records)
+}
+
+/// the progress of `DatabaseSeeder::populate_stream`
+struct PopulateStreamState<'a, F, T> {
+    seeder: &'a mut DatabaseSeeder,
+    filename: &'a str,
+    loader: F,
+    named_records: std::vec::IntoIter<(String, T)>,
+    named_values: Option<Dict<serde_yaml::Value>>,
+    retry_policy: Option<RetryPolicy>,
+    total: usize,
+    inserted: usize,
 }
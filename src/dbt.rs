@@ -0,0 +1,234 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+use std::path::Path;
+
+use crate::{
+    reader::read_file,
+    resolver::{mask_tags, unmask_tags},
+    Dict,
+};
+
+/// DbtSeeds configures how the CSV seeds of dbt are read, see `DatabaseSeeder::dbt_seeds`. Each
+/// `.csv` file is a table whose header row names the columns, and each row is a record labeled
+/// with the value of its key column (the first column by default). The cells are read as yaml
+/// scalars (e.g. `500` as a number), unless the column is hinted to be text or a date, and the
+/// empty ones are left out. Available with the `dbt` feature.
+///
+/// ```rust
+/// use cder::DbtSeeds;
+/// # use anyhow::Result;
+/// #
+/// # fn main() -> Result<()> {
+/// let seeds = DbtSeeds::new()
+///     .key_column("code")
+///     .column_type("items", "code", "varchar(8)");
+///
+/// // or with the column types of a properties file of dbt
+/// let seeds = DbtSeeds::from_properties("tests/fixtures/dbt/properties.yml")?.key_column("code");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DbtSeeds {
+    key_column: Option<String>,
+    /// the types of the columns by the names of the seeds, i.e. the stems of the files
+    column_types: Dict<Dict<String>>,
+}
+
+/// the properties file of dbt, of which the column types of the seeds are read
+#[derive(Deserialize)]
+struct Properties {
+    #[serde(default)]
+    seeds: Vec<SeedProperties>,
+}
+
+#[derive(Deserialize)]
+struct SeedProperties {
+    name: String,
+    #[serde(default)]
+    config: SeedConfig,
+    #[serde(default)]
+    columns: Vec<ColumnProperties>,
+}
+
+#[derive(Deserialize, Default)]
+struct SeedConfig {
+    #[serde(default)]
+    column_types: Dict<String>,
+}
+
+#[derive(Deserialize)]
+struct ColumnProperties {
+    name: String,
+    data_type: Option<String>,
+}
+
+impl DbtSeeds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the column types of the seeds from the properties file of dbt, either by
+    /// `config.column_types` or by `data_type` of the columns:
+    ///
+    /// ```yaml
+    /// seeds:
+    ///   - name: items
+    ///     config:
+    ///       column_types:
+    ///         code: varchar(8)
+    /// ```
+    pub fn from_properties(path: &str) -> Result<Self> {
+        let text = read_file(path, "")?;
+        let properties = serde_yaml::from_str::<Properties>(&text).map_err(|err| {
+            anyhow!(
+                "failed to read the properties of the seeds: {}\n   err: {}",
+                path,
+                err
+            )
+        })?;
+
+        let mut seeds = Self::new();
+        for seed in properties.seeds {
+            let column_types = seeds.column_types.entry(seed.name).or_default();
+            column_types.extend(seed.config.column_types);
+            for column in seed.columns {
+                if let Some(data_type) = column.data_type {
+                    column_types.entry(column.name).or_insert(data_type);
+                }
+            }
+        }
+        Ok(seeds)
+    }
+
+    /// labels the records with the values of the column, rather than the ones of the first column
+    pub fn key_column(mut self, column: &str) -> Self {
+        self.key_column = Some(column.to_string());
+        self
+    }
+
+    /// hints the type of the column of the seed (the stem of the file, e.g. `items` for
+    /// `items.csv`), e.g. `varchar` to read `501` as a string rather than a number
+    pub fn column_type(mut self, seed: &str, column: &str, column_type: &str) -> Self {
+        self.column_types
+            .entry(seed.to_string())
+            .or_default()
+            .insert(column.to_string(), column_type.to_string());
+        self
+    }
+
+    /// translates the CSV seed into a seed file of cder. the tags in the cells are kept as they
+    /// are, e.g. `${{ REF(Alice) }}`
+    pub(crate) fn translate(&self, filename: &str, raw_text: &str) -> Result<String> {
+        let seed = Path::new(filename)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(filename);
+        let column_types = self.column_types.get(seed);
+        let mut tags = Vec::<String>::new();
+        let text = mask_tags(raw_text, &mut tags);
+        let invalid =
+            |err: csv::Error| anyhow!("failed to read the csv: {}\n   err: {}", filename, err);
+
+        let mut reader = csv::Reader::from_reader(text.as_bytes());
+        let headers = reader.headers().map_err(invalid)?.clone();
+        let key_index = match &self.key_column {
+            Some(key_column) => headers
+                .iter()
+                .position(|header| header == key_column)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "the key column `{}` is not found in the header: {}",
+                        key_column,
+                        filename
+                    )
+                })?,
+            None => 0,
+        };
+
+        let mut records = Mapping::new();
+        for (index, row) in reader.records().enumerate() {
+            let row = row.map_err(invalid)?;
+            let label = row
+                .get(key_index)
+                .filter(|label| !label.is_empty())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "the row {} has no key to be labeled with: {}",
+                        index + 1,
+                        filename
+                    )
+                })?;
+
+            let mut record = Mapping::new();
+            for (column, cell) in headers.iter().zip(row.iter()) {
+                if cell.is_empty() {
+                    continue;
+                }
+                let column_type = column_types.and_then(|types| types.get(column));
+                record.insert(Value::from(column), cell_value(cell, column_type));
+            }
+            if records
+                .insert(Value::from(label), Value::Mapping(record))
+                .is_some()
+            {
+                return Err(anyhow!(
+                    "the key `{}` is given to more than one row: {}",
+                    label,
+                    filename
+                ));
+            }
+        }
+
+        let text = serde_yaml::to_string(&records)?;
+        Ok(unmask_tags(&text, &tags))
+    }
+}
+
+/// reads the cell as a yaml scalar, or as a string when the column is hinted to be text or a date
+/// (e.g. `501` of a varchar column)
+fn cell_value(cell: &str, column_type: Option<&String>) -> Value {
+    let is_text = column_type.is_some_and(|column_type| {
+        let column_type = column_type.to_lowercase();
+        ["char", "text", "string", "date", "time", "uuid"]
+            .iter()
+            .any(|text_type| column_type.contains(text_type))
+    });
+    match is_text {
+        true => Value::from(cell),
+        false => serde_yaml::from_str(cell).unwrap_or_else(|_| Value::from(cell)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dbt::*;
+
+    #[test]
+    fn test_translate() {
+        let raw_text = "code,name,price,customer_id\n501,melon,500,${{ REF(Alice) }}\n502,\"orange, navel\",,\n";
+
+        let seeds = DbtSeeds::new().column_type("items", "code", "varchar(5)");
+        assert_eq!(
+            seeds.translate("seeds/items.csv", raw_text).unwrap(),
+            "'501':\n  code: '501'\n  name: melon\n  price: 500\n  customer_id: ${{ REF(Alice) }}\n'502':\n  code: '502'\n  name: orange, navel\n"
+        );
+
+        // the codes are numbers without the hints
+        let seeds = DbtSeeds::new().key_column("name");
+        assert_eq!(
+            seeds.translate("seeds/items.csv", raw_text).unwrap(),
+            "melon:\n  code: 501\n  name: melon\n  price: 500\n  customer_id: ${{ REF(Alice) }}\norange, navel:\n  code: 502\n  name: orange, navel\n"
+        );
+
+        let err = DbtSeeds::new()
+            .key_column("id")
+            .translate("seeds/items.csv", raw_text)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the key column `id` is not found in the header: seeds/items.csv"
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use anyhow::Result;
+use std::collections::HashSet;
+#[cfg(feature = "sqlx")]
+use std::fs;
+
+use crate::{
+    database_seeder::file_table,
+    deserialize_named_records,
+    error::tag_error,
+    reader::{read_seed_file, ReadOptions},
+    resolver::scan_refs,
+};
+
+/// labels defined in a seed file (both as they are and prefixed with the file stem, see
+/// `DatabaseSeeder::namespace_by_file`), and the keys the file refers to with REF tags
+struct FileDependency {
+    filename: String,
+    labels: Vec<String>,
+    refs: Vec<String>,
+}
+
+fn scan_file(filename: &str, base_dir: &str, options: &ReadOptions) -> Result<FileDependency> {
+    let raw_text = read_seed_file(filename, base_dir, options)?;
+    let (masked_text, refs) =
+        scan_refs(&raw_text).map_err(|err| tag_error(filename, &raw_text, err))?;
+    let records = deserialize_named_records::<serde_yaml::Value>(filename, &masked_text)?;
+
+    Ok(FileDependency {
+        filename: filename.to_string(),
+        labels: records
+            .into_iter()
+            .flat_map(|(label, _)| [format!("{}/{}", file_table(filename), label), label])
+            .collect(),
+        refs: ref_labels(refs),
+    })
+}
+
+/// the labels the REF keys refer to: the captured columns (`<label>.<column>`) depend on the label
+fn ref_labels<I>(keys: I) -> Vec<String>
+where
+    I: IntoIterator<Item = String>,
+{
+    keys.into_iter().fold(Vec::new(), |mut labels, key| {
+        let label = key.split('.').next().unwrap_or_default().to_string();
+        if !labels.contains(&label) {
+            labels.push(label);
+        }
+        labels
+    })
+}
+
+/// sorts the files so that every file comes after the files defining the labels it refers to.
+/// the original order is kept as much as possible. REFs to the labels that are not defined in any
+/// of the files are ignored, as they may have been populated beforehand.
+pub(crate) fn sort_by_dependencies(
+    filenames: &[String],
+    base_dir: &str,
+    options: &ReadOptions,
+) -> Result<Vec<String>> {
+    let files = scan_files(filenames, base_dir, options)?;
+
+    Ok(sort_files(&files)?
+        .into_iter()
+        .map(|i| files[i].filename.clone())
+        .collect())
+}
+
+/// sorts the seeded files like `sort_by_dependencies`, from the labels of their records and the REF
+/// keys they have been resolved with (each as `(filename, labels, keys)`), rather than from the
+/// files on disk, which may have been moved or read from another base directory
+pub(crate) fn sort_seeded_files(
+    files: Vec<(String, Vec<String>, Vec<String>)>,
+) -> Result<Vec<String>> {
+    let files = files
+        .into_iter()
+        .map(|(filename, labels, keys)| FileDependency {
+            filename,
+            labels,
+            refs: ref_labels(keys),
+        })
+        .collect::<Vec<FileDependency>>();
+
+    Ok(sort_files(&files)?
+        .into_iter()
+        .map(|i| files[i].filename.clone())
+        .collect())
+}
+
+/// sorts the files like `sort_by_dependencies`, each along with the files it depends on
+pub(crate) fn sort_with_dependencies(
+    filenames: &[String],
+    base_dir: &str,
+    options: &ReadOptions,
+) -> Result<Vec<(String, Vec<String>)>> {
+    let files = scan_files(filenames, base_dir, options)?;
+    let dependencies = file_dependencies(&files);
+
+    Ok(sort_files(&files)?
+        .into_iter()
+        .map(|i| {
+            let dependencies = dependencies[i]
+                .iter()
+                .map(|&j| files[j].filename.clone())
+                .collect();
+            (files[i].filename.clone(), dependencies)
+        })
+        .collect())
+}
+
+/// Checks that every REF in the files refers to a label defined by the files populated earlier
+/// (in the order of `sort_by_dependencies`), or to a key accepted by `is_defined`, e.g. the ones
+/// populated beforehand. All the unresolved references are reported at once.
+pub(crate) fn validate_refs<F>(
+    filenames: &[String],
+    base_dir: &str,
+    options: &ReadOptions,
+    is_defined: F,
+) -> Result<()>
+where
+    F: Fn(&str) -> bool,
+{
+    let files = scan_files(filenames, base_dir, options)?;
+    let mut labels = HashSet::<&str>::new();
+    let mut unresolved = Vec::<String>::new();
+
+    for i in sort_files(&files)? {
+        let file = &files[i];
+        for key in &file.refs {
+            if !labels.contains(key.as_str()) && !is_defined(key) {
+                unresolved.push(format!("{}: REF({})", file.filename, key));
+            }
+        }
+        labels.extend(file.labels.iter().map(|label| label.as_str()));
+    }
+
+    if unresolved.is_empty() {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "{} unresolved reference(s) found:\n   {}",
+        unresolved.len(),
+        unresolved.join("\n   ")
+    ))
+}
+
+/// returns whether any of the other files refers to the labels of the file. the other files that
+/// cannot be read as seed files are ignored, as they are reported when they are populated
+#[cfg(feature = "sqlx")]
+pub(crate) fn is_referenced(
+    filename: &str,
+    others: &[String],
+    base_dir: &str,
+    options: &ReadOptions,
+) -> Result<bool> {
+    let file = scan_file(filename, base_dir, options)?;
+    Ok(others
+        .iter()
+        .filter_map(|other| scan_file(other, base_dir, options).ok())
+        .any(|other| file.labels.iter().any(|label| other.refs.contains(label))))
+}
+
+/// the yaml files in the directory, except for the profile overlays (e.g. `plans.staging.yml`)
+#[cfg(feature = "sqlx")]
+pub(crate) fn yaml_files(dir: &str) -> Result<Vec<String>> {
+    let dir = match dir {
+        "" => ".",
+        dir => dir,
+    };
+    let mut filenames = Vec::new();
+    for entry in fs::read_dir(dir)
+        .map_err(|err| anyhow::anyhow!("Can't read the directory: {}\n   err: {}", dir, err))?
+    {
+        let path = entry?.path();
+        let is_yaml = matches!(
+            path.extension().and_then(|extension| extension.to_str()),
+            Some("yml" | "yaml")
+        );
+        let is_overlay = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|stem| stem.contains('.'));
+        if !is_yaml || is_overlay {
+            continue;
+        }
+        if let Some(filename) = path.file_name().and_then(|name| name.to_str()) {
+            filenames.push(filename.to_string());
+        }
+    }
+    filenames.sort();
+    Ok(filenames)
+}
+
+#[cfg(not(feature = "rayon"))]
+fn scan_files(
+    filenames: &[String],
+    base_dir: &str,
+    options: &ReadOptions,
+) -> Result<Vec<FileDependency>> {
+    filenames
+        .iter()
+        .map(|filename| scan_file(filename, base_dir, options))
+        .collect()
+}
+
+/// the files are scanned in parallel, as parsing dominates the time for many small files
+#[cfg(feature = "rayon")]
+fn scan_files(
+    filenames: &[String],
+    base_dir: &str,
+    options: &ReadOptions,
+) -> Result<Vec<FileDependency>> {
+    use rayon::prelude::*;
+
+    filenames
+        .par_iter()
+        .map(|filename| scan_file(filename, base_dir, options))
+        .collect()
+}
+
+/// returns the indices of the files that each file depends on
+fn file_dependencies(files: &[FileDependency]) -> Vec<Vec<usize>> {
+    files
+        .iter()
+        .enumerate()
+        .map(|(i, file)| {
+            files
+                .iter()
+                .enumerate()
+                .filter(|(j, other)| {
+                    *j != i && other.labels.iter().any(|label| file.refs.contains(label))
+                })
+                .map(|(j, _)| j)
+                .collect::<Vec<usize>>()
+        })
+        .collect()
+}
+
+/// returns the indices of the files, sorted so that every file comes after its dependencies
+fn sort_files(files: &[FileDependency]) -> Result<Vec<usize>> {
+    // dependencies[i] holds the indices of the files that files[i] depends on
+    let dependencies = file_dependencies(files);
+
+    let mut sorted = Vec::<usize>::new();
+
+    while sorted.len() < files.len() {
+        let next = (0..files.len())
+            .find(|i| !sorted.contains(i) && dependencies[*i].iter().all(|j| sorted.contains(j)));
+
+        match next {
+            Some(i) => sorted.push(i),
+            None => {
+                let remaining = (0..files.len())
+                    .filter(|i| !sorted.contains(i))
+                    .map(|i| files[i].filename.as_str())
+                    .collect::<Vec<&str>>();
+                return Err(anyhow::anyhow!(
+                    "circular REF dependencies detected among the files: {}",
+                    remaining.join(", ")
+                ));
+            }
+        }
+    }
+
+    Ok(sorted)
+}
@@ -0,0 +1,187 @@
+//! `miette::Diagnostic` for the errors, available with the `miette` feature, so that the problems
+//! of the seed files are rendered with the snippets of the files, e.g.
+//!
+//! ```text
+//! CDER001 (https://github.com/estie-inc/cder#error-codes)
+//!
+//!   × failed to pre-process embedded tags: orders.yml (line 3, column 16) [CDER001]
+//!   │    err: no record is labeled `Alicia` (REF(Alicia)); did you mean `Alice`?
+//!    ╭─[orders.yml:3:16]
+//!  2 │   id: 1200
+//!  3 │   customer_id: ${{ REF(Alicia) }}
+//!    ·                ─────────┬───────
+//!    ·                         ╰── no record is labeled `Alicia`
+//!  4 │   item_id: ${{ REF(Apple) }}
+//!    ╰────
+//!   help: did you mean `Alice`?
+//! ```
+use miette::{
+    Diagnostic, LabeledSpan, MietteError, MietteSpanContents, SourceCode, SourceSpan, SpanContents,
+};
+use std::fmt;
+
+use crate::{
+    error::{byte_offset, SourceText},
+    CderError,
+};
+
+impl Diagnostic for CderError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(CderError::code(self)))
+    }
+
+    fn url<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new("https://github.com/estie-inc/cder#error-codes"))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        match self {
+            CderError::UnresolvedRef {
+                suggestion: Some(suggestion),
+                ..
+            } => Some(Box::new(format!("did you mean `{}`?", suggestion))),
+            _ => None,
+        }
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        match self {
+            CderError::TagParse { text, .. } | CderError::UnresolvedRef { text, .. } => Some(text),
+            CderError::Deserialize {
+                text: Some(text), ..
+            } => Some(text),
+            _ => None,
+        }
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let (text, line, col, message) = match self {
+            CderError::TagParse {
+                text,
+                line,
+                col,
+                message,
+                ..
+            } => (text, *line, *col, message.clone()),
+            CderError::UnresolvedRef {
+                text,
+                line,
+                col,
+                key,
+                ..
+            } => (text, *line, *col, format!("no record is labeled `{}`", key)),
+            CderError::Deserialize {
+                text: Some(text),
+                line: Some(line),
+                col: Some(col),
+                message,
+                ..
+            } => (text, *line, *col, message.clone()),
+            _ => return None,
+        };
+
+        let offset = byte_offset(text.as_str(), line, col);
+        let span = SourceSpan::new(offset.into(), span_len(&text.as_str()[offset..]));
+        Some(Box::new(std::iter::once(LabeledSpan::new_with_span(
+            Some(message),
+            span,
+        ))))
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        match self {
+            CderError::Multiple { errors, .. } => Some(Box::new(
+                errors.iter().map(|(_, err)| err as &dyn Diagnostic),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// the length of the tag (`${{ ... }}`) or the value the text starts with
+fn span_len(text: &str) -> usize {
+    let line = text.lines().next().unwrap_or_default();
+    if line.starts_with("${{") {
+        if let Some(end) = line.find("}}") {
+            return end + 2;
+        }
+    }
+    line.find(|c: char| c.is_whitespace() || matches!(c, ',' | '}' | ']'))
+        .unwrap_or(line.len())
+        .max(1)
+        .min(text.len())
+}
+
+impl SourceCode for SourceText {
+    fn read_span<'a>(
+        &'a self,
+        span: &SourceSpan,
+        context_lines_before: usize,
+        context_lines_after: usize,
+    ) -> Result<Box<dyn SpanContents<'a> + 'a>, MietteError> {
+        let contents = self
+            .as_str()
+            .read_span(span, context_lines_before, context_lines_after)?;
+        Ok(Box::new(
+            MietteSpanContents::new_named(
+                self.name().to_string(),
+                contents.data(),
+                *contents.span(),
+                contents.line(),
+                contents.column(),
+                contents.line_count(),
+            )
+            .with_language("yaml"),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{diagnostic::*, error::tag_error, resolver::resolve_tags, RefValue};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_diagnostic() {
+        let raw_text =
+            "Order1:\n  customer_id: ${{ REF(Alicia) }}\n  quantity: ${{ CTX(quantity) }}\n";
+        let dict = HashMap::from([("Alice".to_string(), RefValue::Int(1))]);
+        let err = resolve_tags(raw_text, &dict, &Default::default()).unwrap_err();
+        let err = tag_error("orders.yml", raw_text, err)
+            .downcast::<CderError>()
+            .unwrap();
+
+        let errors = err.related().unwrap().collect::<Vec<&dyn Diagnostic>>();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(Diagnostic::code(&err).unwrap().to_string(), "CDER008");
+        assert_eq!(errors[1].code().unwrap().to_string(), "CDER004");
+        assert_eq!(
+            errors[0].help().unwrap().to_string(),
+            "did you mean `Alice`?"
+        );
+
+        let label = errors[0].labels().unwrap().next().unwrap();
+        assert_eq!(
+            &raw_text[label.offset()..label.offset() + label.len()],
+            "${{ REF(Alicia) }}"
+        );
+        assert_eq!(label.label(), Some("no record is labeled `Alicia`"));
+
+        let contents = errors[1]
+            .source_code()
+            .unwrap()
+            .read_span(errors[1].labels().unwrap().next().unwrap().inner(), 0, 0)
+            .unwrap();
+        assert_eq!(contents.name(), Some("orders.yml"));
+        assert_eq!(contents.line(), 2);
+    }
+
+    #[test]
+    fn test_span_len() {
+        assert_eq!(span_len("${{ REF(Alice) }}, quantity: 2 }"), 17);
+        assert_eq!(span_len("two, purchased_at: 2023-01-02 }"), 3);
+        assert_eq!(span_len("two\n"), 3);
+        assert_eq!(span_len("\n  name: Alice"), 1);
+        assert_eq!(span_len(""), 0);
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::{anyhow, Result};
+use serde_yaml::{Mapping, Value};
+
+use crate::resolver::{mask_tags, unmask_tags};
+
+/// Translates a Django fixture, i.e. a JSON array of `{"model": ..., "pk": ..., "fields": {...}}`,
+/// into a seed file, see `DatabaseSeeder::django_fixtures`: each record is labeled with its pk,
+/// and consists of its fields. `model` is left out. Returns None when the text is not an array,
+/// e.g. a seed file of cder read along with the fixtures.
+pub(crate) fn translate_django(filename: &str, raw_text: &str) -> Result<Option<String>> {
+    let mut tags = Vec::<String>::new();
+    let text = mask_tags(raw_text, &mut tags);
+    // json is parsed as yaml, which keeps the order of the fields
+    let Ok(Value::Sequence(objects)) = serde_yaml::from_str::<Value>(&text) else {
+        return Ok(None);
+    };
+
+    let mut records = Mapping::with_capacity(objects.len());
+    for (index, object) in objects.into_iter().enumerate() {
+        let Value::Mapping(mut object) = object else {
+            return Err(anyhow!(
+                "the object #{} must be a mapping of `model`, `pk` and `fields`: {}",
+                index,
+                filename
+            ));
+        };
+        let label = match object.get("pk") {
+            Some(Value::Number(pk)) => pk.to_string(),
+            Some(Value::String(pk)) => pk.clone(),
+            _ => {
+                return Err(anyhow!(
+                    "the object #{} has no pk to be labeled with: {}",
+                    index,
+                    filename
+                ))
+            }
+        };
+        let fields = match object.shift_remove("fields") {
+            Some(Value::Mapping(fields)) => fields,
+            None | Some(Value::Null) => Mapping::new(),
+            Some(_) => {
+                return Err(anyhow!(
+                    "the fields of the pk `{}` must be a mapping: {}",
+                    label,
+                    filename
+                ))
+            }
+        };
+        if records
+            .insert(Value::String(label.clone()), Value::Mapping(fields))
+            .is_some()
+        {
+            return Err(anyhow!(
+                "the pk `{}` is given to more than one object (of different models, if any, have to be split into their files): {}",
+                label,
+                filename
+            ));
+        }
+    }
+
+    let text = serde_yaml::to_string(&records)?;
+    Ok(Some(unmask_tags(&text, &tags)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::django::*;
+
+    #[test]
+    fn test_translate_django() {
+        let raw_text = r#"[
+  {"model": "shop.customer", "pk": 1, "fields": {"name": "Alice", "emails": ["alice@example.com"]}},
+  {"model": "shop.customer", "pk": "bob", "fields": {"name": "Bob", "company_id": ${{ REF(Acme) }}}}
+]"#;
+        assert_eq!(
+            translate_django("customers.json", raw_text).unwrap().unwrap(),
+            "'1':\n  name: Alice\n  emails:\n  - alice@example.com\nbob:\n  name: Bob\n  company_id: ${{ REF(Acme) }}\n"
+        );
+
+        // the seed files of cder are left as they are
+        assert!(translate_django("customers.yml", "Alice:\n  name: Alice\n")
+            .unwrap()
+            .is_none());
+
+        let err = translate_django(
+            "customers.json",
+            r#"[{"model": "a.b", "pk": 1}, {"model": "a.c", "pk": 1}]"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("the pk `1`"), "{}", err);
+    }
+}
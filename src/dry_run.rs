@@ -0,0 +1,380 @@
+use serde::de::{DeserializeOwned, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_yaml::{Error, Value};
+use std::{cell::RefCell, fmt};
+use uuid::Uuid;
+
+use crate::RefValue;
+
+/// DryRunReport summarizes what would have been inserted by a DatabaseSeeder in dry-run mode.
+/// Every file is fully processed (tags are resolved and the records are deserialized), but none of
+/// the loaders are invoked.
+#[derive(Debug, Default, Clone)]
+pub struct DryRunReport {
+    pub files: Vec<DryRunFile>,
+}
+
+/// the records found in a single seed file
+#[derive(Debug, Clone)]
+pub struct DryRunFile {
+    pub filename: String,
+    /// labels of the records, in the order of the file
+    pub labels: Vec<String>,
+    /// the first record of the file, as it would be passed to the loader
+    pub example: Option<(String, serde_yaml::Value)>,
+}
+
+impl DryRunReport {
+    /// total number of records that would have been inserted
+    pub fn record_count(&self) -> usize {
+        self.files.iter().map(|file| file.labels.len()).sum()
+    }
+}
+
+impl fmt::Display for DryRunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for file in &self.files {
+            writeln!(f, "{}: {} record(s)", file.filename, file.labels.len())?;
+
+            if let Some((label, record)) = &file.example {
+                let record = serde_yaml::to_string(record).map_err(|_| fmt::Error)?;
+                writeln!(f, "  e.g. {}:", label)?;
+                for line in record.lines() {
+                    writeln!(f, "    {}", line)?;
+                }
+            }
+        }
+        write!(f, "total: {} record(s)", self.record_count())
+    }
+}
+
+/// the marker the REFs to a placeholder id are read as, so that the id can be typed afterwards by
+/// the field it appears in
+pub(crate) fn placeholder_marker(id: &RefValue) -> RefValue {
+    RefValue::String(format!("<cder-placeholder:{}>", id))
+}
+
+fn marker_regex() -> &'static regex::Regex {
+    static RE: once_cell::sync::OnceCell<regex::Regex> = once_cell::sync::OnceCell::new();
+    RE.get_or_init(|| regex::Regex::new(r#""?<cder-placeholder:(\d+)>"?"#).unwrap())
+}
+
+/// the number of the values that are placeholder markers as a whole
+fn count_placeholders(value: &Value) -> usize {
+    match value {
+        Value::String(text) => usize::from(placeholder_id(text).is_some()),
+        Value::Sequence(values) => values.iter().map(count_placeholders).sum(),
+        Value::Mapping(fields) => fields.values().map(count_placeholders).sum(),
+        Value::Tagged(tagged) => count_placeholders(&tagged.value),
+        _ => 0,
+    }
+}
+
+fn placeholder_id(text: &str) -> Option<u64> {
+    marker_regex()
+        .captures(text)
+        .filter(|captures| captures[0].len() == text.len())
+        .and_then(|captures| captures[1].parse().ok())
+}
+
+/// the type a placeholder id is filled as, told by the field the REF appears in
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) enum PlaceholderKind {
+    #[default]
+    Int,
+    /// e.g. `Uuid`, which is deserialized from a borrowed str
+    Uuid,
+    /// e.g. `String`, which takes the id as the text it is written in
+    Text,
+}
+
+/// tells the kinds of the placeholders in the record (in the order `fill_placeholders` visits
+/// them) by deserializing it into T: a placeholder is filled as a uuid when its field asks for a
+/// str, as a text when it asks for a string, and as an integer otherwise. the deserialization is
+/// a probe, so its errors are left to the one of the filled record
+pub(crate) fn placeholder_kinds<T>(value: &Value) -> Vec<PlaceholderKind>
+where
+    T: DeserializeOwned,
+{
+    let kinds = RefCell::new(vec![PlaceholderKind::default(); count_placeholders(value)]);
+    let _ = T::deserialize(Probe {
+        value,
+        index: 0,
+        kinds: &kinds,
+    });
+    kinds.into_inner()
+}
+
+/// replaces the placeholder markers with the ids: the n-th value that is a marker as a whole
+/// becomes the id of `kinds[n]` (a uuid is `00000000-0000-0000-0000-00000000000n`), while the
+/// markers embedded in strings become the integers
+pub(crate) fn fill_placeholders(value: &mut Value, kinds: &[PlaceholderKind], index: &mut usize) {
+    match value {
+        Value::String(text) => {
+            if let Some(id) = placeholder_id(text) {
+                *value = match kinds.get(*index).copied().unwrap_or_default() {
+                    PlaceholderKind::Int => Value::Number(id.into()),
+                    PlaceholderKind::Uuid => Value::String(Uuid::from_u128(id.into()).to_string()),
+                    PlaceholderKind::Text => Value::String(id.to_string()),
+                };
+                *index += 1;
+            } else if marker_regex().is_match(text) {
+                *text = marker_regex().replace_all(text, "$1").into_owned();
+            }
+        }
+        Value::Sequence(values) => {
+            for value in values {
+                fill_placeholders(value, kinds, index);
+            }
+        }
+        Value::Mapping(fields) => {
+            for value in fields.values_mut() {
+                fill_placeholders(value, kinds, index);
+            }
+        }
+        Value::Tagged(tagged) => fill_placeholders(&mut tagged.value, kinds, index),
+        _ => {}
+    }
+}
+
+/// deserializes a value like serde_yaml does, recording the kinds the placeholders are asked
+/// for. `index` is the one of the first placeholder in the value
+struct Probe<'de, 'p> {
+    value: &'de Value,
+    index: usize,
+    kinds: &'p RefCell<Vec<PlaceholderKind>>,
+}
+
+impl<'de> Probe<'de, '_> {
+    /// visits the value that has placeholders in it, taking the markers as `kind`
+    fn visit<V>(self, kind: PlaceholderKind, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::String(text) => match placeholder_id(text) {
+                Some(id) => {
+                    self.kinds.borrow_mut()[self.index] = kind;
+                    match kind {
+                        PlaceholderKind::Int => visitor.visit_u64(id),
+                        PlaceholderKind::Uuid => {
+                            visitor.visit_string(Uuid::from_u128(id.into()).to_string())
+                        }
+                        PlaceholderKind::Text => visitor.visit_string(id.to_string()),
+                    }
+                }
+                None => self.value.deserialize_any(visitor),
+            },
+            Value::Sequence(values) => visitor.visit_seq(ProbeSeq {
+                values: values.iter(),
+                index: self.index,
+                kinds: self.kinds,
+            }),
+            Value::Mapping(fields) => visitor.visit_map(ProbeMap {
+                fields: fields.iter(),
+                value: None,
+                index: self.index,
+                kinds: self.kinds,
+            }),
+            // the placeholders of tagged values, i.e. enums, are left as integers
+            _ => self.value.deserialize_any(visitor),
+        }
+    }
+}
+
+/// the methods that visit the value with the placeholders taken as the kind, or else deserialize
+/// it as serde_yaml does
+macro_rules! probe_methods {
+    ($($kind:ident: $($method:ident($($arg:ident: $ty:ty),*))*;)*) => {$($(
+        fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            match count_placeholders(self.value) {
+                0 => self.value.$method($($arg,)* visitor),
+                _ => self.visit(PlaceholderKind::$kind, visitor),
+            }
+        }
+    )*)*};
+}
+
+impl<'de> Deserializer<'de> for Probe<'de, '_> {
+    type Error = Error;
+
+    probe_methods! {
+        Int: deserialize_any() deserialize_bool() deserialize_i8() deserialize_i16()
+            deserialize_i32() deserialize_i64() deserialize_i128() deserialize_u8()
+            deserialize_u16() deserialize_u32() deserialize_u64() deserialize_u128()
+            deserialize_f32() deserialize_f64() deserialize_char() deserialize_bytes()
+            deserialize_byte_buf() deserialize_unit() deserialize_unit_struct(name: &'static str)
+            deserialize_seq() deserialize_tuple(len: usize)
+            deserialize_tuple_struct(name: &'static str, len: usize) deserialize_map()
+            deserialize_struct(name: &'static str, fields: &'static [&'static str])
+            deserialize_identifier();
+        Uuid: deserialize_str();
+        Text: deserialize_string();
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match count_placeholders(self.value) {
+            0 => self.value.deserialize_option(visitor),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match count_placeholders(self.value) {
+            0 => self.value.deserialize_newtype_struct(name, visitor),
+            _ => visitor.visit_newtype_struct(self),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.value.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+}
+
+struct ProbeSeq<'de, 'p> {
+    values: std::slice::Iter<'de, Value>,
+    index: usize,
+    kinds: &'p RefCell<Vec<PlaceholderKind>>,
+}
+
+impl<'de> SeqAccess<'de> for ProbeSeq<'de, '_> {
+    type Error = Error;
+
+    fn next_element_seed<S>(&mut self, seed: S) -> Result<Option<S::Value>, Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        let Some(value) = self.values.next() else {
+            return Ok(None);
+        };
+        let index = self.index;
+        self.index += count_placeholders(value);
+        seed.deserialize(Probe {
+            value,
+            index,
+            kinds: self.kinds,
+        })
+        .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
+    }
+}
+
+struct ProbeMap<'de, 'p> {
+    fields: serde_yaml::mapping::Iter<'de>,
+    value: Option<&'de Value>,
+    index: usize,
+    kinds: &'p RefCell<Vec<PlaceholderKind>>,
+}
+
+impl<'de> MapAccess<'de> for ProbeMap<'de, '_> {
+    type Error = Error;
+
+    fn next_key_seed<S>(&mut self, seed: S) -> Result<Option<S::Value>, Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        let Some((key, value)) = self.fields.next() else {
+            return Ok(None);
+        };
+        self.value = Some(value);
+        seed.deserialize(key).map(Some)
+    }
+
+    fn next_value_seed<S>(&mut self, seed: S) -> Result<S::Value, Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| <Error as serde::de::Error>::custom("value is missing"))?;
+        let index = self.index;
+        self.index += count_placeholders(value);
+        seed.deserialize(Probe {
+            value,
+            index,
+            kinds: self.kinds,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dry_run::*;
+
+    #[test]
+    fn test_fill_placeholders() {
+        #[allow(dead_code)]
+        #[derive(serde::Deserialize)]
+        struct Record {
+            company_id: i64,
+            tenant_id: Option<Uuid>,
+            code: String,
+            owner: String,
+            members: Vec<Uuid>,
+        }
+
+        let marker = placeholder_marker(&RefValue::Int(3)).to_yaml();
+        let text = format!(
+            "{{ company_id: {}, tenant_id: {}, code: item-{}, owner: {}, members: [{}, {}] }}",
+            marker, marker, marker, marker, marker, marker
+        );
+        let mut value = serde_yaml::from_str::<Value>(&text).unwrap();
+
+        let kinds = placeholder_kinds::<Record>(&value);
+        assert_eq!(
+            kinds,
+            vec![
+                PlaceholderKind::Int,
+                PlaceholderKind::Uuid,
+                PlaceholderKind::Text,
+                PlaceholderKind::Uuid,
+                PlaceholderKind::Uuid,
+            ]
+        );
+
+        fill_placeholders(&mut value, &kinds, &mut 0);
+        let uuid = "00000000-0000-0000-0000-000000000003";
+        assert_eq!(
+            value,
+            serde_yaml::from_str::<Value>(&format!(
+                "{{ company_id: 3, tenant_id: {uuid}, code: item-3, owner: '3', members: [{uuid}, {uuid}] }}"
+            ))
+            .unwrap()
+        );
+    }
+}
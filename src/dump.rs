@@ -0,0 +1,198 @@
+use anyhow::Result;
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{RefValue, ToRefValue};
+
+/// FixtureDumper takes the reverse direction of DatabaseSeeder: it converts the rows fetched
+/// from a database into labeled YAML fixtures that can be populated later.
+/// The foreign keys are replaced with `${{ REF(label) }}` tags, pointing to the labels of the rows
+/// dumped earlier, so that the relationships are preserved even when the records are given
+/// different ids on reload. Therefore the referenced tables have to be dumped first.
+///
+/// ```rust
+/// use cder::FixtureDumper;
+/// use serde::Serialize;
+/// # use anyhow::Result;
+/// #
+/// # fn main() {
+/// #     dump().unwrap();
+/// # }
+///
+/// #[derive(Serialize)]
+/// struct Company {
+///     id: i64,
+///     name: String,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct User {
+///     id: i64,
+///     name: String,
+///     company_id: i64,
+/// }
+///
+/// fn dump() -> Result<()> {
+///     // the rows fetched from the database
+///     let companies = vec![Company { id: 10, name: "MassiveSoft".to_string() }];
+///     let users = vec![User { id: 3, name: "Alice".to_string(), company_id: 10 }];
+///
+///     let mut dumper = FixtureDumper::new();
+///     let companies_yaml = dumper.dump("companies", &companies, &[])?;
+///     let users_yaml = dumper.dump("users", &users, &[("company_id", "companies")])?;
+///
+///     assert_eq!(companies_yaml, "companies_1:\n  name: MassiveSoft\n");
+///     assert_eq!(
+///         users_yaml,
+///         "users_1:\n  name: Alice\n  company_id: ${{ REF(companies_1) }}\n"
+///     );
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FixtureDumper {
+    /// the column holding the primary key, which is left out of the fixtures (default: `id`)
+    pub id_column: String,
+    // labels of the dumped rows, mapped against their tables and ids
+    labels: HashMap<(String, RefValue), String>,
+}
+
+impl Default for FixtureDumper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FixtureDumper {
+    pub fn new() -> Self {
+        FixtureDumper {
+            id_column: "id".to_string(),
+            labels: HashMap::new(),
+        }
+    }
+
+    /// Registers the label of a row that already exists in some fixture, e.g. with the ids of
+    /// `DatabaseSeeder::get_ids`, so that the rows referring to it are dumped with the same label.
+    pub fn set_label<V: ToRefValue>(&mut self, table: &str, id: V, label: &str) {
+        self.labels.insert(
+            (table.to_string(), normalize(id.to_ref_value())),
+            label.to_string(),
+        );
+    }
+
+    /// returns the label the row has been dumped with
+    pub fn get_label<V: ToRefValue>(&self, table: &str, id: V) -> Option<&str> {
+        self.labels
+            .get(&(table.to_string(), normalize(id.to_ref_value())))
+            .map(|label| label.as_str())
+    }
+
+    /// Dumps the rows of the table into YAML, labeling them as `{table}_{n}` in the given order.
+    /// `references` lists the foreign key columns along with the tables they refer to, e.g.
+    /// `&[("company_id", "companies")]`. Null foreign keys are left as they are, while the ones
+    /// pointing to the rows that have not been dumped yet result in an error.
+    pub fn dump<T>(
+        &mut self,
+        table: &str,
+        rows: &[T],
+        references: &[(&str, &str)],
+    ) -> Result<String>
+    where
+        T: Serialize,
+    {
+        let mut records = Mapping::new();
+        let mut labels = Vec::new();
+
+        for (index, row) in rows.iter().enumerate() {
+            let label = format!("{}_{}", table, index + 1);
+            let mut record = match serde_yaml::to_value(row)? {
+                Value::Mapping(record) => record,
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "rows have to be serialized as mappings: {} ({})",
+                        label,
+                        table
+                    ))
+                }
+            };
+
+            if let Some(id) = record.shift_remove(self.id_column.as_str()) {
+                labels.push((to_ref_value(&id, &label)?, label.clone()));
+            }
+
+            for (column, referenced_table) in references {
+                let Some(value) = record.get_mut(*column) else {
+                    continue;
+                };
+                if value.is_null() {
+                    continue;
+                }
+                let id = to_ref_value(value, &label)?;
+                // as REFs within the same file cannot be resolved, neither can self-references
+                let referenced_label = self
+                    .labels
+                    .get(&(referenced_table.to_string(), id.clone()))
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "the row referred by {}.{} ({}) has not been dumped yet: {}",
+                            table,
+                            column,
+                            id,
+                            referenced_table
+                        )
+                    })?;
+                *value = Value::String(ref_tag(referenced_label));
+            }
+
+            records.insert(Value::String(label), Value::Mapping(record));
+        }
+
+        for (id, label) in labels {
+            self.labels.insert((table.to_string(), id), label);
+        }
+
+        if records.is_empty() {
+            return Ok(String::new());
+        }
+        Ok(serde_yaml::to_string(&records)?)
+    }
+}
+
+fn ref_tag(label: &str) -> String {
+    format!("${{{{ REF({}) }}}}", label)
+}
+
+/// uuids are serialized as strings, so they are compared as uuids whichever way they are given
+fn normalize(value: RefValue) -> RefValue {
+    match value {
+        RefValue::String(value) => match Uuid::parse_str(&value) {
+            Ok(uuid) => RefValue::Uuid(uuid),
+            Err(_) => RefValue::String(value),
+        },
+        RefValue::Composite(values) => {
+            RefValue::Composite(values.into_iter().map(normalize).collect())
+        }
+        value => value,
+    }
+}
+
+fn to_ref_value(value: &Value, label: &str) -> Result<RefValue> {
+    match value {
+        Value::Number(number) => number
+            .as_i64()
+            .map(RefValue::Int)
+            .ok_or_else(|| anyhow::anyhow!("ids have to be integers: {} ({})", number, label)),
+        Value::String(value) => Ok(normalize(value.to_ref_value())),
+        Value::Sequence(values) => values
+            .iter()
+            .map(|value| to_ref_value(value, label))
+            .collect::<Result<Vec<RefValue>>>()
+            .map(RefValue::Composite),
+        _ => Err(anyhow::anyhow!(
+            "ids have to be numbers, strings, or sequences of them: {}",
+            label
+        )),
+    }
+}
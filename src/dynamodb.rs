@@ -0,0 +1,400 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::{
+    collections::HashMap,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use crate::{RefValue, SeedAdapter};
+
+/// the most items BatchWriteItem takes at once
+const BATCH_SIZE: usize = 25;
+
+/// the attribute value of a DynamoDB item, as `AttributeValue` of aws-sdk-dynamodb
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    S(String),
+    /// the number as its text, e.g. `500`
+    N(String),
+    Bool(bool),
+    Null(bool),
+    L(Vec<AttributeValue>),
+    M(HashMap<String, AttributeValue>),
+}
+
+/// the attributes of an item by their names
+pub type DynamoDbItem = HashMap<String, AttributeValue>;
+
+/// the result of a BatchWriteItem request
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BatchWriteOutput {
+    /// the items that have not been written, e.g. because the table has been throttled
+    pub unprocessed_items: Vec<DynamoDbItem>,
+    /// the write capacity units consumed by the request, if reported
+    pub consumed_capacity: Option<f64>,
+}
+
+/// DynamoDbClient writes the items of DynamoDbAdapter, so that the client (and its credentials
+/// and region) are configured as the rest of the application does.
+///
+/// # Examples
+/// ```rust,ignore
+/// use aws_sdk_dynamodb::types::{PutRequest, ReturnConsumedCapacity, WriteRequest};
+/// use cder::{BatchWriteOutput, DynamoDbClient, DynamoDbItem};
+///
+/// struct Dynamo(aws_sdk_dynamodb::Client);
+///
+/// impl DynamoDbClient for Dynamo {
+///     async fn batch_write_item(&self, table: &str, items: Vec<DynamoDbItem>) -> anyhow::Result<BatchWriteOutput> {
+///         let requests = items
+///             .into_iter()
+///             .map(|item| {
+///                 let put = PutRequest::builder().set_item(Some(to_sdk_item(item))).build()?;
+///                 Ok(WriteRequest::builder().put_request(put).build())
+///             })
+///             .collect::<anyhow::Result<Vec<_>>>()?;
+///         let output = self
+///             .0
+///             .batch_write_item()
+///             .request_items(table, requests)
+///             .return_consumed_capacity(ReturnConsumedCapacity::Total)
+///             .send()
+///             .await?;
+///         Ok(BatchWriteOutput {
+///             unprocessed_items: unprocessed_items(&output, table),
+///             consumed_capacity: output.consumed_capacity().first().and_then(|c| c.capacity_units()),
+///         })
+///     }
+/// }
+/// ```
+pub trait DynamoDbClient: Send + Sync {
+    /// writes the items (25 at most) into the table with BatchWriteItem
+    fn batch_write_item(
+        &self,
+        table: &str,
+        items: Vec<DynamoDbItem>,
+    ) -> impl Future<Output = Result<BatchWriteOutput>> + Send;
+}
+
+/// DynamoDbAdapter is an adapter (see `SeedAdapter`) that writes the records into a DynamoDB
+/// table with BatchWriteItem, 25 items at a time with `DatabaseSeeder::populate_many_with`. The
+/// keys of the items are rendered from the templates of `partition_key` and `sort_key`, in which
+/// `{label}` is replaced with the label of the record and `{<field>}` with the value of the field,
+/// and the REFs to the records resolve to their keys (the partition key, or a composite key of
+/// both). The unprocessed items are retried with exponential backoff, and the writes are paced to
+/// the write capacity, if given. Available with the `dynamodb` feature.
+///
+/// # Examples
+/// ```rust,no_run
+/// use cder::{DatabaseSeeder, DynamoDbAdapter, DynamoDbClient};
+/// # use anyhow::Result;
+///
+/// async fn populate_seeds<C: DynamoDbClient>(client: C) -> Result<()> {
+///     let mut seeder = DatabaseSeeder::new();
+///     let mut users = DynamoDbAdapter::new(client, "app")
+///         .partition_key("pk", "USER#{label}")
+///         .sort_key("sk", "PROFILE")
+///         .write_capacity(100.0);
+///
+///     seeder
+///         .populate_many_with::<serde_json::Value, _>("fixtures/users.yml", 25, &mut users)
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+pub struct DynamoDbAdapter<C> {
+    client: C,
+    table: String,
+    partition_key: Option<(String, String)>,
+    sort_key: Option<(String, String)>,
+    write_capacity: Option<f64>,
+    max_attempts: usize,
+    backoff: Duration,
+    /// when the next batch may be written without exceeding the write capacity
+    next_write_at: Option<Instant>,
+}
+
+impl<C> DynamoDbAdapter<C>
+where
+    C: DynamoDbClient,
+{
+    pub fn new(client: C, table: &str) -> Self {
+        Self {
+            client,
+            table: table.to_string(),
+            partition_key: None,
+            sort_key: None,
+            write_capacity: None,
+            max_attempts: 5,
+            backoff: Duration::from_millis(50),
+            next_write_at: None,
+        }
+    }
+
+    /// sets the partition key attribute and its template, e.g. `("pk", "USER#{label}")`. the
+    /// records are expected to have the key attributes themselves unless given
+    pub fn partition_key(mut self, attribute: &str, template: &str) -> Self {
+        self.partition_key = Some((attribute.to_string(), template.to_string()));
+        self
+    }
+
+    /// sets the sort key attribute and its template, e.g. `("sk", "ORDER#{placed_at}")`
+    pub fn sort_key(mut self, attribute: &str, template: &str) -> Self {
+        self.sort_key = Some((attribute.to_string(), template.to_string()));
+        self
+    }
+
+    /// paces the writes to the write capacity units per second, by the capacity consumed by each
+    /// request (or 1 unit per item, if not reported)
+    pub fn write_capacity(mut self, units_per_second: f64) -> Self {
+        self.write_capacity = Some(units_per_second);
+        self
+    }
+
+    /// sets how many times the unprocessed items are written in total (default: 5), waiting the
+    /// backoff (default: 50ms) before the first retry and doubling it every time
+    pub fn retry_unprocessed(mut self, max_attempts: usize, backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    /// the item of the record with its keys, and the id the REFs to it resolve to
+    fn item<T>(&self, label: Option<&str>, record: &T) -> Result<(RefValue, DynamoDbItem)>
+    where
+        T: Serialize,
+    {
+        let JsonValue::Object(fields) = serde_json::to_value(record)? else {
+            return Err(anyhow!(
+                "the records must be structs or mappings to be written as items (table: {})",
+                self.table
+            ));
+        };
+        let mut keys = Vec::new();
+        for (attribute, template) in [&self.partition_key, &self.sort_key].into_iter().flatten() {
+            let key = render_key(template, label, &fields)
+                .map_err(|err| anyhow!("{} (key: {}, table: {})", err, attribute, self.table))?;
+            keys.push((attribute.clone(), key));
+        }
+
+        let mut item = fields
+            .into_iter()
+            .map(|(attribute, value)| (attribute, attribute_value(value)))
+            .collect::<DynamoDbItem>();
+        let mut ids = Vec::with_capacity(keys.len());
+        for (attribute, key) in keys {
+            ids.push(RefValue::String(key.clone()));
+            item.insert(attribute, AttributeValue::S(key));
+        }
+        let id = match ids.len() {
+            0 => RefValue::String(label.unwrap_or_default().to_string()),
+            1 => ids.remove(0),
+            _ => RefValue::Composite(ids),
+        };
+        Ok((id, item))
+    }
+
+    /// writes the items in batches, retrying the unprocessed ones
+    async fn write(&mut self, items: Vec<DynamoDbItem>) -> Result<()> {
+        let mut items = items;
+        while !items.is_empty() {
+            let rest = items.split_off(items.len().min(BATCH_SIZE));
+            let mut batch = items;
+            items = rest;
+
+            let mut attempt = 1;
+            loop {
+                self.pace().await;
+                let count = batch.len();
+                let output = self.client.batch_write_item(&self.table, batch).await?;
+                self.consume(output.consumed_capacity.unwrap_or(count as f64));
+                if output.unprocessed_items.is_empty() {
+                    break;
+                }
+                if attempt >= self.max_attempts {
+                    return Err(anyhow!(
+                        "{} item(s) remain unprocessed after {} attempts (table: {})",
+                        output.unprocessed_items.len(),
+                        attempt,
+                        self.table
+                    ));
+                }
+                let delay = self
+                    .backoff
+                    .saturating_mul(2u32.saturating_pow(attempt as u32 - 1));
+                futures_timer::Delay::new(delay).await;
+                attempt += 1;
+                batch = output.unprocessed_items;
+            }
+        }
+        Ok(())
+    }
+
+    /// waits until the capacity consumed so far has been replenished
+    async fn pace(&self) {
+        if let Some(next_write_at) = self.next_write_at {
+            let now = Instant::now();
+            if next_write_at > now {
+                futures_timer::Delay::new(next_write_at - now).await;
+            }
+        }
+    }
+
+    fn consume(&mut self, units: f64) {
+        let Some(capacity) = self.write_capacity.filter(|capacity| *capacity > 0.0) else {
+            return;
+        };
+        let start = self
+            .next_write_at
+            .filter(|next_write_at| *next_write_at > Instant::now())
+            .unwrap_or_else(Instant::now);
+        self.next_write_at = Some(start + Duration::from_secs_f64(units / capacity));
+    }
+}
+
+impl<T, C> SeedAdapter<T> for DynamoDbAdapter<C>
+where
+    T: Serialize + Send,
+    C: DynamoDbClient,
+{
+    type Id = RefValue;
+
+    async fn insert(&mut self, record: T) -> Result<RefValue> {
+        let (id, item) = self.item(None, &record)?;
+        self.write(vec![item]).await?;
+        Ok(id)
+    }
+
+    async fn insert_labeled(&mut self, label: &str, record: T) -> Result<RefValue> {
+        let (id, item) = self.item(Some(label), &record)?;
+        self.write(vec![item]).await?;
+        Ok(id)
+    }
+
+    async fn insert_many(&mut self, records: Vec<T>) -> Result<Vec<RefValue>> {
+        let (ids, items) = records
+            .iter()
+            .map(|record| self.item(None, record))
+            .collect::<Result<Vec<(RefValue, DynamoDbItem)>>>()?
+            .into_iter()
+            .unzip::<_, _, Vec<RefValue>, Vec<DynamoDbItem>>();
+        self.write(items).await?;
+        Ok(ids)
+    }
+
+    async fn insert_many_labeled(&mut self, records: Vec<(String, T)>) -> Result<Vec<RefValue>> {
+        let (ids, items) = records
+            .iter()
+            .map(|(label, record)| self.item(Some(label), record))
+            .collect::<Result<Vec<(RefValue, DynamoDbItem)>>>()?
+            .into_iter()
+            .unzip::<_, _, Vec<RefValue>, Vec<DynamoDbItem>>();
+        self.write(items).await?;
+        Ok(ids)
+    }
+}
+
+/// renders the key template, e.g. `USER#{label}` or `ORDER#{placed_at}`
+fn render_key(
+    template: &str,
+    label: Option<&str>,
+    fields: &serde_json::Map<String, JsonValue>,
+) -> Result<String> {
+    let mut key = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        key.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| anyhow!("the placeholder is not closed: {}", template))?;
+        let name = &rest[start + 1..end];
+        let value = match (name, label) {
+            ("label", Some(label)) => label.to_string(),
+            ("label", None) => return Err(anyhow!("the record has no label for `{{label}}`")),
+            (name, _) => match fields.get(name) {
+                Some(JsonValue::String(value)) => value.clone(),
+                Some(JsonValue::Number(value)) => value.to_string(),
+                Some(JsonValue::Bool(value)) => value.to_string(),
+                _ => {
+                    return Err(anyhow!(
+                        "the record has no scalar field `{}` for the key",
+                        name
+                    ))
+                }
+            },
+        };
+        key.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+    key.push_str(rest);
+    Ok(key)
+}
+
+fn attribute_value(value: JsonValue) -> AttributeValue {
+    match value {
+        JsonValue::Null => AttributeValue::Null(true),
+        JsonValue::Bool(value) => AttributeValue::Bool(value),
+        JsonValue::Number(number) => AttributeValue::N(number.to_string()),
+        JsonValue::String(text) => AttributeValue::S(text),
+        JsonValue::Array(values) => {
+            AttributeValue::L(values.into_iter().map(attribute_value).collect())
+        }
+        JsonValue::Object(fields) => AttributeValue::M(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, attribute_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dynamodb::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_key() {
+        let JsonValue::Object(fields) = json!({ "email": "alice@example.com", "age": 30 }) else {
+            unreachable!()
+        };
+        assert_eq!(
+            render_key("USER#{label}#{age}", Some("Alice"), &fields).unwrap(),
+            "USER#Alice#30"
+        );
+        assert_eq!(
+            render_key("{email}", None, &fields).unwrap(),
+            "alice@example.com"
+        );
+        let err = render_key("ORDER#{placed_at}", Some("Alice"), &fields).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the record has no scalar field `placed_at` for the key"
+        );
+
+        assert_eq!(
+            attribute_value(json!({ "tags": ["a", 1], "note": null })),
+            AttributeValue::M(HashMap::from([
+                (
+                    "tags".to_string(),
+                    AttributeValue::L(vec![
+                        AttributeValue::S("a".to_string()),
+                        AttributeValue::N("1".to_string())
+                    ])
+                ),
+                ("note".to_string(), AttributeValue::Null(true)),
+            ]))
+        );
+    }
+}
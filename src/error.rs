@@ -0,0 +1,582 @@
+use std::{
+    fmt, io,
+    path::PathBuf,
+    sync::{Arc, OnceLock},
+};
+use thiserror::Error;
+
+use crate::resolver::ResolvedText;
+
+/// CderError is the kind of the errors that the callers may want to handle differently, e.g. to
+/// fall back to another file when the seed file is not found. The functions keep returning
+/// `anyhow::Result`, so the kind is told by downcasting the error. When a file has more than one
+/// problem, all of them are reported at once as `Multiple`, keyed by the labels of the records:
+///
+/// ```rust
+/// use cder::{CderError, DatabaseSeeder};
+///
+/// let mut seeder = DatabaseSeeder::new();
+/// seeder.set_dir("tests/fixtures");
+///
+/// let err = seeder.render("orders.yml").unwrap_err();
+/// match err.downcast_ref::<CderError>() {
+///     Some(CderError::Multiple { errors, .. }) => match &errors[0] {
+///         (Some(label), CderError::UnresolvedRef { key, line, .. }) => {
+///             assert_eq!(label, "Order1");
+///             assert_eq!(key, "Alice");
+///             assert_eq!(*line, 3);
+///         }
+///         (_, err) => panic!("unexpected error: {}", err),
+///     },
+///     _ => panic!("unexpected error: {}", err),
+/// }
+/// ```
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum CderError {
+    /// the seed file cannot be read, e.g. it does not exist
+    #[error("Can't open the file: {path:?} [CDER007]\n   err: {err}")]
+    FileNotFound { path: PathBuf, err: io::Error },
+    /// an embedded tag cannot be processed, e.g. its directive is not supported or the
+    /// environment variable it refers to is not set
+    #[error("failed to pre-process embedded tags: {file} (line {line}, column {col}) [{}]\n   err: {message}", tag_code(directive))]
+    TagParse {
+        /// the directive of the tag, e.g. `ENV`
+        directive: String,
+        file: String,
+        line: usize,
+        col: usize,
+        message: String,
+        text: SourceText,
+    },
+    /// no record has been populated with the label the REF tag refers to
+    #[error("failed to pre-process embedded tags: {file} (line {line}, column {col}) [CDER001]\n   err: no record is labeled `{key}` (REF({key})){}", DisplaySuggestion(suggestion))]
+    UnresolvedRef {
+        key: String,
+        file: String,
+        line: usize,
+        col: usize,
+        /// the label most likely meant, e.g. `Alice` for `Alicia`
+        suggestion: Option<String>,
+        text: SourceText,
+    },
+    /// the records (or the record of the label) cannot be deserialized. the position, if known,
+    /// is the one in the seed file as it is written, before the tags are replaced
+    #[error(
+        "deserialization failed. check the file: {file} [CDER005]{}\n   err: {message}{}",
+        DisplayLabel(label),
+        DisplayPosition(line, col, excerpt)
+    )]
+    Deserialize {
+        file: String,
+        label: Option<String>,
+        line: Option<usize>,
+        col: Option<usize>,
+        message: String,
+        /// the line of the error with a caret under the column
+        excerpt: Option<String>,
+        /// the text the position points in
+        text: Option<SourceText>,
+    },
+    /// the label has already been populated by another file (in strict mode)
+    #[error("label: {label} ({file}) has already been populated [CDER006]")]
+    DuplicateLabel { label: String, file: String },
+    /// all the problems found in the seed file, each with the label of the record it belongs to
+    /// (None for the ones found outside of any record)
+    #[error("{} problems found in the file: {file} [CDER008]{}", errors.len(), DisplayErrors(errors))]
+    Multiple {
+        file: String,
+        errors: Vec<(Option<String>, CderError)>,
+    },
+}
+
+impl CderError {
+    /// the stable code of the kind of the error, which is also included in the message, e.g.
+    /// `[CDER001]`:
+    ///
+    /// | code | error |
+    /// |------|-------|
+    /// | CDER001 | `UnresolvedRef`: no record is labeled with the REF key |
+    /// | CDER002 | `TagParse`: the directive of the tag is not supported |
+    /// | CDER003 | `TagParse`: the environment variable of the ENV (or SECRET) tag is not set |
+    /// | CDER004 | `TagParse`: the context value of the CTX tag is not given |
+    /// | CDER005 | `Deserialize`: the records do not fit the type, or are not valid yaml |
+    /// | CDER006 | `DuplicateLabel`: the label has already been populated (in strict mode) |
+    /// | CDER007 | `FileNotFound`: the seed file cannot be read |
+    /// | CDER008 | `Multiple`: more than one problem is found in the file |
+    ///
+    /// ```rust
+    /// use cder::{CderError, DatabaseSeeder};
+    ///
+    /// let mut seeder = DatabaseSeeder::new();
+    /// let err = seeder.render("missing.yml").unwrap_err();
+    /// let code = err.downcast_ref::<CderError>().map(CderError::code);
+    /// assert_eq!(code, Some("CDER007"));
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            CderError::UnresolvedRef { .. } => "CDER001",
+            CderError::TagParse { directive, .. } => tag_code(directive),
+            CderError::Deserialize { .. } => "CDER005",
+            CderError::DuplicateLabel { .. } => "CDER006",
+            CderError::FileNotFound { .. } => "CDER007",
+            CderError::Multiple { .. } => "CDER008",
+        }
+    }
+}
+
+fn tag_code(directive: &str) -> &'static str {
+    match directive {
+        "ENV" | "SECRET" => "CDER003",
+        "CTX" => "CDER004",
+        _ => "CDER002",
+    }
+}
+
+/// the text of the seed file as it is written, which the positions of the errors point in. it is
+/// shared by the errors found in the same file
+#[derive(Clone)]
+pub struct SourceText {
+    name: String,
+    text: Arc<str>,
+}
+
+impl SourceText {
+    pub(crate) fn new(name: &str, text: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            text: Arc::from(text),
+        }
+    }
+
+    /// the name of the seed file
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+/// the text itself is left out, as it can be the whole seed file
+impl fmt::Debug for SourceText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SourceText")
+            .field("name", &self.name)
+            .field("len", &self.text.len())
+            .finish()
+    }
+}
+
+struct DisplayErrors<'a>(&'a [(Option<String>, CderError)]);
+
+impl fmt::Display for DisplayErrors<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (label, err) in self.0 {
+            let message = err.to_string().replace('\n', "\n   ");
+            match label {
+                Some(label) => write!(f, "\n - {}: {}", label, message)?,
+                None => write!(f, "\n - {}", message)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+struct DisplaySuggestion<'a>(&'a Option<String>);
+
+impl fmt::Display for DisplaySuggestion<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(suggestion) => write!(f, "; did you mean `{}`?", suggestion),
+            None => Ok(()),
+        }
+    }
+}
+
+struct DisplayLabel<'a>(&'a Option<String>);
+
+impl fmt::Display for DisplayLabel<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(label) => write!(f, "\n   label: {}", label),
+            None => Ok(()),
+        }
+    }
+}
+
+struct DisplayPosition<'a>(&'a Option<usize>, &'a Option<usize>, &'a Option<String>);
+
+impl fmt::Display for DisplayPosition<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(line), Some(col)) = (self.0, self.1) {
+            write!(f, " at line {} column {}", line, col)?;
+        }
+        if let Some(excerpt) = self.2 {
+            write!(f, "\n{}", excerpt)?;
+        }
+        Ok(())
+    }
+}
+
+/// the embedded tag that failed to be replaced, at the byte offset in the text
+#[derive(Debug)]
+pub(crate) struct TagFailure {
+    pub offset: usize,
+    pub directive: String,
+    pub key: String,
+    pub err: anyhow::Error,
+}
+
+/// all the tags in the text that failed to be replaced
+#[derive(Debug)]
+pub(crate) struct TagFailures(pub Vec<TagFailure>);
+
+impl fmt::Display for TagFailures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages = self
+            .0
+            .iter()
+            .map(|failure| failure.err.to_string())
+            .collect::<Vec<String>>();
+        write!(f, "{}", messages.join("\n   "))
+    }
+}
+
+impl std::error::Error for TagFailures {}
+
+/// the REF key no record is labeled with
+#[derive(Debug)]
+pub(crate) struct UnknownRef {
+    pub key: String,
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for UnknownRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to idintify a record referred by the key: `{}`{}",
+            self.key,
+            DisplaySuggestion(&self.suggestion)
+        )
+    }
+}
+
+impl std::error::Error for UnknownRef {}
+
+/// converts the error of resolving the tags in the raw text of the file into a CderError, with
+/// the positions of the tags
+pub(crate) fn tag_error(filename: &str, raw_text: &str, err: anyhow::Error) -> anyhow::Error {
+    let failures = match err.downcast::<TagFailures>() {
+        Ok(failures) => failures.0,
+        Err(err) => {
+            return anyhow::anyhow!(
+                "failed to pre-process embedded tags: {}\n   err: {}",
+                filename,
+                err
+            )
+        }
+    };
+
+    let text = SourceText::new(filename, raw_text);
+    let errors = failures
+        .into_iter()
+        .map(|failure| {
+            let (line, col) = line_col(raw_text, failure.offset);
+            let err = match failure.directive.as_str() {
+                "REF" => CderError::UnresolvedRef {
+                    suggestion: failure
+                        .err
+                        .downcast_ref::<UnknownRef>()
+                        .and_then(|err| err.suggestion.clone()),
+                    key: failure.key,
+                    file: filename.to_string(),
+                    line,
+                    col,
+                    text: text.clone(),
+                },
+                _ => CderError::TagParse {
+                    directive: failure.directive,
+                    file: filename.to_string(),
+                    line,
+                    col,
+                    message: failure.err.to_string(),
+                    text: text.clone(),
+                },
+            };
+            (label_at(raw_text, failure.offset), err)
+        })
+        .collect();
+    aggregate(filename, errors).into()
+}
+
+/// returns the error itself when there is only one, or else CderError::Multiple
+pub(crate) fn aggregate(filename: &str, mut errors: Vec<(Option<String>, CderError)>) -> CderError {
+    match errors.len() {
+        1 => errors.remove(0).1,
+        _ => CderError::Multiple {
+            file: filename.to_string(),
+            errors,
+        },
+    }
+}
+
+/// replaces the secrets (see `ResolvedText::secrets`) in the error messages with `***`, so that
+/// the values of the ENV and SECRET tags are not echoed into e.g. CI logs. the other errors are
+/// rebuilt from their messages only when they contain any of the secrets
+pub(crate) fn mask_error(err: anyhow::Error, secrets: &[String]) -> anyhow::Error {
+    if secrets.is_empty() {
+        return err;
+    }
+    match err.downcast::<CderError>() {
+        Ok(err) => mask_cder_error(err, secrets).into(),
+        Err(err) => {
+            let message = format!("{:#}", err);
+            let masked = mask_secrets(&message, secrets);
+            match masked == message {
+                true => err,
+                false => anyhow::anyhow!(masked),
+            }
+        }
+    }
+}
+
+fn mask_cder_error(err: CderError, secrets: &[String]) -> CderError {
+    match err {
+        CderError::TagParse {
+            directive,
+            file,
+            line,
+            col,
+            message,
+            text,
+        } => CderError::TagParse {
+            directive,
+            file,
+            line,
+            col,
+            message: mask_secrets(&message, secrets),
+            text,
+        },
+        CderError::Deserialize {
+            file,
+            label,
+            line,
+            col,
+            message,
+            excerpt,
+            text,
+        } => CderError::Deserialize {
+            file,
+            label,
+            line,
+            col,
+            message: mask_secrets(&message, secrets),
+            excerpt,
+            text,
+        },
+        CderError::Multiple { file, errors } => CderError::Multiple {
+            file,
+            errors: errors
+                .into_iter()
+                .map(|(label, err)| (label, mask_cder_error(err, secrets)))
+                .collect(),
+        },
+        // the others echo the raw text only
+        err => err,
+    }
+}
+
+/// replaces every occurrence of the secrets with `***`, the longer ones first so that a secret
+/// is not left partially masked by another one it contains
+pub(crate) fn mask_secrets(text: &str, secrets: &[String]) -> String {
+    let mut secrets = secrets
+        .iter()
+        .filter(|secret| !secret.is_empty())
+        .collect::<Vec<&String>>();
+    secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+    secrets.into_iter().fold(text.to_string(), |text, secret| {
+        text.replace(secret.as_str(), "***")
+    })
+}
+
+/// the label of the record the byte offset of the text belongs to, i.e. the key of the last
+/// top-level mapping before the offset
+pub(crate) fn label_at(text: &str, offset: usize) -> Option<String> {
+    let preceding = &text[..offset.min(text.len())];
+    // the line of the offset itself, e.g. `Alice: { company_id: ${{ REF(Acme) }} }`
+    let line_end = text[preceding.len()..]
+        .find('\n')
+        .map_or(text.len(), |index| preceding.len() + index);
+    text[..line_end].lines().rev().find_map(|line| {
+        if line.starts_with(|c: char| c.is_whitespace() || c == '#' || c == '-') {
+            return None;
+        }
+        let (key, _) = line.split_once(':')?;
+        let key = key.trim().trim_matches(|c| c == '"' || c == '\'');
+        (!key.is_empty()).then(|| key.to_string())
+    })
+}
+
+/// converts the error of deserializing the resolved text into a CderError, with the position in
+/// the raw text
+pub(crate) fn deserialize_error(
+    filename: &str,
+    raw_text: &str,
+    resolved: &ResolvedText<'_>,
+    err: serde_yaml::Error,
+) -> CderError {
+    let Some(location) = err.location() else {
+        return CderError::Deserialize {
+            file: filename.to_string(),
+            label: None,
+            line: None,
+            col: None,
+            message: err.to_string(),
+            excerpt: None,
+            text: None,
+        };
+    };
+
+    let offset = resolved.raw_offset(byte_offset(
+        &resolved.text,
+        location.line(),
+        location.column(),
+    ));
+    let (line, col) = line_col(raw_text, offset);
+    // the positions in the message are the ones in the resolved text
+    static POSITION: OnceLock<regex::Regex> = OnceLock::new();
+    let position =
+        POSITION.get_or_init(|| regex::Regex::new(r",? at line \d+ column \d+").unwrap());
+    let message = position.replace_all(&err.to_string(), "").into_owned();
+
+    CderError::Deserialize {
+        file: filename.to_string(),
+        label: None,
+        line: Some(line),
+        col: Some(col),
+        message,
+        excerpt: Some(excerpt(raw_text, line, col)),
+        text: Some(SourceText::new(filename, raw_text)),
+    }
+}
+
+/// the error of deserializing the record of the label, whose position is not known
+pub(crate) fn record_error(filename: &str, label: &str, err: serde_yaml::Error) -> CderError {
+    CderError::Deserialize {
+        file: filename.to_string(),
+        label: Some(label.to_string()),
+        line: None,
+        col: None,
+        message: err.to_string(),
+        excerpt: None,
+        text: None,
+    }
+}
+
+/// the label of the record the positioned error is found in
+pub(crate) fn error_label(raw_text: &str, err: &CderError) -> Option<String> {
+    match err {
+        CderError::Deserialize {
+            line: Some(line),
+            col: Some(col),
+            ..
+        } => label_at(raw_text, byte_offset(raw_text, *line, *col)),
+        _ => None,
+    }
+}
+
+/// the byte offset of the line and the column (both 1-based, the column counted in chars)
+pub(crate) fn byte_offset(text: &str, line: usize, col: usize) -> usize {
+    let line_start = match line {
+        0 | 1 => 0,
+        _ => text
+            .match_indices('\n')
+            .nth(line - 2)
+            .map_or(text.len(), |(index, _)| index + 1),
+    };
+    text[line_start..]
+        .char_indices()
+        .nth(col.saturating_sub(1))
+        .map_or(text.len(), |(index, _)| line_start + index)
+}
+
+/// the line of the text with a caret under the column, e.g.
+/// ```text
+///   3 |   quantity: two
+///     |             ^
+/// ```
+fn excerpt(text: &str, line: usize, col: usize) -> String {
+    let number = line.to_string();
+    let padding = " ".repeat(number.len());
+    format!(
+        "  {} | {}\n  {} | {}^",
+        number,
+        text.lines().nth(line - 1).unwrap_or_default(),
+        padding,
+        " ".repeat(col - 1)
+    )
+}
+
+/// the line and the column (both 1-based, the column counted in chars) of the byte offset
+pub(crate) fn line_col(text: &str, offset: usize) -> (usize, usize) {
+    let preceding = &text[..offset.min(text.len())];
+    let line_start = preceding.rfind('\n').map_or(0, |index| index + 1);
+    (
+        preceding.matches('\n').count() + 1,
+        preceding[line_start..].chars().count() + 1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::*;
+
+    #[test]
+    fn test_mask_secrets() {
+        let secrets = vec!["sk-1234".to_string(), "sk-1234-5678".to_string()];
+        assert_eq!(
+            mask_secrets("invalid type: string \"sk-1234-5678\" (sk-1234)", &secrets),
+            "invalid type: string \"***\" (***)"
+        );
+        assert_eq!(mask_secrets("sk-1234", &[]), "sk-1234");
+
+        let err = mask_error(anyhow::anyhow!("token: sk-1234 is rejected"), &secrets);
+        assert_eq!(err.to_string(), "token: *** is rejected");
+    }
+
+    #[test]
+    fn test_line_col() {
+        let text = "Alice:\n  name: 🦊 ${{ REF(Acme) }}\n";
+        assert_eq!(line_col(text, 0), (1, 1));
+        assert_eq!(line_col(text, text.find("${{").unwrap()), (2, 11));
+        assert_eq!(line_col(text, text.len()), (3, 1));
+
+        assert_eq!(byte_offset(text, 1, 1), 0);
+        assert_eq!(byte_offset(text, 2, 11), text.find("${{").unwrap());
+        assert_eq!(
+            excerpt(text, 2, 11),
+            "  2 |   name: 🦊 ${{ REF(Acme) }}\n    |           ^"
+        );
+    }
+
+    #[test]
+    fn test_label_at() {
+        let text = "# users\nAlice:\n  name: Alice\n  # Bob: commented out\n  tags:\n    - admin\n\"Bob\": { name: Bob }\n";
+        assert_eq!(label_at(text, 0), None);
+        assert_eq!(
+            label_at(text, text.find("name").unwrap()),
+            Some("Alice".to_string())
+        );
+        assert_eq!(
+            label_at(text, text.find("admin").unwrap()),
+            Some("Alice".to_string())
+        );
+        assert_eq!(
+            label_at(text, text.find("name: Bob").unwrap()),
+            Some("Bob".to_string())
+        );
+    }
+}
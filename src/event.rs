@@ -0,0 +1,191 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::{future::Future, sync::Arc};
+
+use crate::{RefValue, SeedAdapter};
+
+/// the message EventAdapter publishes for each record
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventMessage {
+    pub topic: String,
+    /// the label of the record, or None when the record is inserted without it (i.e. with
+    /// `SeedAdapter::insert` rather than by DatabaseSeeder)
+    pub key: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub payload: Vec<u8>,
+}
+
+/// EventPublisher publishes the messages of EventAdapter, so that any broker (Kafka, NATS,
+/// a message queue etc.) and its client can be used.
+///
+/// # Examples
+/// ```rust,ignore
+/// use cder::{EventMessage, EventPublisher};
+/// use rdkafka::producer::{FutureProducer, FutureRecord};
+///
+/// struct Kafka(FutureProducer);
+///
+/// impl EventPublisher for Kafka {
+///     async fn publish(&self, message: EventMessage) -> anyhow::Result<()> {
+///         let mut record = FutureRecord::to(&message.topic).payload(&message.payload);
+///         if let Some(key) = &message.key {
+///             record = record.key(key);
+///         }
+///         self.0
+///             .send(record, std::time::Duration::from_secs(5))
+///             .await
+///             .map_err(|(err, _)| anyhow::anyhow!(err))?;
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait EventPublisher: Send + Sync {
+    fn publish(&self, message: EventMessage) -> impl Future<Output = Result<()>> + Send;
+}
+
+type Serializer = Arc<dyn Fn(&JsonValue) -> Result<Vec<u8>> + Send + Sync>;
+
+/// EventAdapter is an adapter (see `SeedAdapter`) that publishes each record as a message to the
+/// topic, keyed by the label of the record, so that the services sourcing their state from the
+/// events are seeded by replaying the fixtures. Use an adapter for each file, as with the tables.
+/// The records are serialized as JSON by default (see `serialize_with`). As nothing is returned
+/// by the broker, the REFs to the records resolve to their labels, or to the values of the field
+/// given by `id_field`. Available with the `events` feature.
+///
+/// # Examples
+/// ```rust,no_run
+/// use cder::{DatabaseSeeder, EventAdapter, EventPublisher};
+/// # use anyhow::Result;
+///
+/// async fn populate_seeds<P: EventPublisher>(publisher: P) -> Result<()> {
+///     let mut seeder = DatabaseSeeder::new();
+///     let mut users = EventAdapter::new(publisher, "users.created")
+///         .id_field("user_id")
+///         .header("source", "cder");
+///
+///     seeder
+///         .populate_with::<serde_json::Value, _>("fixtures/users.yml", &mut users)
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+pub struct EventAdapter<P> {
+    publisher: P,
+    topic: String,
+    headers: Vec<(String, String)>,
+    id_field: Option<String>,
+    serializer: Serializer,
+}
+
+impl<P> EventAdapter<P>
+where
+    P: EventPublisher,
+{
+    pub fn new(publisher: P, topic: &str) -> Self {
+        Self {
+            publisher,
+            topic: topic.to_string(),
+            headers: Vec::new(),
+            id_field: None,
+            serializer: Arc::new(|record| Ok(serde_json::to_vec(record)?)),
+        }
+    }
+
+    /// adds the header to every message
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// takes the ids of the records from the field (e.g. `id` of the aggregate the events belong
+    /// to), rather than using their labels
+    pub fn id_field(mut self, id_field: &str) -> Self {
+        self.id_field = Some(id_field.to_string());
+        self
+    }
+
+    /// serializes the records into the payloads with the function, e.g. as yaml:
+    /// `adapter.serialize_with(|record| Ok(serde_yaml::to_string(record)?.into_bytes()))`
+    pub fn serialize_with<F>(mut self, serializer: F) -> Self
+    where
+        F: Fn(&JsonValue) -> Result<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.serializer = Arc::new(serializer);
+        self
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    pub fn publisher(&self) -> &P {
+        &self.publisher
+    }
+
+    async fn publish<T>(&self, label: Option<&str>, record: T) -> Result<RefValue>
+    where
+        T: Serialize,
+    {
+        let record = serde_json::to_value(&record)?;
+        let id = match (&self.id_field, label) {
+            (Some(id_field), _) => {
+                let id = record.get(id_field).ok_or_else(|| {
+                    anyhow!(
+                        "the record has no `{}` to be its id (topic: {})",
+                        id_field,
+                        self.topic
+                    )
+                })?;
+                RefValue::from_json(id).ok_or_else(|| {
+                    anyhow!(
+                        "`{}` is not a valid id: {} (topic: {})",
+                        id_field,
+                        id,
+                        self.topic
+                    )
+                })?
+            }
+            (None, Some(label)) => RefValue::String(label.to_string()),
+            (None, None) => {
+                return Err(anyhow!(
+                    "the record has neither its label nor `id_field` to be its id (topic: {})",
+                    self.topic
+                ))
+            }
+        };
+
+        let message = EventMessage {
+            topic: self.topic.clone(),
+            key: label.map(|label| label.to_string()),
+            headers: self.headers.clone(),
+            payload: (self.serializer)(&record)?,
+        };
+        self.publisher.publish(message).await?;
+        Ok(id)
+    }
+}
+
+impl<T, P> SeedAdapter<T> for EventAdapter<P>
+where
+    T: Serialize + Send,
+    P: EventPublisher,
+{
+    type Id = RefValue;
+
+    async fn insert(&mut self, record: T) -> Result<RefValue> {
+        self.publish(None, record).await
+    }
+
+    async fn insert_labeled(&mut self, label: &str, record: T) -> Result<RefValue> {
+        self.publish(Some(label), record).await
+    }
+
+    async fn insert_many_labeled(&mut self, records: Vec<(String, T)>) -> Result<Vec<RefValue>> {
+        let mut ids = Vec::with_capacity(records.len());
+        for (label, record) in records {
+            ids.push(self.publish(Some(&label), record).await?);
+        }
+        Ok(ids)
+    }
+}
@@ -0,0 +1,117 @@
+use anyhow::Result;
+use std::future::Future;
+
+use crate::{DatabaseSeeder, SeedAdapter, ToRefValue};
+
+/// Factory builds a record from another one defined in a seed file, whose fields can be
+/// overridden in Rust before it is inserted, see `DatabaseSeeder::factory`. The record inserted
+/// is registered with the seeder under its label (the one of the base record by default, see
+/// `label`), so that the files populated afterwards can refer to it with REF tags.
+///
+/// ```rust
+/// use cder::DatabaseSeeder;
+/// # use serde::Deserialize;
+/// # use anyhow::Result;
+/// #
+/// # #[derive(Deserialize, Clone)]
+/// # struct Item {
+/// #     name: String,
+/// #     price: f64,
+/// # }
+///
+/// # fn main() -> Result<()> {
+/// let mut seeder = DatabaseSeeder::new();
+/// seeder.set_dir("tests/fixtures");
+///
+/// let id = seeder
+///     .factory::<Item>("items.yml", "Melon")?
+///     .label("CheapMelon")
+///     .with(|item| item.price = 100.0)
+///     .insert(&mut seeder, |item| {
+///         assert_eq!(item.name, "melon");
+///         assert_eq!(item.price, 100.0);
+///         Ok(10)
+///     })?;
+///
+/// assert_eq!(id, 10);
+/// assert!(seeder.get_id("CheapMelon").is_some());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Factory<T> {
+    filename: String,
+    label: String,
+    record: T,
+}
+
+impl<T> Factory<T> {
+    pub(crate) fn new(filename: &str, label: &str, record: T) -> Self {
+        Self {
+            filename: filename.to_string(),
+            label: label.to_string(),
+            record,
+        }
+    }
+
+    /// the record as it is built so far
+    pub fn record(&self) -> &T {
+        &self.record
+    }
+
+    /// registers the record with the label instead of the one of the base record, e.g. to
+    /// insert more than one record from the same base
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = label.to_string();
+        self
+    }
+
+    /// overrides the fields of the record
+    pub fn with<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut T),
+    {
+        f(&mut self.record);
+        self
+    }
+
+    /// returns the record without inserting it
+    pub fn build(self) -> T {
+        self.record
+    }
+
+    /// inserts the record with the loader, and registers its id with the seeder
+    pub fn insert<F, U>(self, seeder: &mut DatabaseSeeder, loader: F) -> Result<U>
+    where
+        F: FnOnce(T) -> Result<U>,
+        U: ToRefValue,
+    {
+        let id = loader(self.record)?;
+        seeder.register_id(&self.filename, self.label, &id);
+        Ok(id)
+    }
+
+    /// inserts the record with the async loader, and registers its id with the seeder
+    pub async fn insert_async<Fut, F, U>(self, seeder: &mut DatabaseSeeder, loader: F) -> Result<U>
+    where
+        Fut: Future<Output = Result<U>>,
+        F: FnOnce(T) -> Fut,
+        U: ToRefValue,
+    {
+        let id = loader(self.record).await?;
+        seeder.register_id(&self.filename, self.label, &id);
+        Ok(id)
+    }
+
+    /// inserts the record with the adapter (see `SeedAdapter`), and registers its id with the
+    /// seeder
+    pub async fn insert_with<A>(self, seeder: &mut DatabaseSeeder, adapter: &mut A) -> Result<A::Id>
+    where
+        T: Send,
+        A: SeedAdapter<T>,
+    {
+        let id = adapter.insert(self.record).await?;
+        seeder.register_id(&self.filename, self.label, &id);
+        Ok(id)
+    }
+}
@@ -0,0 +1,227 @@
+use anyhow::Result;
+use serde::Serialize;
+use serde_yaml::Value;
+use std::fmt::Write;
+
+use crate::{
+    database_seeder::file_table,
+    deserialize_named_records,
+    error::tag_error,
+    reader::{read_seed_file, ReadOptions},
+    resolver::{mark_refs, ref_markers},
+};
+
+/// DependencyGraph holds the records of the seed files and the records they refer to with REF
+/// tags, see `DatabaseSeeder::dependency_graph`. It can be rendered with `to_dot` or `to_mermaid`,
+/// or serialized.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct DependencyGraph {
+    /// in the order they are populated
+    pub files: Vec<FileNode>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FileNode {
+    pub filename: String,
+    pub records: Vec<RecordNode>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RecordNode {
+    pub label: String,
+    /// the REF keys of the record, e.g. `Acme`, `companies/Acme` or `Acme.slug`
+    pub refs: Vec<String>,
+}
+
+/// an edge of the graph: the ids of the referring and the referred nodes
+struct Edge {
+    from: String,
+    to: String,
+    /// false when the referred record is not defined in the files, e.g. populated beforehand
+    defined: bool,
+}
+
+impl DependencyGraph {
+    pub(crate) fn read(
+        filenames: &[String],
+        base_dir: &str,
+        options: &ReadOptions,
+    ) -> Result<Self> {
+        let files = filenames
+            .iter()
+            .map(|filename| {
+                let raw_text = read_seed_file(filename, base_dir, options)?;
+                let marked_text =
+                    mark_refs(&raw_text).map_err(|err| tag_error(filename, &raw_text, err))?;
+                let records = deserialize_named_records::<Value>(filename, &marked_text)?
+                    .into_iter()
+                    .map(|(label, record)| {
+                        // the markers are found in the serialized record, wherever they are nested
+                        let text = serde_yaml::to_string(&record)?;
+                        let mut refs = Vec::<String>::new();
+                        for key in ref_markers(&text) {
+                            if !refs.iter().any(|r| r == key) {
+                                refs.push(key.to_string());
+                            }
+                        }
+                        Ok(RecordNode { label, refs })
+                    })
+                    .collect::<Result<Vec<RecordNode>>>()?;
+
+                Ok(FileNode {
+                    filename: filename.clone(),
+                    records,
+                })
+            })
+            .collect::<Result<Vec<FileNode>>>()?;
+
+        Ok(Self { files })
+    }
+
+    /// returns the pairs of the files that refer to the records of the other files, e.g.
+    /// `("users.yml", "companies.yml")`
+    pub fn file_dependencies(&self) -> Vec<(&str, &str)> {
+        let mut dependencies = Vec::new();
+        for file in &self.files {
+            for record in &file.records {
+                for key in &record.refs {
+                    let Some((referred, _)) = self.find_record(key) else {
+                        continue;
+                    };
+                    let dependency = (file.filename.as_str(), referred.filename.as_str());
+                    if dependency.0 != dependency.1 && !dependencies.contains(&dependency) {
+                        dependencies.push(dependency);
+                    }
+                }
+            }
+        }
+        dependencies
+    }
+
+    /// renders the graph in the DOT language of Graphviz, with the records of each file clustered
+    ///
+    /// ```rust
+    /// use cder::DatabaseSeeder;
+    ///
+    /// let mut seeder = DatabaseSeeder::new();
+    /// seeder.set_dir("tests/fixtures");
+    ///
+    /// let graph = seeder.dependency_graph(&["orders.yml", "items.yml"]).unwrap();
+    /// // dot -Tsvg graph.dot > graph.svg
+    /// assert!(graph.to_dot().contains(r#""orders.yml/Order1" -> "items.yml/Apple";"#));
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cder {\n  rankdir=LR;\n  node [shape=box];\n");
+        for (index, file) in self.files.iter().enumerate() {
+            let _ = writeln!(dot, "  subgraph cluster_{} {{", index);
+            let _ = writeln!(dot, "    label={};", quote(&file.filename));
+            for record in &file.records {
+                let _ = writeln!(
+                    dot,
+                    "    {} [label={}];",
+                    quote(&node_id(&file.filename, &record.label)),
+                    quote(&record.label)
+                );
+            }
+            dot.push_str("  }\n");
+        }
+        for edge in self.edges() {
+            let style = match edge.defined {
+                true => "",
+                false => " [style=dashed]",
+            };
+            let _ = writeln!(
+                dot,
+                "  {} -> {}{};",
+                quote(&edge.from),
+                quote(&edge.to),
+                style
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// renders the graph as a mermaid flowchart, with the records of each file in a subgraph
+    pub fn to_mermaid(&self) -> String {
+        let mut ids = Vec::<String>::new();
+        let mut mermaid_id = |id: &str| match ids.iter().position(|known| known == id) {
+            Some(index) => format!("n{}", index),
+            None => {
+                ids.push(id.to_string());
+                format!("n{}", ids.len() - 1)
+            }
+        };
+
+        let mut mermaid = String::from("flowchart LR\n");
+        for (index, file) in self.files.iter().enumerate() {
+            let _ = writeln!(mermaid, "  subgraph f{}[{}]", index, quote(&file.filename));
+            for record in &file.records {
+                let id = mermaid_id(&node_id(&file.filename, &record.label));
+                let _ = writeln!(mermaid, "    {}[{}]", id, quote(&record.label));
+            }
+            mermaid.push_str("  end\n");
+        }
+        for edge in self.edges() {
+            let from = mermaid_id(&edge.from);
+            let to = mermaid_id(&edge.to);
+            let _ = match edge.defined {
+                true => writeln!(mermaid, "  {} --> {}", from, to),
+                false => writeln!(mermaid, "  {} -.-> {}[{}]", from, to, quote(&edge.to)),
+            };
+        }
+        mermaid
+    }
+
+    fn edges(&self) -> Vec<Edge> {
+        let mut edges = Vec::new();
+        for file in &self.files {
+            for record in &file.records {
+                for key in &record.refs {
+                    let to = match self.find_record(key) {
+                        Some((referred, label)) => Edge {
+                            from: node_id(&file.filename, &record.label),
+                            to: node_id(&referred.filename, label),
+                            defined: true,
+                        },
+                        None => Edge {
+                            from: node_id(&file.filename, &record.label),
+                            to: key.clone(),
+                            defined: false,
+                        },
+                    };
+                    if !edges
+                        .iter()
+                        .any(|edge: &Edge| edge.from == to.from && edge.to == to.to)
+                    {
+                        edges.push(to);
+                    }
+                }
+            }
+        }
+        edges
+    }
+
+    /// finds the file and the label of the record the REF key refers to
+    fn find_record(&self, key: &str) -> Option<(&FileNode, &str)> {
+        // the captured columns (`<label>.<column>`) belong to the label
+        let label = key.split('.').next().unwrap_or_default();
+        self.files.iter().find_map(|file| {
+            file.records
+                .iter()
+                .find(|record| {
+                    record.label == label
+                        || format!("{}/{}", file_table(&file.filename), record.label) == label
+                })
+                .map(|record| (file, record.label.as_str()))
+        })
+    }
+}
+
+fn node_id(filename: &str, label: &str) -> String {
+    format!("{}/{}", filename, label)
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\\\""))
+}
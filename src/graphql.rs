@@ -0,0 +1,192 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+
+use crate::{
+    http::json_id_at, HttpAdapter, HttpBody, HttpClient, RefValue, RetryPolicy, SeedAdapter,
+};
+
+/// GraphqlAdapter is an adapter (see `SeedAdapter`) that runs the mutation for each record, with
+/// the fields of the record as its variables, and takes the id out of `data` of the response by
+/// the path (e.g. `createUser.user.id`). The requests are sent by the HttpClient as HttpAdapter
+/// does, with the same headers and retries. The errors in the response fail the record. Available
+/// with the `graphql` feature.
+///
+/// # Examples
+/// ```rust,no_run
+/// use cder::{DatabaseSeeder, GraphqlAdapter, HttpClient};
+/// # use anyhow::Result;
+///
+/// const CREATE_USER: &str = "mutation CreateUser($input: CreateUserInput!) {
+///   createUser(input: $input) { user { id } }
+/// }";
+///
+/// async fn populate_seeds<C: HttpClient>(client: C, token: &str) -> Result<()> {
+///     let mut seeder = DatabaseSeeder::new();
+///     let mut users = GraphqlAdapter::new(client, "https://api.example.com/graphql", CREATE_USER)
+///         .input_variable("input")
+///         .id_path("createUser.user.id")
+///         .bearer_auth(token);
+///
+///     seeder
+///         .populate_with::<serde_json::Value, _>("fixtures/users.yml", &mut users)
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+pub struct GraphqlAdapter<C> {
+    http: HttpAdapter<C>,
+    mutation: String,
+    input_variable: Option<String>,
+    id_path: String,
+}
+
+impl<C> GraphqlAdapter<C>
+where
+    C: HttpClient,
+{
+    pub fn new(client: C, endpoint: &str, mutation: &str) -> Self {
+        Self {
+            http: HttpAdapter::new(client, endpoint),
+            mutation: mutation.to_string(),
+            input_variable: None,
+            id_path: "id".to_string(),
+        }
+    }
+
+    /// passes the record as the single variable (e.g. `$input`), rather than its fields as the
+    /// variables
+    pub fn input_variable(mut self, name: &str) -> Self {
+        self.input_variable = Some(name.to_string());
+        self
+    }
+
+    /// sets the path to the id under `data` of the response, separated by dots (default: `id`),
+    /// e.g. `createUser.user.id`
+    pub fn id_path(mut self, id_path: &str) -> Self {
+        self.id_path = id_path.to_string();
+        self
+    }
+
+    /// adds the header to every request
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.http = self.http.header(name, value);
+        self
+    }
+
+    /// authenticates the requests with `Authorization: Bearer <token>`
+    pub fn bearer_auth(mut self, token: &str) -> Self {
+        self.http = self.http.bearer_auth(token);
+        self
+    }
+
+    /// retries the failed requests, see `HttpAdapter::retry_policy`. the responses with errors
+    /// are not retried
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.http = self.http.retry_policy(retry_policy);
+        self
+    }
+
+    pub fn client(&self) -> &C {
+        self.http.client()
+    }
+
+    /// the body of the request for the record: `{"query": <mutation>, "variables": {...}}`
+    fn request_body(&self, record: JsonValue) -> Result<JsonValue> {
+        let variables = match &self.input_variable {
+            Some(name) => json!({ name: record }),
+            None if record.is_object() => record,
+            None => {
+                return Err(anyhow!(
+                    "the record must be a mapping to be the variables of the mutation, or be passed with `input_variable`: {}",
+                    record
+                ))
+            }
+        };
+        Ok(json!({ "query": self.mutation, "variables": variables }))
+    }
+}
+
+impl<T, C> SeedAdapter<T> for GraphqlAdapter<C>
+where
+    T: Serialize + Send,
+    C: HttpClient,
+{
+    type Id = RefValue;
+
+    async fn insert(&mut self, record: T) -> Result<RefValue> {
+        let body = self.request_body(serde_json::to_value(&record)?)?;
+        let body = self.http.send(HttpBody::Json(body)).await?;
+        let endpoint = self.http.endpoint();
+        let response = serde_json::from_str::<JsonValue>(&body).map_err(|err| {
+            anyhow!(
+                "the response is not JSON: {} (endpoint: {}, response: {})",
+                err,
+                endpoint,
+                body
+            )
+        })?;
+        if let Some(errors) = graphql_errors(&response) {
+            return Err(anyhow!(
+                "the mutation failed: {} (endpoint: {})",
+                errors,
+                endpoint
+            ));
+        }
+        json_id_at(&response, &id_pointer(&self.id_path))
+            .map_err(|err| anyhow!("{} (endpoint: {}, response: {})", err, endpoint, body))
+    }
+}
+
+/// `createUser.user.id` as the JSON pointer to it in the response, i.e. `/data/createUser/user/id`
+fn id_pointer(id_path: &str) -> String {
+    id_path
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .fold("/data".to_string(), |pointer, segment| {
+            format!(
+                "{}/{}",
+                pointer,
+                segment.replace('~', "~0").replace('/', "~1")
+            )
+        })
+}
+
+/// the messages of `errors` of the response, joined with `; `
+fn graphql_errors(response: &JsonValue) -> Option<String> {
+    let errors = response.get("errors")?.as_array()?;
+    if errors.is_empty() {
+        return None;
+    }
+    let messages = errors
+        .iter()
+        .map(
+            |error| match error.get("message").and_then(|message| message.as_str()) {
+                Some(message) => message.to_string(),
+                None => error.to_string(),
+            },
+        )
+        .collect::<Vec<String>>();
+    Some(messages.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graphql::*;
+
+    #[test]
+    fn test_id_pointer() {
+        assert_eq!(id_pointer("createUser.user.id"), "/data/createUser/user/id");
+        assert_eq!(id_pointer("id"), "/data/id");
+
+        let response = json!({
+            "data": null,
+            "errors": [{ "message": "email is taken", "path": ["createUser"] }, { "code": 1 }]
+        });
+        assert_eq!(
+            graphql_errors(&response),
+            Some("email is taken; {\"code\":1}".to_string())
+        );
+        assert_eq!(graphql_errors(&json!({ "data": {}, "errors": [] })), None);
+    }
+}
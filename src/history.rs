@@ -0,0 +1,49 @@
+use serde::Serialize;
+use std::{fmt, time::SystemTime};
+
+/// the outcome of a seed file
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SeedStatus {
+    /// all the records have been processed
+    Completed,
+    /// all the records have been processed, but some of them failed in continue-on-error mode
+    CompletedWithFailures,
+    /// the file has been aborted by a failed record
+    Failed,
+    /// the file has been skipped, as it had been populated before resuming, or it had been
+    /// applied already with version tracking enabled
+    Skipped,
+}
+
+impl fmt::Display for SeedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            SeedStatus::Completed => "completed",
+            SeedStatus::CompletedWithFailures => "completed with failures",
+            SeedStatus::Failed => "failed",
+            SeedStatus::Skipped => "skipped",
+        };
+        f.write_str(status)
+    }
+}
+
+/// a file processed by a DatabaseSeeder, see `DatabaseSeeder::history`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SeedHistoryEntry {
+    pub filename: String,
+    /// records inserted from the file
+    pub records: usize,
+    pub finished_at: SystemTime,
+    pub status: SeedStatus,
+}
+
+impl fmt::Display for SeedHistoryEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} ({} records)",
+            self.filename, self.status, self.records
+        )
+    }
+}
@@ -0,0 +1,273 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::future::Future;
+
+use crate::{RefValue, RetryPolicy, SeedAdapter};
+
+/// the request HttpAdapter sends for each record, with the record as its JSON body
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: HttpBody,
+}
+
+/// the body of an HttpRequest, sent with the content type of `HttpBody::content_type`
+#[derive(Debug, Clone, PartialEq)]
+pub enum HttpBody {
+    Json(JsonValue),
+    /// the values as newline-delimited JSON, e.g. the actions and documents of the `_bulk` API
+    NdJson(Vec<JsonValue>),
+}
+
+impl HttpBody {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            HttpBody::Json(_) => "application/json",
+            HttpBody::NdJson(_) => "application/x-ndjson",
+        }
+    }
+
+    /// the body serialized as its content type
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        match self {
+            HttpBody::Json(value) => Ok(serde_json::to_vec(value)?),
+            HttpBody::NdJson(values) => {
+                let mut bytes = Vec::new();
+                for value in values {
+                    serde_json::to_writer(&mut bytes, value)?;
+                    bytes.push(b'\n');
+                }
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+/// the status and the body of the response to an HttpRequest
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+/// HttpClient sends the requests of HttpAdapter, so that any HTTP client (and its TLS settings,
+/// proxies etc.) can be used. The errors it returns are retried along with the responses of 429
+/// and 5xx, if HttpAdapter has a retry policy.
+///
+/// # Examples
+/// ```rust,ignore
+/// use cder::{HttpClient, HttpRequest, HttpResponse};
+///
+/// struct Reqwest(reqwest::Client);
+///
+/// impl HttpClient for Reqwest {
+///     async fn send(&self, request: HttpRequest) -> anyhow::Result<HttpResponse> {
+///         let mut builder = self
+///             .0
+///             .request(request.method.parse()?, &request.url)
+///             .header("Content-Type", request.body.content_type())
+///             .body(request.body.to_bytes()?);
+///         for (name, value) in &request.headers {
+///             builder = builder.header(name, value);
+///         }
+///         let response = builder.send().await?;
+///         Ok(HttpResponse {
+///             status: response.status().as_u16(),
+///             body: response.text().await?,
+///         })
+///     }
+/// }
+/// ```
+pub trait HttpClient: Send + Sync {
+    fn send(&self, request: HttpRequest) -> impl Future<Output = Result<HttpResponse>> + Send;
+}
+
+/// HttpAdapter is an adapter (see `SeedAdapter`) that POSTs each record as JSON to the endpoint,
+/// for the resources that can only be created through an API. The id of the record is taken from
+/// the response by the JSON pointer (`/id` by default), and registered for the REFs to the record
+/// as the ids returned by the other adapters are. Use an adapter for each file, as with the
+/// tables. Available with the `http` feature.
+///
+/// # Examples
+/// ```rust,no_run
+/// use cder::{DatabaseSeeder, HttpAdapter, HttpClient, RetryPolicy};
+/// # use anyhow::Result;
+///
+/// async fn populate_seeds<C: HttpClient>(client: C, token: &str) -> Result<()> {
+///     let mut seeder = DatabaseSeeder::new();
+///     let mut users = HttpAdapter::new(client, "https://api.example.com/v1/users")
+///         .bearer_auth(token)
+///         .id_pointer("/data/id")
+///         .retry_policy(RetryPolicy::new(3));
+///
+///     seeder
+///         .populate_with::<serde_json::Value, _>("fixtures/users.yml", &mut users)
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+pub struct HttpAdapter<C> {
+    client: C,
+    endpoint: String,
+    method: String,
+    headers: Vec<(String, String)>,
+    id_pointer: String,
+    retry_policy: Option<RetryPolicy>,
+}
+
+impl<C> HttpAdapter<C>
+where
+    C: HttpClient,
+{
+    pub fn new(client: C, endpoint: &str) -> Self {
+        Self {
+            client,
+            endpoint: endpoint.to_string(),
+            method: "POST".to_string(),
+            headers: Vec::new(),
+            id_pointer: "/id".to_string(),
+            retry_policy: None,
+        }
+    }
+
+    /// sets the method of the requests (default: `POST`), e.g. `PUT`
+    pub fn method(mut self, method: &str) -> Self {
+        self.method = method.to_string();
+        self
+    }
+
+    /// adds the header to every request
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// authenticates the requests with `Authorization: Bearer <token>`
+    pub fn bearer_auth(self, token: &str) -> Self {
+        self.header("Authorization", &format!("Bearer {}", token))
+    }
+
+    /// sets the JSON pointer to the id in the response body (default: `/id`), e.g. `/data/id`
+    pub fn id_pointer(mut self, id_pointer: &str) -> Self {
+        self.id_pointer = id_pointer.to_string();
+        self
+    }
+
+    /// retries the failed requests, i.e. the errors of the client and the responses of 429 and
+    /// 5xx. the other responses are not retried
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// replaces the endpoint, e.g. to change its query
+    #[cfg(feature = "search")]
+    pub(crate) fn endpoint_url(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.to_string();
+        self
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    /// sends the body, retrying it with the retry policy, and returns the body of the
+    /// successful (2xx) response
+    pub(crate) async fn send(&self, body: HttpBody) -> Result<String> {
+        let request = HttpRequest {
+            method: self.method.clone(),
+            url: self.endpoint.clone(),
+            headers: self.headers.clone(),
+            body,
+        };
+        let send = || async {
+            let response = self.client.send(request.clone()).await?;
+            match response.status {
+                429 | 500..=599 => Err(anyhow!(
+                    "{} {} responded with {}: {}",
+                    self.method,
+                    self.endpoint,
+                    response.status,
+                    response.body
+                )),
+                _ => Ok(response),
+            }
+        };
+        let response = match &self.retry_policy {
+            Some(retry_policy) => retry_policy.run_operation(send).await?,
+            None => send().await?,
+        };
+        if !(200..300).contains(&response.status) {
+            return Err(anyhow!(
+                "{} {} responded with {}: {}",
+                self.method,
+                self.endpoint,
+                response.status,
+                response.body
+            ));
+        }
+        Ok(response.body)
+    }
+}
+
+impl<T, C> SeedAdapter<T> for HttpAdapter<C>
+where
+    T: Serialize + Send,
+    C: HttpClient,
+{
+    type Id = RefValue;
+
+    async fn insert(&mut self, record: T) -> Result<RefValue> {
+        let body = self
+            .send(HttpBody::Json(serde_json::to_value(&record)?))
+            .await?;
+        extract_id(&body, &self.id_pointer)
+            .map_err(|err| anyhow!("{} (endpoint: {}, response: {})", err, self.endpoint, body))
+    }
+}
+
+/// the id at the JSON pointer of the response body, typed as it can be parsed
+fn extract_id(body: &str, pointer: &str) -> Result<RefValue> {
+    let body = serde_json::from_str::<JsonValue>(body)
+        .map_err(|err| anyhow!("the response is not JSON: {}", err))?;
+    json_id_at(&body, pointer)
+}
+
+pub(crate) fn json_id_at(body: &JsonValue, pointer: &str) -> Result<RefValue> {
+    let id = body
+        .pointer(pointer)
+        .ok_or_else(|| anyhow!("the response has no id at {}", pointer))?;
+    RefValue::from_json(id)
+        .ok_or_else(|| anyhow!("the id at {} is not a valid id: {}", pointer, id))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_extract_id() {
+        let body = r#"{"data": {"id": 42, "key": "a7d6a3d8-6f8b-4c4e-9d4b-2f0e1c3b5a79"}, "ids": [1, "x"]}"#;
+        assert_eq!(extract_id(body, "/data/id").unwrap(), RefValue::Int(42));
+        assert_eq!(
+            extract_id(body, "/data/key").unwrap(),
+            RefValue::Uuid(Uuid::parse_str("a7d6a3d8-6f8b-4c4e-9d4b-2f0e1c3b5a79").unwrap())
+        );
+        assert_eq!(
+            extract_id(body, "/ids").unwrap(),
+            RefValue::Composite(vec![RefValue::Int(1), RefValue::String("x".to_string())])
+        );
+
+        let err = extract_id(body, "/id").unwrap_err();
+        assert_eq!(err.to_string(), "the response has no id at /id");
+        assert!(extract_id("created", "/id").is_err());
+    }
+}
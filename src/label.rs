@@ -0,0 +1,157 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    borrow::Borrow,
+    collections::{hash_map::RandomState, HashMap},
+    fmt,
+    hash::BuildHasher,
+    ops::Deref,
+    sync::Arc,
+};
+
+/// Label is the label of a record (e.g. `Alice`), interned by the DatabaseSeeder so that its ids
+/// (see `DatabaseSeeder::get_ids`), the seeded records and the failures share one allocation per
+/// label, rather than cloning the string in every hop. It reads like a `&str`, and compares with
+/// strings as they are.
+///
+/// ```rust
+/// use cder::Label;
+///
+/// let label = Label::from("Alice");
+/// assert_eq!(label, "Alice");
+/// assert!(label.starts_with("Al"));
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Label(Arc<str>);
+
+/// the ids of the records by their labels. like `Dict`, the hasher defaults to the one of std
+/// regardless of the features, so that the maps returned (e.g. by `DatabaseSeeder::get_ids`) are
+/// of the same type for every crate depending on cder
+pub type LabelMap<T, S = RandomState> = HashMap<Label, T, S>;
+
+/// the hasher of the maps keyed by labels that are kept inside the crate, e.g. the dependencies
+/// `StructLoader` resolves the tags with. with the `ahash` feature, `ahash` is used instead of
+/// SipHash, which is slow for short keys like labels
+#[cfg(feature = "ahash")]
+pub(crate) type LabelHasher = ahash::RandomState;
+#[cfg(not(feature = "ahash"))]
+pub(crate) type LabelHasher = RandomState;
+
+impl Label {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Label {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Label {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Label {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Label {
+    fn from(label: &str) -> Self {
+        Self(Arc::from(label))
+    }
+}
+
+impl From<String> for Label {
+    fn from(label: String) -> Self {
+        Self(Arc::from(label))
+    }
+}
+
+impl From<Label> for String {
+    fn from(label: Label) -> Self {
+        label.0.to_string()
+    }
+}
+
+impl PartialEq<str> for Label {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Label {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Label {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl fmt::Debug for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for Label {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Label {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(Label::from)
+    }
+}
+
+/// returns the label already in the map, if any, so that the same label is allocated only once
+pub(crate) fn intern<T, S: BuildHasher>(labels: &LabelMap<T, S>, label: String) -> Label {
+    match labels.get_key_value(label.as_str()) {
+        Some((interned, _)) => interned.clone(),
+        None => Label::from(label),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::label::*;
+
+    #[test]
+    fn test_intern() {
+        let mut labels = LabelMap::new();
+        let alice = intern(&labels, "Alice".to_string());
+        labels.insert(alice.clone(), 1);
+
+        let interned = intern(&labels, "Alice".to_string());
+        assert!(Arc::ptr_eq(&alice.0, &interned.0));
+        assert!(!Arc::ptr_eq(
+            &alice.0,
+            &intern(&labels, "Bob".to_string()).0
+        ));
+        assert_eq!(labels.get("Alice"), Some(&1));
+    }
+}
@@ -1,48 +1,300 @@
+mod adapter;
+mod builder;
+mod config;
+#[cfg(feature = "cypher")]
+mod cypher;
 mod database_seeder;
+#[cfg(feature = "dbt")]
+mod dbt;
+mod dependency;
+#[cfg(feature = "miette")]
+mod diagnostic;
+mod django;
+mod dry_run;
+mod dump;
+#[cfg(feature = "dynamodb")]
+mod dynamodb;
+mod error;
+#[cfg(feature = "events")]
+mod event;
+mod factory;
+mod graph;
+#[cfg(feature = "graphql")]
+mod graphql;
+mod history;
+#[cfg(feature = "http")]
+mod http;
+mod label;
+mod lint;
+mod logging;
+mod manifest;
+mod nested;
+mod observer;
+mod openapi;
+mod prefetch;
+mod profile;
+#[cfg(feature = "proptest")]
+mod proptest_support;
+mod rails;
 mod reader;
+mod ref_value;
+mod registry;
+mod report;
 mod resolver;
+mod retry;
+#[cfg(feature = "search")]
+mod search;
+mod shared;
+mod sql;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "sqlx")]
+mod sqlx_support;
+mod state;
 mod struct_loader;
-pub use database_seeder::DatabaseSeeder;
+pub mod testing;
+mod throttle;
+mod traits;
+mod transaction;
+mod validation;
+mod verify;
+pub use adapter::SeedAdapter;
+pub use builder::DatabaseSeederBuilder;
+#[cfg(feature = "macros")]
+pub use cder_macros::fixtures;
+#[cfg(all(feature = "macros", feature = "sqlx"))]
+pub use cder_macros::Seedable;
+pub use config::CderConfig;
+#[cfg(feature = "cypher")]
+pub use cypher::{CypherAdapter, CypherExecutor, CypherQuery};
+pub use database_seeder::{DatabaseSeeder, SeedFailure, SeedOutcome, SeededRecord};
+#[cfg(feature = "dbt")]
+pub use dbt::DbtSeeds;
+pub use dry_run::{DryRunFile, DryRunReport};
+pub use dump::FixtureDumper;
+#[cfg(feature = "dynamodb")]
+pub use dynamodb::{
+    AttributeValue, BatchWriteOutput, DynamoDbAdapter, DynamoDbClient, DynamoDbItem,
+};
+pub use error::{CderError, SourceText};
+#[cfg(feature = "events")]
+pub use event::{EventAdapter, EventMessage, EventPublisher};
+pub use factory::Factory;
+pub use graph::{DependencyGraph, FileNode, RecordNode};
+#[cfg(feature = "graphql")]
+pub use graphql::GraphqlAdapter;
+pub use history::{SeedHistoryEntry, SeedStatus};
+#[cfg(feature = "http")]
+pub use http::{HttpAdapter, HttpBody, HttpClient, HttpRequest, HttpResponse};
+pub use label::{Label, LabelMap};
+pub use lint::{LintRules, LintWarning};
+pub use manifest::{ManifestEntry, ManifestOrder, SeedManifest};
+#[cfg(feature = "indicatif")]
+pub use observer::ProgressBarObserver;
+pub use observer::SeedObserver;
+pub use openapi::OpenApiExamples;
+pub use ref_value::{Captures, RefValue, ToRefValue};
+pub use registry::AsyncLoader;
+pub use report::{FileReport, SeedReport};
+pub use retry::RetryPolicy;
+#[cfg(feature = "search")]
+pub use search::BulkIndexAdapter;
+pub use shared::SharedSeeder;
+pub use sql::{SqlFormat, SqlWriter};
+#[cfg(feature = "sqlx")]
+pub use sqlx_support::{SqlxSeedable, SqlxTable};
+pub use state::SeedState;
 pub use struct_loader::StructLoader;
+pub use transaction::{Checkpoint, Transaction};
+pub use validation::{FileStats, FileValidation, ValidationError, ValidationReport};
+pub use verify::{FieldDifference, FixtureDiff, VerificationReport};
 
 use anyhow::Result;
-use reader::read_file;
-use resolver::resolve_tags;
-use serde::de::DeserializeOwned;
-use std::collections::HashMap;
+use error::{aggregate, deserialize_error, error_label, mask_error, record_error, tag_error};
+use reader::{read_seed_file, ReadOptions};
+use resolver::{resolve_tags, ResolvedText, TagScope};
+use serde::de::{DeserializeOwned, Deserializer, MapAccess, Visitor};
+use std::{
+    borrow::Borrow,
+    collections::{hash_map::RandomState, HashMap},
+    fmt,
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+};
 
-pub type Dict<T> = HashMap<String, T>;
+/// the values by their labels. the hasher can be replaced, e.g. with `ahash::RandomState`, for the
+/// maps given to the loaders (see `StructLoader::load`)
+pub type Dict<T, S = RandomState> = HashMap<String, T, S>;
 
-fn load_named_records<T>(
+/// labeled records in the order they appear in the seed file
+type NamedRecords<T> = Vec<(String, T)>;
+
+fn load_named_records<T, S>(
     filename: &str,
     base_dir: &str,
-    dependencies: &Dict<String>,
-) -> Result<Dict<T>>
+    options: &ReadOptions,
+    dependencies: &LabelMap<RefValue, S>,
+    scope: &TagScope,
+) -> Result<NamedRecords<T>>
 where
     T: DeserializeOwned,
+    S: BuildHasher,
 {
     // read contents as string from the seed file
-    let raw_text = read_file(filename, base_dir)?;
+    let raw_text = read_seed_file(filename, base_dir, options)?;
+
+    let resolved = resolve_text(filename, &raw_text, dependencies, scope)?;
+    deserialize_resolved_records(filename, &raw_text, &resolved)
+        .map_err(|err| mask_error(err, &resolved.secrets))
+}
 
+fn resolve_text<'a, K, S>(
+    filename: &str,
+    raw_text: &'a str,
+    dependencies: &HashMap<K, RefValue, S>,
+    scope: &TagScope,
+) -> Result<ResolvedText<'a>>
+where
+    K: Borrow<str> + Eq + Hash,
+    S: BuildHasher,
+{
     // replace embedded tags before deserialization gets started
-    let parsed_text = resolve_tags(&raw_text, dependencies).map_err(|err| {
-        anyhow::anyhow!(
-            "failed to pre-process embedded tags: {}\n   err: {}",
-            filename,
-            err
-        )
-    })?;
+    resolve_tags(raw_text, dependencies, scope).map_err(|err| tag_error(filename, raw_text, err))
+}
+
+/// deserializes the records of the resolved text. the position of the error is reported in the
+/// raw text, i.e. the one the tags are written in
+fn deserialize_resolved_records<T>(
+    filename: &str,
+    raw_text: &str,
+    resolved: &ResolvedText<'_>,
+) -> Result<NamedRecords<T>>
+where
+    T: DeserializeOwned,
+{
+    let err = match serde_yaml::from_str::<OrderedRecords<T>>(&resolved.text) {
+        Ok(records) => return Ok(records.0),
+        Err(err) => deserialize_error(filename, raw_text, resolved, err),
+    };
+    // serde stops at the first error, so the rest of the records are deserialized one by one to
+    // report all of them. nothing more can be told when the text is not even valid yaml
+    let Ok(values) = serde_yaml::from_str::<OrderedRecords<serde_yaml::Value>>(&resolved.text)
+    else {
+        return Err(err.into());
+    };
+
+    let err_label = error_label(raw_text, &err);
+    let mut positioned = Some(err);
+    let mut errors = Vec::new();
+    for (label, value) in values.0 {
+        let Err(record_err) = serde_yaml::from_value::<T>(value) else {
+            continue;
+        };
+        // the positioned error is more helpful than the one without the position
+        let positioned = match err_label.as_ref() == Some(&label) {
+            true => positioned.take(),
+            false => None,
+        };
+        let err = match positioned {
+            Some(CderError::Deserialize {
+                file,
+                line,
+                col,
+                message,
+                excerpt,
+                text,
+                ..
+            }) => CderError::Deserialize {
+                file,
+                // the path to the field starts with the label, e.g. `Order2.quantity: ...`
+                message: message
+                    .strip_prefix(&format!("{}.", label))
+                    .or_else(|| message.strip_prefix(&format!("{}: ", label)))
+                    .unwrap_or(&message)
+                    .to_string(),
+                label: Some(label.clone()),
+                line,
+                col,
+                excerpt,
+                text,
+            },
+            _ => record_error(filename, &label, record_err),
+        };
+        errors.push((Some(label), err));
+    }
+    if let Some(err) = positioned {
+        errors.insert(0, (err_label, err));
+    }
+
+    Err(aggregate(filename, errors).into())
+}
 
+fn deserialize_named_records<T>(filename: &str, parsed_text: &str) -> Result<NamedRecords<T>>
+where
+    T: DeserializeOwned,
+{
     // deserialization
     // currently accepts yaml format only, but this could accept any other serde-compatible format, e.g. json
-    let records = serde_yaml::from_str(&parsed_text).map_err(|err| {
-        anyhow::anyhow!(
-            "deserialization failed. check the file: {}
-            err: {}",
-            filename,
-            err
-        )
-    })?;
-
-    Ok(records)
+    let records: OrderedRecords<T> =
+        serde_yaml::from_str(parsed_text).map_err(|err| CderError::Deserialize {
+            file: filename.to_string(),
+            label: None,
+            line: None,
+            col: None,
+            message: err.to_string(),
+            excerpt: None,
+            text: None,
+        })?;
+
+    Ok(records.0)
+}
+
+/// deserializes a mapping of labeled records, keeping the order of the records.
+/// when a label is duplicated, the latter record overwrites the earlier one
+struct OrderedRecords<T>(NamedRecords<T>);
+
+impl<'de, T> serde::Deserialize<'de> for OrderedRecords<T>
+where
+    T: serde::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct OrderedRecordsVisitor<T>(PhantomData<T>);
+
+        impl<'de, T> Visitor<'de> for OrderedRecordsVisitor<T>
+        where
+            T: serde::Deserialize<'de>,
+        {
+            type Value = OrderedRecords<T>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a mapping of labeled records")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                // the number of the records is known when the document has been parsed already
+                let len = map.size_hint().unwrap_or_default();
+                let mut records = Vec::<(String, T)>::with_capacity(len);
+                let mut positions = HashMap::<String, usize>::with_capacity(len);
+
+                while let Some((label, record)) = map.next_entry::<String, T>()? {
+                    match positions.get(&label) {
+                        Some(&position) => records[position].1 = record,
+                        None => {
+                            positions.insert(label.clone(), records.len());
+                            records.push((label, record));
+                        }
+                    }
+                }
+                Ok(OrderedRecords(records))
+            }
+        }
+
+        deserializer.deserialize_map(OrderedRecordsVisitor(PhantomData))
+    }
 }
@@ -0,0 +1,331 @@
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::fmt;
+
+use crate::{
+    database_seeder::file_table,
+    deserialize_named_records,
+    error::{label_at, tag_error},
+    reader::{read_seed_file, ReadOptions},
+    resolver::{mark_refs, ref_markers, replace_tags, ResolvedText},
+};
+
+/// LintRules configures the conventions checked by `DatabaseSeeder::lint`. They can be placed in
+/// the `[lint]` table of `.cder.toml` (see `CderConfig`).
+///
+/// ```toml
+/// # .cder.toml
+/// [lint]
+/// label_pattern = "^[A-Z][A-Za-z0-9]*$"
+/// unused_labels = true
+/// env_defaults = true
+/// max_fields = 30
+/// max_record_bytes = 4096
+/// max_value_len = 1000
+/// ```
+///
+/// The rules are also checked while the files are populated, see `DatabaseSeeder::warnings`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintRules {
+    /// the regex every label has to match, e.g. `^[A-Z][A-Za-z0-9]*$` (not checked by default)
+    pub label_pattern: Option<String>,
+    /// reports the labels that no REF in the files refers to, within the files some of whose
+    /// labels are referred to (e.g. the companies no user belongs to). default: true
+    pub unused_labels: bool,
+    /// reports the ENV tags without default values, e.g. `${{ ENV(API_KEY) }}` rather than
+    /// `${{ ENV(API_KEY:-dummy) }}`. default: true
+    pub env_defaults: bool,
+    /// the maximum number of the fields of a record
+    pub max_fields: Option<usize>,
+    /// the maximum size of a record in bytes, measured in yaml
+    pub max_record_bytes: Option<usize>,
+    /// the maximum length of a string value in chars
+    pub max_value_len: Option<usize>,
+}
+
+impl Default for LintRules {
+    fn default() -> Self {
+        Self {
+            label_pattern: None,
+            unused_labels: true,
+            env_defaults: true,
+            max_fields: None,
+            max_record_bytes: None,
+            max_value_len: None,
+        }
+    }
+}
+
+/// a record (or a file, when label is None) that breaks one of the LintRules, or that is worth
+/// noting while the file is populated (see `DatabaseSeeder::warnings`)
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LintWarning {
+    pub filename: String,
+    pub label: Option<String>,
+    /// the name of the rule, e.g. `unused_labels`
+    pub rule: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "{}: {}: ", self.filename, label)?,
+            None => write!(f, "{}: ", self.filename)?,
+        }
+        write!(f, "{} [{}]", self.message, self.rule)
+    }
+}
+
+/// the records of a file with their REF keys, and the ENV keys without default values
+struct LintedFile {
+    filename: String,
+    records: Vec<(String, Value, Vec<String>)>,
+    env_keys: Vec<String>,
+}
+
+pub(crate) fn lint_files(
+    filenames: &[String],
+    base_dir: &str,
+    options: &ReadOptions,
+    rules: &LintRules,
+) -> Result<Vec<LintWarning>> {
+    let label_pattern = label_pattern(rules)?;
+
+    let mut files = Vec::new();
+    for filename in filenames {
+        let raw_text = read_seed_file(filename, base_dir, options)?;
+        let marked_text =
+            mark_refs(&raw_text).map_err(|err| tag_error(filename, &raw_text, err))?;
+        let records = deserialize_named_records::<Value>(filename, &marked_text)?
+            .into_iter()
+            .map(|(label, record)| {
+                let text = serde_yaml::to_string(&record)?;
+                let refs = ref_markers(&text).map(str::to_string).collect();
+                Ok((label, record, refs))
+            })
+            .collect::<Result<Vec<(String, Value, Vec<String>)>>>()?;
+
+        files.push(LintedFile {
+            filename: filename.clone(),
+            records,
+            env_keys: env_keys_without_defaults(&raw_text)?,
+        });
+    }
+
+    let mut warnings = Vec::new();
+    for file in &files {
+        if rules.env_defaults {
+            for key in &file.env_keys {
+                warnings.push(LintWarning {
+                    filename: file.filename.clone(),
+                    label: None,
+                    rule: "env_defaults",
+                    message: format!("ENV({}) has no default value", key),
+                });
+            }
+        }
+
+        let is_referred = |label: &str| {
+            let namespaced = format!("{}/{}", file_table(&file.filename), label);
+            files
+                .iter()
+                .flat_map(|other| &other.records)
+                .flat_map(|(_, _, refs)| refs)
+                // the captured columns (`<label>.<column>`) refer to the label
+                .map(|key| key.split('.').next().unwrap_or_default())
+                .any(|key| key == label || key == namespaced)
+        };
+        // the files none of whose labels are referred to are not meant to be referred to
+        let is_referred_file =
+            rules.unused_labels && file.records.iter().any(|(label, ..)| is_referred(label));
+
+        for (label, record, _) in &file.records {
+            warnings.extend(record_warnings(
+                &file.filename,
+                label,
+                record,
+                rules,
+                label_pattern.as_ref(),
+            )?);
+            if is_referred_file && !is_referred(label) {
+                warnings.push(unused_label_warning(&file.filename, label));
+            }
+        }
+    }
+
+    Ok(warnings)
+}
+
+pub(crate) fn label_pattern(rules: &LintRules) -> Result<Option<Regex>> {
+    rules
+        .label_pattern
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .map_err(|err| anyhow::anyhow!("invalid label_pattern\n   err: {}", err))
+}
+
+/// checks the rules on the record itself: `label_pattern`, `max_fields`, `max_record_bytes` and
+/// `max_value_len`
+pub(crate) fn record_warnings(
+    filename: &str,
+    label: &str,
+    record: &Value,
+    rules: &LintRules,
+    label_pattern: Option<&Regex>,
+) -> Result<Vec<LintWarning>> {
+    let mut warnings = Vec::new();
+    let mut warn = |rule: &'static str, message: String| {
+        warnings.push(LintWarning {
+            filename: filename.to_string(),
+            label: Some(label.to_string()),
+            rule,
+            message,
+        })
+    };
+
+    if let Some(pattern) = label_pattern {
+        if !pattern.is_match(label) {
+            warn(
+                "label_pattern",
+                format!("the label does not match `{}`", pattern),
+            );
+        }
+    }
+    if let (Some(max_fields), Some(fields)) = (rules.max_fields, record.as_mapping()) {
+        if fields.len() > max_fields {
+            warn(
+                "max_fields",
+                format!("{} fields exceed the limit of {}", fields.len(), max_fields),
+            );
+        }
+    }
+    if let Some(max_record_bytes) = rules.max_record_bytes {
+        let bytes = serde_yaml::to_string(record)?.len();
+        if bytes > max_record_bytes {
+            warn(
+                "max_record_bytes",
+                format!("{} bytes exceed the limit of {}", bytes, max_record_bytes),
+            );
+        }
+    }
+    if let Some(max_value_len) = rules.max_value_len {
+        for (path, len) in long_strings(record, "", max_value_len) {
+            warn(
+                "max_value_len",
+                format!(
+                    "the value of `{}` is {} chars long, exceeding the limit of {}",
+                    path, len, max_value_len
+                ),
+            );
+        }
+    }
+
+    Ok(warnings)
+}
+
+/// the ENV and CTX tags of the resolved text that have fallen back to their defaults
+pub(crate) fn defaults_used_warnings(
+    filename: &str,
+    raw_text: &str,
+    resolved: &ResolvedText<'_>,
+) -> Vec<LintWarning> {
+    resolved
+        .defaults_used
+        .iter()
+        .map(|(offset, tag)| LintWarning {
+            filename: filename.to_string(),
+            label: label_at(raw_text, *offset),
+            rule: "defaults_used",
+            message: format!("{} is not given, and the default value is used", tag),
+        })
+        .collect()
+}
+
+pub(crate) fn unused_label_warning(filename: &str, label: &str) -> LintWarning {
+    LintWarning {
+        filename: filename.to_string(),
+        label: Some(label.to_string()),
+        rule: "unused_labels",
+        message: "the label is not referred to by any REF".to_string(),
+    }
+}
+
+/// the paths (e.g. `profile.bio`, `tags[2]`) and the lengths of the strings longer than the limit
+fn long_strings(value: &Value, path: &str, limit: usize) -> Vec<(String, usize)> {
+    match value {
+        Value::String(text) => {
+            let len = text.chars().count();
+            match len > limit {
+                true => vec![(path.to_string(), len)],
+                false => Vec::new(),
+            }
+        }
+        Value::Mapping(mapping) => mapping
+            .iter()
+            .flat_map(|(key, value)| {
+                let key = match key {
+                    Value::String(key) => key.clone(),
+                    key => serde_yaml::to_string(key)
+                        .map(|key| key.trim_end().to_string())
+                        .unwrap_or_default(),
+                };
+                let path = match path.is_empty() {
+                    true => key,
+                    false => format!("{}.{}", path, key),
+                };
+                long_strings(value, &path, limit)
+            })
+            .collect(),
+        Value::Sequence(values) => values
+            .iter()
+            .enumerate()
+            .flat_map(|(index, value)| long_strings(value, &format!("{}[{}]", path, index), limit))
+            .collect(),
+        Value::Tagged(tagged) => long_strings(&tagged.value, path, limit),
+        _ => Vec::new(),
+    }
+}
+
+/// the keys of the ENV tags without default values, in the order they appear (without duplicates)
+fn env_keys_without_defaults(raw_text: &str) -> Result<Vec<String>> {
+    let mut keys = Vec::<String>::new();
+    replace_tags(raw_text, |directive, key, default| {
+        if directive == "ENV" && default.is_none() && !keys.iter().any(|known| known == key) {
+            keys.push(key.to_string());
+        }
+        Ok("0".to_string())
+    })?;
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lint::*;
+
+    #[test]
+    fn test_env_keys_without_defaults() {
+        let raw_text = "Alice:\n  email: ${{ ENV(ALICE_EMAIL) }}\n  token: ${{ ENV(TOKEN:-dummy) }}\nBob:\n  email: ${{ ENV(ALICE_EMAIL) }}\n  plan: ${{ REF(Free) }}\n";
+        assert_eq!(
+            env_keys_without_defaults(raw_text).unwrap(),
+            vec!["ALICE_EMAIL".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_long_strings() {
+        let record = serde_yaml::from_str::<Value>(
+            "name: Alice\nprofile:\n  bio: a very long biography\ntags:\n  - short\n  - too long a tag\n",
+        )
+        .unwrap();
+        assert_eq!(
+            long_strings(&record, "", 10),
+            vec![("profile.bio".to_string(), 21), ("tags[1]".to_string(), 14)]
+        );
+        assert!(long_strings(&record, "", 30).is_empty());
+    }
+}
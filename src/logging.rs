@@ -0,0 +1,114 @@
+//! the events of the seeding pipeline (reading, resolving, parsing and inserting the records),
+//! emitted with `tracing` when the `tracing` feature is enabled, or else with `log` when the `log`
+//! feature is. nothing is emitted without them.
+
+/// emits the event of the level (`error`, `warn`, `info`, `debug` or `trace`) with the fields,
+/// e.g. `log_event!(info, "seeded the file", file = filename, inserted = 3)`. the fields are
+/// recorded with their `Display`, and appended to the message as `key=value` with `log`
+macro_rules! log_event {
+    ($level:ident, $message:literal $(, $key:ident = $value:expr)* $(,)?) => {{
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($key = %$value,)* $message);
+        #[cfg(all(feature = "log", not(feature = "tracing")))]
+        log::$level!(concat!($message $(, " ", stringify!($key), "={}")*) $(, $value)*);
+        #[cfg(not(any(feature = "log", feature = "tracing")))]
+        {
+            $(let _ = &$value;)*
+        }
+    }};
+}
+
+pub(crate) use log_event;
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use serde::Deserialize;
+    use std::{
+        fmt,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    use crate::DatabaseSeeder;
+
+    /// records the events as `<level> <message> <key>=<value>...`
+    #[derive(Clone, Default)]
+    struct EventRecorder(Arc<Mutex<Vec<String>>>);
+
+    struct EventVisitor(String);
+
+    impl Visit for EventVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            match field.name() {
+                "message" => self.0.push_str(&format!(" {:?}", value)),
+                name => self.0.push_str(&format!(" {}={:?}", name, value)),
+            }
+        }
+    }
+
+    impl Subscriber for EventRecorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut visitor = EventVisitor(event.metadata().level().to_string());
+            event.record(&mut visitor);
+            self.0.lock().unwrap().push(visitor.0);
+        }
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    #[derive(Deserialize)]
+    struct Item {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    #[test]
+    fn test_log_event() {
+        let recorder = EventRecorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let mut seeder = DatabaseSeeder::new();
+            seeder.set_dir("tests/fixtures");
+            seeder.populate("items.yml", |_: Item| Ok(1)).unwrap();
+        });
+
+        let events = recorder.0.lock().unwrap().clone();
+        let stages = events
+            .iter()
+            .map(|event| event.split(" file=").next().unwrap_or_default())
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            stages,
+            vec![
+                "DEBUG read the seed file",
+                "DEBUG resolved the tags",
+                "DEBUG parsed the records",
+                "INFO seeding the file",
+                "TRACE inserted the record",
+                "TRACE inserted the record",
+                "TRACE inserted the record",
+                "TRACE inserted the record",
+                "INFO seeded the file",
+            ]
+        );
+        assert!(events[2].contains(" file=items.yml records=4 elapsed_ms="));
+        assert!(events[4].contains(" label=Melon id=1"));
+        assert!(events[8].contains(" inserted=4 skipped=0 failed=0 elapsed_ms="));
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::{
+    dependency::sort_by_dependencies,
+    reader::{read_file, ReadOptions},
+};
+
+/// SeedManifest lists the seed files to be populated, along with their models/tables, the profiles
+/// they belong to, and the order they are populated in. It is usually deserialized from a yaml
+/// file (e.g. `seeds.yml`) with `DatabaseSeeder::from_manifest`.
+///
+/// ```yaml
+/// # optional: relative to the directory the manifest is placed in, which is the default
+/// base_dir: fixtures
+/// # optional: `listed` (default) populates the files as listed below,
+/// # `dependencies` sorts them by their REF dependencies
+/// order: dependencies
+/// seeds:
+///   - file: orders.yml
+///     model: Order
+///   - file: items.yml
+///     model: Item
+///     table: products
+///   - file: test_customers.yml
+///     model: Customer
+///     # only populated when either of the profiles is active
+///     profiles: [development, staging]
+/// ```
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SeedManifest {
+    pub base_dir: Option<String>,
+    #[serde(default)]
+    pub order: ManifestOrder,
+    pub seeds: Vec<ManifestEntry>,
+}
+
+/// the order the files listed in a manifest are populated in
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestOrder {
+    #[default]
+    Listed,
+    Dependencies,
+}
+
+/// a seed file listed in a manifest
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ManifestEntry {
+    pub file: String,
+    pub model: Option<String>,
+    pub table: Option<String>,
+    /// profiles the file belongs to. the file is populated regardless of the profile if empty
+    #[serde(default)]
+    pub profiles: Vec<String>,
+}
+
+impl ManifestEntry {
+    /// the table name specified in the manifest, or the file stem of the seed file
+    pub fn table(&self) -> &str {
+        self.table.as_deref().unwrap_or_else(|| {
+            Path::new(&self.file)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or(&self.file)
+        })
+    }
+
+    /// checks if the file has to be populated with the given profile
+    pub fn is_active(&self, profile: Option<&str>) -> bool {
+        self.profiles.is_empty()
+            || profile.is_some_and(|profile| self.profiles.iter().any(|p| p == profile))
+    }
+}
+
+impl SeedManifest {
+    /// reads the manifest from the specified file
+    pub fn load(path: &str) -> Result<Self> {
+        let raw_text = read_file(path, "")?;
+        let mut manifest: SeedManifest = serde_yaml::from_str(&raw_text).map_err(|err| {
+            anyhow::anyhow!(
+                "failed to parse the manifest: {}
+            err: {}",
+                path,
+                err
+            )
+        })?;
+
+        // the base directory is relative to the manifest, as well as the default one
+        let parent = Path::new(path).parent().unwrap_or(Path::new(""));
+        let base_dir = match manifest.base_dir.as_deref() {
+            Some(base_dir) => parent.join(base_dir),
+            None => parent.to_path_buf(),
+        };
+        manifest.base_dir = Some(base_dir.to_string_lossy().into_owned());
+
+        Ok(manifest)
+    }
+
+    /// returns the entries to be populated with the given profile, in the order they have to be
+    /// populated
+    pub fn entries(&self, profile: Option<&str>) -> Result<Vec<ManifestEntry>> {
+        self.entries_with(profile, &ReadOptions::default())
+    }
+
+    /// the entries like `entries`, with the files read by the options of the seeder
+    pub(crate) fn entries_with(
+        &self,
+        profile: Option<&str>,
+        options: &ReadOptions,
+    ) -> Result<Vec<ManifestEntry>> {
+        let entries = self
+            .seeds
+            .iter()
+            .filter(|entry| entry.is_active(profile))
+            .cloned()
+            .collect::<Vec<ManifestEntry>>();
+
+        match self.order {
+            ManifestOrder::Listed => Ok(entries),
+            ManifestOrder::Dependencies => {
+                let filenames = entries
+                    .iter()
+                    .map(|entry| entry.file.clone())
+                    .collect::<Vec<String>>();
+                let base_dir = self.base_dir.as_deref().unwrap_or_default();
+
+                let sorted = sort_by_dependencies(&filenames, base_dir, options)?;
+                Ok(sorted
+                    .iter()
+                    .filter_map(|filename| entries.iter().find(|entry| &entry.file == filename))
+                    .cloned()
+                    .collect())
+            }
+        }
+    }
+}
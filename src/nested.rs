@@ -0,0 +1,147 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde_yaml::Value;
+
+use crate::{error::record_error, NamedRecords, RefValue};
+
+/// a parent record, along with the (not yet deserialized) child records nested under it
+pub(crate) struct NestedRecord<T> {
+    pub(crate) label: String,
+    pub(crate) record: T,
+    pub(crate) children: NamedRecords<Value>,
+}
+
+/// splits the child records off their parents, and deserializes the parents
+pub(crate) fn split_nested_records<T>(
+    filename: &str,
+    named_values: NamedRecords<Value>,
+    children_key: &str,
+) -> Result<Vec<NestedRecord<T>>>
+where
+    T: DeserializeOwned,
+{
+    named_values
+        .into_iter()
+        .map(|(label, mut value)| {
+            let children = take_children(filename, &label, &mut value, children_key)?;
+            let record = deserialize_value(filename, &label, value)?;
+            Ok(NestedRecord {
+                label,
+                record,
+                children,
+            })
+        })
+        .collect()
+}
+
+/// Removes the children from the parent record. The children can be either a sequence, labeled
+/// `<parent>/<key>/<index>`, or a mapping, labeled `<parent>/<key>/<label>`.
+fn take_children(
+    filename: &str,
+    label: &str,
+    value: &mut Value,
+    children_key: &str,
+) -> Result<NamedRecords<Value>> {
+    let children = value
+        .as_mapping_mut()
+        .and_then(|mapping| mapping.shift_remove(children_key));
+
+    match children {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Sequence(children)) => Ok(children
+            .into_iter()
+            .enumerate()
+            .map(|(index, child)| (format!("{}/{}/{}", label, children_key, index), child))
+            .collect()),
+        Some(Value::Mapping(children)) => children
+            .into_iter()
+            .map(|(child_label, child)| match child_label.as_str() {
+                Some(child_label) => {
+                    Ok((format!("{}/{}/{}", label, children_key, child_label), child))
+                }
+                None => Err(anyhow::anyhow!(
+                    "the labels of the children must be strings: {} ({})",
+                    label,
+                    filename
+                )),
+            })
+            .collect(),
+        Some(_) => Err(anyhow::anyhow!(
+            "`{}` must be a sequence or a mapping of records: {} ({})",
+            children_key,
+            label,
+            filename
+        )),
+    }
+}
+
+/// replaces `${{ PARENT }}` tags in the child with the id of the parent, then deserializes it
+pub(crate) fn deserialize_child<C>(
+    filename: &str,
+    label: &str,
+    mut value: Value,
+    parent_id: &RefValue,
+) -> Result<C>
+where
+    C: DeserializeOwned,
+{
+    inject_parent(&mut value, &serde_yaml::to_value(parent_id)?);
+    deserialize_value(filename, label, value)
+}
+
+fn inject_parent(value: &mut Value, parent_id: &Value) {
+    match value {
+        Value::String(text) if is_parent_tag(text) => *value = parent_id.clone(),
+        Value::Sequence(values) => values
+            .iter_mut()
+            .for_each(|value| inject_parent(value, parent_id)),
+        Value::Mapping(mapping) => mapping
+            .values_mut()
+            .for_each(|value| inject_parent(value, parent_id)),
+        Value::Tagged(tagged) => inject_parent(&mut tagged.value, parent_id),
+        _ => {}
+    }
+}
+
+/// matches `${{ PARENT }}`, with or without the spaces inside the braces
+pub(crate) fn is_parent_tag(text: &str) -> bool {
+    text.trim()
+        .strip_prefix("${{")
+        .and_then(|text| text.strip_suffix("}}"))
+        .is_some_and(|directive| directive.trim() == "PARENT")
+}
+
+fn deserialize_value<T>(filename: &str, label: &str, value: Value) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let record =
+        serde_yaml::from_value::<T>(value).map_err(|err| record_error(filename, label, err))?;
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nested::*;
+
+    #[test]
+    fn test_inject_parent() {
+        let mut value: Value = serde_yaml::from_str(
+            r#"
+            customer_id: ${{ PARENT }}
+            note: "${{PARENT}} is not replaced within a text"
+            lines:
+              - owner: ${{ PARENT }}
+            "#,
+        )
+        .unwrap();
+        inject_parent(&mut value, &Value::from(42));
+
+        assert_eq!(value["customer_id"], Value::from(42));
+        assert_eq!(
+            value["note"],
+            Value::from("${{PARENT}} is not replaced within a text")
+        );
+        assert_eq!(value["lines"][0]["owner"], Value::from(42));
+    }
+}
@@ -0,0 +1,81 @@
+use crate::RefValue;
+
+/// SeedObserver gets notified of the progress of a DatabaseSeeder, so that long seeding runs can
+/// report where they are. All the methods do nothing by default; implement the ones you need and
+/// pass the observer to `DatabaseSeeder::set_observer`.
+///
+/// # Examples
+/// ```rust
+/// use cder::{DatabaseSeeder, SeedObserver};
+///
+/// struct Logger;
+///
+/// impl SeedObserver for Logger {
+///     fn on_file_done(&mut self, filename: &str, total: usize, inserted: usize) {
+///         println!("{}: inserted {} of {} records", filename, inserted, total);
+///     }
+/// }
+///
+/// let mut seeder = DatabaseSeeder::new();
+/// seeder.set_observer(Logger);
+/// ```
+pub trait SeedObserver {
+    /// called when the records in the file have been loaded, before any of them is inserted
+    fn on_file_start(&mut self, _filename: &str, _total: usize) {}
+
+    /// called every time a record has been inserted
+    fn on_record_inserted(&mut self, _filename: &str, _label: &str, _id: &RefValue) {}
+
+    /// called when all the records in the file have been processed
+    fn on_file_done(&mut self, _filename: &str, _total: usize, _inserted: usize) {}
+}
+
+/// SeedObserver that renders a progress bar per file with `indicatif`
+#[cfg(feature = "indicatif")]
+pub struct ProgressBarObserver {
+    progress: indicatif::MultiProgress,
+    current: Option<indicatif::ProgressBar>,
+}
+
+#[cfg(feature = "indicatif")]
+impl ProgressBarObserver {
+    pub fn new() -> Self {
+        Self {
+            progress: indicatif::MultiProgress::new(),
+            current: None,
+        }
+    }
+}
+
+#[cfg(feature = "indicatif")]
+impl Default for ProgressBarObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "indicatif")]
+impl SeedObserver for ProgressBarObserver {
+    fn on_file_start(&mut self, filename: &str, total: usize) {
+        let bar = self.progress.add(indicatif::ProgressBar::new(total as u64));
+        if let Ok(style) = indicatif::ProgressStyle::with_template(
+            "{prefix} [{elapsed_precise}] {wide_bar} {pos}/{len}",
+        ) {
+            bar.set_style(style);
+        }
+        bar.set_prefix(filename.to_string());
+        self.current = Some(bar);
+    }
+
+    fn on_record_inserted(&mut self, _filename: &str, _label: &str, _id: &RefValue) {
+        if let Some(bar) = &self.current {
+            bar.inc(1);
+        }
+    }
+
+    fn on_file_done(&mut self, _filename: &str, _total: usize, _inserted: usize) {
+        if let Some(bar) = self.current.take() {
+            bar.finish();
+        }
+    }
+}
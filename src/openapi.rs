@@ -0,0 +1,192 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::{Map, Value as JsonValue};
+use std::collections::BTreeMap;
+
+/// OpenApiExamples turns the loaded records into the examples of an OpenAPI document, keyed by
+/// their labels, so that the API docs and the seeds come from the same fixtures. The records are
+/// serialized as the API would respond with them, i.e. with the serde attributes of the structs.
+///
+/// ```rust
+/// use cder::{Dict, OpenApiExamples, StructLoader};
+/// # use serde::{Deserialize, Serialize};
+/// # use anyhow::Result;
+/// #
+/// # #[derive(Deserialize, Serialize)]
+/// # struct Item {
+/// #     name: String,
+/// #     price: f64,
+/// # }
+///
+/// # fn main() -> Result<()> {
+/// let mut loader = StructLoader::<Item>::new("items.yml", "tests/fixtures");
+/// loader.load(&Dict::<String>::new())?;
+///
+/// let mut examples = OpenApiExamples::new();
+/// examples.add("Item", loader.get_all_records()?)?;
+///
+/// // `examples` of a media type, e.g. of the response of `GET /items/{id}`
+/// let melon = &examples.media_type_examples("Item")["Melon"];
+/// assert_eq!(melon["value"]["name"], "melon");
+///
+/// // or merged into `components.examples` of the document
+/// let mut document = serde_json::json!({ "openapi": "3.1.0", "components": { "schemas": {} } });
+/// examples.merge_into(&mut document)?;
+/// assert_eq!(document["components"]["examples"]["Melon"]["summary"], "Item: Melon");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OpenApiExamples {
+    /// the records by their labels (sorted, so that the documents do not change on every run) by
+    /// the names of the schemas
+    schemas: BTreeMap<String, BTreeMap<String, JsonValue>>,
+}
+
+impl OpenApiExamples {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds the records as the examples of the schema, keyed by their labels, e.g. the records of
+    /// `StructLoader::get_all_records`
+    pub fn add<'a, K, T, I>(&mut self, schema: &str, records: I) -> Result<&mut Self>
+    where
+        K: AsRef<str> + 'a,
+        T: Serialize + 'a,
+        I: IntoIterator<Item = (&'a K, &'a T)>,
+    {
+        let examples = self.schemas.entry(schema.to_string()).or_default();
+        for (label, record) in records {
+            let value = serde_json::to_value(record).map_err(|err| {
+                anyhow!(
+                    "failed to serialize the record: {} ({})\n   err: {}",
+                    label.as_ref(),
+                    schema,
+                    err
+                )
+            })?;
+            examples.insert(label.as_ref().to_string(), value);
+        }
+        Ok(self)
+    }
+
+    /// the names of the schemas the examples have been added to
+    pub fn schemas(&self) -> Vec<&str> {
+        self.schemas.keys().map(String::as_str).collect()
+    }
+
+    /// the examples of the schema as `examples` of a media type object, i.e.
+    /// `{"<label>": {"value": <record>}}`
+    pub fn media_type_examples(&self, schema: &str) -> JsonValue {
+        let examples = self
+            .schemas
+            .get(schema)
+            .into_iter()
+            .flatten()
+            .map(|(label, value)| (label.clone(), serde_json::json!({ "value": value })))
+            .collect::<Map<String, JsonValue>>();
+        JsonValue::Object(examples)
+    }
+
+    /// the examples of all the schemas as `components.examples`, i.e.
+    /// `{"<label>": {"summary": "<schema>: <label>", "value": <record>}}`. fails when a label is
+    /// given to the records of more than one schema
+    pub fn components(&self) -> Result<JsonValue> {
+        Ok(JsonValue::Object(self.example_objects()?))
+    }
+
+    fn example_objects(&self) -> Result<Map<String, JsonValue>> {
+        let mut examples = Map::new();
+        for (schema, records) in &self.schemas {
+            for (label, value) in records {
+                let example = serde_json::json!({
+                    "summary": format!("{}: {}", schema, label),
+                    "value": value,
+                });
+                if examples.insert(label.clone(), example).is_some() {
+                    return Err(anyhow!(
+                        "the label `{}` is given to the examples of more than one schema",
+                        label
+                    ));
+                }
+            }
+        }
+        Ok(examples)
+    }
+
+    /// adds the examples to `components.examples` of the OpenAPI document (parsed from either
+    /// JSON or YAML), overwriting the ones of the same labels
+    pub fn merge_into(&self, document: &mut JsonValue) -> Result<()> {
+        let example_objects = self.example_objects()?;
+        let invalid = |path: &str| anyhow!("`{}` of the document must be an object", path);
+        let document = document
+            .as_object_mut()
+            .ok_or_else(|| invalid("the root"))?;
+        let examples = document
+            .entry("components")
+            .or_insert_with(|| JsonValue::Object(Map::new()))
+            .as_object_mut()
+            .ok_or_else(|| invalid("components"))?
+            .entry("examples")
+            .or_insert_with(|| JsonValue::Object(Map::new()))
+            .as_object_mut()
+            .ok_or_else(|| invalid("components.examples"))?;
+        examples.extend(example_objects);
+        Ok(())
+    }
+
+    /// `components.examples` as a YAML document, e.g. to be referred to by `$ref` from the spec
+    pub fn to_yaml(&self) -> Result<String> {
+        let document = serde_json::json!({ "components": { "examples": self.components()? } });
+        Ok(serde_yaml::to_string(&document)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::openapi::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_openapi_examples() {
+        let users = [
+            ("Bob".to_string(), json!({ "name": "Bob" })),
+            ("Alice".to_string(), json!({ "name": "Alice" })),
+        ];
+        let mut examples = OpenApiExamples::new();
+        examples
+            .add("User", users.iter().map(|(label, user)| (label, user)))
+            .unwrap();
+        assert_eq!(
+            examples.media_type_examples("User"),
+            json!({ "Alice": { "value": { "name": "Alice" } }, "Bob": { "value": { "name": "Bob" } } })
+        );
+        assert_eq!(examples.media_type_examples("Company"), json!({}));
+
+        let mut document =
+            json!({ "openapi": "3.0.3", "components": { "examples": { "Old": {} } } });
+        examples.merge_into(&mut document).unwrap();
+        assert_eq!(
+            document["components"]["examples"],
+            json!({
+                "Old": {},
+                "Bob": { "summary": "User: Bob", "value": { "name": "Bob" } },
+                "Alice": { "summary": "User: Alice", "value": { "name": "Alice" } }
+            })
+        );
+
+        let companies = [("Alice".to_string(), json!({ "name": "Alice Inc." }))];
+        examples
+            .add(
+                "Company",
+                companies.iter().map(|(label, company)| (label, company)),
+            )
+            .unwrap();
+        let err = examples.components().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the label `Alice` is given to the examples of more than one schema"
+        );
+    }
+}
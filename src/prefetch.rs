@@ -0,0 +1,75 @@
+use serde_yaml::Value;
+
+#[cfg(feature = "rayon")]
+use crate::{
+    deserialize_resolved_records,
+    reader::{read_seed_file, ReadOptions},
+    resolve_text,
+    resolver::TagScope,
+    LabelMap, RefValue,
+};
+use crate::{reader::SeedText, resolver::ResolvedText, NamedRecords};
+
+/// a seed file read, resolved and parsed ahead of its seeding, see `DatabaseSeeder::populate_all`
+pub(crate) struct PrefetchedFile {
+    pub raw_text: SeedText,
+    /// None when the file has no tags, see `ResolvedText::detach`
+    pub resolved: Option<ResolvedText<'static>>,
+    pub values: NamedRecords<Value>,
+}
+
+/// reads and parses the files in parallel. the files that fail are left out, so that they are
+/// read again when populated and fail with the errors as they would without prefetching
+#[cfg(feature = "rayon")]
+pub(crate) fn prefetch_files(
+    filenames: &[&String],
+    base_dir: &str,
+    options: &ReadOptions,
+    dict: &LabelMap<RefValue>,
+    scope: &TagScope,
+) -> Vec<(String, PrefetchedFile)> {
+    use rayon::prelude::*;
+
+    filenames
+        .par_iter()
+        .filter_map(|filename| {
+            prefetch_file(filename, base_dir, options, dict, scope)
+                .map(|file| (filename.to_string(), file))
+        })
+        .collect()
+}
+
+#[cfg(feature = "rayon")]
+fn prefetch_file(
+    filename: &str,
+    base_dir: &str,
+    options: &ReadOptions,
+    dict: &LabelMap<RefValue>,
+    scope: &TagScope,
+) -> Option<PrefetchedFile> {
+    let raw_text = read_seed_file(filename, base_dir, options).ok()?;
+    let resolved = resolve_text(filename, &raw_text, dict, scope).ok()?;
+    let values = deserialize_resolved_records::<Value>(filename, &raw_text, &resolved).ok()?;
+    let resolved = resolved.detach();
+    Some(PrefetchedFile {
+        raw_text,
+        resolved,
+        values,
+    })
+}
+
+/// converts the parsed records into the type, or None when any of them does not fit so that the
+/// text is deserialized again for the positioned errors
+pub(crate) fn from_values<T>(values: NamedRecords<Value>) -> Option<NamedRecords<T>>
+where
+    T: serde::de::DeserializeOwned,
+{
+    values
+        .into_iter()
+        .map(|(label, value)| {
+            serde_yaml::from_value::<T>(value)
+                .ok()
+                .map(|record| (label, record))
+        })
+        .collect()
+}
@@ -0,0 +1,132 @@
+use anyhow::Result;
+use serde_yaml::Value;
+use std::path::Path;
+
+use crate::NamedRecords;
+
+/// the key of the profiles a conditional record is populated for
+const PROFILES_KEY: &str = "_profiles";
+
+/// the overlay file of the profile, e.g. `fixtures/plans.yml` => `fixtures/plans.staging.yml`
+pub(crate) fn overlay_filename(filename: &str, profile: &str) -> String {
+    let path = Path::new(filename);
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(filename);
+    let overlay = match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => format!("{}.{}.{}", stem, profile, extension),
+        None => format!("{}.{}", stem, profile),
+    };
+    path.with_file_name(overlay).to_string_lossy().into_owned()
+}
+
+/// Applies the overlay records to the base ones: the fields of a record with the same label are
+/// overwritten, and the records with new labels are appended. Then leaves out the conditional
+/// records (the ones with `_profiles`) that are not meant for the profile.
+pub(crate) fn apply_profile(
+    filename: &str,
+    profile: &str,
+    mut named_values: NamedRecords<Value>,
+    overlay: NamedRecords<Value>,
+) -> Result<NamedRecords<Value>> {
+    for (label, value) in overlay {
+        match named_values.iter_mut().find(|(name, _)| *name == label) {
+            Some((_, Value::Mapping(base))) => {
+                base.extend(value_to_mapping(filename, &label, value)?)
+            }
+            Some((_, base)) => *base = value,
+            None => named_values.push((label, value)),
+        }
+    }
+
+    let mut records = Vec::new();
+    for (label, mut value) in named_values {
+        let profiles = value
+            .as_mapping_mut()
+            .and_then(|mapping| mapping.shift_remove(PROFILES_KEY));
+        if let Some(profiles) = profiles {
+            if !includes_profile(filename, &label, &profiles, profile)? {
+                continue;
+            }
+        }
+        records.push((label, value));
+    }
+    Ok(records)
+}
+
+fn value_to_mapping(filename: &str, label: &str, value: Value) -> Result<serde_yaml::Mapping> {
+    match value {
+        Value::Mapping(mapping) => Ok(mapping),
+        _ => Err(anyhow::anyhow!(
+            "the overlay of a record must be a mapping: {} ({})",
+            label,
+            filename
+        )),
+    }
+}
+
+/// `_profiles` can be either a profile or a sequence of them
+fn includes_profile(filename: &str, label: &str, profiles: &Value, profile: &str) -> Result<bool> {
+    match profiles {
+        Value::String(name) => Ok(name == profile),
+        Value::Sequence(names) => Ok(names.iter().any(|name| name.as_str() == Some(profile))),
+        _ => Err(anyhow::anyhow!(
+            "`{}` must be a profile or a sequence of profiles: {} ({})",
+            PROFILES_KEY,
+            label,
+            filename
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::profile::*;
+
+    #[test]
+    fn test_overlay_filename() {
+        assert_eq!(
+            overlay_filename("fixtures/plans.yml", "staging"),
+            "fixtures/plans.staging.yml"
+        );
+        assert_eq!(overlay_filename("plans", "staging"), "plans.staging");
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let named_values: NamedRecords<Value> = vec![
+            (
+                "Basic".to_string(),
+                serde_yaml::from_str("price: 10").unwrap(),
+            ),
+            (
+                "Trial".to_string(),
+                serde_yaml::from_str("price: 0\n_profiles: [development, staging]").unwrap(),
+            ),
+            (
+                "Debug".to_string(),
+                serde_yaml::from_str("price: 0\n_profiles: development").unwrap(),
+            ),
+        ];
+        let overlay = vec![
+            (
+                "Basic".to_string(),
+                serde_yaml::from_str("price: 1").unwrap(),
+            ),
+            (
+                "Staff".to_string(),
+                serde_yaml::from_str("price: 0").unwrap(),
+            ),
+        ];
+
+        let records = apply_profile("plans.yml", "staging", named_values, overlay).unwrap();
+        let labels = records
+            .iter()
+            .map(|(label, _)| label.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(labels, vec!["Basic", "Trial", "Staff"]);
+        assert_eq!(records[0].1["price"], Value::from(1));
+        assert!(records[1].1.get(PROFILES_KEY).is_none());
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use proptest::{
+    arbitrary::{any, Arbitrary},
+    collection::{vec, SizeRange},
+    strategy::Strategy,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_yaml::Value;
+
+use crate::{
+    deserialize_resolved_records, label::LabelHasher, resolve_text, resolver::TagScope,
+    testing::FixtureBuilder, Label, LabelMap, RefValue,
+};
+
+/// the name of the file the errors of `round_trip` are reported with
+const ROUND_TRIP_FILE: &str = "round_trip.yml";
+
+/// Generates the records of `T` with unique labels (e.g. `Alice_0`, `X9_1`), as in a seed file.
+/// Available with the `proptest` feature.
+///
+/// ```rust
+/// use cder::testing::{labeled_records, round_trip};
+/// use proptest::prelude::*;
+///
+/// proptest! {
+///     fn test_orders(records in labeled_records::<(String, i64)>(0..10)) {
+///         prop_assert_eq!(round_trip(&records).unwrap(), records);
+///     }
+/// }
+/// # fn main() { test_orders(); }
+/// ```
+pub fn labeled_records<T>(size: impl Into<SizeRange>) -> impl Strategy<Value = Vec<(String, T)>>
+where
+    T: Arbitrary,
+{
+    vec(("[A-Z][A-Za-z0-9]{0,11}", any::<T>()), size).prop_map(|records| {
+        // the index after the last underscore keeps the labels unique
+        records
+            .into_iter()
+            .enumerate()
+            .map(|(index, (label, record))| (format!("{}_{}", label, index), record))
+            .collect()
+    })
+}
+
+/// Generates the `FixtureBuilder`s of the records of `T` (see `labeled_records`), e.g. to stress
+/// the loaders with the files written by `FixtureBuilder::write_temp`. Available with the
+/// `proptest` feature.
+pub fn fixture_builder<T>(size: impl Into<SizeRange>) -> impl Strategy<Value = FixtureBuilder>
+where
+    T: Arbitrary + Serialize,
+{
+    labeled_records::<T>(size).prop_map(|records| FixtureBuilder::new().records(records))
+}
+
+/// Writes the records in yaml with their strings and integers replaced by REF tags, then resolves
+/// the tags and deserializes the records back, as the seed files are loaded (without reading or
+/// writing any file). The records returned are expected to equal the given ones, unless the
+/// values are lost in yaml (e.g. `NaN`). Available with the `proptest` feature.
+pub fn round_trip<T>(records: &[(String, T)]) -> Result<Vec<(String, T)>>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let mut dependencies = LabelMap::<RefValue, LabelHasher>::default();
+    let mut builder = FixtureBuilder::new();
+    for (label, record) in records {
+        let record = inject_refs(serde_yaml::to_value(record)?, &mut dependencies);
+        builder = builder.record(label, record);
+    }
+    let raw_text = builder.to_yaml()?;
+
+    let resolved = resolve_text(
+        ROUND_TRIP_FILE,
+        &raw_text,
+        &dependencies,
+        &TagScope::default(),
+    )?;
+    deserialize_resolved_records(ROUND_TRIP_FILE, &raw_text, &resolved)
+}
+
+/// replaces the strings and the integers of the value (but not the keys of the mappings) with REF
+/// tags, each referring to a new label resolved to the value replaced
+fn inject_refs(value: Value, dependencies: &mut LabelMap<RefValue, LabelHasher>) -> Value {
+    let mut inject = |ref_value: RefValue| {
+        let key = format!("cder_round_trip_{}", dependencies.len());
+        let tag = format!("${{{{ REF({}) }}}}", key);
+        dependencies.insert(Label::from(key), ref_value);
+        Value::String(tag)
+    };
+
+    match value {
+        Value::String(value) => inject(RefValue::String(value)),
+        Value::Number(number) => match number.as_i64() {
+            Some(value) => inject(RefValue::Int(value)),
+            None => Value::Number(number),
+        },
+        Value::Sequence(values) => Value::Sequence(
+            values
+                .into_iter()
+                .map(|value| inject_refs(value, dependencies))
+                .collect(),
+        ),
+        Value::Mapping(mapping) => Value::Mapping(
+            mapping
+                .into_iter()
+                .map(|(key, value)| (key, inject_refs(value, dependencies)))
+                .collect(),
+        ),
+        Value::Tagged(mut tagged) => {
+            tagged.value = inject_refs(tagged.value, dependencies);
+            Value::Tagged(tagged)
+        }
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::proptest_support::*;
+
+    #[test]
+    fn test_inject_refs() {
+        let mut dependencies = LabelMap::<RefValue, LabelHasher>::default();
+        let record = serde_yaml::from_str::<Value>("name: Alice\nage: 20\nscore: 1.5\n").unwrap();
+        let injected = inject_refs(record, &mut dependencies);
+
+        assert_eq!(
+            serde_yaml::to_string(&injected).unwrap(),
+            "name: ${{ REF(cder_round_trip_0) }}\nage: ${{ REF(cder_round_trip_1) }}\nscore: 1.5\n"
+        );
+        assert_eq!(
+            dependencies.get("cder_round_trip_0"),
+            Some(&RefValue::String("Alice".to_string()))
+        );
+        assert_eq!(
+            dependencies.get("cder_round_trip_1"),
+            Some(&RefValue::Int(20))
+        );
+    }
+}
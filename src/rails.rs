@@ -0,0 +1,351 @@
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde_yaml::{Mapping, Value};
+use std::path::Path;
+
+use crate::{
+    error::line_col,
+    reader::file_exists,
+    resolver::{has_tag_placeholder, mask_tags, tag_placeholder, unmask_tags},
+};
+
+/// the record the others inherit their fields from (`<<: *DEFAULTS`), which Rails does not insert
+const DEFAULTS_LABEL: &str = "DEFAULTS";
+
+/// the fixture set options of Rails (e.g. `model_class`), which are not a record either
+const FIXTURE_OPTIONS: &str = "_fixture";
+
+/// Translates a Rails fixture into a seed file, see `DatabaseSeeder::rails_fixtures`:
+/// - the ERB expressions that read the environment variables (e.g. `<%= ENV['API_KEY'] %>`) are
+///   replaced with ENV tags, and the literals with their values. the comments (`<%# ... %>`) are
+///   dropped, and any other ERB (e.g. loops) is rejected
+/// - the merge keys (`<<: *DEFAULTS`) are applied, and `DEFAULTS` and `_fixture` are left out
+/// - `$LABEL` is replaced with the label of the record
+/// - an association given by label (e.g. `customer: alice`, or `owner: alice (Customer)` if it is
+///   polymorphic) is replaced with the REF tag to its id (`customer_id: ${{ REF(alice) }}`), when
+///   the fixture of the associated table (`customers.yml`) is found next to the file
+pub(crate) fn translate_rails(filename: &str, base_dir: &str, raw_text: &str) -> Result<String> {
+    let mut tags = Vec::<String>::new();
+    let text = translate_erb(&mask_tags(raw_text, &mut tags), &mut tags).map_err(|err| {
+        anyhow!(
+            "failed to translate the Rails fixture: {}\n   err: {}",
+            filename,
+            err
+        )
+    })?;
+
+    let mut value = match serde_yaml::from_str::<Value>(&text) {
+        Ok(value) => value,
+        // left to the deserialization to report, with the position of the error
+        Err(_) => return Ok(unmask_tags(&text, &tags)),
+    };
+    value.apply_merge()?;
+    let Value::Mapping(mut records) = value else {
+        return Ok(unmask_tags(&text, &tags));
+    };
+    records.shift_remove(DEFAULTS_LABEL);
+    records.shift_remove(FIXTURE_OPTIONS);
+
+    let mut translated = Mapping::with_capacity(records.len());
+    for (label, mut record) in records {
+        if let (Value::String(label), Value::Mapping(fields)) = (&label, &mut record) {
+            replace_label(fields, label);
+            let fields = std::mem::take(fields);
+            record = Value::Mapping(translate_associations(
+                filename, base_dir, fields, &mut tags,
+            ));
+        }
+        translated.insert(label, record);
+    }
+    Ok(unmask_tags(&serde_yaml::to_string(&translated)?, &tags))
+}
+
+/// replaces the ERB with the placeholders of the tags it is translated into (see `mask_tags`), or
+/// with the text it is evaluated into
+fn translate_erb(raw_text: &str, tags: &mut Vec<String>) -> Result<String> {
+    let re = Regex::new(r"(?s)<%(?P<kind>[=#]?)(?P<code>.*?)-?%>")?;
+    let mut text = String::with_capacity(raw_text.len());
+    let mut last = 0;
+    for captures in re.captures_iter(raw_text) {
+        let Some(matched) = captures.get(0) else {
+            continue;
+        };
+        text.push_str(&raw_text[last..matched.start()]);
+        last = matched.end();
+
+        let erb = captures.name("kind").map_or("", |kind| kind.as_str());
+        let code = captures
+            .name("code")
+            .map_or("", |code| code.as_str().trim());
+        match erb {
+            "#" => {}
+            "=" => match translate_expression(code) {
+                Ok(Erb::Tag(tag)) => text.push_str(&tag_placeholder(tags, tag)),
+                Ok(Erb::Text(value)) => text.push_str(&value),
+                Err(err) => {
+                    let (line, _) = line_col(raw_text, matched.start());
+                    return Err(anyhow!("{} (line {})", err, line));
+                }
+            },
+            _ => {
+                let (line, _) = line_col(raw_text, matched.start());
+                return Err(anyhow!(
+                    "only the ERB expressions (`<%= ... %>`) are supported, not `{}` (line {})",
+                    matched.as_str(),
+                    line
+                ));
+            }
+        }
+    }
+    text.push_str(&raw_text[last..]);
+    Ok(text)
+}
+
+/// what an ERB expression is translated into
+#[derive(Debug, PartialEq)]
+enum Erb {
+    /// the tag of cder, e.g. `${{ ENV(API_KEY) }}`
+    Tag(String),
+    /// the value of a literal, embedded in the text as it is
+    Text(String),
+}
+
+/// translates the expressions that read the environment variables, i.e. `ENV['X']`,
+/// `ENV.fetch('X')`, `ENV.fetch('X', 'default')` and `ENV['X'] || 'default'`, and the literals
+fn translate_expression(code: &str) -> Result<Erb> {
+    let env = Regex::new(
+        r#"^ENV(?:\[\s*['"](?P<key>\w+)['"]\s*\]|\.fetch\(\s*['"](?P<fetch_key>\w+)['"]\s*(?:,\s*(?P<fetch_default>[^)]+?)\s*)?\))(?:\s*\|\|\s*(?P<default>.+))?$"#,
+    )?;
+    let Some(captures) = env.captures(code) else {
+        return literal(code)
+            .map(Erb::Text)
+            .ok_or_else(|| anyhow!("the ERB expression is not supported: `{}`", code));
+    };
+
+    let key = captures
+        .name("key")
+        .or_else(|| captures.name("fetch_key"))
+        .map_or("", |key| key.as_str());
+    let default = match captures
+        .name("fetch_default")
+        .or_else(|| captures.name("default"))
+    {
+        Some(default) => literal(default.as_str().trim())
+            .ok_or_else(|| anyhow!("the default value has to be a literal: `{}`", code))?,
+        None => return Ok(Erb::Tag(format!("${{{{ ENV({}) }}}}", key))),
+    };
+
+    if !default.is_empty() && default.chars().all(char::is_alphanumeric) {
+        return Ok(Erb::Tag(format!("${{{{ ENV({}:-{}) }}}}", key, default)));
+    }
+    if default.is_empty() || default.contains(|c: char| c == '"' || c.is_control()) {
+        return Err(anyhow!(
+            "the default value cannot be given to the ENV tag: `{}`",
+            code
+        ));
+    }
+    Ok(Erb::Tag(format!(
+        "${{{{ ENV({}:-\"{}\") }}}}",
+        key, default
+    )))
+}
+
+/// the value of a string, number, boolean or nil literal
+fn literal(code: &str) -> Option<String> {
+    let quoted = |quote: char| {
+        code.strip_prefix(quote)?
+            .strip_suffix(quote)
+            .filter(|value| !value.contains(quote) && !value.contains('\\'))
+    };
+    if let Some(value) = quoted('\'').or_else(|| quoted('"').filter(|value| !value.contains("#{")))
+    {
+        return Some(value.to_string());
+    }
+    match code {
+        "nil" => Some(String::new()),
+        "true" | "false" => Some(code.to_string()),
+        _ => code
+            .parse::<f64>()
+            .ok()
+            .filter(|_| code.starts_with(|c: char| c.is_ascii_digit() || c == '-'))
+            .map(|_| code.to_string()),
+    }
+}
+
+/// replaces `$LABEL` in the strings of the value with the label of the record
+fn replace_label(fields: &mut Mapping, label: &str) {
+    fn replace(value: &mut Value, label: &str) {
+        match value {
+            Value::String(text) if text.contains("$LABEL") => {
+                *text = text.replace("$LABEL", label);
+            }
+            Value::Sequence(values) => values.iter_mut().for_each(|value| replace(value, label)),
+            Value::Mapping(mapping) => mapping
+                .iter_mut()
+                .for_each(|(_, value)| replace(value, label)),
+            Value::Tagged(tagged) => replace(&mut tagged.value, label),
+            _ => {}
+        }
+    }
+    fields
+        .iter_mut()
+        .for_each(|(_, value)| replace(value, label));
+}
+
+/// replaces the associations given by label with the REF tags to the ids, keeping the order of
+/// the fields
+fn translate_associations(
+    filename: &str,
+    base_dir: &str,
+    fields: Mapping,
+    tags: &mut Vec<String>,
+) -> Mapping {
+    let Ok(association) = Regex::new(r"^(?P<label>\w+)(?: \((?P<type>[A-Z]\w*)\))?$") else {
+        return fields;
+    };
+    let mut translated = Mapping::with_capacity(fields.len());
+    for (key, value) in &fields {
+        let (Value::String(name), Value::String(label)) = (key, value) else {
+            translated.insert(key.clone(), value.clone());
+            continue;
+        };
+        let id_key = format!("{}_id", name);
+        let captures = association
+            .captures(label)
+            .filter(|_| !name.ends_with("_id") && !fields.contains_key(id_key.as_str()))
+            .filter(|_| !has_tag_placeholder(label));
+        let Some(captures) = captures else {
+            translated.insert(key.clone(), value.clone());
+            continue;
+        };
+
+        let model = captures.name("type").map(|model| model.as_str());
+        let table = pluralize(&model.map_or_else(|| name.clone(), snake_case));
+        let fixture = Path::new(filename).with_file_name(format!("{}.yml", table));
+        if !file_exists(&fixture.to_string_lossy(), base_dir) {
+            translated.insert(key.clone(), value.clone());
+            continue;
+        }
+
+        let tag = format!("${{{{ REF({}) }}}}", &captures["label"]);
+        translated.insert(
+            Value::String(id_key),
+            Value::String(tag_placeholder(tags, tag)),
+        );
+        if let Some(model) = model {
+            translated.insert(
+                Value::String(format!("{}_type", name)),
+                Value::String(model.to_string()),
+            );
+        }
+    }
+    translated
+}
+
+/// the plural of the (English) noun by the regular rules, e.g. `company` -> `companies`
+fn pluralize(noun: &str) -> String {
+    if let Some(stem) = noun.strip_suffix('y') {
+        if !stem.ends_with(['a', 'e', 'i', 'o', 'u']) {
+            return format!("{}ies", stem);
+        }
+    }
+    match ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|suffix| noun.ends_with(suffix))
+    {
+        true => format!("{}es", noun),
+        false => format!("{}s", noun),
+    }
+}
+
+/// e.g. `LineItem` -> `line_item`
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rails::*;
+
+    #[test]
+    fn test_translate_expression() {
+        let cases = [
+            ("ENV['API_KEY']", "${{ ENV(API_KEY) }}"),
+            ("ENV[\"API_KEY\"]", "${{ ENV(API_KEY) }}"),
+            ("ENV.fetch('API_KEY')", "${{ ENV(API_KEY) }}"),
+            ("ENV.fetch('PORT', 5432)", "${{ ENV(PORT:-5432) }}"),
+            (
+                "ENV['EMAIL'] || 'dev@example.com'",
+                "${{ ENV(EMAIL:-\"dev@example.com\") }}",
+            ),
+        ];
+        for (code, expected) in cases {
+            assert_eq!(
+                translate_expression(code).ok(),
+                Some(Erb::Tag(expected.to_string())),
+                "{}",
+                code
+            );
+        }
+
+        assert_eq!(
+            translate_expression("'melon'").ok(),
+            Some(Erb::Text("melon".to_string()))
+        );
+        assert_eq!(
+            translate_expression("-1.5").ok(),
+            Some(Erb::Text("-1.5".to_string()))
+        );
+        assert_eq!(
+            translate_expression("nil").ok(),
+            Some(Erb::Text(String::new()))
+        );
+        assert!(translate_expression("1.day.ago").is_err());
+        assert!(translate_expression("ENV['NAME'] || ''").is_err());
+        assert!(translate_expression("\"#{name}\"").is_err());
+    }
+
+    #[test]
+    fn test_translate_erb() {
+        let mut tags = Vec::new();
+        let text = translate_erb(
+            "<%# users %>\nalice:\n  key: <%= ENV['KEY'] -%>\n  id: ${{ REF(x) }}\n  age: <%= 20 %>\n",
+            &mut tags,
+        )
+        .unwrap();
+        assert_eq!(
+            text,
+            "\nalice:\n  key: __cder_tag_0__\n  id: ${{ REF(x) }}\n  age: 20\n"
+        );
+        assert_eq!(tags, vec!["${{ ENV(KEY) }}"]);
+
+        let err = translate_erb("a:\n<% 3.times do |i| %>\n", &mut tags).unwrap_err();
+        assert!(err.to_string().contains("(line 2)"), "{}", err);
+    }
+
+    #[test]
+    fn test_pluralize() {
+        assert_eq!(pluralize("customer"), "customers");
+        assert_eq!(pluralize("company"), "companies");
+        assert_eq!(pluralize("day"), "days");
+        assert_eq!(pluralize("address"), "addresses");
+        assert_eq!(pluralize(&snake_case("LineItem")), "line_items");
+    }
+
+    #[test]
+    fn test_translate_rails() {
+        let raw_text = "DEFAULTS: &DEFAULTS\n  status: active\n\norder_1:\n  <<: *DEFAULTS\n  customer: alice\n  reviewer: bob (Customer)\n  item: melon\n  note: $LABEL [<%= ENV['NOTE'] %>]\n";
+        let text = translate_rails("rails/orders.yml", "tests/fixtures", raw_text).unwrap();
+        assert_eq!(
+            text,
+            "order_1:\n  note: order_1 [${{ ENV(NOTE) }}]\n  customer_id: ${{ REF(alice) }}\n  reviewer_id: ${{ REF(bob) }}\n  reviewer_type: Customer\n  item: melon\n  status: active\n"
+        );
+    }
+}
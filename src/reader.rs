@@ -1,14 +1,150 @@
 use anyhow::Result;
-use std::{env, fs, path::PathBuf};
+use sha2::{Digest, Sha256};
+use std::{env, fs, ops::Deref, path::PathBuf};
+
+use crate::{django::translate_django, rails::translate_rails, traits::apply_traits, CderError};
+
+/// the files of this size or larger are memory-mapped rather than read into a String (with the
+/// `memmap2` feature)
+#[cfg(feature = "memmap2")]
+const MMAP_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+/// the text of a seed file, either read into memory or memory-mapped (see `MMAP_THRESHOLD`)
+pub(crate) struct SeedText(Buffer);
+
+enum Buffer {
+    Read(String),
+    #[cfg(feature = "memmap2")]
+    Mapped(memmap2::Mmap),
+}
+
+impl Deref for SeedText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match &self.0 {
+            Buffer::Read(text) => text,
+            // the mapping has been checked to be valid utf-8 in `map_file`
+            #[cfg(feature = "memmap2")]
+            Buffer::Mapped(mmap) => std::str::from_utf8(mmap).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<String> for SeedText {
+    fn from(text: String) -> Self {
+        Self(Buffer::Read(text))
+    }
+}
 
 /// Read seeds from specified file
-pub fn read_file(filename: &str, base_dir: &str) -> Result<String> {
-    let path = env::var("CARGO_MANIFEST_DIR")
+pub fn read_file(filename: &str, base_dir: &str) -> Result<SeedText> {
+    let path = file_path(filename, base_dir);
+
+    #[cfg(feature = "memmap2")]
+    if let Some(text) = map_file(&path, MMAP_THRESHOLD)? {
+        return Ok(text);
+    }
+    let text = fs::read_to_string(&path).map_err(|err| CderError::FileNotFound { path, err })?;
+    Ok(SeedText::from(text))
+}
+
+/// maps the file into memory when it is as large as the threshold, so that a giant fixture is
+/// not buffered as a whole
+#[cfg(feature = "memmap2")]
+fn map_file(path: &PathBuf, threshold: u64) -> Result<Option<SeedText>> {
+    let not_found = |err| CderError::FileNotFound {
+        path: path.clone(),
+        err,
+    };
+    let file = fs::File::open(path).map_err(not_found)?;
+    if file.metadata().map_err(not_found)?.len() < threshold {
+        return Ok(None);
+    }
+
+    // SAFETY: the seed files are not supposed to be modified while they are seeded. the mapping
+    // is read-only, and its contents are checked to be valid utf-8 before they are read as str
+    let mmap = unsafe { memmap2::Mmap::map(&file) }.map_err(not_found)?;
+    if let Err(err) = std::str::from_utf8(&mmap) {
+        return Err(not_found(std::io::Error::new(std::io::ErrorKind::InvalidData, err)).into());
+    }
+    Ok(Some(SeedText(Buffer::Mapped(mmap))))
+}
+
+/// how the seed files are read besides as they are, see `DatabaseSeeder::rails_fixtures`,
+/// `DatabaseSeeder::django_fixtures` and `DatabaseSeeder::dbt_seeds`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct ReadOptions {
+    /// the files are Rails fixtures, translated with `rails::translate_rails`
+    pub rails: bool,
+    /// the files are Django fixtures (if they are arrays), see `django::translate_django`
+    pub django: bool,
+    /// the `.csv` files are the seeds of dbt
+    #[cfg(feature = "dbt")]
+    pub dbt: Option<crate::DbtSeeds>,
+}
+
+/// reads the seeds from the file, translated into the format of cder by the options, with the
+/// traits of the file applied (see `traits::apply_traits`)
+pub(crate) fn read_seed_file(
+    filename: &str,
+    base_dir: &str,
+    options: &ReadOptions,
+) -> Result<SeedText> {
+    let mut text = read_file(filename, base_dir)?;
+    if options.rails {
+        text = SeedText::from(translate_rails(filename, base_dir, &text)?);
+    }
+    if options.django {
+        if let Some(translated) = translate_django(filename, &text)? {
+            text = SeedText::from(translated);
+        }
+    }
+    #[cfg(feature = "dbt")]
+    if let Some(seeds) = options.dbt.as_ref().filter(|_| filename.ends_with(".csv")) {
+        text = SeedText::from(seeds.translate(filename, &text)?);
+    }
+    match apply_traits(filename, &text)? {
+        Some(applied) => Ok(SeedText::from(applied)),
+        None => Ok(text),
+    }
+}
+
+/// returns true when the file exists, e.g. to look for an optional file
+pub(crate) fn file_exists(filename: &str, base_dir: &str) -> bool {
+    file_path(filename, base_dir).is_file()
+}
+
+fn file_path(filename: &str, base_dir: &str) -> PathBuf {
+    env::var("CARGO_MANIFEST_DIR")
         .map(PathBuf::from)
         .unwrap_or_default()
         .join(base_dir)
-        .join(filename);
+        .join(filename)
+}
+
+/// the SHA-256 checksum of the seeds (in hex), to tell whether the file has been changed
+pub(crate) fn checksum(raw_text: &str) -> String {
+    format!("{:x}", Sha256::digest(raw_text.as_bytes()))
+}
+
+#[cfg(all(test, feature = "memmap2"))]
+mod tests {
+    use crate::reader::*;
+
+    #[test]
+    fn test_map_file() {
+        let path = file_path("tests/fixtures/items.yml", "");
+        let text = map_file(&path, 1).unwrap().unwrap();
+        assert!(matches!(text.0, Buffer::Mapped(_)));
+        assert_eq!(&*text, fs::read_to_string(&path).unwrap());
+
+        // the smaller files are read as they are
+        assert!(map_file(&path, u64::MAX).unwrap().is_none());
 
-    fs::read_to_string(&path)
-        .map_err(|err| anyhow::anyhow!("Can't open the file: {:?}\n   err: {}", path, err))
+        let path = env::temp_dir().join("cder_test_map_file.yml");
+        fs::write(&path, b"name: \xff").unwrap();
+        assert!(map_file(&path, 1).is_err());
+        fs::remove_file(&path).unwrap();
+    }
 }
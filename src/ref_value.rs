@@ -0,0 +1,322 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// RefValue is the value (usually the primary key) a label is resolved to by REF tags.
+/// Keeping the type of the value, rather than its string representation, allows the value to be
+/// embedded in the fixtures in the right format, e.g. strings are quoted while integers are not.
+/// It is (de)serialized untagged, i.e. as a plain number, string, or sequence.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RefValue {
+    Int(i64),
+    Uuid(Uuid),
+    String(String),
+    /// composite keys, embedded as a sequence: `[1, "a"]`
+    Composite(Vec<RefValue>),
+}
+
+impl RefValue {
+    /// the id in a JSON document (e.g. the response of an API), typed as it can be parsed:
+    /// integers, uuids, other strings, and arrays of them as composite keys
+    #[cfg(any(feature = "cypher", feature = "events", feature = "http"))]
+    pub(crate) fn from_json(id: &serde_json::Value) -> Option<RefValue> {
+        match id {
+            serde_json::Value::Number(id) => id.as_i64().map(RefValue::Int),
+            serde_json::Value::String(id) => Some(match Uuid::parse_str(id) {
+                Ok(uuid) => RefValue::Uuid(uuid),
+                Err(_) => RefValue::String(id.clone()),
+            }),
+            serde_json::Value::Array(ids) => ids
+                .iter()
+                .map(RefValue::from_json)
+                .collect::<Option<Vec<RefValue>>>()
+                .map(RefValue::Composite),
+            _ => None,
+        }
+    }
+
+    /// the ids handed to `StructLoader` as strings used to be embedded as they were, so the
+    /// numeric ones are regarded as integers to keep deserializing into integer fields
+    pub(crate) fn numeric_as_int(self) -> RefValue {
+        match self {
+            RefValue::String(value) => match value.parse::<i64>() {
+                // "007" stays a string, not to drop the leading zeros
+                Ok(int) if int.to_string() == value => RefValue::Int(int),
+                _ => RefValue::String(value),
+            },
+            value => value,
+        }
+    }
+
+    /// the representation of the value embedded in yaml fixtures
+    pub fn to_yaml(&self) -> String {
+        match self {
+            RefValue::Int(value) => value.to_string(),
+            RefValue::Uuid(value) => format!("\"{}\"", value),
+            RefValue::String(value) => quote(value),
+            RefValue::Composite(values) => {
+                let values = values
+                    .iter()
+                    .map(|value| value.to_yaml())
+                    .collect::<Vec<String>>();
+                format!("[{}]", values.join(", "))
+            }
+        }
+    }
+}
+
+/// surrounds the string with double quotes, escaping the charactors that cannot appear as they
+/// are inside the quotes
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl fmt::Display for RefValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RefValue::Int(value) => write!(f, "{}", value),
+            RefValue::Uuid(value) => write!(f, "{}", value),
+            RefValue::String(value) => write!(f, "{}", value),
+            RefValue::Composite(values) => {
+                let values = values
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<Vec<String>>();
+                write!(f, "({})", values.join(", "))
+            }
+        }
+    }
+}
+
+/// ToRefValue converts the ids returned by the loaders into RefValue.
+/// It is implemented for integers, strings, uuids, and tuples of them (as composite keys).
+pub trait ToRefValue {
+    fn to_ref_value(&self) -> RefValue;
+
+    /// the columns captured along with the id, see `Captures`
+    fn captures(&self) -> Vec<(String, RefValue)> {
+        Vec::new()
+    }
+}
+
+/// Captures can be returned by the loaders in place of a plain id, so that other columns of the
+/// inserted record (e.g. slugs or tokens generated by the database) are stored along with the id.
+/// The columns are referred as `${{ REF(<label>.<column>) }}`, while `${{ REF(<label>) }}` still
+/// refers to the id.
+///
+/// ```rust
+/// use cder::{Captures, DatabaseSeeder};
+/// # use anyhow::Result;
+/// #
+/// # fn main() -> Result<()> {
+/// let mut seeder = DatabaseSeeder::new();
+/// // populates users.yml, returning Captures::new(id).with("slug", slug) from the loader
+/// // ...
+///
+/// // then users' slugs can be referred from other files
+/// // user_slug: ${{ REF(Alice.slug) }}
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Captures {
+    id: RefValue,
+    columns: Vec<(String, RefValue)>,
+}
+
+impl Captures {
+    pub fn new<V: ToRefValue>(id: V) -> Self {
+        Self {
+            id: id.to_ref_value(),
+            columns: Vec::new(),
+        }
+    }
+
+    /// captures the value of the column
+    pub fn with<V: ToRefValue>(mut self, column: &str, value: V) -> Self {
+        self.columns
+            .push((column.to_string(), value.to_ref_value()));
+        self
+    }
+
+    pub fn id(&self) -> &RefValue {
+        &self.id
+    }
+
+    /// returns the captured value of the column
+    pub fn get(&self, column: &str) -> Option<&RefValue> {
+        self.columns
+            .iter()
+            .find(|(captured, _)| captured == column)
+            .map(|(_, value)| value)
+    }
+}
+
+impl ToRefValue for Captures {
+    fn to_ref_value(&self) -> RefValue {
+        self.id.clone()
+    }
+
+    fn captures(&self) -> Vec<(String, RefValue)> {
+        self.columns.clone()
+    }
+}
+
+impl ToRefValue for RefValue {
+    fn to_ref_value(&self) -> RefValue {
+        self.clone()
+    }
+}
+
+impl<T> ToRefValue for &T
+where
+    T: ToRefValue + ?Sized,
+{
+    fn to_ref_value(&self) -> RefValue {
+        (*self).to_ref_value()
+    }
+
+    fn captures(&self) -> Vec<(String, RefValue)> {
+        (*self).captures()
+    }
+}
+
+macro_rules! impl_to_ref_value_for_int {
+    ($($t:ty),*) => {
+        $(
+            impl ToRefValue for $t {
+                fn to_ref_value(&self) -> RefValue {
+                    RefValue::Int(i64::from(*self))
+                }
+            }
+        )*
+    };
+}
+
+impl_to_ref_value_for_int!(i8, i16, i32, i64, u8, u16, u32);
+
+impl ToRefValue for u64 {
+    fn to_ref_value(&self) -> RefValue {
+        // ids beyond i64 are kept as they are, rather than being wrapped around
+        i64::try_from(*self)
+            .map(RefValue::Int)
+            .unwrap_or_else(|_| RefValue::String(self.to_string()))
+    }
+}
+
+impl ToRefValue for str {
+    fn to_ref_value(&self) -> RefValue {
+        RefValue::String(self.to_string())
+    }
+}
+
+impl ToRefValue for String {
+    fn to_ref_value(&self) -> RefValue {
+        RefValue::String(self.clone())
+    }
+}
+
+impl ToRefValue for Uuid {
+    fn to_ref_value(&self) -> RefValue {
+        RefValue::Uuid(*self)
+    }
+}
+
+macro_rules! impl_to_ref_value_for_tuple {
+    ($($name:ident),*) => {
+        impl<$($name),*> ToRefValue for ($($name,)*)
+        where
+            $($name: ToRefValue,)*
+        {
+            #[allow(non_snake_case)]
+            fn to_ref_value(&self) -> RefValue {
+                let ($($name,)*) = self;
+                RefValue::Composite(vec![$($name.to_ref_value()),*])
+            }
+        }
+    };
+}
+
+impl_to_ref_value_for_tuple!(A, B);
+impl_to_ref_value_for_tuple!(A, B, C);
+impl_to_ref_value_for_tuple!(A, B, C, D);
+
+#[cfg(test)]
+mod tests {
+    use crate::ref_value::*;
+
+    #[test]
+    fn test_to_yaml() {
+        assert_eq!(RefValue::Int(42).to_yaml(), "42");
+        assert_eq!(
+            RefValue::String("say \"hi\"\n".to_string()).to_yaml(),
+            r#""say \"hi\"\n""#
+        );
+
+        let uuid = Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+        assert_eq!(
+            RefValue::Uuid(uuid).to_yaml(),
+            r#""67e55044-10b1-426f-9247-bb680e5fe0c8""#
+        );
+
+        let composite =
+            RefValue::Composite(vec![RefValue::Int(1), RefValue::String("a".to_string())]);
+        assert_eq!(composite.to_yaml(), r#"[1, "a"]"#);
+    }
+
+    #[test]
+    fn test_numeric_as_int() {
+        assert_eq!("42".to_ref_value().numeric_as_int(), RefValue::Int(42));
+        assert_eq!("-1".to_ref_value().numeric_as_int(), RefValue::Int(-1));
+        assert_eq!(
+            "007".to_ref_value().numeric_as_int(),
+            RefValue::String("007".to_string())
+        );
+        assert_eq!(
+            "abc".to_ref_value().numeric_as_int(),
+            RefValue::String("abc".to_string())
+        );
+    }
+
+    #[test]
+    fn test_to_ref_value() {
+        assert_eq!(42_i32.to_ref_value(), RefValue::Int(42));
+        assert_eq!(
+            u64::MAX.to_ref_value(),
+            RefValue::String(u64::MAX.to_string())
+        );
+        assert_eq!("foo".to_ref_value(), RefValue::String("foo".to_string()));
+        assert_eq!(
+            (1_i64, "a".to_string()).to_ref_value(),
+            RefValue::Composite(vec![RefValue::Int(1), RefValue::String("a".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_serde() {
+        let uuid = Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+        let value = RefValue::Composite(vec![
+            RefValue::Int(1),
+            RefValue::Uuid(uuid),
+            RefValue::String("a".to_string()),
+        ]);
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, r#"[1,"67e55044-10b1-426f-9247-bb680e5fe0c8","a"]"#);
+        assert_eq!(serde_json::from_str::<RefValue>(&json).unwrap(), value);
+    }
+}
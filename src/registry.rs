@@ -0,0 +1,40 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+use std::future::Future;
+
+use crate::{DatabaseSeeder, ToRefValue};
+
+/// AsyncLoader is implemented for the async closures (or functions) that can be registered to a
+/// DatabaseSeeder with `DatabaseSeeder::register`, i.e. `FnMut(T) -> impl Future<Output =
+/// Result<U>>` where the id `U` implements ToRefValue. You usually don't need to implement it
+/// yourself.
+pub trait AsyncLoader<T>: Send + 'static {
+    type Id: ToRefValue + Send;
+    type Future: Future<Output = Result<Self::Id>> + Send;
+
+    fn call(&mut self, record: T) -> Self::Future;
+}
+
+impl<T, F, Fut, U> AsyncLoader<T> for F
+where
+    F: FnMut(T) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<U>> + Send,
+    U: ToRefValue + Send,
+{
+    type Id = U;
+    type Future = Fut;
+
+    fn call(&mut self, record: T) -> Self::Future {
+        self(record)
+    }
+}
+
+type PopulateFn =
+    Box<dyn for<'a> FnOnce(&'a mut DatabaseSeeder) -> BoxFuture<'a, Result<()>> + Send>;
+
+/// a seed file registered to a DatabaseSeeder, along with the type-erased function that
+/// populates the file
+pub(crate) struct RegisteredFile {
+    pub(crate) filename: String,
+    pub(crate) populate: PopulateFn,
+}
@@ -0,0 +1,72 @@
+use serde::{Serialize, Serializer};
+use std::{fmt, time::Duration};
+
+/// SeedReport summarizes the files populated by a DatabaseSeeder, see `DatabaseSeeder::report`.
+/// It can be printed as it is, or serialized (e.g. into json) for further processing.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct SeedReport {
+    pub files: Vec<FileReport>,
+}
+
+/// the statistics of a single seed file
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct FileReport {
+    pub filename: String,
+    /// records found in the file
+    pub parsed: usize,
+    pub inserted: usize,
+    /// records that have been neither inserted nor failed, e.g. the ones that already exist
+    pub skipped: usize,
+    pub failed: usize,
+    /// wall time spent on the file, serialized in seconds
+    #[serde(serialize_with = "serialize_secs")]
+    pub elapsed: Duration,
+}
+
+impl SeedReport {
+    /// total number of records inserted across the files
+    pub fn inserted(&self) -> usize {
+        self.files.iter().map(|file| file.inserted).sum()
+    }
+
+    /// total number of records that failed to be inserted across the files
+    pub fn failed(&self) -> usize {
+        self.files.iter().map(|file| file.failed).sum()
+    }
+
+    /// total wall time spent on the files
+    pub fn elapsed(&self) -> Duration {
+        self.files.iter().map(|file| file.elapsed).sum()
+    }
+}
+
+impl fmt::Display for SeedReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for file in &self.files {
+            writeln!(
+                f,
+                "{}: {} parsed, {} inserted, {} skipped, {} failed ({:.2}s)",
+                file.filename,
+                file.parsed,
+                file.inserted,
+                file.skipped,
+                file.failed,
+                file.elapsed.as_secs_f64()
+            )?;
+        }
+        write!(
+            f,
+            "total: {} inserted, {} failed ({:.2}s)",
+            self.inserted(),
+            self.failed(),
+            self.elapsed().as_secs_f64()
+        )
+    }
+}
+
+fn serialize_secs<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_f64(duration.as_secs_f64())
+}
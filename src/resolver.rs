@@ -1,5 +1,17 @@
 use anyhow::Result;
-use std::{collections::HashMap, env};
+use std::{
+    borrow::{Borrow, Cow},
+    collections::{HashMap, HashSet},
+    env,
+    hash::{BuildHasher, Hash},
+    ops::Range,
+};
+
+use crate::{
+    error::{TagFailure, TagFailures, UnknownRef},
+    nested::is_parent_tag,
+    RefValue,
+};
 
 macro_rules! regex {
     ($re:literal $(,)?) => {{
@@ -8,6 +20,21 @@ macro_rules! regex {
     }};
 }
 
+/// the scope the embedded tags are resolved in
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TagScope {
+    /// REF keys are looked up in the namespace first (`<namespace>/<key>`), then as they are
+    pub namespace: Option<String>,
+    /// values referred by CTX tags
+    pub context: HashMap<String, RefValue>,
+    /// the environment variables whose values may appear in the errors and the logs. the values
+    /// of the other ENV tags (and of every SECRET tag) are masked, see `ResolvedText::secrets`
+    pub unmasked_env: HashSet<String>,
+    /// values of the ENV and SECRET tags that take precedence over the environment variables, so
+    /// that e.g. the tests can give them without `std::env::set_var`
+    pub env: HashMap<String, String>,
+}
+
 /// replaces embedded custom tags before deserialization
 /// tags can be used to allocate dynamic values to the seed object
 /// format:
@@ -19,14 +46,295 @@ macro_rules! regex {
 ///
 /// currently it accepts following types as directive:
 ///   ENV(FOO_BAR)   ... replace the tag with the environment variable 'FOO'
+///   SECRET(FOO)    ... same as ENV, but the value is always masked in the errors and the logs
 ///   REF(some_name) ... replace the tag with an ID of an object, referred by the key named 'some_name'
+///                      (string ids are quoted, and composite ids are embedded as a sequence)
+///   CTX(some_name) ... replace the tag with the context value named 'some_name' (see TagScope)
 /// constraints:
-///   all keys must consist of alphabet or numbers.
+///   all keys must consist of alphabet or numbers (REF keys may also contain `/`, that separates
+///   the namespace from the label e.g. `acme/Alice`, and `.`, that separates the label from the
+///   captured column e.g. `Alice.slug`).
 ///   default values must consist of alphanumeric, or string surrounded by double quotes "..." (the
 ///   string must not contain any other double quotes or control charactors)
-pub fn resolve_tags(raw_text: &str, dict: &HashMap<String, String>) -> Result<String> {
+///
+/// the positions where the tags have been replaced are kept track of, so that the positions in the
+/// resolved text (e.g. of the deserialization errors) can be mapped back to the raw text
+pub(crate) fn resolve_tags<'a, K, S>(
+    raw_text: &'a str,
+    dict: &HashMap<K, RefValue, S>,
+    scope: &TagScope,
+) -> Result<ResolvedText<'a>>
+where
+    K: Borrow<str> + Eq + Hash,
+    S: BuildHasher,
+{
+    // most of the files have no tags at all, whose text is deserialized as it is
+    if !has_tags(raw_text) {
+        return Ok(ResolvedText::unchanged(raw_text));
+    }
+
+    let mut refs = Vec::new();
+    // the indices of the tags that fall back to their defaults
+    let mut defaults_used = Vec::new();
+    let mut secrets = Vec::new();
+    let mut index = 0;
+
+    let mut resolved = replace_tags_mapped(raw_text, |directive, key, default| {
+        let uses_default = default.is_some()
+            && match directive {
+                "ENV" | "SECRET" => !scope.env.contains_key(key) && env::var(key).is_err(),
+                "CTX" => !scope.context.contains_key(key),
+                _ => false,
+            };
+        if uses_default {
+            defaults_used.push((index, format!("{}({})", directive, key)));
+        }
+        index += 1;
+
+        // finds a value (text) that has to be replaced with the directive/key.
+        // ENV(<key>) ... replace it with the environment var <key>
+        // REF(<key>) ... replace it with the object id referred by the <key>
+        // CTX(<key>) ... replace it with the context value referred by the <key>
+        // SECRET(<key>) ... same as ENV(<key>), but the value is masked
+        match directive {
+            "ENV" | "SECRET" => {
+                let masked = match directive {
+                    "SECRET" => true,
+                    // the short values (e.g. `1`, `dev`) are too common to tell from the rest
+                    _ => !uses_default && !scope.unmasked_env.contains(key),
+                };
+                let value = resolve_env(key, default, &scope.env)?;
+                if masked && (directive == "SECRET" || value.chars().count() >= 4) {
+                    secrets.push(value.clone());
+                }
+                Ok(value)
+            }
+            "REF" => {
+                let scoped_key = scope
+                    .namespace
+                    .as_ref()
+                    .map(|namespace| format!("{}/{}", namespace, key))
+                    .filter(|scoped_key| dict.contains_key(scoped_key.as_str()))
+                    .unwrap_or_else(|| key.to_string());
+                let value = resolve_ref(&scoped_key, dict);
+                refs.push(scoped_key);
+                value
+            }
+            "CTX" => resolve_ctx(key, default, &scope.context),
+            _ => Err(anyhow::anyhow!(
+                "the directive: ` {}` is not supported.",
+                directive
+            )),
+        }
+    })?;
+
+    resolved.refs = refs;
+    resolved.secrets = secrets;
+    resolved.defaults_used = defaults_used
+        .into_iter()
+        .map(|(index, tag)| (resolved.replacements[index].0.start, tag))
+        .collect();
+    Ok(resolved)
+}
+
+/// tells whether the text may contain any embedded tag, without parsing it
+pub(crate) fn has_tags(raw_text: &str) -> bool {
+    raw_text.contains("${{")
+}
+
+/// scans the embedded tags without resolving them, and returns the keys referred by REF tags
+/// along with the text in which every tag is replaced with a placeholder (`0`), so that the
+/// structure of the text (e.g. labels of the records) can be inspected before any record is
+/// persisted.
+pub fn scan_refs(raw_text: &str) -> Result<(Cow<'_, str>, Vec<String>)> {
+    let mut refs = Vec::new();
+
+    let masked_text = replace_tags(raw_text, |directive, key, _| match directive {
+        "ENV" | "SECRET" | "CTX" => Ok("0".to_string()),
+        "REF" => {
+            if !refs.iter().any(|r| r == key) {
+                refs.push(key.to_string());
+            }
+            Ok("0".to_string())
+        }
+        _ => Err(anyhow::anyhow!(
+            "the directive: ` {}` is not supported.",
+            directive
+        )),
+    })?;
+
+    Ok((masked_text, refs))
+}
+
+/// replaces every REF tag with a marker that keeps its key (`__cder_ref(<key>)__`), and the other
+/// tags with a placeholder (`0`), so that the references of each record can be found after the
+/// text is parsed, see `ref_markers`.
+pub(crate) fn mark_refs(raw_text: &str) -> Result<Cow<'_, str>> {
+    replace_tags(raw_text, |directive, key, _| match directive {
+        "ENV" | "SECRET" | "CTX" => Ok("0".to_string()),
+        "REF" => Ok(format!("__cder_ref({})__", key)),
+        _ => Err(anyhow::anyhow!(
+            "the directive: ` {}` is not supported.",
+            directive
+        )),
+    })
+}
+
+/// returns the REF keys marked by `mark_refs` in the text
+pub(crate) fn ref_markers(text: &str) -> impl Iterator<Item = &str> {
+    regex!(r"__cder_ref\(([[:alnum:]_./-]+)\)__")
+        .captures_iter(text)
+        .filter_map(|captures| captures.get(1).map(|key| key.as_str()))
+}
+
+/// the placeholders the tags are kept in by `mask_tags`
+const TAG_PLACEHOLDER: &str = "__cder_tag_";
+
+/// replaces the embedded tags with placeholders, so that the text can be parsed and rewritten as
+/// yaml values (e.g. to translate the fixtures of the other formats), as the tags are not valid
+/// yaml everywhere, e.g. in flow sequences. the tags are put back with `unmask_tags`
+pub(crate) fn mask_tags(raw_text: &str, tags: &mut Vec<String>) -> String {
+    regex!(r"\$\{\{[^}\n]*\}\}")
+        .replace_all(raw_text, |captures: &regex::Captures| {
+            tag_placeholder(tags, captures[0].to_string())
+        })
+        .into_owned()
+}
+
+/// keeps the tag, and returns the placeholder it is put back in place of by `unmask_tags`
+pub(crate) fn tag_placeholder(tags: &mut Vec<String>, tag: String) -> String {
+    tags.push(tag);
+    format!("{}{}__", TAG_PLACEHOLDER, tags.len() - 1)
+}
+
+/// returns true when the text contains any placeholder of `mask_tags`
+pub(crate) fn has_tag_placeholder(text: &str) -> bool {
+    text.contains(TAG_PLACEHOLDER)
+}
+
+/// replaces the placeholders of `mask_tags` with the tags
+pub(crate) fn unmask_tags(text: &str, tags: &[String]) -> String {
+    regex!(r"__cder_tag_(\d+)__")
+        .replace_all(text, |captures: &regex::Captures| {
+            captures[1]
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| tags.get(index))
+                .cloned()
+                .unwrap_or_else(|| captures[0].to_string())
+        })
+        .into_owned()
+}
+
+/// returns the embedded tags that are not well-formed (e.g. `${{ REF(Alice }}`) or whose
+/// directive is not supported, each with its problem. such tags are otherwise left in the text as
+/// they are, or fail the whole text. `${{ PARENT }}` tags are accepted, see
+/// `DatabaseSeeder::populate_nested`
+pub(crate) fn invalid_tags(raw_text: &str) -> Vec<(&str, String)> {
+    // matches anything that looks like a tag, up to the end of the line
+    let re = regex!(r#"\$\{\{[^}\n]*(\}\})?"#);
+
+    re.find_iter(raw_text)
+        .filter_map(|matched| {
+            let tag = matched.as_str();
+            if is_parent_tag(tag) {
+                return None;
+            }
+            let problem = match try_consume(tag) {
+                Ok(ParseResult::Found { directive, .. }) => match directive.as_str() {
+                    "ENV" | "SECRET" | "REF" | "CTX" => return None,
+                    _ => format!("unsupported directive: `{}` in {}", directive, tag),
+                },
+                _ => format!("malformed tag: {}", tag),
+            };
+            Some((tag, problem))
+        })
+        .collect()
+}
+
+/// the text whose tags have been replaced, along with the byte ranges of the tags in the raw text
+/// and the ones of their replacements in the resolved text. the text borrows the raw text when no
+/// tag has been replaced
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct ResolvedText<'a> {
+    pub text: Cow<'a, str>,
+    replacements: Vec<(Range<usize>, Range<usize>)>,
+    /// the REF keys the tags have been resolved with, in the order they appear
+    pub refs: Vec<String>,
+    /// the ENV and CTX tags that have fallen back to their default values (e.g. `ENV(API_KEY)`),
+    /// with their byte offsets in the raw text
+    pub defaults_used: Vec<(usize, String)>,
+    /// the values of the ENV and SECRET tags to be masked in the errors and the logs (see
+    /// `TagScope::unmasked_env`)
+    pub secrets: Vec<String>,
+}
+
+impl<'a> ResolvedText<'a> {
+    /// the raw text as it is, without any tag
+    pub fn unchanged(raw_text: &'a str) -> Self {
+        Self {
+            text: Cow::Borrowed(raw_text),
+            ..Default::default()
+        }
+    }
+
+    /// the resolved text that owns its text, or None when it is the raw text as it is (see
+    /// `unchanged`), so that it can be kept apart from the raw text (see `PrefetchedFile`)
+    #[cfg_attr(not(feature = "rayon"), allow(dead_code))]
+    pub fn detach(self) -> Option<ResolvedText<'static>> {
+        match self.text {
+            Cow::Borrowed(_) => None,
+            Cow::Owned(text) => Some(ResolvedText {
+                text: Cow::Owned(text),
+                replacements: self.replacements,
+                refs: self.refs,
+                defaults_used: self.defaults_used,
+                secrets: self.secrets,
+            }),
+        }
+    }
+
+    /// the number of the tags replaced
+    pub fn tags(&self) -> usize {
+        self.replacements.len()
+    }
+
+    /// maps the byte offset in the resolved text to the one in the raw text. the offsets within
+    /// a replacement are mapped to the start of its tag
+    pub fn raw_offset(&self, offset: usize) -> usize {
+        let mut raw_offset = offset;
+        for (raw, resolved) in &self.replacements {
+            if offset < resolved.start {
+                break;
+            }
+            if offset < resolved.end {
+                return raw.start;
+            }
+            raw_offset = raw.end + (offset - resolved.end);
+        }
+        raw_offset
+    }
+}
+
+/// replaces every embedded tag with the value returned by the replacer,
+/// which receives the directive, the key, and the default value (if any) of the tag. the raw text
+/// is borrowed as it is when it has no tags
+pub(crate) fn replace_tags<F>(raw_text: &str, replacer: F) -> Result<Cow<'_, str>>
+where
+    F: FnMut(&str, &str, Option<String>) -> Result<String>,
+{
+    replace_tags_mapped(raw_text, replacer).map(|resolved| resolved.text)
+}
+
+fn replace_tags_mapped<F>(raw_text: &str, mut replacer: F) -> Result<ResolvedText<'_>>
+where
+    F: FnMut(&str, &str, Option<String>) -> Result<String>,
+{
     let mut index: usize = 0;
     let mut parsed_text: String = "".to_string();
+    // every `${{` is usually a tag to be replaced
+    let mut replacements = Vec::with_capacity(raw_text.matches("${{").count());
+    let mut failures = Vec::new();
 
     while index < raw_text.len() {
         let source_text = &raw_text[index..];
@@ -34,6 +342,10 @@ pub fn resolve_tags(raw_text: &str, dict: &HashMap<String, String>) -> Result<St
         let result = try_consume(source_text)?;
 
         index += match result {
+            // the text is not rebuilt when nothing is to be replaced
+            ParseResult::Nothing if replacements.is_empty() => {
+                return Ok(ResolvedText::unchanged(raw_text));
+            }
             ParseResult::Nothing => {
                 parsed_text.push_str(source_text);
                 source_text.len()
@@ -46,33 +358,109 @@ pub fn resolve_tags(raw_text: &str, dict: &HashMap<String, String>) -> Result<St
                 start,
                 end,
             } => {
-                // finds a value (text) that has to be replaced with the directive/key.
-                // ENV(<key>) ... replace it with the environment var <key>
-                // REF(<key>) ... replace it with the object id referred by the <key>
-                let replacement = match directive.as_str() {
-                    "ENV" => resolve_env(&key, default),
-                    "REF" => resolve_ref(&key, dict),
-                    _ => Err(anyhow::anyhow!(
-                        "the directive: ` {}` is not supported.",
-                        directive
-                    )),
-                }?;
+                // the rest of the tags are still replaced, so that all the failures are reported
+                let replacement = match replacer(&directive, &key, default) {
+                    Ok(replacement) => replacement,
+                    Err(err) => {
+                        failures.push(TagFailure {
+                            offset: index + start,
+                            directive,
+                            key,
+                            err,
+                        });
+                        "0".to_string()
+                    }
+                };
+                // the replacements are usually as short as the tags
+                if replacements.is_empty() {
+                    parsed_text.reserve(raw_text.len());
+                }
                 if start > 0 {
                     parsed_text.push_str(&source_text[..start]);
                 }
+                let resolved_start = parsed_text.len();
                 parsed_text.push_str(&replacement);
+                replacements.push((
+                    index + start..index + end,
+                    resolved_start..parsed_text.len(),
+                ));
                 end
             }
         };
     }
 
-    Ok(parsed_text)
+    if !failures.is_empty() {
+        return Err(TagFailures(failures).into());
+    }
+    Ok(ResolvedText {
+        text: Cow::Owned(parsed_text),
+        replacements,
+        ..Default::default()
+    })
+}
+
+fn resolve_ref<K, S>(key: &str, dict: &HashMap<K, RefValue, S>) -> Result<String>
+where
+    K: Borrow<str> + Eq + Hash,
+    S: BuildHasher,
+{
+    dict.get(key).map(|value| value.to_yaml()).ok_or_else(|| {
+        UnknownRef {
+            key: key.to_string(),
+            suggestion: suggest_key(key, dict.keys().map(Borrow::borrow)),
+        }
+        .into()
+    })
+}
+
+/// the key most likely meant by the unknown key, as most of them are typos: the closest one within
+/// the edit distance of a third of the length (case-insensitive), or the one the key is a prefix of.
+/// the label of a namespaced key (`acme/Alice`) is compared as well
+fn suggest_key<'a>(key: &str, keys: impl Iterator<Item = &'a str>) -> Option<String> {
+    let key = key.to_lowercase();
+    let max_distance = (key.chars().count() / 3).max(1);
+
+    keys.filter_map(|candidate| {
+        let lowercase = candidate.to_lowercase();
+        let label = match key.contains('/') {
+            true => lowercase.as_str(),
+            false => lowercase.rsplit('/').next().unwrap_or_default(),
+        };
+        let distance = edit_distance(&key, label);
+        let is_prefix = key.chars().count() >= 3 && label.starts_with(&key);
+        (distance <= max_distance || is_prefix).then_some((distance, candidate))
+    })
+    .min()
+    .map(|(_, candidate)| candidate.to_string())
+}
+
+/// the levenshtein distance between the strings, counted in chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut distances = (0..=b.len()).collect::<Vec<usize>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut previous = distances[0];
+        distances[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substituted = previous + usize::from(a_char != *b_char);
+            previous = distances[j + 1];
+            distances[j + 1] = substituted.min(previous + 1).min(distances[j] + 1);
+        }
+    }
+    distances[b.len()]
 }
 
-fn resolve_ref(key: &str, dict: &HashMap<String, String>) -> Result<String> {
-    dict.get(key)
-        .map(|value| value.to_owned())
-        .ok_or_else(|| anyhow::anyhow!("failed to idintify a record referred by the key: `{key}`"))
+/// retrieve the value from the context that matches the provided key
+fn resolve_ctx(
+    key: &str,
+    default: Option<String>,
+    context: &HashMap<String, RefValue>,
+) -> Result<String> {
+    match (context.get(key), default) {
+        (Some(value), _) => Ok(value.to_yaml()),
+        (None, Some(value)) => Ok(value),
+        (None, None) => Err(anyhow::anyhow!("context value: `{}` is not found", key)),
+    }
 }
 
 /// this enum is used to hold the type of the directive indicated by the tag
@@ -89,8 +477,16 @@ enum ParseResult {
     Nothing, // no matches
 }
 
-/// retrieve the values from the environment variable that matches the provided key
-fn resolve_env(key: &str, defalut: Option<String>) -> Result<String> {
+/// retrieve the values given to the scope, or from the environment variable that matches the
+/// provided key
+fn resolve_env(
+    key: &str,
+    defalut: Option<String>,
+    values: &HashMap<String, String>,
+) -> Result<String> {
+    if let Some(value) = values.get(key) {
+        return Ok(value.clone());
+    }
     env::var(key).or_else(|_| match defalut {
         Some(value) => Ok(value),
         None => Err(anyhow::anyhow!(
@@ -104,7 +500,7 @@ fn resolve_env(key: &str, defalut: Option<String>) -> Result<String> {
 fn try_consume(source: &str) -> Result<ParseResult> {
     // matches with something like: ${{ AnyTag(some_key) }}
     let re = regex!(
-        r#"\$\{\{\s*(?P<directive>[[:alnum:]]+)\(\s*(?P<key>[[:alnum:]_-]+)(\s*:-\s*(?P<default>([[:alnum:]]+|"[^"[:cntrl:]]+")))?\s*\)\s*\}\}"#
+        r#"\$\{\{\s*(?P<directive>[[:alnum:]]+)\(\s*(?P<key>[[:alnum:]_./-]+)(\s*:-\s*(?P<default>([[:alnum:]]+|"[^"[:cntrl:]]+")))?\s*\)\s*\}\}"#
     );
 
     let captures = match re.captures(source) {
@@ -144,7 +540,6 @@ fn try_consume(source: &str) -> Result<ParseResult> {
 #[cfg(test)]
 mod tests {
     use crate::resolver::*;
-    use std::env;
 
     #[test]
     // test against embedded tags
@@ -153,41 +548,48 @@ mod tests {
             "The quick brown ${{ ENV(FOX) }} jumps over\nthe lazy ${{ REF(dog) }}".to_string();
 
         // when correspoinding env var is defined
-        env::set_var("FOX", "🦊");
+        let scope = TagScope {
+            env: HashMap::from([("FOX".to_string(), "🦊".to_string())]),
+            ..Default::default()
+        };
         // when the ref is successfully resolved
         let dict = HashMap::from([
-            ("swan".to_string(), "🦢".to_string()),
-            ("dog".to_string(), "🐕".to_string()),
+            ("swan".to_string(), RefValue::String("🦢".to_string())),
+            ("dog".to_string(), RefValue::String("🐕".to_string())),
         ]);
-        let parsed_text = resolve_tags(&raw_text, &dict).unwrap();
-        assert_eq!(parsed_text, "The quick brown 🦊 jumps over\nthe lazy 🐕");
+        let parsed_text = resolve_tags(&raw_text, &dict, &scope).unwrap().text;
+        assert_eq!(
+            parsed_text,
+            "The quick brown 🦊 jumps over\nthe lazy \"🐕\""
+        );
 
         // when the ref is undefined
         let dict = HashMap::from([
-            ("swan".to_string(), "🦢".to_string()),
-            ("dolphin".to_string(), "🐬".to_string()),
+            ("swan".to_string(), RefValue::String("🦢".to_string())),
+            ("dolphin".to_string(), RefValue::String("🐬".to_string())),
         ]);
-        let parsed_text = resolve_tags(&raw_text, &dict);
+        let parsed_text = resolve_tags(&raw_text, &dict, &scope);
         assert!(parsed_text.is_err());
 
         // when the dict is empty
-        let dict = HashMap::new();
-        let parsed_text = resolve_tags(&raw_text, &dict);
+        let dict = HashMap::<String, RefValue>::new();
+        let parsed_text = resolve_tags(&raw_text, &dict, &scope);
         assert!(parsed_text.is_err());
 
         // when correspoinding env var is NOT defined
-        env::remove_var("FOX");
         // when the ref is successfully resolved
         let dict = HashMap::from([
-            ("swan".to_string(), "🦢".to_string()),
-            ("dog".to_string(), "🐕".to_string()),
+            ("swan".to_string(), RefValue::String("🦢".to_string())),
+            ("dog".to_string(), RefValue::String("🐕".to_string())),
         ]);
-        let parsed_text = resolve_tags(&raw_text, &dict);
+        let parsed_text = resolve_tags(&raw_text, &dict, &TagScope::default());
         assert!(parsed_text.is_err());
 
         // when the tag cannot be recognized (due to incorrect format)
         let raw_text = "The quick brown ${{ENV(FOX?)}} jumps over\nthe lazy {REF(dog)}".to_string();
-        let parsed_text = resolve_tags(&raw_text, &dict).unwrap();
+        let parsed_text = resolve_tags(&raw_text, &dict, &TagScope::default())
+            .unwrap()
+            .text;
         // it simply outputs the original text as it is
         assert_eq!(
             parsed_text,
@@ -196,44 +598,234 @@ mod tests {
 
         // when the tag contains unsupported directive name
         let raw_text = "The quick brown ${{REFERENCE(fox_id)}} jumps over the lazy dog".to_string();
-        let parsed_text = resolve_tags(&raw_text, &dict);
+        let parsed_text = resolve_tags(&raw_text, &dict, &TagScope::default());
         assert!(parsed_text.is_err());
     }
 
+    #[test]
+    fn test_resolve_text_without_tags() {
+        let raw_text = "Melon:\n  name: melon\n  price: 500\n";
+        let dict = HashMap::<String, RefValue>::new();
+
+        // the raw text is returned as it is, without being copied
+        let resolved = resolve_tags(raw_text, &dict, &TagScope::default()).unwrap();
+        assert!(matches!(resolved.text, Cow::Borrowed(text) if std::ptr::eq(text, raw_text)));
+        assert_eq!(resolved.tags(), 0);
+        assert_eq!(resolved.raw_offset(8), 8);
+        assert_eq!(resolved.detach(), None);
+
+        let resolved =
+            resolve_tags("price: ${{ CTX(price:-500) }}", &dict, &TagScope::default()).unwrap();
+        assert_eq!(resolved.detach().unwrap().text, "price: 500");
+    }
+
+    #[test]
+    fn test_resolve_scoped_tags() {
+        let raw_text =
+            "tenant: ${{ CTX(tenant) }}\nowner: ${{ REF(Alice) }}\nplan: ${{ REF(Basic) }}";
+        let dict = HashMap::from([
+            ("Alice".to_string(), RefValue::Int(1)),
+            ("acme/Alice".to_string(), RefValue::Int(2)),
+            ("Basic".to_string(), RefValue::Int(3)),
+        ]);
+        let scope = TagScope {
+            namespace: Some("acme".to_string()),
+            context: HashMap::from([("tenant".to_string(), RefValue::String("acme".to_string()))]),
+            ..Default::default()
+        };
+
+        // REFs are looked up in the namespace first
+        let parsed_text = resolve_tags(raw_text, &dict, &scope).unwrap().text;
+        assert_eq!(parsed_text, "tenant: \"acme\"\nowner: 2\nplan: 3");
+
+        // namespaced labels can be referred explicitly
+        let parsed_text =
+            resolve_tags("owner: ${{ REF(acme/Alice) }}", &dict, &TagScope::default())
+                .unwrap()
+                .text;
+        assert_eq!(parsed_text, "owner: 2");
+
+        // CTX tags fall back to the default values
+        let parsed_text = resolve_tags(
+            "tenant: ${{ CTX(tenant:-none) }}",
+            &dict,
+            &TagScope::default(),
+        )
+        .unwrap()
+        .text;
+        assert_eq!(parsed_text, "tenant: none");
+        assert!(resolve_tags("tenant: ${{ CTX(tenant) }}", &dict, &TagScope::default()).is_err());
+    }
+
+    #[test]
+    fn test_resolve_secrets() {
+        let raw_text = "key: ${{ SECRET(CDER_TEST_API_KEY) }}\nhost: ${{ ENV(CDER_TEST_HOST) }}\nenv: ${{ ENV(CDER_TEST_ENV) }}\nport: ${{ ENV(CDER_TEST_PORT:-5432) }}";
+        let env = HashMap::from(
+            [
+                ("CDER_TEST_API_KEY", "sk"),
+                ("CDER_TEST_HOST", "db.example.com"),
+                ("CDER_TEST_ENV", "dev"),
+            ]
+            .map(|(key, value)| (key.to_string(), value.to_string())),
+        );
+        let dict = HashMap::<String, RefValue>::new();
+
+        // the short values of ENV tags and the defaults are not masked
+        let scope = TagScope {
+            env: env.clone(),
+            ..Default::default()
+        };
+        let resolved = resolve_tags(raw_text, &dict, &scope).unwrap();
+        assert_eq!(
+            resolved.text,
+            "key: sk\nhost: db.example.com\nenv: dev\nport: 5432"
+        );
+        assert_eq!(resolved.secrets, vec!["sk", "db.example.com"]);
+
+        let scope = TagScope {
+            unmasked_env: ["CDER_TEST_HOST", "CDER_TEST_API_KEY"]
+                .map(str::to_string)
+                .into(),
+            env,
+            ..Default::default()
+        };
+        let resolved = resolve_tags(raw_text, &dict, &scope).unwrap();
+        assert_eq!(resolved.secrets, vec!["sk"]);
+    }
+
+    #[test]
+    fn test_raw_offset() {
+        let raw_text = "a: ${{ REF(alice) }}\nb: ${{ CTX(b:-12345) }}\nc: 3";
+        let dict = HashMap::from([("alice".to_string(), RefValue::Int(1))]);
+        let resolved = resolve_tags(raw_text, &dict, &TagScope::default()).unwrap();
+        assert_eq!(resolved.text, "a: 1\nb: 12345\nc: 3");
+
+        // before, within and after the replacements
+        assert_eq!(resolved.raw_offset(2), 2);
+        assert_eq!(resolved.raw_offset(3), 3);
+        assert_eq!(resolved.raw_offset(5), raw_text.find("b:").unwrap());
+        assert_eq!(resolved.raw_offset(10), raw_text.find("${{ CTX").unwrap());
+        assert_eq!(
+            resolved.raw_offset(resolved.text.len() - 1),
+            raw_text.len() - 1
+        );
+    }
+
+    #[test]
+    fn test_scan_refs() {
+        let raw_text =
+            "The quick brown ${{ ENV(FOX) }} jumps over\nthe lazy ${{ REF(dog) }} and ${{REF(cat)}}, ${{REF(dog)}}";
+
+        // tags are masked regardless of the environment variables or the dict
+        let (masked_text, refs) = scan_refs(raw_text).unwrap();
+        assert_eq!(
+            masked_text,
+            "The quick brown 0 jumps over\nthe lazy 0 and 0, 0"
+        );
+        assert_eq!(refs, vec!["dog".to_string(), "cat".to_string()]);
+
+        // when there is no tags
+        let (masked_text, refs) = scan_refs("foo: bar").unwrap();
+        assert_eq!(masked_text, "foo: bar");
+        assert!(matches!(masked_text, Cow::Borrowed(_)));
+        assert!(refs.is_empty());
+
+        // when nothing looks like a tag but the braces
+        let (masked_text, _) = scan_refs("foo: ${{ bar }}").unwrap();
+        assert!(matches!(masked_text, Cow::Borrowed("foo: ${{ bar }}")));
+
+        // when the tag contains unsupported directive name
+        let raw_text = "The quick brown ${{REFERENCE(fox_id)}} jumps over the lazy dog";
+        assert!(scan_refs(raw_text).is_err());
+    }
+
+    #[test]
+    fn test_invalid_tags() {
+        let raw_text = "Alice:\n  email: ${{ ENV(EMAIL:-\"alice@example.com\") }}\n  company_id: ${{ REF(Acme }}\n  role: ${{ ROLE(admin) }}\n  parent_id: ${{ PARENT }}\n  note: ${{ REF(Acme)\n";
+
+        assert_eq!(
+            invalid_tags(raw_text),
+            vec![
+                (
+                    "${{ REF(Acme }}",
+                    "malformed tag: ${{ REF(Acme }}".to_string()
+                ),
+                (
+                    "${{ ROLE(admin) }}",
+                    "unsupported directive: `ROLE` in ${{ ROLE(admin) }}".to_string()
+                ),
+                ("${{ REF(Acme)", "malformed tag: ${{ REF(Acme)".to_string()),
+            ]
+        );
+        assert!(invalid_tags("foo: bar").is_empty());
+    }
+
     #[test]
     fn test_resolve_ref() {
         let dict = HashMap::from([
-            ("foo".to_string(), "bar".to_string()),
-            ("umi".to_string(), "yama".to_string()),
+            ("foo".to_string(), RefValue::String("bar".to_string())),
+            ("umi".to_string(), RefValue::Int(42)),
         ]);
 
+        // string values are quoted
         let value = resolve_ref("foo", &dict).unwrap();
-        assert_eq!(value, "bar");
+        assert_eq!(value, "\"bar\"");
+
+        let value = resolve_ref("umi", &dict).unwrap();
+        assert_eq!(value, "42");
 
         let value = resolve_ref("BAZ", &dict);
         assert!(value.is_err());
 
-        let dict = HashMap::new();
+        let dict = HashMap::<String, RefValue>::new();
         let value = resolve_ref("foo", &dict);
         assert!(value.is_err());
     }
 
+    #[test]
+    fn test_suggest_key() {
+        let keys = ["Alice", "Bob", "acme/Alison", "Order1", "Order12"]
+            .iter()
+            .map(|key| key.to_string())
+            .collect::<Vec<String>>();
+        let suggest = |key: &str| suggest_key(key, keys.iter().map(String::as_str));
+
+        assert_eq!(suggest("Alicia").as_deref(), Some("Alice"));
+        assert_eq!(suggest("alice").as_deref(), Some("Alice"));
+        assert_eq!(suggest("Bbo").as_deref(), None);
+        assert_eq!(suggest("Bo").as_deref(), Some("Bob"));
+        assert_eq!(suggest("Alisn").as_deref(), Some("acme/Alison"));
+        assert_eq!(suggest("Order").as_deref(), Some("Order1"));
+        assert_eq!(suggest("Carol"), None);
+
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("🦊", "🦊"), 0);
+
+        let dict = HashMap::from([("Alice".to_string(), RefValue::Int(1))]);
+        let err = resolve_ref("Alicia", &dict).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "failed to idintify a record referred by the key: `Alicia`; did you mean `Alice`?"
+        );
+    }
+
     #[test]
     fn test_resolve_env() {
-        let key = "FOO";
+        let key = "CDER_TEST_FOO";
 
         // when correspoinding env var is NOT defined
-        env::remove_var(key);
-        assert!(resolve_env(key, None).is_err());
+        let values = HashMap::new();
+        assert!(resolve_env(key, None, &values).is_err());
 
-        let value = resolve_env(key, Some("default".to_string())).unwrap();
+        let value = resolve_env(key, Some("default".to_string()), &values).unwrap();
         assert_eq!(value, "default");
 
-        // when correspoinding env var is defined
-        env::set_var(key, "SOME_VALUE");
-        assert_eq!(resolve_env(key, None).unwrap(), "SOME_VALUE");
+        // when correspoinding env var is given to the scope
+        let values = HashMap::from([(key.to_string(), "SOME_VALUE".to_string())]);
+        assert_eq!(resolve_env(key, None, &values).unwrap(), "SOME_VALUE");
 
-        let value = resolve_env(key, Some("default".to_string())).unwrap();
+        let value = resolve_env(key, Some("default".to_string()), &values).unwrap();
         assert_eq!(value, "SOME_VALUE");
     }
 
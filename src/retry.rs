@@ -0,0 +1,156 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use std::{future::Future, sync::Arc, thread, time::Duration};
+
+/// RetryPolicy configures how many times a failed insertion is retried, and how long to wait in
+/// between, so that transient failures (deadlocks, connection blips etc.) do not abort the whole
+/// seeding run. Pass it to `DatabaseSeeder::set_retry_policy`.
+///
+/// # Examples
+/// ```rust
+/// use cder::{DatabaseSeeder, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let mut seeder = DatabaseSeeder::new();
+///
+/// // tries up to 3 times, waiting 50ms, then 100ms in between
+/// seeder.set_retry_policy(
+///     RetryPolicy::new(3)
+///         .backoff(Duration::from_millis(50))
+///         .multiplier(2)
+///         .retry_if(|err| err.to_string().contains("deadlock")),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: usize,
+    pub backoff: Duration,
+    pub multiplier: u32,
+    retryable: Arc<dyn Fn(&anyhow::Error) -> bool + Send + Sync>,
+}
+
+impl RetryPolicy {
+    /// retries any error until the insertion has been attempted `max_attempts` times in total,
+    /// waiting 100ms before the first retry and doubling the wait every time
+    pub fn new(max_attempts: usize) -> Self {
+        Self {
+            max_attempts,
+            backoff: Duration::from_millis(100),
+            multiplier: 2,
+            retryable: Arc::new(|_| true),
+        }
+    }
+
+    /// sets the wait before the first retry
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// sets the factor the wait is multiplied by after every retry
+    pub fn multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// retries only the errors that satisfy the predicate
+    pub fn retry_if<F>(mut self, retryable: F) -> Self
+    where
+        F: Fn(&anyhow::Error) -> bool + Send + Sync + 'static,
+    {
+        self.retryable = Arc::new(retryable);
+        self
+    }
+
+    fn should_retry(&self, attempt: usize, err: &anyhow::Error) -> bool {
+        attempt < self.max_attempts && (self.retryable)(err)
+    }
+
+    fn delay(&self, attempt: usize) -> Duration {
+        let exponent = u32::try_from(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.backoff
+            .saturating_mul(self.multiplier.saturating_pow(exponent))
+    }
+
+    /// runs the loader, and retries it with the record deserialized again from the value
+    /// (as the loader takes the ownership of the record)
+    pub(crate) fn run<F, T, U>(
+        &self,
+        record: T,
+        value: &serde_yaml::Value,
+        loader: &mut F,
+    ) -> Result<U>
+    where
+        F: FnMut(T) -> Result<U>,
+        T: DeserializeOwned,
+    {
+        let mut record = Some(record);
+        let mut attempt = 1;
+
+        loop {
+            let input = match record.take() {
+                Some(record) => record,
+                None => serde_yaml::from_value(value.clone())?,
+            };
+
+            match loader(input) {
+                Err(err) if self.should_retry(attempt, &err) => {
+                    thread::sleep(self.delay(attempt));
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// works the same as `run`, but with an async loader
+    pub(crate) async fn run_async<Fut, F, T, U>(
+        &self,
+        record: T,
+        value: &serde_yaml::Value,
+        loader: &mut F,
+    ) -> Result<U>
+    where
+        Fut: Future<Output = Result<U>>,
+        F: FnMut(T) -> Fut,
+        T: DeserializeOwned,
+    {
+        let mut record = Some(record);
+        let mut attempt = 1;
+
+        loop {
+            let input = match record.take() {
+                Some(record) => record,
+                None => serde_yaml::from_value(value.clone())?,
+            };
+
+            match loader(input).await {
+                Err(err) if self.should_retry(attempt, &err) => {
+                    futures_timer::Delay::new(self.delay(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// runs the operation, and retries it as long as it fails with the retryable errors, e.g.
+    /// the requests of `HttpAdapter`
+    #[cfg(feature = "http")]
+    pub(crate) async fn run_operation<Fut, F, U>(&self, mut operation: F) -> Result<U>
+    where
+        Fut: Future<Output = Result<U>>,
+        F: FnMut() -> Fut,
+    {
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Err(err) if self.should_retry(attempt, &err) => {
+                    futures_timer::Delay::new(self.delay(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+
+use crate::{HttpAdapter, HttpBody, HttpClient, RefValue, RetryPolicy, SeedAdapter};
+
+/// BulkIndexAdapter is an adapter (see `SeedAdapter`) that indexes the records as the documents
+/// of the index with the `_bulk` API of Elasticsearch or OpenSearch, a request for each chunk of
+/// `DatabaseSeeder::populate_many_with`. The ids of the documents are the labels of the records,
+/// or the values of the field given by `id_field`, e.g. the ids the REFs resolved to in the
+/// primary database, so that the index stays consistent with it. The documents are indexed (i.e.
+/// overwritten) rather than created, to seed the index again without deleting it. The requests
+/// are sent by the HttpClient as HttpAdapter does, with the same headers and retries. Available
+/// with the `search` feature.
+///
+/// # Examples
+/// ```rust,no_run
+/// use cder::{BulkIndexAdapter, DatabaseSeeder, HttpClient};
+/// # use anyhow::Result;
+///
+/// async fn populate_seeds<C: HttpClient>(client: C, seeder: &mut DatabaseSeeder) -> Result<()> {
+///     // the items have been seeded into the database, and are indexed by the ids there
+///     let mut items = BulkIndexAdapter::new(client, "http://localhost:9200", "items")
+///         .id_field("id")
+///         .refresh(true);
+///
+///     seeder
+///         .populate_many_with::<serde_json::Value, _>("fixtures/search_items.yml", 500, &mut items)
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+pub struct BulkIndexAdapter<C> {
+    http: HttpAdapter<C>,
+    index: String,
+    id_field: Option<String>,
+}
+
+impl<C> BulkIndexAdapter<C>
+where
+    C: HttpClient,
+{
+    /// the adapter that indexes the documents into the index of the cluster at the url, e.g.
+    /// `http://localhost:9200`
+    pub fn new(client: C, url: &str, index: &str) -> Self {
+        let endpoint = format!("{}/_bulk", url.trim_end_matches('/'));
+        Self {
+            http: HttpAdapter::new(client, &endpoint),
+            index: index.to_string(),
+            id_field: None,
+        }
+    }
+
+    /// takes the ids of the documents from the field, rather than using the labels of the records
+    pub fn id_field(mut self, id_field: &str) -> Self {
+        self.id_field = Some(id_field.to_string());
+        self
+    }
+
+    /// refreshes the index after each request, so that the documents are searchable right away
+    /// (e.g. in the tests)
+    pub fn refresh(mut self, refresh: bool) -> Self {
+        let endpoint = self.http.endpoint().split('?').next().unwrap_or_default();
+        let endpoint = match refresh {
+            true => format!("{}?refresh=true", endpoint),
+            false => endpoint.to_string(),
+        };
+        self.http = self.http.endpoint_url(&endpoint);
+        self
+    }
+
+    /// adds the header to every request
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.http = self.http.header(name, value);
+        self
+    }
+
+    /// authenticates the requests with `Authorization: Bearer <token>`
+    pub fn bearer_auth(mut self, token: &str) -> Self {
+        self.http = self.http.bearer_auth(token);
+        self
+    }
+
+    /// retries the failed requests, see `HttpAdapter::retry_policy`. the documents failed to be
+    /// indexed are not retried
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.http = self.http.retry_policy(retry_policy);
+        self
+    }
+
+    pub fn index(&self) -> &str {
+        &self.index
+    }
+
+    pub fn client(&self) -> &C {
+        self.http.client()
+    }
+
+    /// the id of the document for the record
+    fn document_id(&self, label: Option<&str>, document: &JsonValue) -> Result<RefValue> {
+        match (&self.id_field, label) {
+            (Some(id_field), _) => {
+                let id = document.get(id_field).ok_or_else(|| {
+                    anyhow!(
+                        "the record has no `{}` to be its id (index: {})",
+                        id_field,
+                        self.index
+                    )
+                })?;
+                match RefValue::from_json(id) {
+                    Some(RefValue::Composite(_)) | None => Err(anyhow!(
+                        "`{}` is not a valid document id: {} (index: {})",
+                        id_field,
+                        id,
+                        self.index
+                    )),
+                    Some(id) => Ok(id),
+                }
+            }
+            (None, Some(label)) => Ok(RefValue::String(label.to_string())),
+            (None, None) => Err(anyhow!(
+                "the record has neither its label nor `id_field` to be its id (index: {})",
+                self.index
+            )),
+        }
+    }
+
+    /// indexes the documents with a `_bulk` request, failing if any of them is not indexed
+    async fn bulk<T>(&self, records: Vec<(Option<String>, T)>) -> Result<Vec<RefValue>>
+    where
+        T: Serialize,
+    {
+        let mut ids = Vec::with_capacity(records.len());
+        let mut lines = Vec::with_capacity(records.len() * 2);
+        for (label, record) in records {
+            let document = serde_json::to_value(&record)?;
+            let id = self.document_id(label.as_deref(), &document)?;
+            lines.push(json!({ "index": { "_index": self.index, "_id": id.to_string() } }));
+            lines.push(document);
+            ids.push(id);
+        }
+        if ids.is_empty() {
+            return Ok(ids);
+        }
+
+        let body = self.http.send(HttpBody::NdJson(lines)).await?;
+        let endpoint = self.http.endpoint();
+        let response = serde_json::from_str::<JsonValue>(&body).map_err(|err| {
+            anyhow!(
+                "the response is not JSON: {} (endpoint: {}, response: {})",
+                err,
+                endpoint,
+                body
+            )
+        })?;
+        if let Some(errors) = bulk_errors(&response) {
+            return Err(anyhow!(
+                "failed to index the documents: {} (index: {})",
+                errors,
+                self.index
+            ));
+        }
+        Ok(ids)
+    }
+}
+
+impl<T, C> SeedAdapter<T> for BulkIndexAdapter<C>
+where
+    T: Serialize + Send,
+    C: HttpClient,
+{
+    type Id = RefValue;
+
+    async fn insert(&mut self, record: T) -> Result<RefValue> {
+        let mut ids = self.bulk(vec![(None, record)]).await?;
+        Ok(ids.remove(0))
+    }
+
+    async fn insert_labeled(&mut self, label: &str, record: T) -> Result<RefValue> {
+        let mut ids = self.bulk(vec![(Some(label.to_string()), record)]).await?;
+        Ok(ids.remove(0))
+    }
+
+    async fn insert_many(&mut self, records: Vec<T>) -> Result<Vec<RefValue>> {
+        let records = records.into_iter().map(|record| (None, record)).collect();
+        self.bulk(records).await
+    }
+
+    async fn insert_many_labeled(&mut self, records: Vec<(String, T)>) -> Result<Vec<RefValue>> {
+        let records = records
+            .into_iter()
+            .map(|(label, record)| (Some(label), record))
+            .collect();
+        self.bulk(records).await
+    }
+}
+
+/// the errors of the items of the `_bulk` response, as `<id>: <type>: <reason>` joined with `; `
+fn bulk_errors(response: &JsonValue) -> Option<String> {
+    if response.get("errors") != Some(&JsonValue::Bool(true)) {
+        return None;
+    }
+    let messages = response
+        .get("items")
+        .and_then(|items| items.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item.as_object()?.values().next())
+        .filter_map(|result| {
+            let error = result.get("error")?;
+            let id = result.get("_id").and_then(|id| id.as_str()).unwrap_or("?");
+            let message = match (
+                error.get("type").and_then(|kind| kind.as_str()),
+                error.get("reason").and_then(|reason| reason.as_str()),
+            ) {
+                (Some(kind), Some(reason)) => format!("{}: {}", kind, reason),
+                _ => error.to_string(),
+            };
+            Some(format!("{}: {}", id, message))
+        })
+        .collect::<Vec<String>>();
+    match messages.is_empty() {
+        true => Some("the response has errors".to_string()),
+        false => Some(messages.join("; ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search::*;
+
+    #[test]
+    fn test_bulk_errors() {
+        let response = json!({
+            "took": 3,
+            "errors": true,
+            "items": [
+                { "index": { "_index": "items", "_id": "Melon", "status": 201 } },
+                {
+                    "index": {
+                        "_index": "items",
+                        "_id": "Carrot",
+                        "status": 400,
+                        "error": { "type": "mapper_parsing_exception", "reason": "failed to parse field [price]" }
+                    }
+                }
+            ]
+        });
+        assert_eq!(
+            bulk_errors(&response),
+            Some("Carrot: mapper_parsing_exception: failed to parse field [price]".to_string())
+        );
+        assert_eq!(bulk_errors(&json!({ "errors": false, "items": [] })), None);
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use crate::{database_seeder::lock, DatabaseSeeder, ToRefValue};
+
+/// SharedSeeder wraps a DatabaseSeeder so that it can be shared across tasks or threads, and
+/// populated through `&self`, e.g. to seed independent files concurrently from different tokio
+/// tasks. Each file is populated by a fork of the seeder, holding the ids known at that time,
+/// and the fork is joined back once the file is done: files populated at the same time cannot
+/// REF each other, while the ones populated afterwards can REF both.
+///
+/// ```rust
+/// use cder::{DatabaseSeeder, SharedSeeder};
+/// # use serde::Deserialize;
+/// # use anyhow::Result;
+/// #
+/// # #[derive(Deserialize)]
+/// # struct User {
+/// #   name: String,
+/// # }
+/// # #[derive(Deserialize)]
+/// # struct Item {
+/// #   name: String,
+/// # }
+/// #
+/// # async fn insert_user(input: User) -> Result<i64> { Ok(1) }
+/// # async fn insert_item(input: Item) -> Result<i64> { Ok(1) }
+/// #
+/// # fn main() {
+/// #     populate_seeds();
+/// # }
+///
+/// async fn populate_seeds() -> Result<()> {
+///     let seeder = SharedSeeder::new(DatabaseSeeder::new());
+///
+///     // users and items do not depend on each other
+///     let (users, items) = futures::join!(
+///         seeder.populate_async("fixtures/users.yml", insert_user),
+///         seeder.populate_async("fixtures/items.yml", insert_item),
+///     );
+///     users?;
+///     items?;
+///
+///     let seeder = seeder.into_inner()?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SharedSeeder {
+    seeder: Arc<Mutex<DatabaseSeeder>>,
+}
+
+impl SharedSeeder {
+    pub fn new(seeder: DatabaseSeeder) -> Self {
+        Self {
+            seeder: Arc::new(Mutex::new(seeder)),
+        }
+    }
+
+    /// runs the function with the underlying seeder, e.g. to read the ids.
+    /// do not populate files inside the function, as it blocks the other tasks until it returns
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut DatabaseSeeder) -> R,
+    {
+        f(&mut lock(&self.seeder))
+    }
+
+    /// works the same as `DatabaseSeeder::populate`, on a fork of the seeder
+    pub fn populate<F, T, U>(&self, filename: &str, loader: F) -> Result<Vec<U>>
+    where
+        F: FnMut(T) -> Result<U>,
+        T: DeserializeOwned,
+        U: ToRefValue,
+    {
+        let mut fork = lock(&self.seeder).fork();
+        let results = fork.populate(filename, loader);
+        self.join(fork, results)
+    }
+
+    /// works the same as `DatabaseSeeder::populate_async`, on a fork of the seeder
+    pub async fn populate_async<Fut, F, T, U>(&self, filename: &str, loader: F) -> Result<Vec<U>>
+    where
+        Fut: Future<Output = Result<U>>,
+        F: FnMut(T) -> Fut,
+        T: DeserializeOwned,
+        U: ToRefValue,
+    {
+        let mut fork = lock(&self.seeder).fork();
+        let results = fork.populate_async(filename, loader).await;
+        self.join(fork, results)
+    }
+
+    /// Returns the underlying seeder, which fails while the seeder is still shared with other
+    /// tasks.
+    pub fn into_inner(self) -> Result<DatabaseSeeder> {
+        let seeder = Arc::try_unwrap(self.seeder)
+            .map_err(|_| anyhow::anyhow!("the seeder is still shared with other tasks"))?;
+        Ok(seeder.into_inner().unwrap_or_else(|err| err.into_inner()))
+    }
+
+    /// joins the fork even when the population has failed, so that the records inserted until
+    /// then can still be torn down
+    fn join<U>(&self, fork: DatabaseSeeder, results: Result<Vec<U>>) -> Result<Vec<U>> {
+        let joined = lock(&self.seeder).join(fork);
+        let ids = results?;
+        joined?;
+        Ok(ids)
+    }
+}
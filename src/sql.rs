@@ -0,0 +1,337 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_yaml::Value;
+use std::io::Write;
+
+use crate::{RefValue, SeedAdapter};
+
+/// the statements SqlWriter renders the records into
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SqlFormat {
+    /// `INSERT INTO <table> (<columns>) VALUES (<values>);` for each record
+    #[default]
+    Insert,
+    /// `COPY <table> (<columns>) FROM stdin;` followed by the rows in the text format of
+    /// Postgres, in a block for each batch of the records inserted at once, e.g. for each chunk
+    /// of `DatabaseSeeder::populate_many_with`
+    Copy,
+}
+
+/// SqlWriter is an adapter (see `SeedAdapter`) that writes the records as SQL statements instead
+/// of inserting them, e.g. to review the seeds and apply them as plain SQL. The ids are assigned
+/// sequentially from 1 (see `starting_at`) and written into the id column, unless the record has
+/// the column itself, so that REFs resolve to the same ids on every run. The sequences of the
+/// tables are not advanced by the statements.
+///
+/// The columns are the fields of the records as they are serialized, and the values are written
+/// as SQL literals: the sequences and the mappings (e.g. the variants of enums) as JSON.
+///
+/// ```rust
+/// use cder::{DatabaseSeeder, SqlWriter};
+/// # use serde::{Deserialize, Serialize};
+/// # use anyhow::Result;
+/// #
+/// # #[derive(Deserialize, Serialize)]
+/// # struct Item {
+/// #     name: String,
+/// #     price: f64,
+/// # }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<()> {
+/// let mut seeder = DatabaseSeeder::new();
+/// seeder.set_dir("tests/fixtures");
+///
+/// let mut writer = SqlWriter::new(Vec::new(), "items");
+/// seeder.populate_with::<Item, _>("items.yml", &mut writer).await?;
+///
+/// let sql = String::from_utf8(writer.into_inner())?;
+/// assert!(sql.starts_with("INSERT INTO items (id, name, price) VALUES (1, 'melon', 500.0);\n"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SqlWriter<W> {
+    writer: W,
+    table: String,
+    id_column: String,
+    format: SqlFormat,
+    next_id: i64,
+}
+
+/// the columns and the values of a record
+type Row = Vec<(String, Value)>;
+
+impl<W> SqlWriter<W>
+where
+    W: Write,
+{
+    pub fn new(writer: W, table: &str) -> Self {
+        Self {
+            writer,
+            table: table.to_string(),
+            id_column: "id".to_string(),
+            format: SqlFormat::default(),
+            next_id: 1,
+        }
+    }
+
+    /// the column the ids are written into, `id` by default
+    pub fn id_column(mut self, id_column: &str) -> Self {
+        self.id_column = id_column.to_string();
+        self
+    }
+
+    pub fn format(mut self, format: SqlFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// assigns the ids from the given one, e.g. to continue the ids of the table
+    pub fn starting_at(mut self, id: i64) -> Self {
+        self.next_id = id;
+        self
+    }
+
+    /// the id the next record is assigned, unless it has the id column itself
+    pub fn next_id(&self) -> i64 {
+        self.next_id
+    }
+
+    /// returns the writer, e.g. the buffer the statements have been written into
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// serializes the record into its columns and values, with the id column first if the id is
+    /// assigned
+    fn row<T>(&mut self, record: &T) -> Result<(RefValue, Row)>
+    where
+        T: Serialize,
+    {
+        let Value::Mapping(fields) = serde_yaml::to_value(record)? else {
+            return Err(anyhow!(
+                "the records must be structs or mappings to be written as SQL (table: {})",
+                self.table
+            ));
+        };
+        let mut row = fields
+            .into_iter()
+            .map(|(column, value)| match column {
+                Value::String(column) => Ok((column, value)),
+                column => Err(anyhow!(
+                    "the column must be a string: {:?} (table: {})",
+                    column,
+                    self.table
+                )),
+            })
+            .collect::<Result<Row>>()?;
+
+        let id = match row.iter().find(|(column, _)| *column == self.id_column) {
+            Some((_, id)) => match id {
+                Value::Number(number) => number.as_i64().map(RefValue::Int),
+                Value::String(text) => Some(RefValue::String(text.clone())),
+                _ => None,
+            }
+            .ok_or_else(|| {
+                anyhow!(
+                    "the id must be an integer or a string: {:?} (table: {})",
+                    id,
+                    self.table
+                )
+            })?,
+            None => {
+                let id = self.next_id;
+                self.next_id += 1;
+                row.insert(0, (self.id_column.clone(), Value::from(id)));
+                RefValue::Int(id)
+            }
+        };
+        Ok((id, row))
+    }
+
+    fn write_insert(&mut self, row: &Row) -> Result<()> {
+        let (columns, values) = row
+            .iter()
+            .map(|(column, value)| (column.as_str(), sql_literal(value)))
+            .unzip::<_, _, Vec<&str>, Vec<String>>();
+        writeln!(
+            self.writer,
+            "INSERT INTO {} ({}) VALUES ({});",
+            self.table,
+            columns.join(", "),
+            values.join(", ")
+        )?;
+        Ok(())
+    }
+
+    /// writes the rows in COPY blocks, starting another one wherever the columns change
+    fn write_copy(&mut self, rows: &[Row]) -> Result<()> {
+        let mut columns = Vec::<&str>::new();
+        for row in rows {
+            let row_columns = row
+                .iter()
+                .map(|(column, _)| column.as_str())
+                .collect::<Vec<&str>>();
+            if row_columns != columns {
+                if !columns.is_empty() {
+                    writeln!(self.writer, "\\.")?;
+                }
+                writeln!(
+                    self.writer,
+                    "COPY {} ({}) FROM stdin;",
+                    self.table,
+                    row_columns.join(", ")
+                )?;
+                columns = row_columns;
+            }
+            let values = row
+                .iter()
+                .map(|(_, value)| copy_text(value))
+                .collect::<Vec<String>>();
+            writeln!(self.writer, "{}", values.join("\t"))?;
+        }
+        if !columns.is_empty() {
+            writeln!(self.writer, "\\.")?;
+        }
+        Ok(())
+    }
+}
+
+impl<T, W> SeedAdapter<T> for SqlWriter<W>
+where
+    T: Serialize + Send,
+    W: Write + Send,
+{
+    type Id = RefValue;
+
+    async fn insert(&mut self, record: T) -> Result<RefValue> {
+        let (id, row) = self.row(&record)?;
+        match self.format {
+            SqlFormat::Insert => self.write_insert(&row)?,
+            SqlFormat::Copy => self.write_copy(&[row])?,
+        }
+        Ok(id)
+    }
+
+    async fn insert_many(&mut self, records: Vec<T>) -> Result<Vec<RefValue>> {
+        let (ids, rows) = records
+            .iter()
+            .map(|record| self.row(record))
+            .collect::<Result<Vec<(RefValue, Row)>>>()?
+            .into_iter()
+            .unzip::<_, _, Vec<RefValue>, Vec<Row>>();
+        match self.format {
+            SqlFormat::Insert => {
+                for row in &rows {
+                    self.write_insert(row)?;
+                }
+            }
+            SqlFormat::Copy => self.write_copy(&rows)?,
+        }
+        Ok(ids)
+    }
+}
+
+/// the value as a SQL literal, e.g. `'O''Brien'`
+fn sql_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Bool(value) => value.to_string().to_uppercase(),
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => quote(text),
+        value => quote(&json_text(value)),
+    }
+}
+
+/// the value in the text format of COPY, e.g. `\N` for NULL
+pub(crate) fn copy_text(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return "\\N".to_string(),
+        Value::Bool(true) => return "t".to_string(),
+        Value::Bool(false) => return "f".to_string(),
+        Value::Number(number) => return number.to_string(),
+        Value::String(text) => text.clone(),
+        value => json_text(value),
+    };
+    text.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// the sequences and the mappings as JSON. the variants of enums (e.g. `!Family {...}`) are
+/// written as they are by serde_json, i.e. `{"Family": {...}}`, or `Premium` for `!Premium`
+pub(crate) fn json_text(value: &Value) -> String {
+    match json_value(value) {
+        serde_json::Value::String(text) => text,
+        value => value.to_string(),
+    }
+}
+
+fn json_value(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Bool(value) => serde_json::Value::Bool(*value),
+        Value::Number(number) => serde_json::to_value(number).unwrap_or_default(),
+        Value::String(text) => serde_json::Value::String(text.clone()),
+        Value::Sequence(values) => values.iter().map(json_value).collect(),
+        Value::Mapping(mapping) => serde_json::Value::Object(
+            mapping
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        Value::String(key) => key.clone(),
+                        key => json_text(key),
+                    };
+                    (key, json_value(value))
+                })
+                .collect(),
+        ),
+        Value::Tagged(tagged) => {
+            let variant = tagged.tag.to_string();
+            let variant = variant.trim_start_matches('!').to_string();
+            // the unit variants, e.g. `!Premium`
+            if tagged.value.is_null() {
+                return serde_json::Value::String(variant);
+            }
+            serde_json::Value::Object([(variant, json_value(&tagged.value))].into_iter().collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sql::*;
+
+    #[test]
+    fn test_sql_literal() {
+        let value = serde_yaml::from_str::<Value>(
+            "name: O'Brien\nactive: true\nscore: null\ntags: [a, b]\nplan: !Family { shared_membership: 4 }\n",
+        )
+        .unwrap();
+        let literals = value
+            .as_mapping()
+            .unwrap()
+            .values()
+            .map(sql_literal)
+            .collect::<Vec<String>>();
+        assert_eq!(
+            literals,
+            vec![
+                "'O''Brien'",
+                "TRUE",
+                "NULL",
+                "'[\"a\",\"b\"]'",
+                "'{\"Family\":{\"shared_membership\":4}}'"
+            ]
+        );
+
+        assert_eq!(copy_text(&Value::from("a\tb\\c\n")), "a\\tb\\\\c\\n");
+        assert_eq!(copy_text(&Value::Null), "\\N");
+    }
+}
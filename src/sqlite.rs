@@ -0,0 +1,192 @@
+//! Seeding SQLite databases with sqlx, e.g. an in-memory database for unit tests that want a real
+//! SQL database with the fixtures in it. Available with the `sqlite` feature.
+
+use anyhow::Result;
+use serde_json::Value as JsonValue;
+use sqlx::{
+    query::Query,
+    sqlite::{SqliteArguments, SqliteConnection, SqliteRow},
+    Connection, Row, Sqlite,
+};
+
+use crate::{sqlx_support::parse_id, DatabaseSeeder, RefValue, SeedAdapter};
+
+/// SqliteTable is a SeedAdapter that inserts the records into a SQLite table as they are written
+/// in the fixtures, without any model: the fields of each record are bound to the columns of the
+/// same names, with the sequences and mappings stored as JSON text, and the columns left out get
+/// their defaults. The ids are returned as integers, uuids or strings, whichever they can be
+/// parsed as.
+///
+/// ```rust,no_run
+/// use cder::{sqlite::SqliteTable, DatabaseSeeder};
+/// # use sqlx::sqlite::SqliteConnection;
+/// # use anyhow::Result;
+///
+/// async fn populate_seeds(connection: &mut SqliteConnection) -> Result<()> {
+///     let mut seeder = DatabaseSeeder::new();
+///     let mut users = SqliteTable::new(connection, "users");
+///
+///     seeder
+///         .populate_with::<serde_json::Value, _>("fixtures/users.yml", &mut users)
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+pub struct SqliteTable<'c> {
+    connection: &'c mut SqliteConnection,
+    table: String,
+    id_column: String,
+}
+
+impl<'c> SqliteTable<'c> {
+    pub fn new(connection: &'c mut SqliteConnection, table: &str) -> Self {
+        Self {
+            connection,
+            table: table.to_string(),
+            id_column: "id".to_string(),
+        }
+    }
+
+    /// sets the primary key column (default: `id`)
+    pub fn id_column(mut self, id_column: &str) -> Self {
+        self.id_column = id_column.to_string();
+        self
+    }
+
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+}
+
+/// builds `INSERT INTO <table> (<columns>) VALUES (?, ?, ...) RETURNING CAST(<id_column> AS TEXT)`
+fn insert_statement(table: &str, columns: &[&str], id_column: &str) -> String {
+    let placeholders = vec!["?"; columns.len()];
+    format!(
+        "INSERT INTO {} ({}) VALUES ({}) RETURNING CAST({} AS TEXT)",
+        table,
+        columns.join(", "),
+        placeholders.join(", "),
+        id_column
+    )
+}
+
+/// binds the value as the SQLite type it maps to: sequences and mappings are bound as JSON text
+fn bind_value<'q>(
+    query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    value: &JsonValue,
+) -> Query<'q, Sqlite, SqliteArguments<'q>> {
+    match value {
+        JsonValue::Null => query.bind(None::<String>),
+        JsonValue::Bool(value) => query.bind(*value),
+        JsonValue::Number(value) => match value.as_i64() {
+            Some(value) => query.bind(value),
+            None => query.bind(value.as_f64()),
+        },
+        JsonValue::String(value) => query.bind(value.clone()),
+        value => query.bind(value.to_string()),
+    }
+}
+
+impl SeedAdapter<JsonValue> for SqliteTable<'_> {
+    type Id = RefValue;
+
+    async fn insert(&mut self, record: JsonValue) -> Result<RefValue> {
+        let fields = record
+            .as_object()
+            .ok_or_else(|| anyhow::anyhow!("the record must be a mapping: {}", record))?;
+        let columns = fields
+            .keys()
+            .map(|column| column.as_str())
+            .collect::<Vec<&str>>();
+        let statement = insert_statement(&self.table, &columns, &self.id_column);
+
+        let mut query = sqlx::query(&statement);
+        for value in fields.values() {
+            query = bind_value(query, value);
+        }
+        let row: SqliteRow = query.fetch_one(&mut *self.connection).await?;
+        Ok(parse_id(row.try_get(0)?))
+    }
+
+    async fn truncate(&mut self, table: &str) -> Result<()> {
+        let statement = format!("DELETE FROM {}", table);
+        sqlx::query(&statement)
+            .execute(&mut *self.connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&mut self, id: &RefValue) -> Result<()> {
+        let statement = format!("DELETE FROM {} WHERE {} = ?", self.table, self.id_column);
+        // the id is compared with the column as it is, so that the primary key index is used.
+        // uuids are stored as text, as they are inserted from the fixtures
+        let query = sqlx::query(&statement);
+        let query = match id {
+            RefValue::Int(id) => query.bind(*id),
+            RefValue::Uuid(id) => query.bind(id.to_string()),
+            RefValue::String(id) => query.bind(id.clone()),
+            RefValue::Composite(_) => {
+                return Err(anyhow::anyhow!(
+                    "a composite id cannot be compared with a single id column: {}",
+                    id
+                ))
+            }
+        };
+        query.execute(&mut *self.connection).await?;
+        Ok(())
+    }
+}
+
+/// Opens an in-memory SQLite database, creates the tables with the schema (any number of SQL
+/// statements), and seeds the files listed in the manifest (see `SeedManifest`) into the tables
+/// of the entries, in the order of the manifest. As the database lives as long as the connection,
+/// the connection is returned rather than a pool.
+///
+/// ```rust,no_run
+/// use cder::sqlite::seed_in_memory;
+/// use sqlx::Row;
+/// # use anyhow::Result;
+///
+/// # async fn test_orders() -> Result<()> {
+/// let schema = "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT, price INTEGER);";
+/// let mut connection = seed_in_memory("fixtures/seeds.yml", schema).await?;
+///
+/// let row = sqlx::query("SELECT COUNT(*) FROM items")
+///     .fetch_one(&mut connection)
+///     .await?;
+/// assert_eq!(row.get::<i64, _>(0), 4);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn seed_in_memory(manifest: &str, schema: &str) -> Result<SqliteConnection> {
+    let mut connection = SqliteConnection::connect("sqlite::memory:").await?;
+    sqlx::raw_sql(schema).execute(&mut connection).await?;
+
+    let mut seeder = DatabaseSeeder::from_manifest(manifest)?;
+    let mut entries = Vec::new();
+    seeder.populate_manifest(None, |_, entry| {
+        entries.push(entry.clone());
+        Ok(())
+    })?;
+    for entry in &entries {
+        let mut table = SqliteTable::new(&mut connection, entry.table());
+        seeder
+            .populate_with::<JsonValue, _>(&entry.file, &mut table)
+            .await?;
+    }
+
+    Ok(connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sqlite::*;
+
+    #[test]
+    fn test_insert_statement() {
+        assert_eq!(
+            insert_statement("items", &["name", "price"], "id"),
+            "INSERT INTO items (name, price) VALUES (?, ?) RETURNING CAST(id AS TEXT)"
+        );
+    }
+}
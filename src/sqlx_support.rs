@@ -0,0 +1,671 @@
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+use sqlx::{
+    postgres::{PgArguments, PgPool, PgRow},
+    query::Query,
+    Decode, Postgres, Row, Type,
+};
+use std::marker::PhantomData;
+use uuid::Uuid;
+
+use crate::{
+    database_seeder::file_table, sql::copy_text, DatabaseSeeder, RefValue, SeedAdapter, ToRefValue,
+};
+
+/// SqlxSeedable describes how a model is inserted into a Postgres table with sqlx: the table
+/// name, the columns, and the values bound to them (in the same order as the columns).
+/// Available with the `sqlx` feature.
+///
+/// # Examples
+/// ```rust
+/// use cder::SqlxSeedable;
+/// use serde::Deserialize;
+/// use sqlx::{postgres::PgArguments, query::Query, Postgres};
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///   name: String,
+///   email: String,
+/// }
+///
+/// impl SqlxSeedable for User {
+///     type Id = i64;
+///
+///     fn table() -> &'static str {
+///         "users"
+///     }
+///
+///     fn columns() -> &'static [&'static str] {
+///         &["name", "email"]
+///     }
+///
+///     fn bind(self, query: Query<'_, Postgres, PgArguments>) -> Query<'_, Postgres, PgArguments> {
+///         query.bind(self.name).bind(self.email)
+///     }
+/// }
+/// ```
+pub trait SqlxSeedable: Sized {
+    /// the type of the primary key, returned by the INSERT statement
+    type Id: for<'r> Decode<'r, Postgres> + Type<Postgres> + ToRefValue + Send + Unpin;
+
+    fn table() -> &'static str;
+
+    fn columns() -> &'static [&'static str];
+
+    /// the primary key column returned by the INSERT statement
+    fn id_column() -> &'static str {
+        "id"
+    }
+
+    /// binds the values in the same order as `columns`
+    fn bind(self, query: Query<'_, Postgres, PgArguments>) -> Query<'_, Postgres, PgArguments>;
+}
+
+/// builds `INSERT INTO <table> (<columns>) VALUES ($1, $2, ...) RETURNING <id_column>`
+fn insert_statement<T>() -> String
+where
+    T: SqlxSeedable,
+{
+    let columns = T::columns();
+    let placeholders = (1..=columns.len())
+        .map(|index| format!("${}", index))
+        .collect::<Vec<String>>();
+
+    format!(
+        "INSERT INTO {} ({}) VALUES ({}) RETURNING {}",
+        T::table(),
+        columns.join(", "),
+        placeholders.join(", "),
+        T::id_column()
+    )
+}
+
+/// the table the applied seed files are recorded in, see `DatabaseSeeder::track_versions`
+const VERSIONS_TABLE: &str = "_cder_seeds";
+
+async fn create_versions_table(pool: &PgPool) -> Result<()> {
+    let statement = format!(
+        "CREATE TABLE IF NOT EXISTS {} (filename TEXT PRIMARY KEY, checksum TEXT NOT NULL, applied_at TIMESTAMPTZ NOT NULL DEFAULT now())",
+        VERSIONS_TABLE
+    );
+    sqlx::query(&statement).execute(pool).await?;
+    Ok(())
+}
+
+async fn truncate_table(pool: &PgPool, table: &str) -> Result<()> {
+    let statement = format!("TRUNCATE {} CASCADE", table);
+    sqlx::query(&statement).execute(pool).await?;
+    Ok(())
+}
+
+/// binds the id as the type it has been parsed as, so that it is compared with the id column as
+/// it is, which keeps the primary key index in use
+fn bind_id<'q>(
+    query: Query<'q, Postgres, PgArguments>,
+    id: &RefValue,
+) -> Result<Query<'q, Postgres, PgArguments>> {
+    match id {
+        RefValue::Int(id) => Ok(query.bind(*id)),
+        RefValue::Uuid(id) => Ok(query.bind(*id)),
+        RefValue::String(id) => Ok(query.bind(id.clone())),
+        RefValue::Composite(_) => Err(anyhow!(
+            "a composite id cannot be compared with a single id column: {}",
+            id
+        )),
+    }
+}
+
+async fn delete_row(pool: &PgPool, table: &str, id_column: &str, id: &RefValue) -> Result<()> {
+    let statement = format!("DELETE FROM {} WHERE {} = $1", table, id_column);
+    bind_id(sqlx::query(&statement), id)?.execute(pool).await?;
+    Ok(())
+}
+
+async fn applied_checksum(pool: &PgPool, filename: &str) -> Result<Option<String>> {
+    create_versions_table(pool).await?;
+    let statement = format!(
+        "SELECT checksum FROM {} WHERE filename = $1",
+        VERSIONS_TABLE
+    );
+    let row: Option<PgRow> = sqlx::query(&statement)
+        .bind(filename)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|row| row.try_get(0)).transpose()?)
+}
+
+async fn record_applied(pool: &PgPool, filename: &str, checksum: &str) -> Result<()> {
+    create_versions_table(pool).await?;
+    let statement = format!(
+        "INSERT INTO {} (filename, checksum) VALUES ($1, $2) ON CONFLICT (filename) DO UPDATE SET checksum = EXCLUDED.checksum, applied_at = now()",
+        VERSIONS_TABLE
+    );
+    sqlx::query(&statement)
+        .bind(filename)
+        .bind(checksum)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// SeedAdapter that inserts SqlxSeedable records with the pool
+struct SqlxAdapter<T> {
+    pool: PgPool,
+    statement: String,
+    model: PhantomData<fn(T)>,
+}
+
+impl<T> SeedAdapter<T> for SqlxAdapter<T>
+where
+    T: SqlxSeedable + Send,
+{
+    type Id = T::Id;
+
+    async fn insert(&mut self, record: T) -> Result<T::Id> {
+        let row: PgRow = record
+            .bind(sqlx::query(&self.statement))
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(row.try_get(0)?)
+    }
+
+    async fn truncate(&mut self, table: &str) -> Result<()> {
+        truncate_table(&self.pool, table).await
+    }
+
+    async fn delete(&mut self, id: &RefValue) -> Result<()> {
+        delete_row(&self.pool, T::table(), T::id_column(), id).await
+    }
+
+    async fn applied_checksum(&mut self, filename: &str) -> Result<Option<String>> {
+        applied_checksum(&self.pool, filename).await
+    }
+
+    async fn record_applied(&mut self, filename: &str, checksum: &str) -> Result<()> {
+        record_applied(&self.pool, filename, checksum).await
+    }
+}
+
+/// SqlxTable is a SeedAdapter that inserts the records into a Postgres table as they are written
+/// in the fixtures, without any model: the fields of each record are mapped to the columns of the
+/// same names (converted by `json_populate_record`), and the columns left out get their defaults.
+/// The ids are returned as integers, uuids or strings, whichever they can be parsed as.
+/// Available with the `sqlx` feature.
+///
+/// ```rust,no_run
+/// use cder::{DatabaseSeeder, SqlxTable};
+/// # use sqlx::postgres::PgPool;
+/// # use anyhow::Result;
+///
+/// async fn populate_seeds(pool: &PgPool) -> Result<()> {
+///     let mut seeder = DatabaseSeeder::new();
+///     let mut users = SqlxTable::new(pool, "users");
+///
+///     seeder
+///         .populate_with::<serde_json::Value, _>("fixtures/users.yml", &mut users)
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+pub struct SqlxTable {
+    pool: PgPool,
+    table: String,
+    id_column: String,
+}
+
+impl SqlxTable {
+    pub fn new(pool: &PgPool, table: &str) -> Self {
+        Self {
+            pool: pool.clone(),
+            table: table.to_string(),
+            id_column: "id".to_string(),
+        }
+    }
+
+    /// sets the primary key column (default: `id`)
+    pub fn id_column(mut self, id_column: &str) -> Self {
+        self.id_column = id_column.to_string();
+        self
+    }
+
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// fetches the rows matching the condition (e.g. `plan = 'Premium'`), or all the rows, in the
+    /// order of their ids. The rows can be turned into fixtures with `FixtureDumper::dump`.
+    pub async fn rows(&self, condition: Option<&str>) -> Result<Vec<JsonValue>> {
+        let statement = select_statement(&self.table, condition, &self.id_column);
+        let rows: Vec<PgRow> = sqlx::query(&statement).fetch_all(&self.pool).await?;
+        rows.into_iter()
+            .map(|row| Ok(serde_json::from_str(&row.try_get::<String, _>(0)?)?))
+            .collect()
+    }
+
+    /// returns the columns of the single-column foreign keys along with the tables they refer to,
+    /// e.g. `[("company_id", "companies")]`, as taken by `FixtureDumper::dump`
+    pub async fn foreign_keys(&self) -> Result<Vec<(String, String)>> {
+        let rows: Vec<PgRow> = sqlx::query(FOREIGN_KEYS_STATEMENT)
+            .bind(&self.table)
+            .fetch_all(&self.pool)
+            .await?;
+        rows.into_iter()
+            .map(|row| Ok((row.try_get(0)?, row.try_get(1)?)))
+            .collect()
+    }
+}
+
+/// the foreign keys of the table ($1) consisting of a single column, in the order of the columns
+const FOREIGN_KEYS_STATEMENT: &str = "SELECT a.attname::text, c.confrelid::regclass::text \
+    FROM pg_constraint c \
+    JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = c.conkey[1] \
+    WHERE c.contype = 'f' AND c.conrelid = $1::regclass AND cardinality(c.conkey) = 1 \
+    ORDER BY a.attnum";
+
+/// builds `SELECT to_json(t)::text FROM <table> t WHERE <condition> ORDER BY <id_column>`
+fn select_statement(table: &str, condition: Option<&str>, id_column: &str) -> String {
+    let condition = match condition {
+        Some(condition) => format!(" WHERE {}", condition),
+        None => String::new(),
+    };
+    format!(
+        "SELECT to_json(t)::text FROM {} t{} ORDER BY {}",
+        table, condition, id_column
+    )
+}
+
+/// builds `INSERT INTO <table> (<columns>) SELECT <columns> FROM json_populate_record(...)`
+fn json_insert_statement(table: &str, columns: &[&str], id_column: &str) -> String {
+    let columns = columns.join(", ");
+    format!(
+        "INSERT INTO {} ({}) SELECT {} FROM json_populate_record(NULL::{}, $1::json) RETURNING {}::text",
+        table, columns, columns, table, id_column
+    )
+}
+
+/// groups the records into the blocks of COPY, each of the consecutive records with the same
+/// columns, with the rows in the text format (tab-separated, a line for each)
+fn copy_blocks(
+    filename: &str,
+    named_records: Vec<(String, YamlValue)>,
+) -> Result<Vec<(Vec<String>, String)>> {
+    let mut blocks = Vec::<(Vec<String>, String)>::new();
+    for (label, record) in named_records {
+        let YamlValue::Mapping(fields) = record else {
+            return Err(anyhow::anyhow!(
+                "the record must be a mapping: {} ({})",
+                label,
+                filename
+            ));
+        };
+        let mut columns = Vec::with_capacity(fields.len());
+        let mut values = Vec::with_capacity(fields.len());
+        for (column, value) in &fields {
+            let column = column.as_str().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "the column must be a string: {:?} ({} in {})",
+                    column,
+                    label,
+                    filename
+                )
+            })?;
+            columns.push(column.to_string());
+            values.push(copy_text(value));
+        }
+        match blocks.last_mut() {
+            Some((block_columns, rows)) if *block_columns == columns => {
+                rows.push_str(&values.join("\t"));
+                rows.push('\n');
+            }
+            _ => blocks.push((columns, format!("{}\n", values.join("\t")))),
+        }
+    }
+    Ok(blocks)
+}
+
+/// the id returned as text, typed as it can be parsed
+pub(crate) fn parse_id(id: String) -> RefValue {
+    if let Ok(id) = id.parse::<i64>() {
+        return RefValue::Int(id);
+    }
+    match Uuid::parse_str(&id) {
+        Ok(uuid) => RefValue::Uuid(uuid),
+        Err(_) => RefValue::String(id),
+    }
+}
+
+impl SeedAdapter<JsonValue> for SqlxTable {
+    type Id = RefValue;
+
+    async fn insert(&mut self, record: JsonValue) -> Result<RefValue> {
+        let columns = record
+            .as_object()
+            .ok_or_else(|| anyhow::anyhow!("the record must be a mapping: {}", record))?
+            .keys()
+            .map(|column| column.as_str())
+            .collect::<Vec<&str>>();
+        let statement = json_insert_statement(&self.table, &columns, &self.id_column);
+
+        let row: PgRow = sqlx::query(&statement)
+            .bind(record.to_string())
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(parse_id(row.try_get(0)?))
+    }
+
+    async fn truncate(&mut self, table: &str) -> Result<()> {
+        truncate_table(&self.pool, table).await
+    }
+
+    async fn delete(&mut self, id: &RefValue) -> Result<()> {
+        delete_row(&self.pool, &self.table, &self.id_column, id).await
+    }
+
+    async fn fetch(&mut self, id: &RefValue) -> Result<Option<JsonValue>> {
+        let statement = format!(
+            "SELECT to_json(t)::text FROM {} t WHERE {} = $1",
+            self.table, self.id_column
+        );
+        let row: Option<PgRow> = bind_id(sqlx::query(&statement), id)?
+            .fetch_optional(&self.pool)
+            .await?;
+        match row {
+            Some(row) => Ok(Some(serde_json::from_str(&row.try_get::<String, _>(0)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn ids(&mut self) -> Result<Vec<RefValue>> {
+        let statement = format!(
+            "SELECT {}::text FROM {} ORDER BY {}",
+            self.id_column, self.table, self.id_column
+        );
+        let rows: Vec<PgRow> = sqlx::query(&statement).fetch_all(&self.pool).await?;
+        rows.into_iter()
+            .map(|row| Ok(parse_id(row.try_get(0)?)))
+            .collect()
+    }
+
+    async fn applied_checksum(&mut self, filename: &str) -> Result<Option<String>> {
+        applied_checksum(&self.pool, filename).await
+    }
+
+    async fn record_applied(&mut self, filename: &str, checksum: &str) -> Result<()> {
+        record_applied(&self.pool, filename, checksum).await
+    }
+}
+
+impl DatabaseSeeder {
+    /// Inserts the records in the file into the table described by `SqlxSeedable`, and registers
+    /// the ids returned by the database. Available with the `sqlx` feature.
+    ///
+    /// ```rust,no_run
+    /// use cder::{DatabaseSeeder, SqlxSeedable};
+    /// # use serde::Deserialize;
+    /// # use sqlx::{postgres::{PgArguments, PgPool}, query::Query, Postgres};
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize)]
+    /// # struct User {
+    /// #   name: String,
+    /// # }
+    /// #
+    /// # impl SqlxSeedable for User {
+    /// #     type Id = i64;
+    /// #     fn table() -> &'static str { "users" }
+    /// #     fn columns() -> &'static [&'static str] { &["name"] }
+    /// #     fn bind(self, query: Query<'_, Postgres, PgArguments>) -> Query<'_, Postgres, PgArguments> {
+    /// #         query.bind(self.name)
+    /// #     }
+    /// # }
+    ///
+    /// async fn populate_seeds(pool: &PgPool) -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///
+    ///     seeder.populate_sqlx::<User>(pool, "fixtures/users.yml").await?;
+    ///
+    ///     // deletes the users inserted above
+    ///     seeder.teardown_sqlx::<User>(pool, "fixtures/users.yml").await
+    /// }
+    /// ```
+    pub async fn populate_sqlx<T>(&mut self, pool: &PgPool, filename: &str) -> Result<Vec<T::Id>>
+    where
+        T: SqlxSeedable + DeserializeOwned + Send,
+    {
+        let mut adapter = SqlxAdapter::<T> {
+            pool: pool.clone(),
+            statement: insert_statement::<T>(),
+            model: PhantomData,
+        };
+
+        let outcomes = self.populate_with(filename, &mut adapter).await?;
+        Ok(outcomes
+            .into_iter()
+            .filter_map(|outcome| outcome.into_id())
+            .collect())
+    }
+
+    /// Sets the pool the models are inserted with by `populate_model` and `teardown_model`.
+    /// Available with the `sqlx` feature.
+    pub fn set_pool(&mut self, pool: &PgPool) {
+        self.pool = Some(pool.clone());
+    }
+
+    /// Inserts the records in the file with the pool set by `set_pool`, as `populate_sqlx` does.
+    /// With `#[derive(cder::Seedable)]` (with the `macros` feature), the models are inserted
+    /// without writing any glue. Available with the `sqlx` feature.
+    ///
+    /// ```rust,ignore
+    /// #[derive(Deserialize, cder::Seedable)]
+    /// #[seedable(table = "users")]
+    /// struct User {
+    ///     name: String,
+    ///     email: String,
+    /// }
+    ///
+    /// async fn populate_seeds(pool: &PgPool) -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::builder().base_dir("fixtures").pool(pool).build()?;
+    ///
+    ///     seeder.populate_model::<User>("users.yml").await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn populate_model<T>(&mut self, filename: &str) -> Result<Vec<T::Id>>
+    where
+        T: SqlxSeedable + DeserializeOwned + Send,
+    {
+        let pool = self.model_pool()?;
+        self.populate_sqlx::<T>(&pool, filename).await
+    }
+
+    /// Deletes the records inserted from the file by `populate_model`, as `teardown_sqlx` does.
+    /// Available with the `sqlx` feature.
+    pub async fn teardown_model<T>(&mut self, filename: &str) -> Result<()>
+    where
+        T: SqlxSeedable + Send,
+    {
+        let pool = self.model_pool()?;
+        self.teardown_sqlx::<T>(&pool, filename).await
+    }
+
+    fn model_pool(&self) -> Result<PgPool> {
+        self.pool.clone().ok_or_else(|| {
+            anyhow!("no pool has been set to insert the models with, see `set_pool`")
+        })
+    }
+
+    /// Loads the records in the file into the table of its file stem with COPY, which is an order
+    /// of magnitude faster than inserting them one by one for large files, e.g. the reference
+    /// tables. The fields of the records are mapped to the columns of the same names, as with
+    /// `SqlxTable`, and are sent in the text format of COPY, i.e. converted by Postgres into the
+    /// types of the columns. Either all the records are loaded or none of them.
+    ///
+    /// As COPY returns no ids, the labels of the file cannot be referred to afterwards. The files
+    /// whose labels are referred to by the other seed files (the entries of the manifest, or else
+    /// the yaml files in the base directory) are therefore inserted row by row with `SqlxTable`,
+    /// registering their ids as usual. Returns the number of the records loaded. Available with
+    /// the `sqlx` feature.
+    ///
+    /// ```rust,no_run
+    /// use cder::DatabaseSeeder;
+    /// # use sqlx::postgres::PgPool;
+    /// # use anyhow::Result;
+    ///
+    /// async fn populate_seeds(pool: &PgPool) -> Result<()> {
+    ///     let mut seeder = DatabaseSeeder::new();
+    ///     seeder.set_dir("fixtures");
+    ///
+    ///     // 100k postal codes nothing refers to by label
+    ///     seeder.populate_copy(pool, "postal_codes.yml").await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn populate_copy(&mut self, pool: &PgPool, filename: &str) -> Result<u64> {
+        let mut table = SqlxTable::new(pool, file_table(filename));
+        if self.is_referenced(filename)? {
+            let outcomes = self
+                .populate_with::<JsonValue, _>(filename, &mut table)
+                .await?;
+            return Ok(outcomes.len() as u64);
+        }
+
+        if self.skip_resumed(filename) {
+            return Ok(0);
+        }
+        if self.dry_run {
+            self.populate_dry_run::<JsonValue>(filename)?;
+            return Ok(0);
+        }
+        let checksum = self.version_checksum(filename)?;
+        if let Some(checksum) = &checksum {
+            if self
+                .skip_applied::<JsonValue, _>(filename, checksum, &mut table)
+                .await?
+            {
+                return Ok(0);
+            }
+        }
+
+        let named_records = self.load_records::<YamlValue>(filename)?;
+        let total = named_records.len();
+        self.notify_file_start(filename, total);
+
+        let mut transaction = pool.begin().await?;
+        let mut copied = 0;
+        for (columns, rows) in copy_blocks(filename, named_records)? {
+            let statement = format!("COPY {} ({}) FROM STDIN", table.table(), columns.join(", "));
+            let mut copy_in = transaction.copy_in_raw(&statement).await?;
+            if let Err(err) = copy_in.send(rows.into_bytes()).await {
+                copy_in.abort(err.to_string()).await?;
+                return Err(err.into());
+            }
+            copied += copy_in.finish().await?;
+        }
+        transaction.commit().await?;
+
+        self.notify_file_done(filename, total, total);
+        self.record_version::<JsonValue, _>(filename, checksum, &mut table)
+            .await?;
+        Ok(copied)
+    }
+
+    /// Deletes the records inserted from the file by `populate_sqlx`, in the reverse order of
+    /// insertion. Available with the `sqlx` feature.
+    pub async fn teardown_sqlx<T>(&mut self, pool: &PgPool, filename: &str) -> Result<()>
+    where
+        T: SqlxSeedable + Send,
+    {
+        let mut adapter = SqlxAdapter::<T> {
+            pool: pool.clone(),
+            statement: insert_statement::<T>(),
+            model: PhantomData,
+        };
+
+        self.teardown_with(filename, &mut adapter).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sqlx_support::*;
+
+    struct Item;
+
+    impl SqlxSeedable for Item {
+        type Id = i64;
+
+        fn table() -> &'static str {
+            "items"
+        }
+
+        fn columns() -> &'static [&'static str] {
+            &["name", "price"]
+        }
+
+        fn bind(self, query: Query<'_, Postgres, PgArguments>) -> Query<'_, Postgres, PgArguments> {
+            query
+        }
+    }
+
+    #[test]
+    fn test_json_insert_statement() {
+        assert_eq!(
+            json_insert_statement("items", &["name", "price"], "id"),
+            "INSERT INTO items (name, price) SELECT name, price FROM json_populate_record(NULL::items, $1::json) RETURNING id::text"
+        );
+        assert_eq!(parse_id("42".to_string()), RefValue::Int(42));
+        assert_eq!(
+            parse_id("melon".to_string()),
+            RefValue::String("melon".to_string())
+        );
+    }
+
+    #[test]
+    fn test_select_statement() {
+        assert_eq!(
+            select_statement("customers", Some("plan = 'Premium'"), "id"),
+            "SELECT to_json(t)::text FROM customers t WHERE plan = 'Premium' ORDER BY id"
+        );
+        assert_eq!(
+            select_statement("customers", None, "id"),
+            "SELECT to_json(t)::text FROM customers t ORDER BY id"
+        );
+    }
+
+    #[test]
+    fn test_copy_blocks() {
+        let records = serde_yaml::from_str::<Vec<(String, YamlValue)>>(
+            "- [Melon, { name: melon, price: 500 }]\n- [Orange, { name: \"orange\\tnavel\", price: 200 }]\n- [Free, { name: sample, price: null, tags: [a] }]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            copy_blocks("items.yml", records).unwrap(),
+            vec![
+                (
+                    vec!["name".to_string(), "price".to_string()],
+                    "melon\t500\norange\\tnavel\t200\n".to_string()
+                ),
+                (
+                    vec!["name".to_string(), "price".to_string(), "tags".to_string()],
+                    "sample\t\\N\t[\"a\"]\n".to_string()
+                ),
+            ]
+        );
+
+        // the items are referred to by the orders, which are not referred to by any file
+        let mut seeder = DatabaseSeeder::new();
+        seeder.set_dir("tests/fixtures");
+        assert!(seeder.is_referenced("items.yml").unwrap());
+        assert!(!seeder.is_referenced("orders.yml").unwrap());
+    }
+
+    #[test]
+    fn test_insert_statement() {
+        assert_eq!(
+            insert_statement::<Item>(),
+            "INSERT INTO items (name, price) VALUES ($1, $2) RETURNING id"
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs};
+
+use crate::RefValue;
+
+/// SeedState is a snapshot of a DatabaseSeeder: the files it has processed and the ids of the
+/// records, mapped against their labels. It is saved with `DatabaseSeeder::save_state` as a json
+/// file, so that the ids remain available to other tools after the seeding process has exited.
+///
+/// ```json
+/// {
+///   "files": ["items.yml", "customers.yml"],
+///   "ids": {
+///     "Alice": 1,
+///     "Apple": "5f1c0b6e-8a4e-4f7c-9a0e-3b1e0c2d4f5a"
+///   }
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SeedState {
+    pub files: Vec<String>,
+    /// sorted by labels, so that the saved file is stable
+    pub ids: BTreeMap<String, RefValue>,
+}
+
+impl SeedState {
+    /// reads the state from the specified json file
+    pub fn load(path: &str) -> Result<Self> {
+        let raw_text = fs::read_to_string(path).map_err(|err| {
+            anyhow::anyhow!("Can't open the state file: {:?}\n   err: {}", path, err)
+        })?;
+        serde_json::from_str(&raw_text).map_err(|err| {
+            anyhow::anyhow!(
+                "failed to parse the state file: {:?}\n   err: {}",
+                path,
+                err
+            )
+        })
+    }
+
+    /// writes the state into the specified json file
+    pub fn save(&self, path: &str) -> Result<()> {
+        let text = serde_json::to_string_pretty(self)?;
+        fs::write(path, text).map_err(|err| {
+            anyhow::anyhow!("Can't write the state file: {:?}\n   err: {}", path, err)
+        })
+    }
+}
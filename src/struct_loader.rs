@@ -1,7 +1,18 @@
 use anyhow::Result;
 use serde::de::DeserializeOwned;
+#[cfg(any(feature = "csv", feature = "parquet"))]
+use serde::Serialize;
+#[cfg(any(feature = "csv", feature = "parquet"))]
+use serde_yaml::Value;
+use std::hash::BuildHasher;
+#[cfg(any(feature = "csv", feature = "parquet"))]
+use std::io::Write;
 
-use crate::{load_named_records, Dict};
+use crate::{
+    label::LabelHasher, load_named_records, reader::ReadOptions, resolver::TagScope,
+    validation::validate_records, CderConfig, Dict, Label, LabelMap, RefValue, ToRefValue,
+    ValidationReport,
+};
 
 /// StructLoader deserializes struct instances from specified file.
 /// To resolve embedded tags, you need to provide HashMap that indicates corresponding records to
@@ -57,6 +68,10 @@ where
     pub filename: String,
     pub base_dir: String,
     named_records: Option<Dict<T>>,
+    /// the labels of the records in the order they appear in the file
+    labels: Vec<String>,
+    tag_scope: TagScope,
+    read_options: ReadOptions,
 }
 
 impl<T> StructLoader<T>
@@ -68,10 +83,50 @@ where
             filename: filename.to_string(),
             base_dir: base_dir.to_string(),
             named_records: None,
+            labels: Vec::new(),
+            tag_scope: TagScope::default(),
+            read_options: ReadOptions::default(),
         }
     }
 
-    pub fn load(&mut self, dependencies: &Dict<String>) -> Result<&Self> {
+    /// creates the loader with the base_dir of the global configuration, see `CderConfig`
+    pub fn with_config(filename: &str, config: &CderConfig) -> Self {
+        Self::new(filename, config.base_dir.as_deref().unwrap_or_default())
+    }
+
+    /// sets the value that `${{ ENV(key) }}` tags are replaced with, in preference to the
+    /// environment variable, see `DatabaseSeeder::set_env`
+    pub fn set_env(&mut self, key: &str, value: &str) -> &mut Self {
+        self.tag_scope
+            .env
+            .insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// reads the file as a fixture of Rails, see `DatabaseSeeder::rails_fixtures`
+    pub fn rails_fixtures(&mut self, enabled: bool) -> &mut Self {
+        self.read_options.rails = enabled;
+        self
+    }
+
+    /// reads the file as a fixture of Django, see `DatabaseSeeder::django_fixtures`
+    pub fn django_fixtures(&mut self, enabled: bool) -> &mut Self {
+        self.read_options.django = enabled;
+        self
+    }
+
+    /// reads the file as a CSV seed of dbt if it is `.csv`, see `DatabaseSeeder::dbt_seeds`
+    #[cfg(feature = "dbt")]
+    pub fn dbt_seeds(&mut self, seeds: crate::DbtSeeds) -> &mut Self {
+        self.read_options.dbt = Some(seeds);
+        self
+    }
+
+    pub fn load<V, S>(&mut self, dependencies: &Dict<V, S>) -> Result<&Self>
+    where
+        V: ToRefValue,
+        S: BuildHasher,
+    {
         if self.named_records.is_some() {
             return Err(anyhow::anyhow!(
                 "filename : {} the records have been loaded already",
@@ -79,12 +134,71 @@ where
             ));
         }
 
-        let records = load_named_records::<T>(&self.filename, &self.base_dir, dependencies)?;
-        self.set_records(records)?;
+        let dependencies = dependencies
+            .iter()
+            .map(|(label, id)| {
+                (
+                    Label::from(label.as_str()),
+                    id.to_ref_value().numeric_as_int(),
+                )
+            })
+            .collect::<LabelMap<RefValue, LabelHasher>>();
+        let records = load_named_records::<T, _>(
+            &self.filename,
+            &self.base_dir,
+            &self.read_options,
+            &dependencies,
+            &self.tag_scope,
+        )?;
+        // the labels are unique already, as the latter of the duplicated records is kept
+        let labels = records.iter().map(|(label, _)| label.clone()).collect();
+        self.set_records(records.into_iter().collect())?;
+        self.labels = labels;
 
         Ok(self)
     }
 
+    /// Checks the file without loading the records: the tags are resolved with the dependencies,
+    /// and the records are deserialized, reporting all the problems found at once.
+    ///
+    /// ```rust
+    /// use cder::{Dict, StructLoader};
+    /// # use serde::Deserialize;
+    /// # #[derive(Deserialize)]
+    /// # struct Item {
+    /// #     name: String,
+    /// #     price: f64,
+    /// # }
+    ///
+    /// let loader = StructLoader::<Item>::new("items.yml", "tests/fixtures");
+    /// let report = loader.validate(&Dict::<i64>::new());
+    /// assert!(report.is_valid());
+    /// assert_eq!(report.files[0].stats.records, 4);
+    /// ```
+    pub fn validate<V, S>(&self, dependencies: &Dict<V, S>) -> ValidationReport
+    where
+        V: ToRefValue,
+        S: BuildHasher,
+    {
+        let dependencies = dependencies
+            .iter()
+            .map(|(label, id)| {
+                (
+                    Label::from(label.as_str()),
+                    id.to_ref_value().numeric_as_int(),
+                )
+            })
+            .collect::<LabelMap<RefValue, LabelHasher>>();
+        let file = validate_records::<T, _>(
+            &self.filename,
+            &self.base_dir,
+            &self.read_options,
+            &dependencies,
+            &self.tag_scope,
+        );
+        ValidationReport { files: vec![file] }
+    }
+
     pub fn get(&self, key: &str) -> Result<&T> {
         let records = self.get_records()?;
         records.get(key).ok_or_else(|| {
@@ -121,3 +235,226 @@ where
         })
     }
 }
+
+/// the loaded records flattened into the columns: the columns in the order they first appear,
+/// and the values of each record along with its label
+#[cfg(any(feature = "csv", feature = "parquet"))]
+type FlattenedRows<'a> = (Vec<String>, Vec<(&'a String, Vec<(String, Value)>)>);
+
+#[cfg(any(feature = "csv", feature = "parquet"))]
+impl<T> StructLoader<T>
+where
+    T: DeserializeOwned + Serialize,
+{
+    /// flattens the loaded records in the order of the file, see `flatten_cells`
+    fn flattened_rows(&self) -> Result<FlattenedRows<'_>> {
+        let records = self.get_records()?;
+        let mut columns = Vec::<String>::new();
+        let mut rows = Vec::with_capacity(self.labels.len());
+        for label in &self.labels {
+            let Some(record) = records.get(label) else {
+                continue;
+            };
+            let mut cells = Vec::new();
+            flatten_cells("", serde_yaml::to_value(record)?, &mut cells);
+            for (column, _) in &cells {
+                if !columns.contains(column) {
+                    columns.push(column.clone());
+                }
+            }
+            rows.push((label, cells));
+        }
+        Ok((columns, rows))
+    }
+}
+
+#[cfg(feature = "csv")]
+impl<T> StructLoader<T>
+where
+    T: DeserializeOwned + Serialize,
+{
+    /// Writes the loaded records as CSV, a row for each record in the order of the file. The
+    /// first column is the label, followed by the fields flattened into the columns, e.g.
+    /// `address.city` for the field of a nested struct. The sequences and the variants of enums
+    /// with values are written as JSON (e.g. `["a","b"]`), and the null values as empty cells.
+    /// Available with the `csv` feature.
+    ///
+    /// ```rust
+    /// use cder::{Dict, StructLoader};
+    /// # use serde::{Deserialize, Serialize};
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize, Serialize)]
+    /// # struct Item {
+    /// #     name: String,
+    /// #     price: u32,
+    /// # }
+    ///
+    /// # fn main() -> Result<()> {
+    /// let mut loader = StructLoader::<Item>::new("items.yml", "tests/fixtures");
+    /// loader.load(&Dict::<String>::new())?;
+    ///
+    /// let mut csv = Vec::new();
+    /// loader.to_csv(&mut csv)?;
+    /// assert!(String::from_utf8(csv)?.starts_with("label,name,price\nMelon,melon,500\n"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_csv<W>(&self, writer: W) -> Result<()>
+    where
+        W: Write,
+    {
+        let (columns, rows) = self.flattened_rows()?;
+
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(std::iter::once("label").chain(columns.iter().map(String::as_str)))?;
+        for (label, cells) in rows {
+            let row = columns.iter().map(|column| {
+                cells
+                    .iter()
+                    .find(|(cell_column, _)| cell_column == column)
+                    .map(|(_, cell)| cell_text(cell))
+                    .unwrap_or_default()
+            });
+            writer.write_record(std::iter::once(label.clone()).chain(row))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl<T> StructLoader<T>
+where
+    T: DeserializeOwned + Serialize,
+{
+    /// Writes the loaded records as a Parquet file, a row for each record in the order of the
+    /// file. The columns are flattened as `to_csv` does: the label first, then the fields such as
+    /// `address.city`. The columns of booleans, integers and numbers are typed as such, while the
+    /// others are strings, with the sequences and the variants of enums with values as JSON. The
+    /// fields missing in or null for a record are nulls. Available with the `parquet` feature.
+    ///
+    /// ```rust
+    /// use cder::{Dict, StructLoader};
+    /// # use serde::{Deserialize, Serialize};
+    /// # use anyhow::Result;
+    /// #
+    /// # #[derive(Deserialize, Serialize)]
+    /// # struct Item {
+    /// #     name: String,
+    /// #     price: u32,
+    /// # }
+    ///
+    /// # fn main() -> Result<()> {
+    /// let mut loader = StructLoader::<Item>::new("items.yml", "tests/fixtures");
+    /// loader.load(&Dict::<String>::new())?;
+    ///
+    /// let mut parquet = Vec::new();
+    /// loader.to_parquet(&mut parquet)?;
+    /// assert!(parquet.starts_with(b"PAR1"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_parquet<W>(&self, writer: W) -> Result<()>
+    where
+        W: Write + Send,
+    {
+        use arrow_array::{
+            ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray,
+        };
+        use arrow_schema::{DataType, Field, Schema};
+        use std::sync::Arc;
+
+        let (columns, rows) = self.flattened_rows()?;
+
+        let mut fields = vec![Field::new("label", DataType::Utf8, false)];
+        let mut arrays: Vec<ArrayRef> = vec![Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|(label, _)| label.as_str()),
+        ))];
+        for column in &columns {
+            let values = rows
+                .iter()
+                .map(|(_, cells)| {
+                    cells
+                        .iter()
+                        .find(|(cell_column, _)| cell_column == column)
+                        .map(|(_, cell)| cell)
+                        .filter(|cell| !cell.is_null())
+                })
+                .collect::<Vec<Option<&Value>>>();
+            let present = || values.iter().flatten();
+            let array: ArrayRef = if present().all(|value| value.is_bool()) {
+                Arc::new(
+                    values
+                        .iter()
+                        .map(|value| value.and_then(Value::as_bool))
+                        .collect::<BooleanArray>(),
+                )
+            } else if present().all(|value| value.is_i64()) {
+                Arc::new(
+                    values
+                        .iter()
+                        .map(|value| value.and_then(Value::as_i64))
+                        .collect::<Int64Array>(),
+                )
+            } else if present().all(|value| value.is_number()) {
+                Arc::new(
+                    values
+                        .iter()
+                        .map(|value| value.and_then(Value::as_f64))
+                        .collect::<Float64Array>(),
+                )
+            } else {
+                Arc::new(
+                    values
+                        .iter()
+                        .map(|value| value.map(cell_text))
+                        .collect::<StringArray>(),
+                )
+            };
+            fields.push(Field::new(column, array.data_type().clone(), true));
+            arrays.push(array);
+        }
+
+        let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?;
+        let mut writer = parquet::arrow::ArrowWriter::try_new(writer, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+}
+
+/// flattens the value into the cells of the columns, joining the keys of the nested mappings
+/// with dots
+#[cfg(any(feature = "csv", feature = "parquet"))]
+fn flatten_cells(column: &str, value: Value, cells: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Mapping(fields) => {
+            for (key, value) in fields {
+                let key = match key {
+                    Value::String(key) => key,
+                    key => crate::sql::json_text(&key),
+                };
+                let column = match column {
+                    "" => key,
+                    column => format!("{}.{}", column, key),
+                };
+                flatten_cells(&column, value, cells);
+            }
+        }
+        value => cells.push((column.to_string(), value)),
+    }
+}
+
+/// the text of the cell: the sequences and the variants of enums with values as JSON, and the
+/// null values as empty strings
+#[cfg(any(feature = "csv", feature = "parquet"))]
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(value) => value.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => text.clone(),
+        value => crate::sql::json_text(value),
+    }
+}
@@ -0,0 +1,1142 @@
+//! Helpers to test the seeding code without a database.
+//!
+//! `MockAdapter` keeps the records in memory and assigns them sequential ids, so that the loaders
+//! (and `SeedAdapter`s) of the seeds can be tested as they are used with the real tables:
+//!
+//! ```rust
+//! use cder::{testing::MockAdapter, DatabaseSeeder};
+//! # use serde::Deserialize;
+//! # use anyhow::Result;
+//! #
+//! # #[derive(Deserialize, Clone)]
+//! # struct Item {
+//! #     name: String,
+//! #     price: f64,
+//! # }
+//!
+//! fn main() -> Result<()> {
+//!     let mut seeder = DatabaseSeeder::new();
+//!     seeder.set_dir("tests/fixtures");
+//!
+//!     let items = MockAdapter::<Item>::new().fail_when(|item| item.name == "carrot");
+//!     seeder.continue_on_error(true);
+//!     let ids = seeder.populate("items.yml", |item: Item| items.push(item))?;
+//!
+//!     assert_eq!(ids, vec![1, 2, 3]);
+//!     assert_eq!(items.get(2).unwrap().name, "orange");
+//!     assert_eq!(seeder.failures()[0].label, "Carrot");
+//!     Ok(())
+//! }
+//! ```
+//!
+//! `Fixtures` loads the records of the seed files for a test, see also `#[cder::fixtures]` with
+//! the `macros` feature.
+use anyhow::Result;
+use regex::Regex;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_yaml::Value;
+use std::{
+    env, fmt, fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+};
+
+#[cfg(feature = "proptest")]
+pub use crate::proptest_support::{fixture_builder, labeled_records, round_trip};
+use crate::{
+    database_seeder::file_table,
+    deserialize_named_records,
+    error::tag_error,
+    reader::{read_seed_file, ReadOptions},
+    resolver::replace_tags,
+    verify::values_match,
+    DatabaseSeeder, Label, RefValue, SeedAdapter, StructLoader,
+};
+
+type FailurePredicate<T> = Box<dyn Fn(&T) -> bool + Send>;
+
+/// MockAdapter is an in-memory table: the records inserted are kept in the order of insertion,
+/// with the ids assigned sequentially from 1 (see `starting_at`). The clones share the same
+/// records, so that a clone can be moved into the loader while the original is inspected
+/// afterwards.
+///
+/// Besides `SeedAdapter` (with which the records can also be deleted, fetched and listed), the
+/// records can be inserted synchronously with `push`. The insertions can be made to fail with
+/// `fail_when` and `fail_at`, to test how the failures are handled.
+pub struct MockAdapter<T> {
+    table: Arc<Mutex<MockTable<T>>>,
+}
+
+struct MockTable<T> {
+    records: Vec<(i64, T)>,
+    next_id: i64,
+    /// the number of the insertions attempted, including the failed ones
+    attempts: usize,
+    fail_when: Vec<FailurePredicate<T>>,
+    fail_at: Vec<usize>,
+}
+
+impl<T> MockAdapter<T> {
+    pub fn new() -> Self {
+        Self {
+            table: Arc::new(Mutex::new(MockTable {
+                records: Vec::new(),
+                next_id: 1,
+                attempts: 0,
+                fail_when: Vec::new(),
+                fail_at: Vec::new(),
+            })),
+        }
+    }
+
+    /// assigns the ids from the given one
+    pub fn starting_at(self, id: i64) -> Self {
+        self.lock().next_id = id;
+        self
+    }
+
+    /// makes the insertions of the records that match the predicate fail
+    pub fn fail_when<F>(self, predicate: F) -> Self
+    where
+        F: Fn(&T) -> bool + Send + 'static,
+    {
+        self.lock().fail_when.push(Box::new(predicate));
+        self
+    }
+
+    /// makes the n-th insertion (counted from 1, including the failed ones) fail
+    pub fn fail_at(self, nth: usize) -> Self {
+        self.lock().fail_at.push(nth);
+        self
+    }
+
+    /// inserts the record, and returns the id assigned to it
+    pub fn push(&self, record: T) -> Result<i64> {
+        let mut table = self.lock();
+        table.attempts += 1;
+        let attempt = table.attempts;
+        if table.fail_at.contains(&attempt)
+            || table.fail_when.iter().any(|predicate| predicate(&record))
+        {
+            return Err(anyhow::anyhow!(
+                "the insertion #{} has failed (injected by MockAdapter)",
+                attempt
+            ));
+        }
+
+        let id = table.next_id;
+        table.next_id += 1;
+        table.records.push((id, record));
+        Ok(id)
+    }
+
+    /// the ids of the records, in the order of insertion
+    pub fn inserted_ids(&self) -> Vec<i64> {
+        self.lock().records.iter().map(|(id, _)| *id).collect()
+    }
+
+    /// the number of the records
+    pub fn len(&self) -> usize {
+        self.lock().records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().records.is_empty()
+    }
+
+    /// the number of the insertions attempted, including the failed ones
+    pub fn attempts(&self) -> usize {
+        self.lock().attempts
+    }
+
+    /// removes the record, and returns it if it has existed
+    pub fn remove(&self, id: i64) -> Option<T> {
+        let mut table = self.lock();
+        let index = table
+            .records
+            .iter()
+            .position(|(record_id, _)| *record_id == id)?;
+        Some(table.records.remove(index).1)
+    }
+
+    /// removes all the records. the ids are not reused
+    pub fn clear(&self) {
+        self.lock().records.clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockTable<T>> {
+        self.table.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T> MockAdapter<T>
+where
+    T: Clone,
+{
+    /// the records, in the order of insertion
+    pub fn records(&self) -> Vec<T> {
+        self.lock()
+            .records
+            .iter()
+            .map(|(_, record)| record.clone())
+            .collect()
+    }
+
+    /// the record of the id, if any
+    pub fn get(&self, id: i64) -> Option<T> {
+        self.lock()
+            .records
+            .iter()
+            .find(|(record_id, _)| *record_id == id)
+            .map(|(_, record)| record.clone())
+    }
+
+    /// the records that match the predicate, with their ids
+    pub fn find<F>(&self, predicate: F) -> Vec<(i64, T)>
+    where
+        F: Fn(&T) -> bool,
+    {
+        self.lock()
+            .records
+            .iter()
+            .filter(|(_, record)| predicate(record))
+            .cloned()
+            .collect()
+    }
+}
+
+impl<T> Default for MockAdapter<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for MockAdapter<T> {
+    fn clone(&self) -> Self {
+        Self {
+            table: Arc::clone(&self.table),
+        }
+    }
+}
+
+impl<T> fmt::Debug for MockAdapter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let table = self.lock();
+        f.debug_struct("MockAdapter")
+            .field("records", &table.records.len())
+            .field("next_id", &table.next_id)
+            .field("attempts", &table.attempts)
+            .finish()
+    }
+}
+
+impl<T> SeedAdapter<T> for MockAdapter<T>
+where
+    T: Clone + Send,
+{
+    type Id = i64;
+
+    async fn insert(&mut self, record: T) -> Result<i64> {
+        self.push(record)
+    }
+
+    async fn truncate(&mut self, _table: &str) -> Result<()> {
+        self.clear();
+        Ok(())
+    }
+
+    async fn delete(&mut self, id: &RefValue) -> Result<()> {
+        match mock_id(id).and_then(|id| self.remove(id)) {
+            Some(_) => Ok(()),
+            None => Err(anyhow::anyhow!("no record is found with the id: {}", id)),
+        }
+    }
+
+    async fn fetch(&mut self, id: &RefValue) -> Result<Option<T>> {
+        Ok(mock_id(id).and_then(|id| self.get(id)))
+    }
+
+    async fn ids(&mut self) -> Result<Vec<RefValue>> {
+        Ok(self.inserted_ids().into_iter().map(RefValue::Int).collect())
+    }
+}
+
+fn mock_id(id: &RefValue) -> Option<i64> {
+    match id {
+        RefValue::Int(id) => Some(*id),
+        _ => None,
+    }
+}
+
+/// Fixtures are the records of the seed files loaded for a test, without a database: the files
+/// are populated in the given order, with the ids assigned sequentially from 1 in each file, so
+/// that REFs are resolved as usual. The records are deserialized into the type they are asked
+/// for, see `get` and `find`.
+///
+/// This is what `#[cder::fixtures]` (with the `macros` feature) loads the arguments of the test
+/// with.
+///
+/// ```rust
+/// use cder::{testing::Fixtures, RefValue};
+/// # use serde::Deserialize;
+/// # use anyhow::Result;
+/// #
+/// # #[derive(Deserialize)]
+/// # struct Item {
+/// #     name: String,
+/// #     price: f64,
+/// # }
+///
+/// # fn main() -> Result<()> {
+/// let fixtures = Fixtures::load("tests/fixtures", &["items.yml"])?;
+///
+/// let melon = fixtures.get::<Item>("Melon")?;
+/// assert_eq!(melon.price, 500.0);
+/// assert_eq!(fixtures.id("Melon"), Some(&RefValue::Int(1)));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Fixtures {
+    base_dir: String,
+    records: Vec<(Label, RefValue, Value)>,
+}
+
+impl Fixtures {
+    /// loads the files in the directory (relative to the current directory, unless absolute)
+    pub fn load(base_dir: &str, filenames: &[&str]) -> Result<Self> {
+        let mut seeder = DatabaseSeeder::new();
+        seeder.set_dir(base_dir);
+
+        let mut values = Vec::new();
+        for filename in filenames {
+            let mut next_id = 0;
+            seeder.populate(filename, |value: Value| {
+                next_id += 1;
+                values.push(value);
+                Ok(next_id)
+            })?;
+        }
+
+        let records = seeder
+            .seeded_records()
+            .iter()
+            .zip(values)
+            .map(|(record, value)| (record.label.clone(), record.id.clone(), value))
+            .collect();
+        Ok(Self {
+            base_dir: base_dir.to_string(),
+            records,
+        })
+    }
+
+    /// the labels of the records, in the order of the files and the records
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.records.iter().map(|(label, ..)| label.as_str())
+    }
+
+    /// the id assigned to the record of the label
+    pub fn id(&self, label: &str) -> Option<&RefValue> {
+        self.records
+            .iter()
+            .find(|(record_label, ..)| record_label == label)
+            .map(|(_, id, _)| id)
+    }
+
+    /// deserializes the record of the label
+    pub fn get<T>(&self, label: &str) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let (label, _, value) = self
+            .records
+            .iter()
+            .find(|(record_label, ..)| record_label == label)
+            .ok_or_else(|| anyhow::anyhow!("no record is labeled `{}`", label))?;
+        deserialize_fixture(label, value)
+    }
+
+    /// deserializes the record whose label matches the name, ignoring the cases and the
+    /// underscores (e.g. `order_1` for `Order1`), so that the labels can be named after the
+    /// variables. the label that is exactly the name comes first
+    pub fn find<T>(&self, name: &str) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        if self.id(name).is_some() {
+            return self.get(name);
+        }
+
+        let normalized = normalize_name(name);
+        let matched = self
+            .records
+            .iter()
+            .filter(|(label, ..)| normalize_name(label) == normalized)
+            .collect::<Vec<_>>();
+        match matched.as_slice() {
+            [(label, _, value)] => deserialize_fixture(label, value),
+            [] => Err(anyhow::anyhow!("no record is labeled like `{}`", name)),
+            _ => Err(anyhow::anyhow!(
+                "`{}` is ambiguous among the labels: {}",
+                name,
+                matched
+                    .iter()
+                    .map(|(label, ..)| label.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+impl Fixtures {
+    /// Renders the seed file with its tags resolved deterministically, so that the document can
+    /// be compared with a snapshot (see `assert_snapshot`, or `insta::assert_snapshot!`): REFs
+    /// are replaced with the ids of the fixtures, and the tags whose values depend on where the
+    /// test runs with the stand-ins:
+    /// - ENV tags with their default values, or `"ENV(<key>)"` without them, regardless of the
+    ///   environment variables
+    /// - SECRET tags with `"SECRET(<key>)"`
+    /// - CTX tags with their default values (`index` with 0), or `"CTX(<key>)"` without them
+    ///
+    /// ```rust
+    /// use cder::testing::Fixtures;
+    /// # use anyhow::Result;
+    ///
+    /// # fn main() -> Result<()> {
+    /// let fixtures = Fixtures::load("tests/fixtures", &["items.yml", "customers.yml"])?;
+    ///
+    /// let rendered = fixtures.render("orders.yml")?;
+    /// assert!(rendered.contains("customer_id: 1\n  item_id: 3\n"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn render(&self, filename: &str) -> Result<String> {
+        let raw_text = read_seed_file(filename, &self.base_dir, &ReadOptions::default())?;
+        let rendered = replace_tags(&raw_text, |directive, key, default| match directive {
+            "REF" => self
+                .id(key)
+                .map(RefValue::to_yaml)
+                .ok_or_else(|| anyhow::anyhow!("no fixture is labeled `{}`", key)),
+            "CTX" if key == "index" => Ok(default.unwrap_or_else(|| "0".to_string())),
+            "ENV" | "CTX" => Ok(default.unwrap_or_else(|| format!("\"{}({})\"", directive, key))),
+            "SECRET" => Ok(format!("\"SECRET({})\"", key)),
+            _ => Err(anyhow::anyhow!(
+                "the directive: ` {}` is not supported.",
+                directive
+            )),
+        })
+        .map_err(|err| tag_error(filename, &raw_text, err))?;
+        Ok(rendered.into_owned())
+    }
+}
+
+/// InMemorySeeder runs the seeding as `DatabaseSeeder` does (the tags resolved, the ids tracked
+/// for REFs, and the files sorted by the manifest or by their dependencies), but inserts the
+/// records into in-memory tables (see `MockAdapter`), so that the manifests and the order of the
+/// files can be tested without any database. Each table assigns the ids sequentially from 1, and
+/// the records are kept as `serde_yaml::Value`s, deserialized into the types asked for.
+///
+/// The files are put into the tables named after their file stems (e.g. `users` for
+/// `fixtures/users.yml`), or the tables of the manifest.
+///
+/// ```rust
+/// use cder::{testing::InMemorySeeder, RefValue};
+/// # use serde::Deserialize;
+/// # use anyhow::Result;
+/// #
+/// # #[derive(Deserialize)]
+/// # struct Order {
+/// #     item_id: i64,
+/// #     quantity: i64,
+/// # }
+///
+/// # fn main() -> Result<()> {
+/// let mut seeder = InMemorySeeder::new();
+/// seeder.seeder_mut().set_dir("tests/fixtures");
+///
+/// // the files are populated in the order of their dependencies
+/// seeder.populate_all(&["orders.yml", "items.yml", "customers.yml"])?;
+/// assert_eq!(seeder.tables(), vec!["items", "customers", "orders"]);
+///
+/// let order = seeder.get::<Order>("Order1")?;
+/// assert_eq!(seeder.seeder().get_id("Apple"), Some(&RefValue::Int(3)));
+/// assert_eq!(order.item_id, 3);
+/// assert_eq!(seeder.table("items").unwrap().len(), 4);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct InMemorySeeder {
+    seeder: DatabaseSeeder,
+    tables: Vec<(String, MockAdapter<Value>)>,
+    /// the files populated, with their tables, in the order of population
+    files: Vec<(String, String)>,
+}
+
+impl InMemorySeeder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// creates the seeder from the manifest file, see `DatabaseSeeder::from_manifest`
+    pub fn from_manifest(path: &str) -> Result<Self> {
+        DatabaseSeeder::from_manifest(path).map(Self::from)
+    }
+
+    /// the seeder the files are populated with, e.g. to look up the ids of the labels
+    pub fn seeder(&self) -> &DatabaseSeeder {
+        &self.seeder
+    }
+
+    /// the seeder the files are populated with, e.g. to set the directory or the context values
+    pub fn seeder_mut(&mut self) -> &mut DatabaseSeeder {
+        &mut self.seeder
+    }
+
+    /// populates the file into the table named after its file stem, and returns the ids
+    pub fn populate(&mut self, filename: &str) -> Result<Vec<i64>> {
+        self.populate_into(filename, file_table(filename))
+    }
+
+    /// populates the file into the table, and returns the ids
+    pub fn populate_into(&mut self, filename: &str, table: &str) -> Result<Vec<i64>> {
+        populate_table(
+            &mut self.seeder,
+            &mut self.tables,
+            &mut self.files,
+            filename,
+            table,
+        )
+    }
+
+    /// populates the files sorted by their dependencies, see `DatabaseSeeder::sort_by_dependencies`
+    pub fn populate_all(&mut self, filenames: &[&str]) -> Result<()> {
+        for filename in self.seeder.sort_by_dependencies(filenames)? {
+            self.populate(&filename)?;
+        }
+        Ok(())
+    }
+
+    /// populates the files of the manifest that are active with the profile into their tables,
+    /// see `DatabaseSeeder::populate_manifest`
+    pub fn populate_manifest(&mut self, profile: Option<&str>) -> Result<()> {
+        let (tables, files) = (&mut self.tables, &mut self.files);
+        self.seeder.populate_manifest(profile, |seeder, entry| {
+            populate_table(seeder, tables, files, &entry.file, entry.table()).map(|_| ())
+        })
+    }
+
+    /// the names of the tables, in the order they have been first populated
+    pub fn tables(&self) -> Vec<&str> {
+        self.tables.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// the table of the name, if any records have been populated into it
+    pub fn table(&self, name: &str) -> Option<&MockAdapter<Value>> {
+        self.tables
+            .iter()
+            .find(|(table, _)| table == name)
+            .map(|(_, records)| records)
+    }
+
+    /// the files populated, in the order of population
+    pub fn files(&self) -> Vec<&str> {
+        self.files.iter().map(|(file, _)| file.as_str()).collect()
+    }
+
+    /// deserializes the records of the table, in the order of insertion
+    pub fn records<T>(&self, table: &str) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let records = self
+            .table(table)
+            .ok_or_else(|| anyhow::anyhow!("no record has been populated into `{}`", table))?;
+        records
+            .records()
+            .iter()
+            .map(|value| deserialize_fixture(table, value))
+            .collect()
+    }
+
+    /// deserializes the record of the label, as it has been inserted
+    pub fn get<T>(&self, label: &str) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let record = self
+            .seeder
+            .seeded_records()
+            .iter()
+            .rev()
+            .find(|record| record.label == label)
+            .ok_or_else(|| anyhow::anyhow!("no record is labeled `{}`", label))?;
+        let table = self
+            .files
+            .iter()
+            .rev()
+            .find(|(file, _)| *file == *record.filename)
+            .and_then(|(_, table)| self.table(table));
+        let value = match (table, &record.id) {
+            (Some(table), RefValue::Int(id)) => table.get(*id),
+            _ => None,
+        }
+        .ok_or_else(|| anyhow::anyhow!("the record `{}` is not found", label))?;
+        deserialize_fixture(label, &value)
+    }
+}
+
+impl From<DatabaseSeeder> for InMemorySeeder {
+    fn from(seeder: DatabaseSeeder) -> Self {
+        Self {
+            seeder,
+            tables: Vec::new(),
+            files: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Debug for InMemorySeeder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InMemorySeeder")
+            .field("tables", &self.tables)
+            .field("files", &self.files)
+            .finish_non_exhaustive()
+    }
+}
+
+/// populates the file into the table, which is created on the first population
+fn populate_table(
+    seeder: &mut DatabaseSeeder,
+    tables: &mut Vec<(String, MockAdapter<Value>)>,
+    files: &mut Vec<(String, String)>,
+    filename: &str,
+    table: &str,
+) -> Result<Vec<i64>> {
+    let records = match tables.iter().find(|(name, _)| name == table) {
+        Some((_, records)) => records.clone(),
+        None => {
+            let records = MockAdapter::new();
+            tables.push((table.to_string(), records.clone()));
+            records
+        }
+    };
+    let ids = seeder.populate(filename, |record: Value| records.push(record))?;
+    files.push((filename.to_string(), table.to_string()));
+    Ok(ids)
+}
+
+/// FixtureBuilder builds a seed file from the records constructed in the test, for the fixtures
+/// that depend on what is only known at runtime. The records are serialized in the order they
+/// are added, and the strings that are tags (e.g. `"${{ REF(Alice) }}"`) are written as tags, so
+/// that they are resolved as in the files.
+///
+/// ```rust
+/// use cder::{testing::FixtureBuilder, Dict};
+/// use serde::{Deserialize, Serialize};
+/// use serde_json::json;
+/// # use anyhow::Result;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Order {
+///     customer_id: i64,
+///     quantity: i64,
+/// }
+///
+/// # fn main() -> Result<()> {
+/// let file = FixtureBuilder::new()
+///     .record("Order1", Order { customer_id: 1, quantity: 2 })
+///     .record("Order2", json!({ "customer_id": "${{ REF(Alice) }}", "quantity": 1 }))
+///     .write_temp("orders.yml")?;
+///
+/// let mut loader = file.loader::<Order>();
+/// loader.load(&Dict::from([("Alice".to_string(), 10)]))?;
+/// assert_eq!(loader.get("Order2")?.customer_id, 10);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct FixtureBuilder {
+    records: Vec<(String, serde_yaml::Result<Value>)>,
+}
+
+impl FixtureBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds the record with the label. the record that cannot be serialized fails `to_yaml` and
+    /// the others
+    pub fn record<T>(mut self, label: &str, record: T) -> Self
+    where
+        T: Serialize,
+    {
+        self.records
+            .push((label.to_string(), serde_yaml::to_value(record)));
+        self
+    }
+
+    /// adds the records with their labels
+    pub fn records<I, L, T>(self, records: I) -> Self
+    where
+        I: IntoIterator<Item = (L, T)>,
+        L: AsRef<str>,
+        T: Serialize,
+    {
+        records.into_iter().fold(self, |builder, (label, record)| {
+            builder.record(label.as_ref(), record)
+        })
+    }
+
+    /// the number of the records
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// the records written in yaml, as in the seed files
+    pub fn to_yaml(&self) -> Result<String> {
+        let mut mapping = serde_yaml::Mapping::with_capacity(self.records.len());
+        for (label, record) in &self.records {
+            let record = record.as_ref().map_err(|err| {
+                anyhow::anyhow!("failed to serialize `{}`\n   err: {}", label, err)
+            })?;
+            mapping.insert(Value::String(label.clone()), record.clone());
+        }
+        let text = serde_yaml::to_string(&mapping)?;
+
+        // the tags are quoted as strings, which would be resolved into strings
+        let quoted_tag = Regex::new(r#"'(\$\{\{[^'\n]*\}\})'|"(\$\{\{[^"\n]*\}\})""#)?;
+        Ok(quoted_tag
+            .replace_all(&text, |captures: &regex::Captures| {
+                captures
+                    .get(1)
+                    .or_else(|| captures.get(2))
+                    .map_or_else(String::new, |tag| tag.as_str().replace("''", "'"))
+            })
+            .into_owned())
+    }
+
+    /// writes the records to the file in the directory
+    pub fn write(&self, base_dir: &str, filename: &str) -> Result<()> {
+        let path = Path::new(base_dir).join(filename);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, self.to_yaml()?).map_err(|err| {
+            anyhow::anyhow!(
+                "failed to write the fixture: {}\n   err: {}",
+                path.display(),
+                err
+            )
+        })
+    }
+
+    /// writes the records to the file in a temporary directory, which is removed when the returned
+    /// file is dropped
+    pub fn write_temp(&self, filename: &str) -> Result<FixtureFile> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        let dir = env::temp_dir().join(format!(
+            "cder-fixtures-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = FixtureFile {
+            base_dir: dir.to_string_lossy().into_owned(),
+            filename: filename.to_string(),
+        };
+        self.write(&file.base_dir, filename)?;
+        Ok(file)
+    }
+}
+
+/// a seed file written by `FixtureBuilder::write_temp`, which is removed along with its directory
+/// when dropped
+#[derive(Debug)]
+pub struct FixtureFile {
+    base_dir: String,
+    filename: String,
+}
+
+impl FixtureFile {
+    pub fn base_dir(&self) -> &str {
+        &self.base_dir
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// the loader of the file
+    pub fn loader<T>(&self) -> StructLoader<T>
+    where
+        T: DeserializeOwned,
+    {
+        StructLoader::new(&self.filename, &self.base_dir)
+    }
+
+    /// the seeder that reads the files from the directory of the file
+    pub fn seeder(&self) -> DatabaseSeeder {
+        let mut seeder = DatabaseSeeder::new();
+        seeder.set_dir(&self.base_dir);
+        seeder
+    }
+}
+
+impl Drop for FixtureFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.base_dir);
+    }
+}
+
+/// Compares the contents with the snapshot `tests/snapshots/<name>.snap` (relative to the
+/// current directory, i.e. the crate root under `cargo test`), which is written in the format of
+/// `insta`, so that the snapshots can be reviewed with `cargo insta review` as well.
+///
+/// When they differ (or the snapshot does not exist yet), the contents are written to
+/// `<name>.snap.new` and the assertion fails with the difference. The snapshot is written
+/// instead with `CDER_UPDATE_SNAPSHOTS=1` (or `INSTA_UPDATE=always`).
+///
+/// ```rust,no_run
+/// use cder::testing::{assert_snapshot, Fixtures};
+/// # use anyhow::Result;
+///
+/// # fn main() -> Result<()> {
+/// let fixtures = Fixtures::load("tests/fixtures", &["items.yml", "customers.yml"])?;
+/// assert_snapshot("orders", &fixtures.render("orders.yml")?);
+/// # Ok(())
+/// # }
+/// ```
+pub fn assert_snapshot(name: &str, contents: &str) {
+    if let Err(message) = compare_snapshot(Path::new("tests/snapshots"), name, contents) {
+        panic!("{}", message);
+    }
+}
+
+fn compare_snapshot(dir: &Path, name: &str, contents: &str) -> std::result::Result<(), String> {
+    let path = dir.join(format!("{}.snap", name));
+    let snapshot = format!(
+        "---\nsource: cder\nexpression: {}\n---\n{}\n",
+        name,
+        contents.trim_end()
+    );
+    let write = |path: &Path| {
+        fs::create_dir_all(dir)
+            .and_then(|_| fs::write(path, &snapshot))
+            .map_err(|err| {
+                format!(
+                    "failed to write the snapshot: {}\n   err: {}",
+                    path.display(),
+                    err
+                )
+            })
+    };
+
+    let updates = env::var("CDER_UPDATE_SNAPSHOTS").is_ok_and(|value| value == "1")
+        || env::var("INSTA_UPDATE").is_ok_and(|value| value == "always");
+    if updates {
+        return write(&path);
+    }
+
+    let expected = fs::read_to_string(&path).ok();
+    let expected = expected.as_deref().map(snapshot_contents);
+    if expected == Some(contents.trim_end()) {
+        return Ok(());
+    }
+
+    let new_path = dir.join(format!("{}.snap.new", name));
+    write(&new_path)?;
+    Err(match expected {
+        Some(expected) => format!(
+            "the snapshot `{}` does not match (the new one is written to {}):\n{}",
+            name,
+            new_path.display(),
+            line_diff(expected, contents.trim_end())
+        ),
+        None => format!(
+            "the snapshot `{}` does not exist yet (the new one is written to {})",
+            name,
+            new_path.display()
+        ),
+    })
+}
+
+/// the contents of the snapshot without the metadata of insta
+fn snapshot_contents(snapshot: &str) -> &str {
+    let contents = match snapshot.strip_prefix("---\n") {
+        Some(rest) => rest
+            .split_once("\n---\n")
+            .map_or(snapshot, |(_, contents)| contents),
+        None => snapshot,
+    };
+    contents.trim_end()
+}
+
+/// Compares the records with the ones of the seed file `tests/fixtures/<filename>` (relative to
+/// the current directory, as `assert_snapshot`), in the order they are defined. The records are
+/// compared deeply, regarding the numbers of the same value as equal whether they are integers or
+/// floats (e.g. `500` in the file and `500.0` of a `f64` field), and the labels are not compared.
+/// The tags of the file are resolved as `DatabaseSeeder::render` does.
+///
+/// When they differ, the assertion fails with the difference of the records written in yaml,
+/// each labeled after the record of the file in the same position.
+///
+/// ```rust
+/// use cder::{testing::{assert_matches_fixture, MockAdapter}, DatabaseSeeder};
+/// # use serde::{Deserialize, Serialize};
+/// # use anyhow::Result;
+/// #
+/// # #[derive(Serialize, Deserialize, Clone)]
+/// # struct Item {
+/// #     name: String,
+/// #     price: f64,
+/// # }
+///
+/// # fn main() -> Result<()> {
+/// let mut seeder = DatabaseSeeder::new();
+/// seeder.set_dir("tests/fixtures");
+///
+/// let items = MockAdapter::<Item>::new();
+/// seeder.populate("items.yml", |item: Item| items.push(item))?;
+/// assert_matches_fixture(items.records(), "items.yml");
+/// # Ok(())
+/// # }
+/// ```
+pub fn assert_matches_fixture<I, T>(records: I, filename: &str)
+where
+    I: IntoIterator<Item = T>,
+    T: Serialize,
+{
+    if let Err(message) = compare_fixture(Path::new("tests/fixtures"), records, filename) {
+        panic!("{}", message);
+    }
+}
+
+fn compare_fixture<I, T>(dir: &Path, records: I, filename: &str) -> std::result::Result<(), String>
+where
+    I: IntoIterator<Item = T>,
+    T: Serialize,
+{
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&dir.to_string_lossy());
+    let expected = seeder
+        .render(filename)
+        .and_then(|text| deserialize_named_records::<Value>(filename, &text))
+        .map_err(|err| {
+            format!(
+                "failed to load the fixture: {}\n   err: {:#}",
+                filename, err
+            )
+        })?;
+    let actual = records
+        .into_iter()
+        .map(serde_yaml::to_value)
+        .collect::<serde_yaml::Result<Vec<Value>>>()
+        .map_err(|err| format!("failed to serialize the records\n   err: {}", err))?;
+
+    let matches = |index: usize| match (expected.get(index), actual.get(index)) {
+        (Some((_, expected)), Some(actual)) => values_match(expected, actual),
+        _ => false,
+    };
+    if expected.len() == actual.len() && (0..actual.len()).all(matches) {
+        return Ok(());
+    }
+
+    let mut expected_records = serde_yaml::Mapping::with_capacity(expected.len());
+    for (label, record) in &expected {
+        expected_records.insert(Value::String(label.clone()), record.clone());
+    }
+    let mut actual_records = serde_yaml::Mapping::with_capacity(actual.len());
+    for (index, record) in actual.iter().enumerate() {
+        // the records that match are written as they are in the file, e.g. `500` for `500.0`
+        let (label, record) = match expected.get(index) {
+            Some((label, expected)) if matches(index) => (label.clone(), expected),
+            Some((label, _)) => (label.clone(), record),
+            None => (format!("#{}", index + 1), record),
+        };
+        actual_records.insert(Value::String(label), record.clone());
+    }
+    let to_yaml =
+        |records: &serde_yaml::Mapping| serde_yaml::to_string(records).unwrap_or_default();
+
+    Err(format!(
+        "the records do not match the fixture `{}` ({} expected, {} actual):\n{}",
+        filename,
+        expected.len(),
+        actual.len(),
+        line_diff(&to_yaml(&expected_records), &to_yaml(&actual_records))
+    ))
+}
+
+/// the lines removed from the expected text (`-`) and the ones added to it (`+`), along with the
+/// unchanged lines around them
+pub(crate) fn line_diff(expected: &str, actual: &str) -> String {
+    // the unchanged lines shown before and after the changes
+    const CONTEXT: usize = 2;
+
+    let expected = expected.lines().collect::<Vec<&str>>();
+    let actual = actual.lines().collect::<Vec<&str>>();
+
+    // the lengths of the longest common subsequences of the suffixes
+    let mut lengths = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lengths[i][j] = match expected[i] == actual[j] {
+                true => lengths[i + 1][j + 1] + 1,
+                false => lengths[i + 1][j].max(lengths[i][j + 1]),
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            lines.push((' ', expected[i]));
+            i += 1;
+            j += 1;
+        } else if i < expected.len()
+            && (j == actual.len() || lengths[i + 1][j] >= lengths[i][j + 1])
+        {
+            lines.push(('-', expected[i]));
+            i += 1;
+        } else {
+            lines.push(('+', actual[j]));
+            j += 1;
+        }
+    }
+
+    let changed = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, (sign, _))| *sign != ' ')
+        .map(|(index, _)| index)
+        .collect::<Vec<usize>>();
+    let is_shown = |index: usize| {
+        changed
+            .iter()
+            .any(|changed| index + CONTEXT >= *changed && index <= changed + CONTEXT)
+    };
+
+    let mut diff = Vec::new();
+    for (index, (sign, line)) in lines.iter().enumerate() {
+        if is_shown(index) {
+            diff.push(format!(" {} {}", sign, line));
+        } else if index > 0 && is_shown(index - 1) {
+            diff.push("   ...".to_string());
+        }
+    }
+    diff.join("\n")
+}
+
+fn deserialize_fixture<T>(label: &str, value: &Value) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    serde_yaml::from_value(value.clone())
+        .map_err(|err| anyhow::anyhow!("failed to deserialize `{}`\n   err: {}", label, err))
+}
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '_' | '-'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::*;
+
+    #[test]
+    fn test_line_diff() {
+        let expected = "a: 1\nb: 2\nc: 3\nd: 4\ne: 5\nf: 6\ng: 7\n";
+        let actual = "a: 1\nb: 2\nc: 3\nd: 40\ne: 5\nf: 6\ng: 7\nh: 8\n";
+        assert_eq!(
+            line_diff(expected, actual),
+            "   b: 2\n   c: 3\n - d: 4\n + d: 40\n   e: 5\n   f: 6\n   g: 7\n + h: 8"
+        );
+        assert_eq!(line_diff("a: 1\nb: 2", "a: 1"), "   a: 1\n - b: 2");
+        assert_eq!(line_diff("a: 1", "a: 1"), "");
+    }
+
+    #[test]
+    fn test_compare_fixture() {
+        let dir = env::temp_dir().join(format!("cder-expected-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("items.yml"),
+            "Melon:\n  name: melon\n  price: 500\nApple:\n  name: apple\n  price: 100\n",
+        )
+        .unwrap();
+
+        let item = |name: &str, price: f64| serde_json::json!({ "name": name, "price": price });
+        let items = [item("melon", 500.0), item("apple", 100.0)];
+        assert!(compare_fixture(&dir, &items, "items.yml").is_ok());
+
+        let items = [
+            item("melon", 500.0),
+            item("apple", 120.0),
+            item("kiwi", 80.0),
+        ];
+        let err = compare_fixture(&dir, &items, "items.yml").unwrap_err();
+        assert_eq!(
+            err,
+            "the records do not match the fixture `items.yml` (2 expected, 3 actual):\n   Apple:\n     name: apple\n -   price: 100\n +   price: 120.0\n + '#3':\n +   name: kiwi\n +   price: 80.0"
+        );
+
+        let err = compare_fixture(&dir, &items, "missing.yml").unwrap_err();
+        assert!(err.starts_with("failed to load the fixture: missing.yml"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fixture_builder_to_yaml() {
+        let builder = FixtureBuilder::new()
+            .record(
+                "Order1",
+                serde_json::json!({
+                    "customer_id": "${{ REF(Alice) }}",
+                    "email": "${{ ENV(EMAIL:-\"alice@example.com\") }}",
+                    "note": "it's ${{ not a tag",
+                }),
+            )
+            .records([("Order2", serde_json::json!({ "quantity": 1 }))]);
+        assert_eq!(
+            builder.to_yaml().unwrap(),
+            "Order1:\n  customer_id: ${{ REF(Alice) }}\n  email: ${{ ENV(EMAIL:-\"alice@example.com\") }}\n  note: it's ${{ not a tag\nOrder2:\n  quantity: 1\n"
+        );
+    }
+
+    #[test]
+    fn test_compare_snapshot() {
+        let dir = env::temp_dir().join(format!("cder-snapshots-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        // the snapshot to be accepted is written next to the missing one
+        let err = compare_snapshot(&dir, "orders", "a: 1\n").unwrap_err();
+        assert!(err.contains("does not exist yet"));
+        let new_snapshot = fs::read_to_string(dir.join("orders.snap.new")).unwrap();
+        assert_eq!(
+            new_snapshot,
+            "---\nsource: cder\nexpression: orders\n---\na: 1\n"
+        );
+
+        fs::rename(dir.join("orders.snap.new"), dir.join("orders.snap")).unwrap();
+        assert!(compare_snapshot(&dir, "orders", "a: 1").is_ok());
+        let err = compare_snapshot(&dir, "orders", "a: 2").unwrap_err();
+        assert!(err.ends_with(" - a: 1\n + a: 2"));
+
+        // the snapshots without the metadata are compared as they are
+        assert_eq!(snapshot_contents("a: 1\n\n"), "a: 1");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,54 @@
+use std::time::{Duration, Instant};
+
+/// Throttle spaces out the insertions evenly, so that no more than the given number of records
+/// are inserted per second. see `DatabaseSeeder::set_rate_limit`
+#[derive(Debug, Clone)]
+pub(crate) struct Throttle {
+    interval: Duration,
+    next: Option<Instant>,
+}
+
+impl Throttle {
+    pub(crate) fn new(records_per_second: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / records_per_second.max(1),
+            next: None,
+        }
+    }
+
+    /// returns how long to wait before the next insertion, reserving its slot
+    pub(crate) fn reserve(&mut self) -> Duration {
+        let now = Instant::now();
+        let next = self.next.filter(|next| *next > now).unwrap_or(now);
+        self.next = Some(next + self.interval);
+        next - now
+    }
+
+    /// waits until the slot of the next insertion comes
+    pub(crate) async fn wait(&mut self) {
+        wait_for(self.reserve()).await;
+    }
+}
+
+pub(crate) async fn wait_for(duration: Duration) {
+    if !duration.is_zero() {
+        futures_timer::Delay::new(duration).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::throttle::*;
+
+    #[test]
+    fn test_reserve() {
+        let mut throttle = Throttle::new(10);
+        assert_eq!(throttle.reserve(), Duration::ZERO);
+
+        // the following slots are 100ms apart
+        let wait = throttle.reserve();
+        assert!(wait > Duration::from_millis(90) && wait <= Duration::from_millis(100));
+        let wait = throttle.reserve();
+        assert!(wait > Duration::from_millis(190) && wait <= Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::{anyhow, Result};
+use serde_yaml::{Mapping, Value};
+
+use crate::resolver::{mask_tags, unmask_tags};
+
+/// the key of the traits defined in a seed file, which is not a record itself
+const TRAITS_KEY: &str = "_traits";
+
+/// the key of the traits a record opts into
+const RECORD_TRAITS_KEY: &str = "traits";
+
+/// Applies the traits of the file to the records that opt into them, e.g.
+///
+/// ```yaml
+/// _traits:
+///   premium: { plan: Premium }
+///   jp: { country_code: 81 }
+///
+/// Alice:
+///   name: Alice
+///   traits: [premium, jp]
+/// ```
+///
+/// is read as `Alice: { name: Alice, plan: Premium, country_code: 81 }`. The traits are merged in
+/// the order they are listed (the later ones overwriting the fields of the earlier ones), and the
+/// fields of the record itself take precedence over all of them. Returns None when the file
+/// defines no traits, leaving `traits` of the records as it is.
+pub(crate) fn apply_traits(filename: &str, raw_text: &str) -> Result<Option<String>> {
+    if !raw_text.contains(TRAITS_KEY) {
+        return Ok(None);
+    }
+    let mut tags = Vec::<String>::new();
+    let text = mask_tags(raw_text, &mut tags);
+    // the errors of the text that is not valid yaml are left to the deserialization to report
+    let Ok(Value::Mapping(mut records)) = serde_yaml::from_str::<Value>(&text) else {
+        return Ok(None);
+    };
+    let Some(traits) = records.shift_remove(TRAITS_KEY) else {
+        return Ok(None);
+    };
+    let Value::Mapping(traits) = traits else {
+        return Err(anyhow!(
+            "`{}` must be a mapping of the traits by their names: {}",
+            TRAITS_KEY,
+            filename
+        ));
+    };
+
+    for (label, record) in records.iter_mut() {
+        let Value::Mapping(fields) = record else {
+            continue;
+        };
+        let Some(names) = fields.shift_remove(RECORD_TRAITS_KEY) else {
+            continue;
+        };
+        let label = label.as_str().unwrap_or_default();
+        let mut merged = Mapping::new();
+        for name in trait_names(filename, label, &names)? {
+            match traits.get(name) {
+                Some(Value::Mapping(trait_fields)) => merged.extend(trait_fields.clone()),
+                Some(Value::Null) => {}
+                Some(_) => {
+                    return Err(anyhow!(
+                        "the trait `{}` must be a mapping of the fields: {}",
+                        name,
+                        filename
+                    ))
+                }
+                None => {
+                    return Err(anyhow!(
+                        "the trait `{}` is not defined in the file: {} ({})",
+                        name,
+                        filename,
+                        label
+                    ))
+                }
+            }
+        }
+        for (key, value) in merged {
+            if !fields.contains_key(&key) {
+                fields.insert(key, value);
+            }
+        }
+    }
+
+    let text = serde_yaml::to_string(&records)?;
+    Ok(Some(unmask_tags(&text, &tags)))
+}
+
+/// `traits` can be either the name of a trait or a sequence of them
+fn trait_names<'a>(filename: &str, label: &str, names: &'a Value) -> Result<Vec<&'a str>> {
+    let invalid = || {
+        anyhow!(
+            "`{}` must be a trait or a sequence of traits: {} ({})",
+            RECORD_TRAITS_KEY,
+            label,
+            filename
+        )
+    };
+    match names {
+        Value::String(name) => Ok(vec![name.as_str()]),
+        Value::Sequence(names) => names
+            .iter()
+            .map(|name| name.as_str().ok_or_else(invalid))
+            .collect(),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::traits::*;
+
+    #[test]
+    fn test_apply_traits() {
+        let raw_text = "_traits:\n  premium: { plan: Premium, company_id: ${{ REF(Acme) }} }\n  jp: { country_code: 81, plan: Basic }\n\nAlice:\n  name: Alice\n  traits: [premium, jp]\nBob:\n  name: Bob\n  plan: Free\n  traits: premium\n";
+        assert_eq!(
+            apply_traits("users.yml", raw_text).unwrap().unwrap(),
+            "Alice:\n  name: Alice\n  plan: Basic\n  company_id: ${{ REF(Acme) }}\n  country_code: 81\nBob:\n  name: Bob\n  plan: Free\n  company_id: ${{ REF(Acme) }}\n"
+        );
+
+        // `traits` is left as it is unless the file defines the traits
+        assert!(apply_traits("users.yml", "Alice:\n  traits: [a]\n")
+            .unwrap()
+            .is_none());
+
+        let err = apply_traits("users.yml", "_traits: {}\nAlice:\n  traits: [vip]\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the trait `vip` is not defined in the file: users.yml (Alice)"
+        );
+    }
+}
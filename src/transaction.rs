@@ -0,0 +1,53 @@
+use anyhow::Result;
+
+/// Transaction represents a unit of work on the insertion target that can be committed or rolled
+/// back as a whole. Implement this for the transaction type of your database driver, and pass it
+/// to `DatabaseSeeder::populate_in_transaction`.
+///
+/// # Examples
+/// ```rust
+/// use anyhow::Result;
+/// use cder::Transaction;
+///
+/// // a transaction handle provided by your database driver
+/// struct Tx {
+///   // connection, savepoint etc.
+/// }
+///
+/// impl Transaction for Tx {
+///     fn commit(self) -> Result<()> {
+///         // COMMIT
+///         Ok(())
+///     }
+///
+///     fn rollback(self) -> Result<()> {
+///         // ROLLBACK
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait Transaction {
+    fn commit(self) -> Result<()>;
+    fn rollback(self) -> Result<()>;
+}
+
+/// Checkpoint holds the state of a DatabaseSeeder at a certain point, so that the seeder can be
+/// restored later when the corresponding insertions have been rolled back: the ids, the seeded
+/// records and the failures, along with the report, the history, the warnings and the dry-run
+/// report of the files populated since, and the REFs and the secrets found in them.
+/// Useful when the transaction has to be managed by the caller (e.g. with async database
+/// drivers), see `DatabaseSeeder::checkpoint`.
+pub struct Checkpoint {
+    pub(crate) filenames_len: usize,
+    pub(crate) seeded_records_len: usize,
+    pub(crate) failures_len: usize,
+    pub(crate) report_len: usize,
+    pub(crate) history_len: usize,
+    pub(crate) warnings_len: usize,
+    pub(crate) dry_run_files_len: usize,
+    pub(crate) dry_run_placeholders_len: usize,
+    pub(crate) name_resolver: crate::LabelMap<crate::RefValue>,
+    pub(crate) referred_keys: std::collections::HashSet<String>,
+    pub(crate) file_refs: crate::Dict<std::collections::HashSet<String>>,
+    pub(crate) secrets: std::collections::HashSet<String>,
+}
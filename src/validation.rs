@@ -0,0 +1,415 @@
+use serde::{
+    de::{DeserializeOwned, Deserializer, IgnoredAny, MapAccess, Visitor},
+    Serialize,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    hash::BuildHasher,
+};
+
+use crate::{
+    database_seeder::file_table,
+    deserialize_resolved_records,
+    error::mask_error,
+    lint::defaults_used_warnings,
+    reader::{read_seed_file, ReadOptions},
+    resolve_text,
+    resolver::{invalid_tags, scan_refs, TagScope},
+    CderError, LabelMap, LintWarning, RefValue,
+};
+
+/// ValidationReport lists the problems of the seed files found without inserting anything, file
+/// by file, see `DatabaseSeeder::validate` and `StructLoader::validate`. It can be printed as it
+/// is, or serialized (e.g. into json) for CI dashboards.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct ValidationReport {
+    pub files: Vec<FileValidation>,
+}
+
+/// the problems and the statistics of a single seed file
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct FileValidation {
+    pub filename: String,
+    /// the problems that fail the file to be populated
+    pub errors: Vec<ValidationError>,
+    /// the problems worth noting, see `LintRules`
+    pub warnings: Vec<LintWarning>,
+    pub stats: FileStats,
+}
+
+/// a problem that fails the file to be populated
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// the label of the record the problem is found in, if known
+    pub label: Option<String>,
+    /// the stable code of the problem, e.g. `CDER001` (see `CderError::code`)
+    pub code: Option<&'static str>,
+    /// the position of the problem in the file as it is written, if known
+    pub line: Option<usize>,
+    pub col: Option<usize>,
+    pub message: String,
+}
+
+/// the statistics of a single seed file
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct FileStats {
+    pub bytes: usize,
+    /// records found in the file
+    pub records: usize,
+    /// distinct REF keys in the file
+    pub refs: usize,
+}
+
+impl ValidationReport {
+    /// returns true when no error is found in any file (warnings do not matter)
+    pub fn is_valid(&self) -> bool {
+        self.errors() == 0
+    }
+
+    /// total number of errors across the files
+    pub fn errors(&self) -> usize {
+        self.files.iter().map(|file| file.errors.len()).sum()
+    }
+
+    /// total number of warnings across the files
+    pub fn warnings(&self) -> usize {
+        self.files.iter().map(|file| file.warnings.len()).sum()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for file in &self.files {
+            writeln!(
+                f,
+                "{}: {} record(s), {} error(s), {} warning(s)",
+                file.filename,
+                file.stats.records,
+                file.errors.len(),
+                file.warnings.len()
+            )?;
+            for error in &file.errors {
+                writeln!(f, "   error: {}", error)?;
+            }
+            for warning in &file.warnings {
+                writeln!(f, "   warning: {}", warning)?;
+            }
+        }
+        write!(
+            f,
+            "total: {} error(s), {} warning(s)",
+            self.errors(),
+            self.warnings()
+        )
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(label) = &self.label {
+            write!(f, "{}: ", label)?;
+        }
+        write!(f, "{}", self.message)?;
+        if let (Some(line), Some(col)) = (self.line, self.col) {
+            write!(f, " (line {}, column {})", line, col)?;
+        }
+        if let Some(code) = self.code {
+            write!(f, " [{}]", code)?;
+        }
+        Ok(())
+    }
+}
+
+impl ValidationError {
+    fn new(code: &'static str, label: Option<String>, message: String) -> Self {
+        Self {
+            label,
+            code: Some(code),
+            line: None,
+            col: None,
+            message,
+        }
+    }
+
+    /// the errors of loading the file, one for each problem of `CderError::Multiple`
+    pub(crate) fn from_error(err: &anyhow::Error) -> Vec<Self> {
+        match err.downcast_ref::<CderError>() {
+            Some(err) => Self::from_cder_error(None, err),
+            None => vec![Self {
+                label: None,
+                code: None,
+                line: None,
+                col: None,
+                message: format!("{:#}", err),
+            }],
+        }
+    }
+
+    fn from_cder_error(label: Option<&String>, err: &CderError) -> Vec<Self> {
+        let (label, line, col, message) = match err {
+            CderError::Multiple { errors, .. } => {
+                return errors
+                    .iter()
+                    .flat_map(|(label, err)| Self::from_cder_error(label.as_ref(), err))
+                    .collect();
+            }
+            CderError::TagParse {
+                line, col, message, ..
+            } => (label.cloned(), Some(*line), Some(*col), message.clone()),
+            CderError::UnresolvedRef { key, line, col, .. } => (
+                label.cloned(),
+                Some(*line),
+                Some(*col),
+                format!("unresolved reference: REF({})", key),
+            ),
+            CderError::Deserialize {
+                label: own_label,
+                line,
+                col,
+                message,
+                ..
+            } => (
+                own_label.as_ref().or(label).cloned(),
+                *line,
+                *col,
+                message.clone(),
+            ),
+            CderError::DuplicateLabel { label, .. } => (
+                Some(label.clone()),
+                None,
+                None,
+                "the label has already been populated".to_string(),
+            ),
+            err => (label.cloned(), None, None, err.to_string()),
+        };
+        vec![Self {
+            label,
+            code: Some(err.code()),
+            line,
+            col,
+            message,
+        }]
+    }
+}
+
+/// Checks the files in the order they are given, and returns all the problems found in each file:
+/// the files that cannot be read or parsed, the malformed tags, the labels defined twice in a
+/// file, and the REFs to the labels that are neither defined by the earlier files nor accepted by
+/// `is_defined` (e.g. the ones populated beforehand).
+/// In strict mode, the labels that have already been defined are reported too.
+pub(crate) fn validate_files<F>(
+    filenames: &[String],
+    base_dir: &str,
+    options: &ReadOptions,
+    strict: bool,
+    is_defined: F,
+) -> Vec<FileValidation>
+where
+    F: Fn(&str) -> bool,
+{
+    let mut files = Vec::new();
+    // the file each label is defined in, both as it is and prefixed with the file stem
+    let mut defined = HashMap::<String, String>::new();
+
+    for filename in filenames {
+        let mut file = FileValidation {
+            filename: filename.clone(),
+            ..Default::default()
+        };
+        validate_file(
+            &mut file,
+            base_dir,
+            options,
+            strict,
+            &mut defined,
+            &is_defined,
+        );
+        files.push(file);
+    }
+
+    files
+}
+
+fn validate_file<F>(
+    file: &mut FileValidation,
+    base_dir: &str,
+    options: &ReadOptions,
+    strict: bool,
+    defined: &mut HashMap<String, String>,
+    is_defined: F,
+) where
+    F: Fn(&str) -> bool,
+{
+    let filename = &file.filename;
+    let mut report = |code: &'static str, label: Option<&str>, problem: String| {
+        file.errors.push(ValidationError::new(
+            code,
+            label.map(str::to_string),
+            problem,
+        ))
+    };
+
+    let raw_text = match read_seed_file(filename, base_dir, options) {
+        Ok(raw_text) => raw_text,
+        Err(err) => {
+            report("CDER007", None, err.to_string());
+            return;
+        }
+    };
+    file.stats.bytes = raw_text.len();
+
+    // the invalid tags are masked as well as the valid ones, so that the rest of the file
+    // can still be checked
+    let mut valid_text = raw_text.to_string();
+    for (tag, problem) in invalid_tags(&raw_text) {
+        valid_text = valid_text.replacen(tag, "0", 1);
+        report("CDER002", None, problem);
+    }
+
+    let (masked_text, refs) = match scan_refs(&valid_text) {
+        Ok(scanned) => scanned,
+        Err(err) => {
+            report(
+                "CDER002",
+                None,
+                format!("failed to pre-process embedded tags: {}", err),
+            );
+            return;
+        }
+    };
+    let labels = match serde_yaml::from_str::<Labels>(&masked_text) {
+        Ok(labels) => labels.0,
+        Err(err) => {
+            report("CDER005", None, format!("failed to parse: {}", err));
+            return;
+        }
+    };
+
+    for key in &refs {
+        // the captured columns (`<label>.<column>`) are defined along with the label
+        let label = key.split('.').next().unwrap_or_default();
+        if !defined.contains_key(label) && !is_defined(label) {
+            report(
+                "CDER001",
+                None,
+                format!("unresolved reference: REF({})", key),
+            );
+        }
+    }
+
+    let mut labels_in_file = HashSet::new();
+    for label in &labels {
+        if !labels_in_file.insert(label.clone()) {
+            report(
+                "CDER006",
+                Some(label),
+                format!("duplicate label: {}", label),
+            );
+            continue;
+        }
+        if strict {
+            match defined.get(label) {
+                Some(other) => report(
+                    "CDER006",
+                    Some(label),
+                    format!("label {} is already defined in {}", label, other),
+                ),
+                None if is_defined(label) => report(
+                    "CDER006",
+                    Some(label),
+                    format!("label {} has already been populated", label),
+                ),
+                None => {}
+            }
+        }
+        defined.insert(
+            format!("{}/{}", file_table(filename), label),
+            filename.clone(),
+        );
+        defined.insert(label.clone(), filename.clone());
+    }
+
+    file.stats.records = labels.len();
+    file.stats.refs = refs.len();
+}
+
+/// Resolves the tags of the file with the dependencies, and deserializes its records as they would
+/// be loaded, see `StructLoader::validate`
+pub(crate) fn validate_records<T, S>(
+    filename: &str,
+    base_dir: &str,
+    options: &ReadOptions,
+    dependencies: &LabelMap<RefValue, S>,
+    scope: &TagScope,
+) -> FileValidation
+where
+    T: DeserializeOwned,
+    S: BuildHasher,
+{
+    let mut file = FileValidation {
+        filename: filename.to_string(),
+        ..Default::default()
+    };
+
+    let raw_text = match read_seed_file(filename, base_dir, options) {
+        Ok(raw_text) => raw_text,
+        Err(err) => {
+            file.errors = ValidationError::from_error(&err);
+            return file;
+        }
+    };
+    file.stats.bytes = raw_text.len();
+    if let Ok((masked_text, refs)) = scan_refs(&raw_text) {
+        file.stats.refs = refs.len();
+        file.stats.records =
+            serde_yaml::from_str::<Labels>(&masked_text).map_or(0, |labels| labels.0.len());
+    }
+
+    let resolved = match resolve_text(filename, &raw_text, dependencies, scope) {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            file.errors = ValidationError::from_error(&err);
+            return file;
+        }
+    };
+    file.warnings = defaults_used_warnings(filename, &raw_text, &resolved);
+
+    if let Err(err) = deserialize_resolved_records::<T>(filename, &raw_text, &resolved) {
+        file.errors = ValidationError::from_error(&mask_error(err, &resolved.secrets));
+    }
+    file
+}
+
+/// the labels of the records in the order they appear, including the duplicated ones
+struct Labels(Vec<String>);
+
+impl<'de> serde::Deserialize<'de> for Labels {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct LabelsVisitor;
+
+        impl<'de> Visitor<'de> for LabelsVisitor {
+            type Value = Labels;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a mapping of labeled records")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut labels = Vec::new();
+                while let Some((label, IgnoredAny)) = map.next_entry::<String, IgnoredAny>()? {
+                    labels.push(label);
+                }
+                Ok(Labels(labels))
+            }
+        }
+
+        deserializer.deserialize_map(LabelsVisitor)
+    }
+}
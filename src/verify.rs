@@ -0,0 +1,214 @@
+use serde::Serialize;
+use serde_yaml::Value;
+use std::{collections::HashSet, fmt};
+
+use crate::RefValue;
+
+/// VerificationReport lists the differences between a seed file and the rows in the database,
+/// see `DatabaseSeeder::verify`.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct VerificationReport {
+    pub filename: String,
+    /// records whose rows have been fetched and compared
+    pub verified: usize,
+    /// labels whose ids are not known to the seeder, which cannot be verified
+    pub unknown: Vec<String>,
+    /// labels whose rows are not found in the database
+    pub missing: Vec<String>,
+    pub differences: Vec<FieldDifference>,
+}
+
+/// a field whose value in the database differs from the one defined in the fixture
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FieldDifference {
+    pub label: String,
+    pub field: String,
+    /// the value defined in the fixture
+    pub expected: Value,
+    /// the value in the database (null when the row has no such field)
+    pub actual: Value,
+}
+
+impl VerificationReport {
+    /// returns true when every record matches its row
+    pub fn is_clean(&self) -> bool {
+        self.unknown.is_empty() && self.missing.is_empty() && self.differences.is_empty()
+    }
+}
+
+impl fmt::Display for VerificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} verified, {} unknown, {} missing, {} differences",
+            self.filename,
+            self.verified,
+            self.unknown.len(),
+            self.missing.len(),
+            self.differences.len()
+        )?;
+        for label in &self.unknown {
+            write!(f, "\n   {}: unknown id", label)?;
+        }
+        for label in &self.missing {
+            write!(f, "\n   {}: missing", label)?;
+        }
+        for difference in &self.differences {
+            write!(
+                f,
+                "\n   {}.{}: expected {}, actual {}",
+                difference.label,
+                difference.field,
+                inline(&difference.expected),
+                inline(&difference.actual)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// FixtureDiff lists the changes that populating a seed file would make to the rows in the
+/// database, see `DatabaseSeeder::diff`.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct FixtureDiff {
+    pub filename: String,
+    /// labels whose rows are not found in the database (or whose ids are not known)
+    pub added: Vec<String>,
+    /// fields whose values in the database differ from the ones defined in the fixtures
+    pub changed: Vec<FieldDifference>,
+    /// ids of the rows that are not defined in the fixtures
+    pub removed: Vec<RefValue>,
+    /// records whose rows match the fixtures
+    pub unchanged: usize,
+}
+
+impl FixtureDiff {
+    /// builds the diff from the verification of the file, and the ids of the rows that are not
+    /// defined in the file
+    pub(crate) fn new(report: VerificationReport, removed: Vec<RefValue>) -> Self {
+        let changed_labels = report
+            .differences
+            .iter()
+            .map(|difference| difference.label.as_str())
+            .collect::<HashSet<&str>>()
+            .len();
+
+        Self {
+            unchanged: report.verified - changed_labels,
+            filename: report.filename,
+            added: report.unknown.into_iter().chain(report.missing).collect(),
+            changed: report.differences,
+            removed,
+        }
+    }
+
+    /// returns true when populating the file would change nothing
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl fmt::Display for FixtureDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} added, {} changed, {} removed, {} unchanged",
+            self.filename,
+            self.added.len(),
+            self.changed.len(),
+            self.removed.len(),
+            self.unchanged
+        )?;
+        for label in &self.added {
+            write!(f, "\n + {}", label)?;
+        }
+        for difference in &self.changed {
+            write!(
+                f,
+                "\n ~ {}.{}: {} -> {}",
+                difference.label,
+                difference.field,
+                inline(&difference.actual),
+                inline(&difference.expected)
+            )?;
+        }
+        for id in &self.removed {
+            write!(f, "\n - {}", id)?;
+        }
+        Ok(())
+    }
+}
+
+/// renders the value in a single line, e.g. `[1, 2]` rather than a yaml block
+fn inline(value: &Value) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| format!("{:?}", value))
+}
+
+/// compares the fields defined in the fixture with the ones of the fetched row; the fields
+/// that are not defined in the fixture are not compared
+pub(crate) fn compare_fields(
+    label: &str,
+    expected: &Value,
+    actual: &Value,
+) -> Vec<FieldDifference> {
+    let Some(fields) = expected.as_mapping() else {
+        return match values_match(expected, actual) {
+            true => Vec::new(),
+            false => vec![FieldDifference {
+                label: label.to_string(),
+                field: String::new(),
+                expected: expected.clone(),
+                actual: actual.clone(),
+            }],
+        };
+    };
+
+    fields
+        .iter()
+        .filter_map(|(field, expected)| {
+            let actual = actual.get(field).cloned().unwrap_or(Value::Null);
+            if values_match(expected, &actual) {
+                return None;
+            }
+            let field = match field {
+                Value::String(field) => field.clone(),
+                field => inline(field),
+            };
+            Some(FieldDifference {
+                label: label.to_string(),
+                field,
+                expected: expected.clone(),
+                actual,
+            })
+        })
+        .collect()
+}
+
+/// compares the values, regarding the numbers of the same value as equal whether they are
+/// integers or floats, e.g. `500` in the fixture and `500.0` from a f64 column
+pub(crate) fn values_match(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::Number(expected), Value::Number(actual)) => {
+            expected == actual || expected.as_f64() == actual.as_f64()
+        }
+        (Value::Sequence(expected), Value::Sequence(actual)) => {
+            expected.len() == actual.len()
+                && expected
+                    .iter()
+                    .zip(actual)
+                    .all(|(expected, actual)| values_match(expected, actual))
+        }
+        (Value::Mapping(expected), Value::Mapping(actual)) => {
+            expected.len() == actual.len()
+                && expected.iter().all(|(key, expected)| {
+                    actual
+                        .get(key)
+                        .is_some_and(|actual| values_match(expected, actual))
+                })
+        }
+        (Value::Tagged(expected), Value::Tagged(actual)) => {
+            expected.tag == actual.tag && values_match(&expected.value, &actual.value)
+        }
+        (expected, actual) => expected == actual,
+    }
+}
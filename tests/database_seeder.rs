@@ -5,7 +5,13 @@ use test_utils::{
 extern crate cder;
 
 use anyhow::Result;
-use cder::DatabaseSeeder;
+use cder::{
+    Captures, CderConfig, CderError, DatabaseSeeder, LintRules, LintWarning, RefValue, RetryPolicy,
+    SeedObserver, SeedOutcome, SeedState, SeedStatus, StructLoader, Transaction,
+};
+use std::cell::Cell;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::runtime::Runtime;
 
 #[test]
@@ -199,3 +205,2138 @@ fn test_database_seeder_populate_orders() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_database_seeder_get_ids() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mock_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+        ("carrot".to_string(), 4),
+    ]);
+    let rt = Runtime::new().unwrap();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    assert!(seeder.get_ids().is_empty());
+    assert_eq!(seeder.get_id("Melon"), None);
+
+    seeder.populate("items.yml", |input: Item| {
+        let mut mock_table = mock_table.clone();
+        rt.block_on(mock_table.insert(input))
+    })?;
+
+    assert_eq!(seeder.get_id("Melon"), Some(&RefValue::Int(1)));
+    assert_eq!(seeder.get_id("Carrot"), Some(&RefValue::Int(4)));
+    assert_eq!(seeder.get_id("Banana"), None);
+
+    let ids = seeder.get_ids();
+    assert_eq!(ids.len(), 4);
+    assert_eq!(ids.get("Orange"), Some(&RefValue::Int(2)));
+    assert_eq!(ids.get("Apple"), Some(&RefValue::Int(3)));
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_populate_batch() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mock_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+        ("carrot".to_string(), 4),
+    ]);
+    let rt = Runtime::new().unwrap();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    let mut chunk_sizes = Vec::new();
+    let ids = seeder.populate_batch("items.yml", 3, |inputs: Vec<Item>| {
+        chunk_sizes.push(inputs.len());
+        inputs
+            .into_iter()
+            .map(|input| {
+                let mut mock_table = mock_table.clone();
+                rt.block_on(mock_table.insert(input))
+            })
+            .collect::<Result<Vec<i64>>>()
+    })?;
+    assert_eq!(chunk_sizes, vec![3, 1]);
+
+    let persisted_records = mock_table.get_records();
+    let records = sort_records_by_ids(persisted_records, ids);
+
+    assert_eq!(records[0].name, "melon");
+    assert_eq!(records[1].name, "orange");
+    assert_eq!(records[2].name, "apple");
+    assert_eq!(records[3].name, "carrot");
+
+    // labels are mapped against the ids returned in the same order
+    assert_eq!(seeder.get_id("Melon"), Some(&RefValue::Int(1)));
+    assert_eq!(seeder.get_id("Carrot"), Some(&RefValue::Int(4)));
+
+    // when the loader returns ids that do not correspond to the records
+    let results = seeder.populate_batch("items.yml", 2, |_: Vec<Item>| Ok(vec![1]));
+    assert!(results.is_err());
+
+    // when the chunk_size is zero
+    let results = seeder.populate_batch("items.yml", 0, |_: Vec<Item>| Ok(Vec::<i64>::new()));
+    assert!(results.is_err());
+
+    Ok(())
+}
+
+// records whether the transaction has been committed or rolled back
+struct MockTransaction<'a> {
+    committed: &'a Cell<Option<bool>>,
+}
+
+impl Transaction for MockTransaction<'_> {
+    fn commit(self) -> Result<()> {
+        self.committed.set(Some(true));
+        Ok(())
+    }
+
+    fn rollback(self) -> Result<()> {
+        self.committed.set(Some(false));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_database_seeder_populate_in_transaction() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let rt = Runtime::new().unwrap();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    {
+        // when all the records are persisted successfully
+        let committed = Cell::new(None);
+        let mock_items_table = MockTable::<Item>::new(vec![
+            ("melon".to_string(), 1),
+            ("orange".to_string(), 2),
+            ("apple".to_string(), 3),
+            ("carrot".to_string(), 4),
+        ]);
+        seeder.populate_in_transaction(
+            MockTransaction {
+                committed: &committed,
+            },
+            |seeder, _tx| {
+                seeder.populate("items.yml", |input: Item| {
+                    let mut mock_items_table = mock_items_table.clone();
+                    rt.block_on(mock_items_table.insert(input))
+                })
+            },
+        )?;
+
+        assert_eq!(committed.get(), Some(true));
+        assert_eq!(seeder.get_ids().len(), 4);
+    }
+
+    {
+        // when one of the records fails to be persisted
+        let committed = Cell::new(None);
+        let mock_customers_table =
+            MockTable::<Customer>::new(vec![("Alice".to_string(), 1), ("Bob".to_string(), 2)]);
+        let results = seeder.populate_in_transaction(
+            MockTransaction {
+                committed: &committed,
+            },
+            |seeder, _tx| {
+                seeder.populate("customers.yml", |input: Customer| {
+                    let mut mock_customers_table = mock_customers_table.clone();
+                    rt.block_on(mock_customers_table.insert(input))
+                })
+            },
+        );
+
+        assert!(results.is_err());
+        assert_eq!(committed.get(), Some(false));
+        // the ids registered inside the transaction are discarded
+        assert_eq!(seeder.get_ids().len(), 4);
+        assert_eq!(seeder.get_id("Alice"), None);
+        assert_eq!(seeder.get_id("Melon"), Some(&RefValue::Int(1)));
+        // and so is the report of the file
+        assert_eq!(seeder.report().files.len(), 1);
+        assert_eq!(seeder.report().inserted(), 4);
+        assert_eq!(seeder.history().len(), 1);
+    }
+
+    {
+        // the REFs of the files rolled back no longer count as the uses of the labels
+        let is_unused = |warning: &LintWarning| warning.rule == "unused_labels";
+        assert!(!seeder.warnings().iter().any(is_unused));
+
+        let checkpoint = seeder.checkpoint();
+        seeder.populate("customers.yml", |_: Customer| Ok(1))?;
+        seeder.populate("orders.yml", |_: Order| Ok(1))?;
+        // Orange is the only item not ordered
+        assert!(seeder.warnings().iter().any(is_unused));
+
+        seeder.rollback_to(checkpoint);
+        assert!(!seeder.warnings().iter().any(is_unused));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_dry_run() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.dry_run(true);
+
+    // the loaders are never invoked
+    seeder.populate("items.yml", |_: Item| -> Result<i64> { unreachable!() })?;
+    seeder.populate("customers.yml", |_: Customer| -> Result<i64> {
+        unreachable!()
+    })?;
+    let ids = seeder.populate("orders.yml", |_: Order| -> Result<i64> { unreachable!() })?;
+    assert!(ids.is_empty());
+
+    let report = seeder.dry_run_report();
+    assert_eq!(report.files.len(), 3);
+    assert_eq!(report.record_count(), 11);
+
+    assert!(report.files[0].filename.ends_with("items.yml"));
+    assert_eq!(
+        report.files[0].labels,
+        vec!["Melon", "Orange", "Apple", "Carrot"]
+    );
+    let (label, example) = report.files[0].example.as_ref().unwrap();
+    assert_eq!(label, "Melon");
+    assert_eq!(example["name"].as_str(), Some("melon"));
+
+    // REFs are resolved with placeholder ids
+    assert_eq!(seeder.get_id("Apple"), Some(&RefValue::Int(3)));
+    assert_eq!(seeder.get_id("Alice"), Some(&RefValue::Int(5)));
+    assert_eq!(seeder.get_id("Order1"), Some(&RefValue::Int(8)));
+
+    let summary = report.to_string();
+    assert!(summary.contains("items.yml: 4 record(s)"));
+    assert!(summary.contains("total: 11 record(s)"));
+
+    // which are typed by the fields the REFs appear in
+    #[derive(serde::Deserialize)]
+    struct UuidOrder {
+        customer_id: uuid::Uuid,
+        item_id: i64,
+    }
+    // the hooks are applied once to each record, as in a real run
+    let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = calls.clone();
+    seeder.before_insert(move |_, _| {
+        counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(())
+    });
+    seeder.populate("orders.yml", |_: UuidOrder| -> Result<i64> {
+        unreachable!()
+    })?;
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 4);
+    let (_, example) = seeder.dry_run_report().files[3].example.clone().unwrap();
+    let order = serde_yaml::from_value::<UuidOrder>(example)?;
+    assert_eq!(order.customer_id, uuid::Uuid::from_u128(5));
+    assert_eq!(order.item_id, 3);
+
+    // and removed when the dry run ends
+    seeder.dry_run(false);
+    assert_eq!(seeder.get_id("Apple"), None);
+    assert!(seeder
+        .populate("orders.yml", |_: Order| -> Result<i64> { Ok(1) })
+        .is_err());
+
+    // unresolvable REFs are still detected
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.dry_run(true);
+    let results = seeder.populate("orders.yml", |_: Order| -> Result<i64> { unreachable!() });
+    assert!(results.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_teardown() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let rt = Runtime::new().unwrap();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    let mock_items_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+        ("carrot".to_string(), 4),
+    ]);
+    seeder.populate("items.yml", |input: Item| {
+        let mut mock_items_table = mock_items_table.clone();
+        rt.block_on(mock_items_table.insert(input))
+    })?;
+    let mock_customers_table = MockTable::<Customer>::new(vec![
+        ("Alice".to_string(), 1),
+        ("Bob".to_string(), 2),
+        ("Developer".to_string(), 3),
+    ]);
+    seeder.populate("customers.yml", |input: Customer| {
+        let mut mock_customers_table = mock_customers_table.clone();
+        rt.block_on(mock_customers_table.insert(input))
+    })?;
+
+    let seeded_records = seeder.seeded_records();
+    assert_eq!(seeded_records.len(), 7);
+    assert_eq!(seeded_records[0].table(), "items");
+    assert_eq!(seeded_records[6].table(), "customers");
+    // the records of a file share their filename
+    assert_eq!(&*seeded_records[0].filename, "items.yml");
+    assert!(Arc::ptr_eq(
+        &seeded_records[0].filename,
+        &seeded_records[3].filename
+    ));
+
+    {
+        // when the deleter fails halfway
+        let mut deleted = Vec::new();
+        let results = seeder.teardown(|table, id| {
+            if deleted.len() == 2 {
+                return Err(anyhow::anyhow!("delete failed"));
+            }
+            deleted.push((table.to_string(), id.to_string()));
+            Ok(())
+        });
+
+        assert!(results.is_err());
+        assert_eq!(seeder.seeded_records().len(), 5);
+        assert_eq!(seeder.get_ids().len(), 5);
+    }
+
+    {
+        // deletes the rest of the records in the reverse order
+        let mut deleted = Vec::new();
+        seeder.teardown(|table, id| {
+            deleted.push((table.to_string(), id.to_string()));
+            Ok(())
+        })?;
+
+        assert_eq!(deleted.len(), 5);
+        assert_eq!(deleted[0].0, "customers");
+        assert!(deleted[4..].iter().all(|(table, _)| table == "items"));
+        assert!(seeder.seeded_records().is_empty());
+        assert!(seeder.get_ids().is_empty());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_teardown_by_dependencies() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut items_seeder = DatabaseSeeder::new();
+    items_seeder.set_dir(&base_dir);
+    items_seeder.populate("items.yml", |item: Item| Ok(item.name.len() as i64))?;
+    let mut customers_seeder = DatabaseSeeder::new();
+    customers_seeder.set_dir(&base_dir);
+    customers_seeder.populate("customers.yml", |customer: Customer| {
+        Ok(customer.name.len() as i64)
+    })?;
+
+    // orders are recorded before the items and the customers they refer to
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.restore_state(items_seeder.state());
+    seeder.restore_state(customers_seeder.state());
+    seeder.populate("orders.yml", |order: Order| Ok(order.id))?;
+    seeder.merge(items_seeder)?;
+    seeder.merge(customers_seeder)?;
+    assert_eq!(seeder.seeded_records()[0].table(), "orders");
+
+    // the order is told by the REFs resolved at populate time, without reading the files again
+    seeder.set_dir("moved");
+    let mut deleted = Vec::new();
+    seeder.teardown(|table, _| {
+        deleted.push(table.to_string());
+        Ok(())
+    })?;
+    assert_eq!(deleted.len(), 11);
+    assert!(deleted[..4].iter().all(|table| table == "orders"));
+    assert!(deleted[4..7].iter().all(|table| table == "customers"));
+    assert!(deleted[7..].iter().all(|table| table == "items"));
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_populate_idempotent() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let rt = Runtime::new().unwrap();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    // melon already exists, and carrot is out of stock
+    let mock_items_table =
+        MockTable::<Item>::new(vec![("orange".to_string(), 2), ("apple".to_string(), 3)]);
+    let outcomes =
+        seeder.populate_idempotent("items.yml", |input: Item| match input.name.as_str() {
+            "melon" => Ok(SeedOutcome::Exists(1)),
+            "carrot" => Ok(SeedOutcome::Skipped),
+            _ => {
+                let mut mock_items_table = mock_items_table.clone();
+                rt.block_on(mock_items_table.insert(input))
+                    .map(SeedOutcome::Inserted)
+            }
+        })?;
+
+    assert_eq!(outcomes.len(), 4);
+    assert!(outcomes.contains(&SeedOutcome::Exists(1)));
+    assert!(outcomes.contains(&SeedOutcome::Inserted(2)));
+    assert!(outcomes.contains(&SeedOutcome::Skipped));
+    assert_eq!(mock_items_table.get_records().len(), 2);
+
+    // existing records can be referred to, but skipped ones cannot
+    assert_eq!(seeder.get_id("Melon"), Some(&RefValue::Int(1)));
+    assert_eq!(seeder.get_id("Apple"), Some(&RefValue::Int(3)));
+    assert_eq!(seeder.get_id("Carrot"), None);
+
+    // only the inserted records are subject to teardown
+    let mut deleted = Vec::new();
+    seeder.teardown(|_, id| {
+        deleted.push(id.to_string());
+        Ok(())
+    })?;
+    deleted.sort();
+    assert_eq!(deleted, vec!["2", "3"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_sort_by_dependencies() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    // dependencies come first
+    let filenames = seeder.sort_by_dependencies(&["orders.yml", "items.yml", "customers.yml"])?;
+    assert_eq!(filenames, vec!["items.yml", "customers.yml", "orders.yml"]);
+
+    // the original order is kept when there are no dependencies
+    let filenames = seeder.sort_by_dependencies(&["customers.yml", "items.yml"])?;
+    assert_eq!(filenames, vec!["customers.yml", "items.yml"]);
+
+    // REFs to the files that are not listed are ignored
+    let filenames = seeder.sort_by_dependencies(&["orders.yml", "items.yml"])?;
+    assert_eq!(filenames, vec!["items.yml", "orders.yml"]);
+
+    // when the files refer to each other
+    let results = seeder.sort_by_dependencies(&["circular/hens.yml", "circular/eggs.yml"]);
+    let err = results.unwrap_err().to_string();
+    assert!(err.contains("circular/hens.yml"));
+    assert!(err.contains("circular/eggs.yml"));
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_validate_refs() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    // all the references are defined, regardless of the order of the files
+    seeder.validate_refs(&["orders.yml", "items.yml", "customers.yml"])?;
+
+    // every unresolved reference is reported at once
+    let results = seeder.validate_refs(&["orders.yml", "items.yml"]);
+    let err = results.unwrap_err().to_string();
+    assert!(err.contains("3 unresolved reference(s)"));
+    assert!(err.contains("orders.yml: REF(Alice)"));
+    assert!(err.contains("orders.yml: REF(Bob)"));
+    assert!(!err.contains("REF(Apple)"));
+
+    // the records populated beforehand can be referred to
+    seeder.populate("customers.yml", |_: Customer| Ok(1))?;
+    seeder.validate_refs(&["orders.yml", "items.yml"])?;
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_validate_files() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.validate_files(&["items.yml", "customers.yml", "orders.yml"])?;
+
+    // the files are checked in the order they are given
+    let err = seeder
+        .validate_files(&["orders.yml", "items.yml", "customers.yml"])
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("6 problem(s) found"));
+    assert!(err.contains("orders.yml: unresolved reference: REF(Alice)"));
+    assert!(err.contains("orders.yml: unresolved reference: REF(Apple)"));
+
+    // every problem of the files is reported at once
+    seeder.set_dir(&format!("{}/invalid", base_dir));
+    let err = seeder
+        .validate_files(&["companies.yml", "users.yml", "broken.yml", "missing.yml"])
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("6 problem(s) found"));
+    assert!(err.contains("companies.yml: duplicate label: Acme"));
+    assert!(err.contains("users.yml: malformed tag: ${{ ENV(ALICE_EMAIL }}"));
+    assert!(err.contains("users.yml: unsupported directive: `ROLE` in ${{ ROLE(admin) }}"));
+    assert!(err.contains("users.yml: unresolved reference: REF(Initech)"));
+    assert!(err.contains("broken.yml: failed to parse"));
+    assert!(err.contains("missing.yml: Can't open the file"));
+
+    // the labels defined by other files are reported in strict mode
+    seeder.strict(true);
+    let err = seeder
+        .validate_files(&["companies.yml", "users.yml"])
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("users.yml: label Globex is already defined in companies.yml"));
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_validate() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    let report = seeder.validate(&["items.yml", "customers.yml", "orders.yml"]);
+    assert!(report.is_valid());
+    assert_eq!(
+        report
+            .files
+            .iter()
+            .map(|file| (file.stats.records, file.stats.refs))
+            .collect::<Vec<(usize, usize)>>(),
+        vec![(4, 0), (3, 0), (4, 6)]
+    );
+    // the warnings of the warning rules are reported along with the errors
+    assert_eq!(report.warnings(), 1);
+    assert_eq!(report.files[0].warnings[0].label.as_deref(), Some("Orange"));
+
+    // the errors are reported file by file
+    seeder.set_dir(&format!("{}/invalid", base_dir));
+    let report = seeder.validate(&["companies.yml", "users.yml", "missing.yml"]);
+    assert_eq!(report.errors(), 5);
+    assert_eq!(report.warnings(), 0);
+    let codes = |index: usize| {
+        report.files[index]
+            .errors
+            .iter()
+            .map(|error| error.code.unwrap_or_default())
+            .collect::<Vec<&str>>()
+    };
+    assert_eq!(codes(0), vec!["CDER006"]);
+    assert_eq!(codes(1), vec!["CDER002", "CDER002", "CDER001"]);
+    assert_eq!(codes(2), vec!["CDER007"]);
+    assert_eq!(report.files[0].errors[0].label.as_deref(), Some("Acme"));
+    assert_eq!(
+        report.files[1].errors[2].to_string(),
+        "unresolved reference: REF(Initech) [CDER001]"
+    );
+
+    let json = serde_json::to_value(&report)?;
+    assert_eq!(json["files"][1]["filename"], "users.yml");
+    assert_eq!(json["files"][1]["stats"]["records"], 3);
+    assert_eq!(json["files"][2]["errors"][0]["code"], "CDER007");
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_render() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    // the REFs cannot be resolved before the records are populated
+    assert!(seeder.render("orders.yml").is_err());
+
+    seeder.restore_state(SeedState {
+        files: Vec::new(),
+        ids: [("Alice", 1), ("Bob", 2), ("Dev", 3)]
+            .into_iter()
+            .chain([("Apple", 11), ("Melon", 12), ("Carrot", 13)])
+            .map(|(label, id)| (label.to_string(), RefValue::Int(id)))
+            .collect(),
+    });
+    let text = seeder.render("orders.yml")?;
+    assert!(text.starts_with("Order1:\n  id: 1200\n  customer_id: 1\n  item_id: 11\n"));
+    assert!(!text.contains("${{"));
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_dependency_graph() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    let graph = seeder.dependency_graph(&["orders.yml", "customers.yml", "items.yml"])?;
+    let filenames = graph
+        .files
+        .iter()
+        .map(|file| file.filename.as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(filenames, vec!["customers.yml", "items.yml", "orders.yml"]);
+
+    let orders = &graph.files[2];
+    assert_eq!(orders.records[0].label, "Order1");
+    assert_eq!(orders.records[0].refs, vec!["Alice", "Apple"]);
+    assert_eq!(
+        graph.file_dependencies(),
+        vec![("orders.yml", "customers.yml"), ("orders.yml", "items.yml")]
+    );
+
+    let dot = graph.to_dot();
+    assert!(dot.contains(r#""orders.yml/Order1" -> "customers.yml/Alice";"#));
+    assert!(dot.contains(r#""orders.yml/Order4" -> "items.yml/Melon";"#));
+
+    // the records referred to but not defined in the files are drawn with dashed lines
+    let graph = seeder.dependency_graph(&["orders.yml"])?;
+    assert!(graph
+        .to_dot()
+        .contains(r#""orders.yml/Order1" -> "Alice" [style=dashed];"#));
+    assert!(graph.file_dependencies().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_lint() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    let filenames = ["items.yml", "customers.yml", "orders.yml"];
+    let warnings = seeder
+        .lint(&filenames, &LintRules::default())?
+        .iter()
+        .map(|warning| warning.to_string())
+        .collect::<Vec<String>>();
+    // the orders are not referred to by any file, while the other items are
+    assert_eq!(
+        warnings,
+        vec!["items.yml: Orange: the label is not referred to by any REF [unused_labels]"]
+    );
+
+    let rules = LintRules {
+        label_pattern: Some("^[A-Z][a-z]+$".to_string()),
+        unused_labels: false,
+        max_fields: Some(4),
+        ..LintRules::default()
+    };
+    let warnings = seeder.lint(&filenames, &rules)?;
+    let rules_of = |label: &str| {
+        warnings
+            .iter()
+            .filter(|warning| warning.label.as_deref() == Some(label))
+            .map(|warning| warning.rule)
+            .collect::<Vec<&str>>()
+    };
+    assert_eq!(rules_of("Order1"), vec!["label_pattern", "max_fields"]);
+    assert!(rules_of("Alice").is_empty());
+    assert!(rules_of("Orange").is_empty());
+
+    let rules = LintRules {
+        label_pattern: Some("[".to_string()),
+        ..LintRules::default()
+    };
+    assert!(seeder.lint(&filenames, &rules).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_warnings() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::builder()
+        .base_dir(&base_dir)
+        .warning_rules(LintRules {
+            max_value_len: Some(20),
+            ..LintRules::default()
+        })
+        .config(CderConfig::default())
+        .build()?;
+
+    let mut ids = 0..;
+    seeder.populate("items.yml", |_: Item| Ok(ids.next().unwrap()))?;
+    seeder.populate("customers.yml", |_: Customer| Ok(ids.next().unwrap()))?;
+    seeder.populate("orders.yml", |_: Order| Ok(ids.next().unwrap()))?;
+
+    // the warnings do not fail the seeding
+    assert_eq!(seeder.seeded_records().len(), 11);
+    let warnings = seeder
+        .warnings()
+        .iter()
+        .map(|warning| warning.to_string())
+        .collect::<Vec<String>>();
+    assert_eq!(
+        warnings,
+        vec![
+            "customers.yml: Dev: ENV(DEV_EMAIL) is not given, and the default value is used [defaults_used]",
+            "customers.yml: Bob: the value of `emails[1]` is 21 chars long, exceeding the limit of 20 [max_value_len]",
+            "customers.yml: Dev: the value of `emails[0]` is 21 chars long, exceeding the limit of 20 [max_value_len]",
+            "items.yml: Orange: the label is not referred to by any REF [unused_labels]",
+        ]
+    );
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    assert!(seeder
+        .set_warning_rules(LintRules {
+            label_pattern: Some("[".to_string()),
+            ..LintRules::default()
+        })
+        .is_err());
+    seeder.set_warning_rules(LintRules {
+        unused_labels: false,
+        ..LintRules::default()
+    })?;
+    seeder.populate("items.yml", |_: Item| Ok(1))?;
+    assert!(seeder.warnings().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_populate_manifest() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let rt = Runtime::new().unwrap();
+
+    let mut seeder = DatabaseSeeder::from_manifest(&format!("{}/seeds.yml", base_dir))?;
+    assert_eq!(seeder.base_dir, base_dir);
+
+    let manifest = seeder.manifest().unwrap();
+    assert_eq!(manifest.seeds.len(), 3);
+    assert_eq!(manifest.seeds[1].table(), "products");
+    assert_eq!(manifest.seeds[2].table(), "customers");
+
+    // customers are not populated without the profile, so orders cannot be resolved
+    let mut models = Vec::new();
+    let results = seeder.populate_manifest(None, |_, entry| {
+        models.push(entry.model.clone().unwrap());
+        Ok(())
+    });
+    assert!(results.is_ok());
+    assert_eq!(models, vec!["Item", "Order"]);
+
+    // populates the files in the order of dependencies
+    let mock_items_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+        ("carrot".to_string(), 4),
+    ]);
+    let mock_customers_table = MockTable::<Customer>::new(vec![
+        ("Alice".to_string(), 1),
+        ("Bob".to_string(), 2),
+        ("Developer".to_string(), 3),
+    ]);
+    let mock_orders_table = MockTable::<Order>::new(vec![
+        ("1200".to_string(), 1),
+        ("1201".to_string(), 2),
+        ("1202".to_string(), 3),
+        ("1203".to_string(), 4),
+    ]);
+    seeder.populate_manifest(Some("development"), |seeder, entry| {
+        match entry.model.as_deref() {
+            Some("Item") => seeder.populate(&entry.file, |input: Item| {
+                let mut mock_items_table = mock_items_table.clone();
+                rt.block_on(mock_items_table.insert(input))
+            })?,
+            Some("Customer") => seeder.populate(&entry.file, |input: Customer| {
+                let mut mock_customers_table = mock_customers_table.clone();
+                rt.block_on(mock_customers_table.insert(input))
+            })?,
+            Some("Order") => seeder.populate(&entry.file, |input: Order| {
+                let mut mock_orders_table = mock_orders_table.clone();
+                rt.block_on(mock_orders_table.insert(input))
+            })?,
+            _ => unreachable!(),
+        };
+        Ok(())
+    })?;
+
+    assert_eq!(mock_orders_table.get_records().len(), 4);
+    assert_eq!(seeder.get_ids().len(), 11);
+
+    // the records are torn down from the tables of the manifest, as well as cleaned
+    let mut tables = Vec::new();
+    seeder.teardown(|table, _| {
+        if !tables.contains(&table.to_string()) {
+            tables.push(table.to_string());
+        }
+        Ok(())
+    })?;
+    assert_eq!(tables, vec!["orders", "customers", "products"]);
+
+    // base_dir is relative to the manifest
+    let mut seeder = DatabaseSeeder::from_manifest(&format!("{}/manifests/seeds.yml", base_dir))?;
+    assert_eq!(seeder.base_dir, format!("{}/manifests/..", base_dir));
+    seeder.populate_manifest(None, |seeder, entry| {
+        seeder.populate(&entry.file, |input: Item| {
+            let mut mock_items_table = mock_items_table.clone();
+            rt.block_on(mock_items_table.insert(input))
+        })?;
+        Ok(())
+    })?;
+    assert_eq!(seeder.get_ids().len(), 4);
+
+    // when the seeder is not created from a manifest
+    let mut seeder = DatabaseSeeder::new();
+    assert!(seeder.populate_manifest(None, |_, _| Ok(())).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_populate_returning() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.continue_on_error(true);
+
+    // the models are handed back along with the ids, except for the failed ones
+    let items = seeder.populate_returning("items.yml", |item: Item| {
+        if item.name == "orange" {
+            return Err(anyhow::anyhow!("out of stock"));
+        }
+        let id = item.name.len() as i64;
+        Ok((id, item))
+    })?;
+
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0].0, 5);
+    assert_eq!(items[0].1.name, "melon");
+    assert_eq!(items[1].1.name, "apple");
+    assert_eq!(items[2].1.name, "carrot");
+    assert_eq!(seeder.get_id("Carrot"), Some(&RefValue::Int(6)));
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_populate_with_profile() -> Result<()> {
+    #[derive(serde::Deserialize)]
+    struct Plan {
+        name: String,
+        price: i64,
+    }
+
+    let base_dir = get_test_base_dir();
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&format!("{}/profiles", base_dir));
+
+    let mut plans = Vec::new();
+    seeder.populate_with_profile("plans.yml", "staging", |plan: Plan| {
+        plans.push((plan.name, plan.price));
+        Ok(plans.len() as i64)
+    })?;
+    // overlaid by plans.staging.yml
+    assert_eq!(
+        plans,
+        vec![
+            ("basic".to_string(), 1),
+            ("premium".to_string(), 5000),
+            ("trial".to_string(), 0),
+            ("staff".to_string(), 0),
+        ]
+    );
+    assert_eq!(seeder.get_id("Staff"), Some(&RefValue::Int(4)));
+    assert_eq!(seeder.get_id("Debug"), None);
+
+    // the development profile has no overlay file
+    let mut plans = Vec::new();
+    seeder.populate_with_profile("plans.yml", "development", |plan: Plan| {
+        plans.push(plan.name);
+        Ok(plans.len() as i64)
+    })?;
+    assert_eq!(plans, vec!["basic", "premium", "trial", "debug"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_namespace_by_file() -> Result<()> {
+    #[derive(serde::Deserialize)]
+    struct User {
+        name: String,
+    }
+    #[derive(serde::Deserialize)]
+    struct Post {
+        title: String,
+        author_id: i64,
+    }
+
+    let base_dir = get_test_base_dir();
+    let mut seeder = DatabaseSeeder::builder()
+        .base_dir(&format!("{}/namespaced", base_dir))
+        .namespace_by_file(true)
+        .build()?;
+
+    // sorted by the qualified REFs
+    let filenames = seeder.sort_by_dependencies(&["posts.yml", "admins.yml", "members.yml"])?;
+    assert_eq!(filenames, vec!["admins.yml", "members.yml", "posts.yml"]);
+
+    seeder.populate("admins.yml", |user: User| {
+        assert_eq!(user.name, "root");
+        Ok(1)
+    })?;
+    seeder.populate("members.yml", |user: User| {
+        assert_eq!(user.name, "moderator");
+        Ok(2)
+    })?;
+    assert_eq!(seeder.get_id("admins/Admin"), Some(&RefValue::Int(1)));
+    assert_eq!(seeder.get_id("members/Admin"), Some(&RefValue::Int(2)));
+    assert_eq!(seeder.get_id("Admin"), None);
+
+    // the labels do not collide
+    let mut authors = Vec::new();
+    seeder.populate("posts.yml", |post: Post| {
+        authors.push((post.title, post.author_id));
+        Ok(authors.len() as i64)
+    })?;
+    assert_eq!(
+        authors,
+        vec![("Maintenance".to_string(), 1), ("Hello".to_string(), 2)]
+    );
+    assert_eq!(seeder.get_id("posts/Welcome"), Some(&RefValue::Int(2)));
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_populate_nested() -> Result<()> {
+    #[derive(serde::Deserialize)]
+    struct Buyer {
+        name: String,
+    }
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Purchase {
+        item: String,
+        customer_id: i64,
+    }
+
+    let base_dir = get_test_base_dir();
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&format!("{}/nested", base_dir));
+
+    let mut purchases = Vec::new();
+    let ids = seeder.populate_nested(
+        "customers.yml",
+        "orders",
+        |buyer: Buyer| Ok(buyer.name.len() as i64),
+        |purchase: Purchase| {
+            purchases.push(purchase);
+            Ok(100 + purchases.len() as i64)
+        },
+    )?;
+
+    // the children are inserted right after their parent, with the id of the parent
+    assert_eq!(
+        ids,
+        vec![(5, vec![101, 102]), (3, vec![103]), (7, Vec::new())]
+    );
+    assert_eq!(
+        purchases,
+        vec![
+            Purchase {
+                item: "apple".to_string(),
+                customer_id: 5
+            },
+            Purchase {
+                item: "melon".to_string(),
+                customer_id: 5
+            },
+            Purchase {
+                item: "carrot".to_string(),
+                customer_id: 3
+            },
+        ]
+    );
+    assert_eq!(seeder.get_id("Alice/orders/1"), Some(&RefValue::Int(102)));
+    assert_eq!(seeder.get_id("Bob/orders/First"), Some(&RefValue::Int(103)));
+    assert_eq!(seeder.report().files[0].inserted, 6);
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_composite_keys() -> Result<()> {
+    #[derive(serde::Deserialize)]
+    struct Membership {
+        tenant: String,
+        name: String,
+    }
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Task {
+        title: String,
+        tenant_id: i64,
+        assignee_id: i64,
+        membership: (i64, i64),
+    }
+
+    let base_dir = get_test_base_dir();
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&format!("{}/composite", base_dir));
+    seeder.set_key_columns("memberships.yml", &["tenant_id", "user_id"]);
+
+    let filenames = seeder.sort_by_dependencies(&["tasks.yml", "memberships.yml"])?;
+    assert_eq!(filenames, vec!["memberships.yml", "tasks.yml"]);
+
+    seeder.populate("memberships.yml", |membership: Membership| {
+        Ok((membership.tenant.len() as i64, membership.name.len() as i64))
+    })?;
+    assert_eq!(
+        seeder.get_id("Alice"),
+        Some(&RefValue::Composite(vec![
+            RefValue::Int(4),
+            RefValue::Int(5)
+        ]))
+    );
+    assert_eq!(seeder.get_id("Bob.user_id"), Some(&RefValue::Int(3)));
+
+    let mut tasks = Vec::new();
+    seeder.populate("tasks.yml", |task: Task| {
+        tasks.push(task);
+        Ok(tasks.len() as i64)
+    })?;
+    assert_eq!(
+        tasks,
+        vec![Task {
+            title: "review".to_string(),
+            tenant_id: 4,
+            assignee_id: 5,
+            membership: (6, 3),
+        }]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_captures() -> Result<()> {
+    #[derive(serde::Deserialize)]
+    struct User {
+        name: String,
+    }
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Profile {
+        user_id: i64,
+        user_slug: String,
+    }
+
+    let base_dir = get_test_base_dir();
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&format!("{}/captures", base_dir));
+
+    // profiles come after users, as they refer to the captured columns of the users
+    let filenames = seeder.sort_by_dependencies(&["profiles.yml", "users.yml"])?;
+    assert_eq!(filenames, vec!["users.yml", "profiles.yml"]);
+
+    let captures = seeder.populate("users.yml", |user: User| {
+        let id = user.name.len() as i64;
+        Ok(Captures::new(id).with("slug", user.name.to_lowercase()))
+    })?;
+    assert_eq!(
+        captures[0].get("slug"),
+        Some(&RefValue::String("alice".to_string()))
+    );
+    assert_eq!(seeder.get_id("Alice"), Some(&RefValue::Int(5)));
+    assert_eq!(
+        seeder.get_id("Bob.slug"),
+        Some(&RefValue::String("bob".to_string()))
+    );
+
+    let mut profiles = Vec::new();
+    seeder.populate("profiles.yml", |profile: Profile| {
+        profiles.push(profile);
+        Ok(profiles.len() as i64)
+    })?;
+    assert_eq!(
+        profiles,
+        vec![
+            Profile {
+                user_id: 5,
+                user_slug: "alice".to_string()
+            },
+            Profile {
+                user_id: 3,
+                user_slug: "bob".to_string()
+            },
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_merge() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    // each module owns its seeder
+    let mut items_seeder = DatabaseSeeder::new();
+    items_seeder.set_dir(&base_dir);
+    items_seeder.populate("items.yml", |item: Item| Ok(item.name.len() as i64))?;
+
+    let mut customers_seeder = DatabaseSeeder::new();
+    customers_seeder.set_dir(&base_dir);
+    customers_seeder.populate("customers.yml", |customer: Customer| {
+        Ok(customer.name.len() as i64)
+    })?;
+
+    let mut seeder = DatabaseSeeder::builder()
+        .base_dir(&base_dir)
+        .strict(true)
+        .build()?;
+    seeder.merge(items_seeder)?;
+    seeder.merge(customers_seeder)?;
+
+    // REFs resolve across the merged seeders
+    let mut orders = Vec::new();
+    seeder.populate("orders.yml", |order: Order| {
+        orders.push(order);
+        Ok(orders.len() as i64)
+    })?;
+    assert_eq!(orders[0].item_id, 5);
+    assert_eq!(
+        seeder.filenames,
+        vec!["items.yml", "customers.yml", "orders.yml"]
+    );
+    assert_eq!(seeder.seeded_records().len(), 11);
+    assert_eq!(seeder.report().files.len(), 3);
+
+    // the labels collide
+    let mut items_seeder = DatabaseSeeder::new();
+    items_seeder.set_dir(&base_dir);
+    items_seeder.populate("items.yml", |_: Item| Ok(0))?;
+    let err = seeder.merge(items_seeder).unwrap_err().to_string();
+    assert!(err.contains("Apple, Carrot, Melon, Orange"));
+    assert_eq!(seeder.get_id("Apple"), Some(&RefValue::Int(5)));
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_scope() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.populate("items.yml", |_: Item| Ok(1))?;
+
+    // the labels registered inside the scope are discarded, while the others are kept
+    seeder.scope(|seeder| -> Result<()> {
+        seeder.populate("customers.yml", |_: Customer| Ok(2))?;
+        seeder.populate("orders.yml", |_: Order| Ok(3))?;
+        Ok(())
+    })?;
+    assert_eq!(seeder.get_id("Melon"), Some(&RefValue::Int(1)));
+    assert_eq!(seeder.get_id("Alice"), None);
+    assert_eq!(seeder.get_id("Order1"), None);
+    assert_eq!(seeder.seeded_records().len(), 11);
+
+    // the missing REFs are not masked by the previous scope
+    let results = seeder.populate("orders.yml", |_: Order| Ok(3));
+    assert!(results.is_err());
+
+    seeder.clear_refs();
+    assert!(seeder.get_ids().is_empty());
+    assert_eq!(seeder.seeded_records().len(), 11);
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_history() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.restore_state(SeedState {
+        files: vec!["customers.yml".to_string()],
+        ..SeedState::default()
+    });
+
+    seeder.populate("items.yml", |_: Item| Ok(1))?;
+    seeder.populate("customers.yml", |_: Customer| Ok(2))?;
+    let results = seeder.populate("items.yml", |item: Item| match item.name.as_str() {
+        "apple" => Err(anyhow::anyhow!("duplicated")),
+        _ => Ok(3),
+    });
+    assert!(results.is_err());
+    seeder.continue_on_error(true);
+    seeder.populate("items.yml", |item: Item| match item.name.as_str() {
+        "apple" => Err(anyhow::anyhow!("duplicated")),
+        _ => Ok(4),
+    })?;
+
+    let history = seeder
+        .history()
+        .iter()
+        .map(|entry| (entry.filename.as_str(), entry.records, entry.status))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        history,
+        vec![
+            ("items.yml", 4, SeedStatus::Completed),
+            ("customers.yml", 0, SeedStatus::Skipped),
+            ("items.yml", 2, SeedStatus::Failed),
+            ("items.yml", 3, SeedStatus::CompletedWithFailures),
+        ]
+    );
+    assert_eq!(
+        seeder.history()[3].to_string(),
+        "items.yml: completed with failures (3 records)"
+    );
+    assert!(seeder.history()[0].finished_at <= seeder.history()[3].finished_at);
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_builder() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    // the profile is used when none is given
+    let seeder = DatabaseSeeder::builder()
+        .manifest(&format!("{}/seeds.yml", base_dir))
+        .profile("development")
+        .build()?;
+    assert_eq!(seeder.base_dir, base_dir);
+    let mut tables = Vec::new();
+    seeder.clean_manifest(None, |table| {
+        tables.push(table.to_string());
+        Ok(())
+    })?;
+    assert_eq!(tables, vec!["orders", "customers", "products"]);
+
+    // the manifest cannot be loaded
+    let results = DatabaseSeeder::builder().manifest("missing.yml").build();
+    assert!(results.is_err());
+
+    // labels cannot be populated twice in strict mode
+    let mut seeder = DatabaseSeeder::builder()
+        .base_dir(&base_dir)
+        .strict(true)
+        .build()?;
+    seeder.populate("items.yml", |_: Item| Ok(1))?;
+    let results = seeder.populate("items.yml", |_: Item| Ok(2));
+    let err = results.unwrap_err().to_string();
+    assert!(err.contains("label: Melon (items.yml) has already been populated"));
+    assert_eq!(seeder.get_id("Melon"), Some(&RefValue::Int(1)));
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_error_kinds() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::builder()
+        .base_dir(&base_dir)
+        .strict(true)
+        .build()?;
+
+    let err = seeder.populate("missing.yml", |_: Item| Ok(1)).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<CderError>(),
+        Some(CderError::FileNotFound { .. })
+    ));
+
+    // all the unresolved REFs are reported with their labels and positions
+    let err = seeder.populate("orders.yml", |_: Order| Ok(1)).unwrap_err();
+    let Some(CderError::Multiple { file, errors }) = err.downcast_ref::<CderError>() else {
+        panic!("unexpected error: {}", err);
+    };
+    assert_eq!(file, "orders.yml");
+    assert_eq!(errors.len(), 8);
+    match &errors[0] {
+        (
+            Some(label),
+            CderError::UnresolvedRef {
+                key,
+                file,
+                line,
+                col,
+                ..
+            },
+        ) => {
+            assert_eq!(label, "Order1");
+            assert_eq!((key.as_str(), file.as_str()), ("Alice", "orders.yml"));
+            assert_eq!((*line, *col), (3, 16));
+        }
+        (label, err) => panic!("unexpected error: {:?}: {}", label, err),
+    }
+    assert!(matches!(
+        &errors[2],
+        (Some(label), CderError::UnresolvedRef { key, .. }) if label == "Order2" && key == "Bob"
+    ));
+    assert!(err
+        .to_string()
+        .starts_with("8 problems found in the file: orders.yml [CDER008]\n - Order1: failed to pre-process embedded tags: orders.yml (line 3, column 16) [CDER001]\n      err: no record is labeled `Alice` (REF(Alice))\n - Order1:"));
+
+    // the REFs are resolved, whereas the directive is not supported
+    seeder.restore_state(SeedState {
+        files: Vec::new(),
+        ids: [("Acme", 1), ("Initech", 2), ("Alice", 3), ("Banana", 11)]
+            .into_iter()
+            .map(|(label, id)| (label.to_string(), RefValue::Int(id)))
+            .collect(),
+    });
+    let err = seeder
+        .populate("invalid/users.yml", |_: Customer| Ok(1))
+        .unwrap_err();
+    let Some(CderError::Multiple { errors, .. }) = err.downcast_ref::<CderError>() else {
+        panic!("unexpected error: {}", err);
+    };
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(
+        &errors[0],
+        (
+            Some(label),
+            CderError::TagParse {
+                line: 8,
+                col: 9,
+                ..
+            }
+        ) if label == "Bob"
+    ));
+    assert!(matches!(
+        &errors[1],
+        (Some(label), CderError::UnresolvedRef { key, .. }) if label == "Globex" && key == "Globex"
+    ));
+    let codes = errors
+        .iter()
+        .map(|(_, err)| err.code())
+        .collect::<Vec<&str>>();
+    assert_eq!(codes, vec!["CDER002", "CDER001"]);
+
+    let err = seeder
+        .populate("invalid/broken.yml", |_: Customer| Ok(1))
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<CderError>(),
+        Some(CderError::Deserialize { label: None, .. })
+    ));
+
+    // the records that do not fit the type
+    let err = seeder.populate("items.yml", |_: Order| Ok(1)).unwrap_err();
+    let Some(CderError::Multiple { file, errors }) = err.downcast_ref::<CderError>() else {
+        panic!("unexpected error: {}", err);
+    };
+    assert_eq!(file, "items.yml");
+    let labels = errors
+        .iter()
+        .map(|(label, _)| label.as_deref().unwrap())
+        .collect::<Vec<&str>>();
+    assert_eq!(labels, vec!["Melon", "Orange", "Apple", "Carrot"]);
+    // the first one is found with its position, and the rest by deserializing the records one by one
+    assert!(matches!(
+        &errors[0].1,
+        CderError::Deserialize { line: Some(2), label: Some(label), .. } if label == "Melon"
+    ));
+    assert!(matches!(
+        &errors[3].1,
+        CderError::Deserialize { line: None, label: Some(label), .. } if label == "Carrot"
+    ));
+
+    // the position is the one in the file as it is written, before the tags are replaced
+    let err = seeder
+        .populate("invalid/orders.yml", |_: Order| Ok(1))
+        .unwrap_err();
+    match err.downcast_ref::<CderError>() {
+        Some(CderError::Deserialize {
+            label,
+            line,
+            col,
+            excerpt: Some(excerpt),
+            ..
+        }) => {
+            assert_eq!(label.as_deref(), Some("Order2"));
+            assert_eq!((*line, *col), (Some(7), Some(92)));
+            assert!(
+                excerpt.starts_with("  7 | Order2: { id: 1201, customer_id: ${{ REF(Alice) }},")
+            );
+        }
+        _ => panic!("unexpected error: {}", err),
+    }
+    assert!(err
+        .to_string()
+        .contains("expected i64 at line 7 column 92\n"));
+
+    seeder.populate("items.yml", |_: Item| Ok(1))?;
+    let err = seeder.populate("items.yml", |_: Item| Ok(2)).unwrap_err();
+    match err.downcast_ref::<CderError>() {
+        Some(CderError::DuplicateLabel { label, file }) => {
+            assert_eq!((label.as_str(), file.as_str()), ("Melon", "items.yml"));
+        }
+        _ => panic!("unexpected error: {}", err),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_config() -> Result<()> {
+    let config_dir = std::env::temp_dir().join("cder_test_config");
+    std::fs::create_dir_all(&config_dir)?;
+    let path = config_dir.join(".cder.toml");
+    std::fs::write(&path, "base_dir = \"fixtures\"\nstrict = true\n")?;
+    let config = CderConfig::from_file(path.to_str().unwrap())?;
+    std::fs::remove_dir_all(&config_dir)?;
+
+    // resolved against the directory of the config file
+    let base_dir = config_dir.join("fixtures");
+    assert_eq!(config.base_dir.as_deref(), base_dir.to_str());
+    assert_eq!(config.strict, Some(true));
+
+    // the settings given explicitly take precedence over the config
+    let mut seeder = DatabaseSeeder::builder()
+        .base_dir(&get_test_base_dir())
+        .config(config.clone())
+        .build()?;
+    seeder.populate("items.yml", |_: Item| Ok(1))?;
+    assert!(seeder.populate("items.yml", |_: Item| Ok(2)).is_err());
+
+    let seeder = DatabaseSeeder::builder()
+        .strict(false)
+        .config(config.clone())
+        .build()?;
+    assert_eq!(Some(seeder.base_dir.as_str()), base_dir.to_str());
+
+    let loader = StructLoader::<Item>::with_config("items.yml", &config);
+    assert_eq!(Some(loader.base_dir.as_str()), base_dir.to_str());
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_clean() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    // referencing tables are truncated first
+    let mut tables = Vec::new();
+    seeder.clean(&["items.yml", "orders.yml", "customers.yml"], |table| {
+        tables.push(table.to_string());
+        Ok(())
+    })?;
+    assert_eq!(tables, vec!["orders", "customers", "items"]);
+
+    // stops at the first error
+    let mut tables = Vec::new();
+    let results = seeder.clean(&["items.yml", "orders.yml"], |table| {
+        tables.push(table.to_string());
+        Err(anyhow::anyhow!("permission denied"))
+    });
+    assert!(results.is_err());
+    assert_eq!(tables, vec!["orders"]);
+
+    // with the manifest, the tables specified there are used
+    let seeder = DatabaseSeeder::from_manifest(&format!("{}/seeds.yml", base_dir))?;
+    let mut tables = Vec::new();
+    seeder.clean_manifest(Some("development"), |table| {
+        tables.push(table.to_string());
+        Ok(())
+    })?;
+    assert_eq!(tables, vec!["orders", "customers", "products"]);
+
+    Ok(())
+}
+
+// records the notified events
+struct MockObserver {
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+impl SeedObserver for MockObserver {
+    fn on_file_start(&mut self, filename: &str, total: usize) {
+        let mut events = self.events.lock().unwrap();
+        events.push(format!("start {} {}", filename, total));
+    }
+
+    fn on_record_inserted(&mut self, filename: &str, _label: &str, _id: &RefValue) {
+        let mut events = self.events.lock().unwrap();
+        events.push(format!("inserted {}", filename));
+    }
+
+    fn on_file_done(&mut self, filename: &str, total: usize, inserted: usize) {
+        let mut events = self.events.lock().unwrap();
+        events.push(format!("done {} {}/{}", filename, inserted, total));
+    }
+}
+
+#[test]
+fn test_database_seeder_observer() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let rt = Runtime::new().unwrap();
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.set_observer(MockObserver {
+        events: events.clone(),
+    });
+
+    let mock_customers_table = MockTable::<Customer>::new(vec![
+        ("Alice".to_string(), 1),
+        ("Bob".to_string(), 2),
+        ("Developer".to_string(), 3),
+    ]);
+    seeder.populate("customers.yml", |input: Customer| {
+        let mut mock_customers_table = mock_customers_table.clone();
+        rt.block_on(mock_customers_table.insert(input))
+    })?;
+
+    let events = events.lock().unwrap();
+    assert_eq!(
+        *events,
+        vec![
+            "start customers.yml 3",
+            "inserted customers.yml",
+            "inserted customers.yml",
+            "inserted customers.yml",
+            "done customers.yml 3/3",
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_retry() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let rt = Runtime::new().unwrap();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.set_retry_policy(
+        RetryPolicy::new(3)
+            .backoff(Duration::from_millis(1))
+            .retry_if(|err| err.to_string().contains("deadlock")),
+    );
+
+    let mock_customers_table = MockTable::<Customer>::new(vec![
+        ("Alice".to_string(), 1),
+        ("Bob".to_string(), 2),
+        ("Developer".to_string(), 3),
+    ]);
+
+    {
+        // when the insertion succeeds within the max attempts
+        let mut attempts = Vec::new();
+        seeder.populate("customers.yml", |input: Customer| {
+            attempts.push(input.name.clone());
+            // fails twice for Bob
+            if input.name == "Bob" && attempts.iter().filter(|name| *name == "Bob").count() < 3 {
+                return Err(anyhow::anyhow!("deadlock detected"));
+            }
+            let mut mock_customers_table = mock_customers_table.clone();
+            rt.block_on(mock_customers_table.insert(input))
+        })?;
+
+        assert_eq!(attempts.iter().filter(|name| *name == "Bob").count(), 3);
+        assert_eq!(mock_customers_table.get_records().len(), 3);
+        // the record is restored on every attempt
+        assert!(mock_customers_table.get_records().iter().any(|customer| {
+            customer.name == "Bob"
+                && customer.plan
+                    == Plan::Family {
+                        shared_membership: 4,
+                    }
+        }));
+    }
+
+    {
+        // when the insertion keeps failing
+        let mut attempts = 0;
+        let results = seeder.populate("customers.yml", |_: Customer| -> Result<i64> {
+            attempts += 1;
+            Err(anyhow::anyhow!("deadlock detected"))
+        });
+        assert!(results.is_err());
+        assert_eq!(attempts, 3);
+    }
+
+    {
+        // when the error is not retryable
+        let mut attempts = 0;
+        let results = seeder.populate("customers.yml", |_: Customer| -> Result<i64> {
+            attempts += 1;
+            Err(anyhow::anyhow!("unique constraint violated"))
+        });
+        assert!(results.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_continue_on_error() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let rt = Runtime::new().unwrap();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.continue_on_error(true);
+
+    // carrot is not registered, so that the insertion fails
+    let mock_items_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+    ]);
+    let ids = seeder.populate("items.yml", |input: Item| {
+        let mut mock_items_table = mock_items_table.clone();
+        rt.block_on(mock_items_table.insert(input))
+    })?;
+    assert_eq!(ids.len(), 3);
+
+    let failures = seeder.failures();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].label, "Carrot");
+    assert_eq!(failures[0].filename, "items.yml");
+    assert_eq!(failures[0].error.to_string(), "insert failed");
+
+    let err = seeder.check_failures().unwrap_err().to_string();
+    assert!(err.starts_with("3 succeeded, 1 failed:"));
+    assert!(err.contains("Carrot (items.yml): insert failed"));
+
+    // the file is not saved as processed, so that it is populated again when resumed
+    assert!(seeder.filenames.contains(&"items.yml".to_string()));
+    assert!(seeder.state().files.is_empty());
+    assert_eq!(seeder.state().ids.len(), 3);
+
+    // the failures do not matter unless continue_on_error is enabled
+    seeder.continue_on_error(false);
+    let results = seeder.populate("items.yml", |input: Item| {
+        let mut mock_items_table = mock_items_table.clone();
+        rt.block_on(mock_items_table.insert(input))
+    });
+    assert!(results.is_err());
+    assert_eq!(seeder.failures().len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_factory() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.populate("items.yml", |item: Item| Ok(item.name))?;
+
+    // the records are built from the ones in the file, and registered for REFs
+    let mut customers = Vec::new();
+    let plans = [
+        ("Alice", Plan::Standard),
+        ("Bob", Plan::Premium),
+        ("Dev", Plan::Premium),
+    ];
+    for (label, plan) in plans {
+        let factory = seeder
+            .factory::<Customer>("customers.yml", label)?
+            .with(|customer| customer.plan = plan);
+        assert!(!factory.record().emails.is_empty());
+        factory.insert(&mut seeder, |customer| {
+            customers.push(customer);
+            Ok(customers.len() as i64 * 10)
+        })?;
+    }
+    let carol = seeder
+        .factory::<Customer>("customers.yml", "Alice")?
+        .label("Carol")
+        .with(|customer| customer.name = "Carol".to_string())
+        .build();
+    assert_eq!(carol.name, "Carol");
+    assert_eq!(customers[0].plan, Plan::Standard);
+    assert_eq!(customers[1].emails.len(), 2);
+    assert_eq!(seeder.get_id("Bob"), Some(&RefValue::Int(20)));
+    assert_eq!(seeder.get_id("Carol"), None);
+
+    // the records of the other files refer to the ones of the factories
+    assert!(seeder
+        .render("orders.yml")?
+        .contains("customer_id: 20\n  item_id: \"melon\""));
+
+    assert!(seeder
+        .factory::<Customer>("customers.yml", "Carol")
+        .is_err());
+    assert!(seeder.factory::<Customer>("items.yml", "Melon").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_secret_masking() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.set_env("CDER_TEST_ITEM_NAME", "sk-live-1234");
+    seeder.set_env("CDER_TEST_ITEM_PRICE", "cheap-as-chips");
+    let err = seeder
+        .populate("invalid/secrets.yml", |_: Item| Ok(1))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("invalid type: string \"***\", expected f64"));
+    assert!(!err.contains("cheap-as-chips"));
+
+    // the allowed environment variables are left as they are
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.set_env("CDER_TEST_ITEM_NAME", "sk-live-1234");
+    seeder.set_env("CDER_TEST_ITEM_PRICE", "cheap-as-chips");
+    seeder.set_unmasked_env(&["CDER_TEST_ITEM_PRICE"]);
+    let err = seeder
+        .populate("invalid/secrets.yml", |_: Item| Ok(1))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("invalid type: string \"cheap-as-chips\", expected f64"));
+
+    // the errors of the loader are masked as well
+    seeder.set_env("CDER_TEST_ITEM_PRICE", "100");
+    seeder.continue_on_error(true);
+    seeder.populate("invalid/secrets.yml", |item: Item| -> Result<i64> {
+        Err(anyhow::anyhow!("{} is already taken", item.name))
+    })?;
+    assert_eq!(
+        seeder.failures()[0].error.to_string(),
+        "*** is already taken"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_insert_hooks() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let rt = Runtime::new().unwrap();
+    let inserted = Arc::new(Mutex::new(Vec::new()));
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.before_insert(|_, record| {
+        if let Some(price) = record.get_mut("price") {
+            *price = (price.as_f64().unwrap_or_default() * 2.0).into();
+        }
+        Ok(())
+    });
+    let recorder = inserted.clone();
+    seeder.after_insert(move |label, id| {
+        recorder
+            .lock()
+            .unwrap()
+            .push((label.to_string(), id.clone()));
+    });
+
+    let mock_items_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+        ("carrot".to_string(), 4),
+    ]);
+    let ids = seeder.populate("items.yml", |input: Item| {
+        let mut mock_items_table = mock_items_table.clone();
+        rt.block_on(mock_items_table.insert(input))
+    })?;
+
+    let records = sort_records_by_ids(mock_items_table.get_records(), ids);
+    assert_eq!(records[0].price, 1000.0);
+    assert_eq!(records[3].price, 300.0);
+
+    let mut inserted = inserted.lock().unwrap().clone();
+    inserted.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(inserted.len(), 4);
+    assert_eq!(inserted[0], ("Apple".to_string(), RefValue::Int(3)));
+
+    // the file fails when the hook rejects the records
+    seeder.before_insert(|label, _| match label {
+        "Carrot" => Err(anyhow::anyhow!("carrot is not a fruit")),
+        _ => Ok(()),
+    });
+    let results = seeder.populate("items.yml", |input: Item| {
+        let mut mock_items_table = mock_items_table.clone();
+        rt.block_on(mock_items_table.insert(input))
+    });
+    assert!(results
+        .unwrap_err()
+        .to_string()
+        .contains("carrot is not a fruit"));
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_computed_fields() -> Result<()> {
+    #[derive(serde::Deserialize)]
+    struct Product {
+        name: String,
+        price: f64,
+        tenant_id: i64,
+        created_by: String,
+    }
+
+    let base_dir = get_test_base_dir();
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.computed_fields(|label, _| {
+        vec![
+            ("tenant_id".to_string(), RefValue::Int(7)),
+            (
+                "created_by".to_string(),
+                RefValue::String(format!("seeder:{}", label)),
+            ),
+            // defined in the fixture
+            ("price".to_string(), RefValue::Int(0)),
+        ]
+    });
+
+    let mut products = Vec::new();
+    seeder.populate("items.yml", |product: Product| {
+        products.push(product);
+        Ok(products.len() as i64)
+    })?;
+    assert_eq!(products.len(), 4);
+    assert_eq!(products[0].name, "melon");
+    assert_eq!(products[0].price, 500.0);
+    assert!(products.iter().all(|product| product.tenant_id == 7));
+    assert_eq!(products[3].created_by, "seeder:Carrot");
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_report() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let rt = Runtime::new().unwrap();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.continue_on_error(true);
+
+    // carrot is not registered, so that the insertion fails
+    let mock_items_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+    ]);
+    seeder.populate("items.yml", |input: Item| {
+        let mut mock_items_table = mock_items_table.clone();
+        rt.block_on(mock_items_table.insert(input))
+    })?;
+
+    seeder.populate_idempotent("customers.yml", |input: Customer| {
+        match input.name.as_str() {
+            "Alice" => Ok(SeedOutcome::Inserted(1)),
+            "Bob" => Ok(SeedOutcome::Exists(2)),
+            _ => Ok(SeedOutcome::Skipped),
+        }
+    })?;
+
+    let report = seeder.report();
+    assert_eq!(report.files.len(), 2);
+
+    assert_eq!(report.files[0].filename, "items.yml");
+    assert_eq!(report.files[0].parsed, 4);
+    assert_eq!(report.files[0].inserted, 3);
+    assert_eq!(report.files[0].skipped, 0);
+    assert_eq!(report.files[0].failed, 1);
+
+    assert_eq!(report.files[1].filename, "customers.yml");
+    assert_eq!(report.files[1].parsed, 3);
+    assert_eq!(report.files[1].inserted, 1);
+    assert_eq!(report.files[1].skipped, 2);
+    assert_eq!(report.files[1].failed, 0);
+
+    assert_eq!(report.inserted(), 4);
+    assert_eq!(report.failed(), 1);
+    assert!(report
+        .to_string()
+        .starts_with("items.yml: 4 parsed, 3 inserted, 0 skipped, 1 failed"));
+
+    let json = serde_json::to_value(report)?;
+    assert_eq!(json["files"][1]["skipped"], 2);
+    assert!(json["files"][1]["elapsed"].is_f64());
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_insertion_order() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    for _ in 0..10 {
+        let mut names = Vec::new();
+        seeder.populate("items.yml", |input: Item| {
+            names.push(input.name);
+            Ok(names.len() as i64)
+        })?;
+        assert_eq!(names, vec!["melon", "orange", "apple", "carrot"]);
+    }
+    assert_eq!(seeder.get_id("Apple"), Some(&RefValue::Int(3)));
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_populate_per_tenant() -> Result<()> {
+    #[derive(serde::Deserialize)]
+    struct Account {
+        name: String,
+        tenant: String,
+    }
+    #[derive(serde::Deserialize)]
+    struct Project {
+        tenant: String,
+        owner_id: i64,
+        item_id: i64,
+    }
+
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    // records that are not namespaced are shared among the tenants
+    seeder.populate("items.yml", |input: Item| match input.name.as_str() {
+        "apple" => Ok(3),
+        _ => Ok(0),
+    })?;
+
+    let mut accounts = Vec::new();
+    let ids = seeder.populate_per_tenant(
+        &["acme", "globex"],
+        "tenants/accounts.yml",
+        |tenant, input: Account| {
+            assert_eq!(tenant.to_string(), input.tenant);
+            accounts.push(format!("{}/{}", input.tenant, input.name));
+            Ok(accounts.len() as i64)
+        },
+    )?;
+    assert_eq!(ids, vec![1, 2, 3, 4]);
+    assert_eq!(
+        accounts,
+        vec!["acme/admin", "acme/guest", "globex/admin", "globex/guest"]
+    );
+    assert_eq!(seeder.get_id("acme/Admin"), Some(&RefValue::Int(1)));
+    assert_eq!(seeder.get_id("globex/Admin"), Some(&RefValue::Int(3)));
+    assert_eq!(seeder.get_id("Admin"), None);
+
+    // REFs are resolved within the same tenant
+    let mut projects = Vec::new();
+    seeder.populate_per_tenant(
+        &["acme", "globex"],
+        "tenants/projects.yml",
+        |_, input: Project| {
+            projects.push((input.tenant, input.owner_id, input.item_id));
+            Ok(projects.len() as i64)
+        },
+    )?;
+    assert_eq!(
+        projects,
+        vec![("acme".to_string(), 1, 3), ("globex".to_string(), 3, 3)]
+    );
+    assert_eq!(seeder.get_id("globex/Project1"), Some(&RefValue::Int(2)));
+
+    // CTX tags cannot be resolved outside of the tenants
+    let result = seeder.populate("tenants/accounts.yml", |_: Account| Ok(0));
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_with_scale() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.with_scale(3);
+
+    let mut names = Vec::new();
+    seeder.populate("scale/items.yml", |input: Item| {
+        names.push(input.name);
+        Ok(names.len() as i64)
+    })?;
+    assert_eq!(
+        names,
+        vec!["banana0", "kiwi0", "banana1", "kiwi1", "banana2", "kiwi2"]
+    );
+
+    // REFs refer to the base copies
+    assert_eq!(seeder.get_id("Banana"), Some(&RefValue::Int(1)));
+    assert_eq!(seeder.get_id("Kiwi#2"), Some(&RefValue::Int(6)));
+    assert_eq!(seeder.report().files[0].inserted, 6);
+
+    // the index is 0 without scaling
+    seeder.with_scale(1);
+    let mut names = Vec::new();
+    seeder.populate("scale/items.yml", |input: Item| {
+        names.push(input.name);
+        Ok(names.len() as i64)
+    })?;
+    assert_eq!(names, vec!["banana0", "kiwi0"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_rails_fixtures() -> Result<()> {
+    #[derive(serde::Deserialize)]
+    struct RailsCustomer {
+        name: String,
+        email: String,
+        country: String,
+    }
+    #[derive(serde::Deserialize)]
+    struct RailsOrder {
+        customer_id: i64,
+        reviewer_id: i64,
+        reviewer_type: String,
+        item: String,
+        quantity: i64,
+    }
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&format!("{}/rails", get_test_base_dir()));
+    seeder.rails_fixtures(true);
+    seeder.set_env("ALICE_EMAIL", "alice@example.org");
+
+    // the associations by label are REFs, so the files are sorted by them
+    assert_eq!(
+        seeder.sort_by_dependencies(&["orders.yml", "customers.yml"])?,
+        vec!["customers.yml", "orders.yml"]
+    );
+
+    let mut customers = Vec::new();
+    seeder.populate("customers.yml", |customer: RailsCustomer| {
+        customers.push(customer);
+        Ok(customers.len() as i64 + 10)
+    })?;
+    assert_eq!(customers.len(), 2);
+    assert_eq!(customers[0].name, "Alice");
+    assert_eq!(customers[0].email, "alice@example.org");
+    assert_eq!(customers[1].email, "bob@example.com");
+    assert!(customers.iter().all(|customer| customer.country == "Japan"));
+
+    let mut orders = Vec::new();
+    seeder.populate("orders.yml", |order: RailsOrder| {
+        orders.push(order);
+        Ok(orders.len() as i64)
+    })?;
+    assert_eq!(orders[0].customer_id, 11);
+    assert_eq!(orders[0].reviewer_id, 12);
+    assert_eq!(orders[0].reviewer_type, "Customer");
+    // items.yml is not found next to the file, so `item` is not an association
+    assert_eq!(orders[0].item, "melon");
+    assert_eq!(orders[0].quantity, 2);
+
+    // the files are read as they are unless enabled
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&format!("{}/rails", get_test_base_dir()));
+    assert!(seeder.render("customers.yml")?.contains("<%="));
+
+    Ok(())
+}
+
+#[test]
+fn test_database_seeder_traits() -> Result<()> {
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&format!("{}/traits", get_test_base_dir()));
+
+    let mut customers = Vec::new();
+    seeder.populate("customers.yml", |customer: Customer| {
+        customers.push(customer);
+        Ok(customers.len() as i64)
+    })?;
+
+    assert_eq!(customers.len(), 3);
+    assert_eq!(customers[0].plan, Plan::Premium);
+    assert_eq!(customers[0].country_code, Some(81));
+    // the later traits overwrite the earlier ones
+    assert_eq!(
+        customers[1].plan,
+        Plan::Family {
+            shared_membership: 4
+        }
+    );
+    assert_eq!(customers[1].country_code, None);
+    // the fields of the record take precedence over the traits
+    assert_eq!(customers[2].plan, Plan::Standard);
+    assert_eq!(customers[2].country_code, Some(81));
+    assert_eq!(customers[2].emails, vec!["developer@example.com"]);
+
+    Ok(())
+}
+
+#[cfg(feature = "dbt")]
+#[test]
+fn test_database_seeder_dbt_seeds() -> Result<()> {
+    #[derive(serde::Deserialize)]
+    struct DbtItem {
+        code: String,
+        name: String,
+        price: f64,
+    }
+
+    let base_dir = format!("{}/dbt", get_test_base_dir());
+    let seeds = cder::DbtSeeds::from_properties(&format!("{}/properties.yml", base_dir))?;
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.dbt_seeds(seeds.key_column("code"));
+
+    let mut items = Vec::new();
+    seeder.populate("items.csv", |item: DbtItem| {
+        items.push(item);
+        Ok(items.len() as i64 * 10)
+    })?;
+    // the codes are read as strings by the column types of the properties
+    assert_eq!(items[0].code, "501");
+    assert_eq!(items[1].name, "orange");
+    assert_eq!(items[1].price, 200.0);
+    assert_eq!(seeder.get_id("502"), Some(&RefValue::Int(20)));
+
+    let mut orders = Vec::new();
+    seeder.populate("orders.csv", |order: Order| {
+        orders.push(order);
+        Ok(orders.len() as i64)
+    })?;
+    assert_eq!(orders[0].item_id, 20);
+    assert_eq!(orders[0].quantity, 2);
+    assert_eq!(
+        orders[0].purchased_at,
+        parse_datetime("2021-03-01 15:15:44")?
+    );
+    assert!(seeder.get_id("O1").is_some());
+
+    Ok(())
+}
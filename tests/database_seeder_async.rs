@@ -5,7 +5,13 @@ use test_utils::{
 extern crate cder;
 
 use anyhow::Result;
-use cder::DatabaseSeeder;
+use cder::{
+    testing::MockAdapter, CderError, DatabaseSeeder, RefValue, RetryPolicy, SeedAdapter,
+    SeedOutcome, SeedState, SeedStatus, SharedSeeder, SqlFormat, SqlWriter,
+};
+use futures::StreamExt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 #[test]
 fn test_database_seeder_new() {
@@ -207,3 +213,1657 @@ async fn test_database_seeder_populate_async_orders() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_database_seeder_populate_async_concurrent_orders() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    let mock_items_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+        ("carrot".to_string(), 4),
+    ]);
+    seeder
+        .populate_async_concurrent("items.yml", 4, |input: Item| {
+            let mut mock_items_table = mock_items_table.clone();
+            async move { mock_items_table.insert(input).await }
+        })
+        .await?;
+    let mock_customers_table = MockTable::<Customer>::new(vec![
+        ("Alice".to_string(), 1),
+        ("Bob".to_string(), 2),
+        ("Developer".to_string(), 3),
+    ]);
+    seeder
+        .populate_async_concurrent("customers.yml", 2, |input: Customer| {
+            let mut mock_customers_table = mock_customers_table.clone();
+            async move { mock_customers_table.insert(input).await }
+        })
+        .await?;
+
+    let mock_orders_table = MockTable::<Order>::new(vec![
+        ("1200".to_string(), 1),
+        ("1201".to_string(), 2),
+        ("1202".to_string(), 3),
+        ("1203".to_string(), 4),
+    ]);
+    let ids = seeder
+        .populate_async_concurrent("orders.yml", 4, |input: Order| {
+            let mut mock_orders_table = mock_orders_table.clone();
+            async move { mock_orders_table.insert(input).await }
+        })
+        .await?;
+    assert_eq!(seeder.get_id("Order3"), Some(&RefValue::Int(3)));
+
+    let persisted_records = mock_orders_table.get_records();
+    let records = sort_records_by_ids(persisted_records, ids);
+
+    assert_eq!(records[0].id, 1200);
+    assert_eq!(records[0].customer_id, 1);
+    assert_eq!(records[0].item_id, 3);
+
+    assert_eq!(records[1].id, 1201);
+    assert_eq!(records[1].customer_id, 2);
+    assert_eq!(records[1].item_id, 1);
+
+    assert_eq!(records[2].id, 1202);
+    assert_eq!(records[2].customer_id, 1);
+    assert_eq!(records[2].item_id, 4);
+
+    assert_eq!(records[3].id, 1203);
+    assert_eq!(records[3].customer_id, 3);
+    assert_eq!(records[3].item_id, 1);
+
+    // a slow insertion does not keep the others from starting, while the ids keep the order
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    let completed = AtomicUsize::new(0);
+    let ids = seeder
+        .populate_async_concurrent("items.yml", 2, |input: Item| {
+            let completed = &completed;
+            async move {
+                if input.name == "melon" {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    assert_eq!(completed.load(Ordering::SeqCst), 3);
+                }
+                completed.fetch_add(1, Ordering::SeqCst);
+                Ok(input.name)
+            }
+        })
+        .await?;
+    assert_eq!(ids, vec!["melon", "orange", "apple", "carrot"]);
+
+    // when the concurrency is zero
+    let results = seeder
+        .populate_async_concurrent("items.yml", 0, |input: Item| {
+            let mut mock_items_table = mock_items_table.clone();
+            async move { mock_items_table.insert(input).await }
+        })
+        .await;
+    assert!(results.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_database_seeder_populate_async_concurrent_failure() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    // apple is not registered, so that its insertion fails
+    let mock_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("carrot".to_string(), 4),
+    ]);
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    let results = seeder
+        .populate_async_concurrent("items.yml", 4, |input: Item| {
+            let mut mock_table = mock_table.clone();
+            async move { mock_table.insert(input).await }
+        })
+        .await;
+    assert!(results.is_err());
+
+    // the records inserted before the failure stay resolvable, and are torn down
+    assert_eq!(seeder.get_id("Melon"), Some(&RefValue::Int(1)));
+    assert_eq!(seeder.get_id("Orange"), Some(&RefValue::Int(2)));
+    assert_eq!(seeder.get_id("Carrot"), Some(&RefValue::Int(4)));
+    assert_eq!(seeder.get_id("Apple"), None);
+
+    let mut deleted = Vec::new();
+    seeder
+        .teardown_async(|table, id| {
+            deleted.push((table, id));
+            async { Ok(()) }
+        })
+        .await?;
+    deleted.sort_by_key(|(_, id)| id.to_string());
+    assert_eq!(
+        deleted,
+        vec![
+            ("items".to_string(), RefValue::Int(1)),
+            ("items".to_string(), RefValue::Int(2)),
+            ("items".to_string(), RefValue::Int(4)),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_database_seeder_populate_async_retry() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mock_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+        ("carrot".to_string(), 4),
+    ]);
+    let failures = AtomicUsize::new(2);
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.set_retry_policy(RetryPolicy::new(3).backoff(Duration::from_millis(1)));
+
+    let ids = seeder
+        .populate_async("items.yml", |input: Item| {
+            let mut mock_table = mock_table.clone();
+            // the first two insertions fail
+            let fails = failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            async move {
+                if fails {
+                    return Err(anyhow::anyhow!("connection reset"));
+                }
+                mock_table.insert(input).await
+            }
+        })
+        .await?;
+
+    assert_eq!(ids.len(), 4);
+    assert_eq!(mock_table.get_records().len(), 4);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_database_seeder_concurrency() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mock_items_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+        ("carrot".to_string(), 4),
+    ]);
+
+    let mut seeder = DatabaseSeeder::builder()
+        .base_dir(&base_dir)
+        .concurrency(4)
+        .build()?;
+    let ids = seeder
+        .populate_async("items.yml", |input: Item| {
+            let mut mock_items_table = mock_items_table.clone();
+            async move { mock_items_table.insert(input).await }
+        })
+        .await?;
+
+    assert_eq!(ids.len(), 4);
+    assert_eq!(mock_items_table.get_records().len(), 4);
+    assert_eq!(seeder.get_id("Melon"), Some(&RefValue::Int(1)));
+
+    // the failed insertions are retried while the others are in flight
+    let mock_retried_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+        ("carrot".to_string(), 4),
+    ]);
+    let failures = AtomicUsize::new(2);
+    let mut seeder = DatabaseSeeder::builder()
+        .base_dir(&base_dir)
+        .concurrency(4)
+        .retry_policy(RetryPolicy::new(3).backoff(Duration::from_millis(1)))
+        .build()?;
+    let ids = seeder
+        .populate_async("items.yml", |input: Item| {
+            let mut mock_table = mock_retried_table.clone();
+            let fails = failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            async move {
+                if fails {
+                    return Err(anyhow::anyhow!("connection reset"));
+                }
+                mock_table.insert(input).await
+            }
+        })
+        .await?;
+    assert_eq!(ids.len(), 4);
+    assert_eq!(mock_retried_table.get_records().len(), 4);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_database_seeder_populate_async_returning() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mock_items_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+        ("carrot".to_string(), 4),
+    ]);
+
+    let mut seeder = DatabaseSeeder::builder()
+        .base_dir(&base_dir)
+        .concurrency(2)
+        .build()?;
+    let items = seeder
+        .populate_async_returning("items.yml", |input: Item| {
+            let mut mock_items_table = mock_items_table.clone();
+            async move {
+                let id = mock_items_table.insert(input.clone()).await?;
+                Ok((id, input))
+            }
+        })
+        .await?;
+
+    // the models are paired with their ids, even when inserted concurrently
+    assert_eq!(items.len(), 4);
+    for (id, item) in items {
+        let expected = match item.name.as_str() {
+            "melon" => 1,
+            "orange" => 2,
+            "apple" => 3,
+            _ => 4,
+        };
+        assert_eq!(id, expected);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_database_seeder_rate_limit() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mock_items_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+        ("carrot".to_string(), 4),
+    ]);
+
+    // 4 records at 20 records/sec take at least 150ms, even when inserted concurrently
+    let mut seeder = DatabaseSeeder::builder()
+        .base_dir(&base_dir)
+        .rate_limit(20)
+        .concurrency(4)
+        .build()?;
+    let started_at = Instant::now();
+    seeder
+        .populate_async("items.yml", |input: Item| {
+            let mut mock_items_table = mock_items_table.clone();
+            async move { mock_items_table.insert(input).await }
+        })
+        .await?;
+
+    assert!(started_at.elapsed() >= Duration::from_millis(150));
+    assert_eq!(mock_items_table.get_records().len(), 4);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_shared_seeder() -> Result<()> {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<DatabaseSeeder>();
+    assert_send_sync::<SharedSeeder>();
+
+    let base_dir = get_test_base_dir();
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    let seeder = SharedSeeder::new(seeder);
+
+    let mock_items_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+        ("carrot".to_string(), 4),
+    ]);
+    let mock_customers_table = MockTable::<Customer>::new(vec![
+        ("Alice".to_string(), 1),
+        ("Bob".to_string(), 2),
+        ("Developer".to_string(), 3),
+    ]);
+
+    // the independent files are populated from different tasks
+    let items = {
+        let seeder = seeder.clone();
+        let mock_items_table = mock_items_table.clone();
+        tokio::spawn(async move {
+            seeder
+                .populate_async("items.yml", |input: Item| {
+                    let mut mock_items_table = mock_items_table.clone();
+                    async move { mock_items_table.insert(input).await }
+                })
+                .await
+        })
+    };
+    let customers = {
+        let seeder = seeder.clone();
+        let mock_customers_table = mock_customers_table.clone();
+        tokio::spawn(async move {
+            seeder
+                .populate_async("customers.yml", |input: Customer| {
+                    let mut mock_customers_table = mock_customers_table.clone();
+                    async move { mock_customers_table.insert(input).await }
+                })
+                .await
+        })
+    };
+    items.await??;
+    customers.await??;
+
+    // the files populated afterwards can refer to both
+    let mock_orders_table = MockTable::<Order>::new(vec![
+        ("1200".to_string(), 1),
+        ("1201".to_string(), 2),
+        ("1202".to_string(), 3),
+        ("1203".to_string(), 4),
+    ]);
+    seeder
+        .populate_async("orders.yml", |input: Order| {
+            let mut mock_orders_table = mock_orders_table.clone();
+            async move { mock_orders_table.insert(input).await }
+        })
+        .await?;
+
+    assert_eq!(seeder.with(|seeder| seeder.get_ids().len()), 11);
+    let seeder = seeder.into_inner()?;
+    assert_eq!(seeder.seeded_records().len(), 11);
+    assert_eq!(seeder.filenames.len(), 3);
+    assert_eq!(seeder.filenames[2], "orders.yml");
+    assert_eq!(seeder.get_id("Order1"), Some(&RefValue::Int(1)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_database_seeder_populate_all() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    let mock_items_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+        ("carrot".to_string(), 4),
+    ]);
+    let mock_customers_table = MockTable::<Customer>::new(vec![
+        ("Alice".to_string(), 1),
+        ("Bob".to_string(), 2),
+        ("Developer".to_string(), 3),
+    ]);
+    let mock_orders_table = MockTable::<Order>::new(vec![
+        ("1200".to_string(), 1),
+        ("1201".to_string(), 2),
+        ("1202".to_string(), 3),
+        ("1203".to_string(), 4),
+    ]);
+
+    // registered regardless of the dependencies
+    {
+        let mock_orders_table = mock_orders_table.clone();
+        seeder.register::<Order>("orders.yml", move |input| {
+            let mut mock_orders_table = mock_orders_table.clone();
+            async move { mock_orders_table.insert(input).await }
+        });
+    }
+    {
+        let mock_items_table = mock_items_table.clone();
+        seeder.register::<Item>("items.yml", move |input| {
+            let mut mock_items_table = mock_items_table.clone();
+            async move { mock_items_table.insert(input).await }
+        });
+    }
+    {
+        let mock_customers_table = mock_customers_table.clone();
+        seeder.register::<Customer>("customers.yml", move |input| {
+            let mut mock_customers_table = mock_customers_table.clone();
+            async move { mock_customers_table.insert(input).await }
+        });
+    }
+
+    seeder.populate_all().await?;
+
+    assert_eq!(
+        seeder.filenames,
+        vec!["items.yml", "customers.yml", "orders.yml"]
+    );
+    assert_eq!(mock_items_table.get_records().len(), 4);
+    assert_eq!(mock_customers_table.get_records().len(), 3);
+    assert_eq!(mock_orders_table.get_records().len(), 4);
+    assert_eq!(seeder.get_id("Order4"), Some(&RefValue::Int(4)));
+
+    // the registrations have been consumed
+    seeder.populate_all().await?;
+    assert_eq!(seeder.filenames.len(), 3);
+
+    // nothing is inserted when some of the REFs cannot be resolved
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    {
+        let mock_items_table = mock_items_table.clone();
+        seeder.register::<Item>("items.yml", move |input| {
+            let mut mock_items_table = mock_items_table.clone();
+            async move { mock_items_table.insert(input).await }
+        });
+    }
+    seeder.register::<Order>("orders.yml", |_| async { Ok(0) });
+
+    let err = seeder.populate_all().await.unwrap_err().to_string();
+    assert!(err.contains("orders.yml: REF(Alice)"));
+    assert!(seeder.filenames.is_empty());
+    assert_eq!(mock_items_table.get_records().len(), 4);
+
+    // the files parsed ahead (with the rayon feature) fail as they would otherwise
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.register::<Order>("items.yml", |_| async { Ok(0) });
+    let err = seeder.populate_all().await.unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<CderError>().map(CderError::code),
+        Some("CDER008")
+    );
+    // the errors are found with their positions
+    assert!(err.to_string().contains("2 |   name: melon"));
+
+    Ok(())
+}
+
+// adapter that inserts items into the mock table, except for the ones already "existing"
+struct MockItemsAdapter {
+    table: MockTable<Item>,
+    existing: Vec<(String, i64)>,
+    deleted: Vec<String>,
+    applied: Vec<(String, String)>,
+}
+
+impl SeedAdapter<Item> for MockItemsAdapter {
+    type Id = i64;
+
+    async fn insert(&mut self, record: Item) -> Result<i64> {
+        self.table.insert(record).await
+    }
+
+    async fn exists(&mut self, record: &Item) -> Result<Option<i64>> {
+        Ok(self
+            .existing
+            .iter()
+            .find(|(name, _)| *name == record.name)
+            .map(|(_, id)| *id))
+    }
+
+    async fn delete(&mut self, id: &RefValue) -> Result<()> {
+        self.deleted.push(id.to_string());
+        Ok(())
+    }
+
+    async fn applied_checksum(&mut self, filename: &str) -> Result<Option<String>> {
+        Ok(self
+            .applied
+            .iter()
+            .find(|(applied, _)| applied == filename)
+            .map(|(_, checksum)| checksum.clone()))
+    }
+
+    async fn record_applied(&mut self, filename: &str, checksum: &str) -> Result<()> {
+        self.applied.retain(|(applied, _)| applied != filename);
+        self.applied
+            .push((filename.to_string(), checksum.to_string()));
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_database_seeder_populate_with_adapter() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    let mut adapter = MockItemsAdapter {
+        table: MockTable::<Item>::new(vec![
+            ("orange".to_string(), 2),
+            ("apple".to_string(), 3),
+            ("carrot".to_string(), 4),
+        ]),
+        existing: vec![("melon".to_string(), 1)],
+        deleted: Vec::new(),
+        applied: Vec::new(),
+    };
+
+    let outcomes = seeder.populate_with("items.yml", &mut adapter).await?;
+    assert_eq!(outcomes.len(), 4);
+    assert!(outcomes.contains(&SeedOutcome::Exists(1)));
+    assert_eq!(adapter.table.get_records().len(), 3);
+    assert_eq!(seeder.get_id("Melon"), Some(&RefValue::Int(1)));
+    assert_eq!(seeder.get_id("Carrot"), Some(&RefValue::Int(4)));
+
+    // only the inserted records are deleted
+    seeder.teardown_with("items.yml", &mut adapter).await?;
+    adapter.deleted.sort();
+    assert_eq!(adapter.deleted, vec!["2", "3", "4"]);
+    assert!(seeder.seeded_records().is_empty());
+
+    // inserts the records in chunks
+    let mut adapter = MockItemsAdapter {
+        table: MockTable::<Item>::new(vec![
+            ("melon".to_string(), 1),
+            ("orange".to_string(), 2),
+            ("apple".to_string(), 3),
+            ("carrot".to_string(), 4),
+        ]),
+        existing: Vec::new(),
+        deleted: Vec::new(),
+        applied: Vec::new(),
+    };
+    let ids = seeder
+        .populate_many_with("items.yml", 3, &mut adapter)
+        .await?;
+    assert_eq!(ids.len(), 4);
+    assert_eq!(adapter.table.get_records().len(), 4);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_database_seeder_populate_with_mock_adapter() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    let mut items = MockAdapter::<Item>::new().starting_at(100);
+    let outcomes = seeder.populate_with("items.yml", &mut items).await?;
+    assert_eq!(outcomes.len(), 4);
+    assert_eq!(items.inserted_ids(), vec![100, 101, 102, 103]);
+    assert_eq!(
+        items.get(102).map(|item| item.name),
+        Some("apple".to_string())
+    );
+    assert_eq!(seeder.get_id("Carrot"), Some(&RefValue::Int(103)));
+    let melon = items.fetch(&RefValue::Int(100)).await?;
+    assert_eq!(melon.map(|item| item.price), Some(500.0));
+
+    // the records are deleted from the mock
+    seeder.teardown_with("items.yml", &mut items).await?;
+    assert!(items.is_empty());
+
+    // the failures are injected by the position and by the record
+    let customers = MockAdapter::<Customer>::new()
+        .fail_at(1)
+        .fail_when(|customer| customer.name == "Developer");
+    seeder.continue_on_error(true);
+    let ids = seeder
+        .populate_async("customers.yml", |customer: Customer| {
+            let customers = customers.clone();
+            async move { customers.push(customer) }
+        })
+        .await?;
+    assert_eq!(ids, vec![1]);
+    assert_eq!(customers.attempts(), 3);
+    assert_eq!(customers.records()[0].name, "Bob");
+    assert_eq!(
+        seeder
+            .failures()
+            .iter()
+            .map(|failure| failure.label.as_str())
+            .collect::<Vec<&str>>(),
+        vec!["Alice", "Dev"]
+    );
+
+    Ok(())
+}
+
+// adapter that fetches items from the rows of a long-lived environment
+struct ItemsSnapshot {
+    rows: Vec<(i64, Item)>,
+}
+
+impl SeedAdapter<Item> for ItemsSnapshot {
+    type Id = i64;
+
+    async fn insert(&mut self, _record: Item) -> Result<i64> {
+        Err(anyhow::anyhow!("the snapshot is read-only"))
+    }
+
+    async fn fetch(&mut self, id: &RefValue) -> Result<Option<Item>> {
+        Ok(self
+            .rows
+            .iter()
+            .find(|(row_id, _)| RefValue::Int(*row_id) == *id)
+            .map(|(_, item)| item.clone()))
+    }
+
+    async fn ids(&mut self) -> Result<Vec<RefValue>> {
+        Ok(self.rows.iter().map(|(id, _)| RefValue::Int(*id)).collect())
+    }
+}
+
+#[tokio::test]
+async fn test_database_seeder_verify() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mut adapter = ItemsSnapshot {
+        rows: vec![
+            (
+                1,
+                Item {
+                    name: "melon".to_string(),
+                    price: 500.0,
+                },
+            ),
+            (
+                2,
+                Item {
+                    name: "orange".to_string(),
+                    price: 250.0,
+                },
+            ),
+            (
+                4,
+                Item {
+                    name: "carrot".to_string(),
+                    price: 150.0,
+                },
+            ),
+        ],
+    };
+
+    // the ids of the labels populated earlier
+    let mut state = SeedState::default();
+    state.ids.insert("Melon".to_string(), RefValue::Int(1));
+    state.ids.insert("Orange".to_string(), RefValue::Int(2));
+    state.ids.insert("Apple".to_string(), RefValue::Int(3));
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.restore_state(state);
+
+    let report = seeder.verify("items.yml", &mut adapter).await?;
+    assert!(!report.is_clean());
+    assert_eq!(report.verified, 2);
+    assert_eq!(report.unknown, vec!["Carrot"]);
+    assert_eq!(report.missing, vec!["Apple"]);
+    assert_eq!(report.differences.len(), 1);
+    assert_eq!(report.differences[0].label, "Orange");
+    assert_eq!(report.differences[0].field, "price");
+    assert_eq!(
+        report.to_string(),
+        "items.yml: 2 verified, 1 unknown, 1 missing, 1 differences\n   Carrot: unknown id\n   Apple: missing\n   Orange.price: expected 200, actual 250.0"
+    );
+
+    // nothing has been inserted
+    assert!(seeder.seeded_records().is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_database_seeder_diff() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mut adapter = ItemsSnapshot {
+        rows: vec![
+            (
+                1,
+                Item {
+                    name: "melon".to_string(),
+                    price: 500.0,
+                },
+            ),
+            (
+                2,
+                Item {
+                    name: "orange".to_string(),
+                    price: 250.0,
+                },
+            ),
+            (
+                5,
+                Item {
+                    name: "lemon".to_string(),
+                    price: 120.0,
+                },
+            ),
+        ],
+    };
+
+    let mut state = SeedState::default();
+    state.ids.insert("Melon".to_string(), RefValue::Int(1));
+    state.ids.insert("Orange".to_string(), RefValue::Int(2));
+    state.ids.insert("Apple".to_string(), RefValue::Int(3));
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.restore_state(state);
+
+    let diff = seeder.diff("items.yml", &mut adapter).await?;
+    assert!(!diff.is_empty());
+    assert_eq!(diff.added, vec!["Carrot", "Apple"]);
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.removed, vec![RefValue::Int(5)]);
+    assert_eq!(diff.unchanged, 1);
+    assert_eq!(
+        diff.to_string(),
+        "items.yml: 2 added, 1 changed, 1 removed, 1 unchanged\n + Carrot\n + Apple\n ~ Orange.price: 250.0 -> 200\n - 5"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_database_seeder_track_versions() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mut adapter = MockItemsAdapter {
+        table: MockTable::<Item>::new(vec![
+            ("melon".to_string(), 1),
+            ("orange".to_string(), 2),
+            ("apple".to_string(), 3),
+            ("carrot".to_string(), 4),
+        ]),
+        existing: Vec::new(),
+        deleted: Vec::new(),
+        applied: Vec::new(),
+    };
+
+    let mut seeder = DatabaseSeeder::builder()
+        .base_dir(&base_dir)
+        .track_versions(true)
+        .build()?;
+    let outcomes = seeder.populate_with("items.yml", &mut adapter).await?;
+    assert_eq!(outcomes.len(), 4);
+    assert_eq!(adapter.applied.len(), 1);
+    assert_eq!(adapter.applied[0].0, "items.yml");
+    assert_eq!(adapter.applied[0].1.len(), 64);
+
+    // the file applied already is skipped on the next run
+    let mut seeder = DatabaseSeeder::builder()
+        .base_dir(&base_dir)
+        .track_versions(true)
+        .build()?;
+    let outcomes = seeder.populate_with("items.yml", &mut adapter).await?;
+    assert!(outcomes.is_empty());
+    assert_eq!(adapter.table.get_records().len(), 4);
+    assert_eq!(seeder.history()[0].status, SeedStatus::Skipped);
+
+    // as is the case with populate_many_with
+    let ids = seeder
+        .populate_many_with("items.yml", 2, &mut adapter)
+        .await?;
+    assert!(ids.is_empty());
+
+    // the file changed since it was applied is not populated again
+    adapter.applied[0].1 = "outdated".to_string();
+    let results = seeder.populate_with("items.yml", &mut adapter).await;
+    let err = results.unwrap_err().to_string();
+    assert!(err.contains("the seed file has been changed since it was applied: items.yml"));
+    assert_eq!(adapter.table.get_records().len(), 4);
+
+    // the versions are not checked unless enabled
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.populate_with("items.yml", &mut adapter).await?;
+    assert_eq!(adapter.table.get_records().len(), 8);
+    assert_eq!(adapter.applied[0].1, "outdated");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_database_seeder_save_state() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mock_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+        ("carrot".to_string(), 4),
+    ]);
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder
+        .populate_async("items.yml", |input: Item| {
+            let mut mock_table = mock_table.clone();
+            async move { mock_table.insert(input).await }
+        })
+        .await?;
+
+    let path = std::env::temp_dir().join("cder_test_save_state.json");
+    let path = path.to_str().unwrap();
+    seeder.save_state(path)?;
+
+    let state = SeedState::load(path)?;
+    std::fs::remove_file(path)?;
+    assert_eq!(state, seeder.state());
+    assert_eq!(state.files, vec!["items.yml".to_string()]);
+    assert_eq!(state.ids.len(), 4);
+    assert_eq!(state.ids.get("Melon"), Some(&RefValue::Int(1)));
+    assert_eq!(state.ids.get("Carrot"), Some(&RefValue::Int(4)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_database_seeder_resume_from() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let path = std::env::temp_dir().join("cder_test_resume_from.json");
+    let path = path.to_str().unwrap();
+
+    {
+        // the run is interrupted after items.yml has been populated
+        let mock_items_table = MockTable::<Item>::new(vec![
+            ("melon".to_string(), 1),
+            ("orange".to_string(), 2),
+            ("apple".to_string(), 3),
+            ("carrot".to_string(), 4),
+        ]);
+        let mut seeder = DatabaseSeeder::new();
+        seeder.set_dir(&base_dir);
+        seeder
+            .populate_async("items.yml", |input: Item| {
+                let mut mock_items_table = mock_items_table.clone();
+                async move { mock_items_table.insert(input).await }
+            })
+            .await?;
+        seeder.save_state(path)?;
+    }
+
+    let mut seeder = DatabaseSeeder::resume_from(path)?;
+    std::fs::remove_file(path)?;
+    seeder.set_dir(&base_dir);
+    assert_eq!(seeder.filenames, vec!["items.yml".to_string()]);
+    assert_eq!(seeder.get_id("Apple"), Some(&RefValue::Int(3)));
+
+    // the processed file is skipped
+    let ids = seeder
+        .populate_async("items.yml", |_: Item| async move {
+            Err::<i64, _>(anyhow::anyhow!("items.yml should have been skipped"))
+        })
+        .await?;
+    assert!(ids.is_empty());
+
+    let mock_customers_table = MockTable::<Customer>::new(vec![
+        ("Alice".to_string(), 1),
+        ("Bob".to_string(), 2),
+        ("Developer".to_string(), 3),
+    ]);
+    seeder
+        .populate_async("customers.yml", |input: Customer| {
+            let mut mock_customers_table = mock_customers_table.clone();
+            async move { mock_customers_table.insert(input).await }
+        })
+        .await?;
+
+    let mock_orders_table = MockTable::<Order>::new(vec![
+        ("1200".to_string(), 1),
+        ("1201".to_string(), 2),
+        ("1202".to_string(), 3),
+        ("1203".to_string(), 4),
+    ]);
+    let ids = seeder
+        .populate_async("orders.yml", |input: Order| {
+            let mut mock_orders_table = mock_orders_table.clone();
+            async move { mock_orders_table.insert(input).await }
+        })
+        .await?;
+
+    // REFs to the records populated before resuming are resolved
+    let records = sort_records_by_ids(mock_orders_table.get_records(), ids);
+    assert_eq!(records[0].item_id, 3);
+    assert_eq!(records[1].item_id, 1);
+    assert_eq!(
+        seeder.filenames,
+        vec![
+            "items.yml".to_string(),
+            "customers.yml".to_string(),
+            "orders.yml".to_string()
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_database_seeder_populate_stream() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mock_table = MockTable::<Item>::new(vec![
+        ("melon".to_string(), 1),
+        ("orange".to_string(), 2),
+        ("apple".to_string(), 3),
+    ]);
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+    seeder.continue_on_error(true);
+
+    // carrot is not registered, so that the insertion fails
+    let mut results = seeder
+        .populate_stream("items.yml", |input: Item| {
+            let mut mock_table = mock_table.clone();
+            async move { mock_table.insert(input).await }
+        })
+        .collect::<Vec<Result<(String, i64)>>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<(String, i64)>>>()?;
+    results.sort();
+
+    assert_eq!(
+        results,
+        vec![
+            ("Apple".to_string(), 3),
+            ("Melon".to_string(), 1),
+            ("Orange".to_string(), 2),
+        ]
+    );
+    assert_eq!(seeder.get_id("Orange"), Some(&RefValue::Int(2)));
+    assert_eq!(seeder.failures().len(), 1);
+    assert_eq!(seeder.filenames, vec!["items.yml".to_string()]);
+
+    // the stream ends with the error unless continue_on_error is enabled
+    seeder.continue_on_error(false);
+    let results = seeder
+        .populate_stream("items.yml", |input: Item| {
+            let mut mock_table = mock_table.clone();
+            async move { mock_table.insert(input).await }
+        })
+        .collect::<Vec<Result<(String, i64)>>>()
+        .await;
+    assert!(results.last().unwrap().is_err());
+
+    // errors on loading the file are yielded as well
+    let results = seeder
+        .populate_stream("missing.yml", |_: Item| async move { Ok(1) })
+        .collect::<Vec<Result<(String, i64)>>>()
+        .await;
+    assert_eq!(results.len(), 1);
+    assert!(results[0].is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_database_seeder_factory_with_mock_adapter() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&base_dir);
+
+    let mut items = MockAdapter::<Item>::new().starting_at(100);
+    let id = seeder
+        .factory::<Item>("items.yml", "Melon")?
+        .label("Watermelon")
+        .with(|item| item.name = "watermelon".to_string())
+        .insert_with(&mut seeder, &mut items)
+        .await?;
+    assert_eq!(id, 100);
+    assert_eq!(seeder.get_id("Watermelon"), Some(&RefValue::Int(100)));
+    assert_eq!(seeder.get_id("Melon"), None);
+    assert_eq!(items.get(100).map(|item| item.price), Some(500.0));
+
+    let id = seeder
+        .factory::<Item>("items.yml", "Apple")?
+        .insert_async(&mut seeder, |item| {
+            let items = items.clone();
+            async move { items.push(item) }
+        })
+        .await?;
+    assert_eq!(seeder.get_id("Apple"), Some(&RefValue::Int(id)));
+    assert_eq!(items.len(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_database_seeder_sql_writer() -> Result<()> {
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&get_test_base_dir());
+
+    let mut sql = Vec::<u8>::new();
+    for (filename, table) in [
+        ("items.yml", "items"),
+        ("customers.yml", "customers"),
+        ("orders.yml", "orders"),
+    ] {
+        let mut writer = SqlWriter::new(&mut sql, table);
+        seeder
+            .populate_with::<serde_yaml::Value, _>(filename, &mut writer)
+            .await?;
+    }
+    let sql = String::from_utf8(sql)?;
+    let lines = sql.lines().collect::<Vec<&str>>();
+
+    assert_eq!(lines.len(), 11);
+    assert_eq!(
+        lines[0],
+        "INSERT INTO items (id, name, price) VALUES (1, 'melon', 500);"
+    );
+    assert_eq!(
+        lines[4],
+        "INSERT INTO customers (id, name, emails, plan) VALUES (1, 'Alice', '[\"alice@example.com\"]', 'Premium');"
+    );
+    // the ids of the orders are their own, and the REFs resolve to the ids assigned above
+    assert_eq!(
+        lines[7],
+        "INSERT INTO orders (id, customer_id, item_id, quantity, purchased_at) VALUES (1200, 1, 3, 2, '2021-03-01T15:15:44');"
+    );
+    assert_eq!(seeder.get_id("Order1"), Some(&RefValue::Int(1200)));
+
+    // the same ids are written on every run
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&get_test_base_dir());
+    let mut copy = SqlWriter::new(Vec::new(), "items").format(SqlFormat::Copy);
+    seeder
+        .populate_many_with::<serde_yaml::Value, _>("items.yml", 10, &mut copy)
+        .await?;
+    assert_eq!(
+        String::from_utf8(copy.into_inner())?,
+        "COPY items (id, name, price) FROM stdin;\n1\tmelon\t500\n2\torange\t200\n3\tapple\t100\n4\tcarrot\t150\n\\.\n"
+    );
+
+    Ok(())
+}
+
+// client that answers with sequential ids, failing the first request with 503
+#[cfg(feature = "http")]
+#[derive(Default)]
+struct MockHttpClient {
+    requests: std::sync::Mutex<Vec<cder::HttpRequest>>,
+}
+
+#[cfg(feature = "http")]
+impl cder::HttpClient for MockHttpClient {
+    async fn send(&self, request: cder::HttpRequest) -> Result<cder::HttpResponse> {
+        let mut requests = self.requests.lock().unwrap();
+        requests.push(request);
+        let (status, body) = match requests.len() {
+            1 => (503, "unavailable".to_string()),
+            n => (201, format!(r#"{{"data": {{"id": "item-{}"}}}}"#, n - 1)),
+        };
+        Ok(cder::HttpResponse { status, body })
+    }
+}
+
+#[cfg(feature = "http")]
+#[tokio::test]
+async fn test_database_seeder_http_adapter() -> Result<()> {
+    use cder::HttpAdapter;
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&get_test_base_dir());
+
+    let mut items = HttpAdapter::new(MockHttpClient::default(), "https://api.test/items")
+        .bearer_auth("secret")
+        .id_pointer("/data/id")
+        .retry_policy(RetryPolicy::new(2).backoff(Duration::from_millis(1)));
+    seeder
+        .populate_with::<serde_json::Value, _>("items.yml", &mut items)
+        .await?;
+    assert_eq!(
+        seeder.get_id("Melon"),
+        Some(&RefValue::String("item-1".to_string()))
+    );
+    assert_eq!(
+        seeder.get_id("Carrot"),
+        Some(&RefValue::String("item-4".to_string()))
+    );
+
+    // the 503 is retried with the same request
+    let requests = items.client().requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 5);
+    assert_eq!(requests[0], requests[1]);
+    assert_eq!(requests[1].method, "POST");
+    assert_eq!(
+        requests[1].headers,
+        vec![("Authorization".to_string(), "Bearer secret".to_string())]
+    );
+    assert_eq!(
+        requests[1].body,
+        cder::HttpBody::Json(serde_json::json!({ "name": "melon", "price": 500 }))
+    );
+
+    // fails without the retry policy
+    let mut items = HttpAdapter::new(MockHttpClient::default(), "https://api.test/items");
+    let err = seeder
+        .populate_with::<serde_json::Value, _>("items.yml", &mut items)
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{:#}", err)
+            .contains("POST https://api.test/items responded with 503: unavailable"),
+        "{:#}",
+        err
+    );
+
+    Ok(())
+}
+
+// server that creates the items with sequential ids, except for the carrots
+#[cfg(feature = "graphql")]
+#[derive(Default)]
+struct MockGraphqlClient {
+    requests: std::sync::Mutex<Vec<cder::HttpRequest>>,
+}
+
+#[cfg(feature = "graphql")]
+impl cder::HttpClient for MockGraphqlClient {
+    async fn send(&self, request: cder::HttpRequest) -> Result<cder::HttpResponse> {
+        let mut requests = self.requests.lock().unwrap();
+        let cder::HttpBody::Json(variables) = &request.body else {
+            panic!("the mutation must be sent as JSON")
+        };
+        let body = match variables.pointer("/variables/input/name") {
+            Some(name) if name == "carrot" => {
+                r#"{"data": null, "errors": [{"message": "carrots are sold out"}]}"#.to_string()
+            }
+            _ => format!(
+                r#"{{"data": {{"createItem": {{"item": {{"id": {}}}}}}}}}"#,
+                requests.len() + 1
+            ),
+        };
+        requests.push(request);
+        Ok(cder::HttpResponse { status: 200, body })
+    }
+}
+
+#[cfg(feature = "graphql")]
+#[tokio::test]
+async fn test_database_seeder_graphql_adapter() -> Result<()> {
+    use cder::GraphqlAdapter;
+
+    const CREATE_ITEM: &str =
+        "mutation ($input: ItemInput!) { createItem(input: $input) { item { id } } }";
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&get_test_base_dir());
+    seeder.continue_on_error(true);
+
+    let mut items = GraphqlAdapter::new(
+        MockGraphqlClient::default(),
+        "https://api.test/graphql",
+        CREATE_ITEM,
+    )
+    .input_variable("input")
+    .id_path("createItem.item.id")
+    .bearer_auth("secret");
+    seeder
+        .populate_with::<serde_json::Value, _>("items.yml", &mut items)
+        .await?;
+    assert_eq!(seeder.get_id("Melon"), Some(&RefValue::Int(1)));
+    assert_eq!(seeder.get_id("Apple"), Some(&RefValue::Int(3)));
+    assert_eq!(seeder.get_id("Carrot"), None);
+
+    let failures = seeder.failures();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].label, "Carrot");
+    assert!(
+        failures[0]
+            .to_string()
+            .contains("the mutation failed: carrots are sold out"),
+        "{}",
+        failures[0]
+    );
+
+    let requests = items.client().requests.lock().unwrap().clone();
+    assert_eq!(
+        requests[0].body,
+        cder::HttpBody::Json(serde_json::json!({
+            "query": CREATE_ITEM,
+            "variables": { "input": { "name": "melon", "price": 500 } }
+        }))
+    );
+
+    Ok(())
+}
+
+// publisher that keeps the messages in memory
+#[cfg(feature = "events")]
+#[derive(Default)]
+struct MockPublisher {
+    messages: std::sync::Mutex<Vec<cder::EventMessage>>,
+}
+
+#[cfg(feature = "events")]
+impl cder::EventPublisher for MockPublisher {
+    async fn publish(&self, message: cder::EventMessage) -> Result<()> {
+        self.messages.lock().unwrap().push(message);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "events")]
+#[tokio::test]
+async fn test_database_seeder_event_adapter() -> Result<()> {
+    use cder::EventAdapter;
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&get_test_base_dir());
+
+    // the REFs to the items resolve to their labels
+    let mut items =
+        EventAdapter::new(MockPublisher::default(), "items.created").header("source", "cder");
+    seeder
+        .populate_many_with::<serde_json::Value, _>("items.yml", 2, &mut items)
+        .await?;
+    assert_eq!(
+        seeder.get_id("Melon"),
+        Some(&RefValue::String("Melon".to_string()))
+    );
+    let messages = items.publisher().messages.lock().unwrap().clone();
+    assert_eq!(messages.len(), 4);
+    assert_eq!(messages[0].topic, "items.created");
+    assert_eq!(messages[0].key.as_deref(), Some("Melon"));
+    assert_eq!(
+        messages[0].headers,
+        vec![("source".to_string(), "cder".to_string())]
+    );
+    assert_eq!(messages[0].payload, br#"{"name":"melon","price":500}"#);
+
+    let mut customers = MockAdapter::<Customer>::new();
+    seeder
+        .populate_with("customers.yml", &mut customers)
+        .await?;
+
+    // or to the values of the id field, with the payloads serialized as yaml
+    let mut orders = EventAdapter::new(MockPublisher::default(), "orders.placed")
+        .id_field("id")
+        .serialize_with(|record| Ok(serde_yaml::to_string(record)?.into_bytes()));
+    seeder
+        .populate_with::<serde_json::Value, _>("orders.yml", &mut orders)
+        .await?;
+    assert_eq!(seeder.get_id("Order1"), Some(&RefValue::Int(1200)));
+    let messages = orders.publisher().messages.lock().unwrap().clone();
+    assert_eq!(messages[0].key.as_deref(), Some("Order1"));
+    assert_eq!(
+        String::from_utf8(messages[0].payload.clone())?,
+        "id: 1200\ncustomer_id: 1\nitem_id: Apple\nquantity: 2\npurchased_at: 2021-03-01T15:15:44\n"
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "dynamodb")]
+#[derive(Default)]
+struct MockDynamoDbClient {
+    batches: std::sync::Mutex<Vec<Vec<cder::DynamoDbItem>>>,
+}
+
+#[cfg(feature = "dynamodb")]
+impl cder::DynamoDbClient for MockDynamoDbClient {
+    async fn batch_write_item(
+        &self,
+        table: &str,
+        mut items: Vec<cder::DynamoDbItem>,
+    ) -> Result<cder::BatchWriteOutput> {
+        assert_eq!(table, "app");
+        let mut batches = self.batches.lock().unwrap();
+        // the last item of the first batch is throttled
+        let unprocessed_items = match batches.is_empty() {
+            true => vec![items.pop().unwrap()],
+            false => Vec::new(),
+        };
+        let consumed_capacity = Some(items.len() as f64);
+        batches.push(items);
+        Ok(cder::BatchWriteOutput {
+            unprocessed_items,
+            consumed_capacity,
+        })
+    }
+}
+
+#[cfg(feature = "dynamodb")]
+#[tokio::test]
+async fn test_database_seeder_dynamodb_adapter() -> Result<()> {
+    use cder::{AttributeValue, DynamoDbAdapter};
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&get_test_base_dir());
+
+    let mut items = DynamoDbAdapter::new(MockDynamoDbClient::default(), "app")
+        .partition_key("pk", "ITEM#{label}")
+        .sort_key("sk", "PRICE#{price}")
+        .retry_unprocessed(2, Duration::from_millis(10))
+        .write_capacity(1000.0);
+    seeder
+        .populate_many_with::<serde_json::Value, _>("items.yml", 4, &mut items)
+        .await?;
+
+    // the REFs to the items resolve to their keys
+    assert_eq!(
+        seeder.get_id("Melon"),
+        Some(&RefValue::Composite(vec![
+            RefValue::String("ITEM#Melon".to_string()),
+            RefValue::String("PRICE#500".to_string()),
+        ]))
+    );
+    let batches = items.client().batches.lock().unwrap().clone();
+    assert_eq!(batches.iter().map(Vec::len).collect::<Vec<_>>(), vec![3, 1]);
+    let melon = &batches[0][0];
+    assert_eq!(melon["pk"], AttributeValue::S("ITEM#Melon".to_string()));
+    assert_eq!(melon["name"], AttributeValue::S("melon".to_string()));
+    assert_eq!(melon["price"], AttributeValue::N("500".to_string()));
+
+    // fails when the items remain unprocessed
+    let mut items = DynamoDbAdapter::new(MockDynamoDbClient::default(), "app")
+        .partition_key("pk", "ITEM#{label}")
+        .retry_unprocessed(1, Duration::ZERO);
+    let err = seeder
+        .populate_many_with::<serde_json::Value, _>("items.yml", 4, &mut items)
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("1 item(s) remain unprocessed after 1 attempts (table: app)"));
+
+    Ok(())
+}
+
+// cluster that indexes the documents, except for the carrots
+#[cfg(feature = "search")]
+#[derive(Default)]
+struct MockSearchClient {
+    requests: std::sync::Mutex<Vec<cder::HttpRequest>>,
+}
+
+#[cfg(feature = "search")]
+impl cder::HttpClient for MockSearchClient {
+    async fn send(&self, request: cder::HttpRequest) -> Result<cder::HttpResponse> {
+        let cder::HttpBody::NdJson(lines) = &request.body else {
+            panic!("the documents must be sent as NDJSON")
+        };
+        let items = lines
+            .chunks(2)
+            .map(|lines| {
+                let id = &lines[0]["index"]["_id"];
+                match lines[1]["name"].as_str() {
+                    Some("carrot") => serde_json::json!({ "index": {
+                        "_id": id,
+                        "status": 400,
+                        "error": { "type": "mapper_parsing_exception", "reason": "no carrots" }
+                    }}),
+                    _ => serde_json::json!({ "index": { "_id": id, "status": 201 } }),
+                }
+            })
+            .collect::<Vec<_>>();
+        let errors = items.iter().any(|item| item["index"]["status"] != 201);
+        self.requests.lock().unwrap().push(request);
+        let body = serde_json::json!({ "took": 1, "errors": errors, "items": items });
+        Ok(cder::HttpResponse {
+            status: 200,
+            body: body.to_string(),
+        })
+    }
+}
+
+#[cfg(feature = "search")]
+#[tokio::test]
+async fn test_database_seeder_bulk_index_adapter() -> Result<()> {
+    use cder::{BulkIndexAdapter, HttpBody};
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&get_test_base_dir());
+    seeder.continue_on_error(true);
+
+    let mut items =
+        BulkIndexAdapter::new(MockSearchClient::default(), "http://search.test/", "items")
+            .refresh(true)
+            .bearer_auth("secret");
+    seeder
+        .populate_many_with::<serde_json::Value, _>("items.yml", 2, &mut items)
+        .await?;
+
+    // the ids of the documents are the labels, and the chunk of the carrot fails
+    assert_eq!(
+        seeder.get_id("Melon"),
+        Some(&RefValue::String("Melon".to_string()))
+    );
+    assert_eq!(seeder.get_id("Carrot"), None);
+    let failures = seeder.failures();
+    assert!(
+        failures.iter().any(|failure| failure
+            .to_string()
+            .contains("Carrot: mapper_parsing_exception: no carrots")),
+        "{:?}",
+        failures
+    );
+
+    let requests = items.client().requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].url, "http://search.test/_bulk?refresh=true");
+    assert_eq!(
+        requests[0].body,
+        HttpBody::NdJson(vec![
+            serde_json::json!({ "index": { "_index": "items", "_id": "Melon" } }),
+            serde_json::json!({ "name": "melon", "price": 500 }),
+            serde_json::json!({ "index": { "_index": "items", "_id": "Orange" } }),
+            serde_json::json!({ "name": "orange", "price": 200 }),
+        ])
+    );
+    assert_eq!(
+        String::from_utf8(requests[0].body.to_bytes()?)?
+            .lines()
+            .count(),
+        4
+    );
+
+    // or the ids in the records, e.g. the ones the REFs resolved to
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&get_test_base_dir());
+    let mut items = MockAdapter::<Item>::new();
+    seeder.populate_with("items.yml", &mut items).await?;
+    let mut customers = MockAdapter::<Customer>::new();
+    seeder
+        .populate_with("customers.yml", &mut customers)
+        .await?;
+    let mut orders =
+        BulkIndexAdapter::new(MockSearchClient::default(), "http://search.test", "orders")
+            .id_field("id");
+    seeder
+        .populate_many_with::<serde_json::Value, _>("orders.yml", 10, &mut orders)
+        .await?;
+    assert_eq!(seeder.get_id("Order1"), Some(&RefValue::Int(1200)));
+    let requests = orders.client().requests.lock().unwrap().clone();
+    let HttpBody::NdJson(lines) = &requests[0].body else {
+        unreachable!()
+    };
+    assert_eq!(
+        lines[0],
+        serde_json::json!({ "index": { "_index": "orders", "_id": "1200" } })
+    );
+    assert_eq!(lines[1]["customer_id"], 1);
+
+    Ok(())
+}
+
+// executor that keeps the queries in memory
+#[cfg(feature = "cypher")]
+#[derive(Default)]
+struct MockCypherExecutor {
+    queries: std::sync::Mutex<Vec<cder::CypherQuery>>,
+}
+
+#[cfg(feature = "cypher")]
+impl cder::CypherExecutor for MockCypherExecutor {
+    async fn run(&self, query: cder::CypherQuery) -> Result<()> {
+        self.queries.lock().unwrap().push(query);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cypher")]
+#[tokio::test]
+async fn test_database_seeder_cypher_adapter() -> Result<()> {
+    use cder::CypherAdapter;
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&get_test_base_dir());
+
+    // the REFs to the items resolve to their labels
+    let mut items = CypherAdapter::new(MockCypherExecutor::default(), "Item");
+    seeder
+        .populate_many_with::<serde_json::Value, _>("items.yml", 10, &mut items)
+        .await?;
+    assert_eq!(
+        seeder.get_id("Melon"),
+        Some(&RefValue::String("Melon".to_string()))
+    );
+    let queries = items.executor().queries.lock().unwrap().clone();
+    assert_eq!(queries.len(), 1);
+    assert_eq!(
+        queries[0].query,
+        "UNWIND $rows AS row\nMERGE (n:`Item` {`label`: row.key})\nSET n += row.properties"
+    );
+    assert_eq!(
+        queries[0].params["rows"][0],
+        serde_json::json!({
+            "key": "Melon",
+            "properties": { "label": "Melon", "name": "melon", "price": 500 },
+            "targets": []
+        })
+    );
+
+    let mut customers = MockAdapter::<Customer>::new();
+    seeder
+        .populate_with("customers.yml", &mut customers)
+        .await?;
+
+    // and the REFs of the orders become the relationships
+    let mut orders = CypherAdapter::new(MockCypherExecutor::default(), "Order")
+        .id_field("id")
+        .relationship("customer_id", "PLACED_BY", "Customer.id")
+        .relationship("item_id", "CONTAINS", "Item");
+    seeder
+        .populate_many_with::<serde_json::Value, _>("orders.yml", 10, &mut orders)
+        .await?;
+    assert_eq!(seeder.get_id("Order1"), Some(&RefValue::Int(1200)));
+    let queries = orders.executor().queries.lock().unwrap().clone();
+    assert_eq!(queries.len(), 3);
+    assert_eq!(
+        queries[2].query,
+        "UNWIND $rows AS row\nMATCH (n:`Order` {`id`: row.key})\nUNWIND row.targets[1] AS target\nMATCH (m:`Item` {`label`: target})\nMERGE (n)-[:`CONTAINS`]->(m)"
+    );
+    assert_eq!(
+        queries[0].params["rows"][0],
+        serde_json::json!({
+            "key": 1200,
+            "properties": { "id": 1200, "quantity": 2, "purchased_at": "2021-03-01T15:15:44" },
+            "targets": [[1], ["Apple"]]
+        })
+    );
+
+    Ok(())
+}
+
+#[cfg(all(feature = "macros", feature = "sqlx"))]
+#[tokio::test]
+async fn test_database_seeder_seedable_model() -> Result<()> {
+    use cder::SqlxSeedable;
+
+    #[allow(dead_code)]
+    #[derive(serde::Deserialize, cder::Seedable)]
+    struct OrderItem {
+        name: String,
+        #[seedable(column = "unit_price")]
+        price: f64,
+        #[seedable(skip)]
+        note: Option<String>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(serde::Deserialize, cder::Seedable)]
+    #[seedable(table = "app.users", id = i32, id_column = "user_id")]
+    struct User {
+        name: String,
+    }
+
+    assert_eq!(OrderItem::table(), "order_items");
+    assert_eq!(OrderItem::columns(), &["name", "unit_price"]);
+    assert_eq!(OrderItem::id_column(), "id");
+    assert_eq!(User::table(), "app.users");
+    assert_eq!(User::id_column(), "user_id");
+
+    // the models are inserted with the pool of the seeder
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(&get_test_base_dir());
+    let err = seeder
+        .populate_model::<OrderItem>("items.yml")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "no pool has been set to insert the models with, see `set_pool`"
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn test_sqlite_seed_in_memory() -> Result<()> {
+    use sqlx::Row;
+
+    let schema = "CREATE TABLE companies (id INTEGER PRIMARY KEY, name TEXT NOT NULL, tags TEXT);
+        CREATE TABLE members (id INTEGER PRIMARY KEY, name TEXT NOT NULL, company_id INTEGER REFERENCES companies (id), admin BOOLEAN NOT NULL);";
+    let manifest = format!("{}/sqlite/seeds.yml", get_test_base_dir());
+    let mut connection = cder::sqlite::seed_in_memory(&manifest, schema).await?;
+
+    let rows = sqlx::query(
+        "SELECT m.name, c.name, c.tags, m.admin FROM members m JOIN companies c ON c.id = m.company_id ORDER BY m.id",
+    )
+    .fetch_all(&mut connection)
+    .await?;
+    let members = rows
+        .iter()
+        .map(|row| {
+            (
+                row.get::<String, _>(0),
+                row.get::<String, _>(1),
+                row.get::<String, _>(2),
+                row.get::<bool, _>(3),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        members,
+        vec![
+            (
+                "Alice".to_string(),
+                "Acme".to_string(),
+                r#"["manufacturing"]"#.to_string(),
+                true
+            ),
+            (
+                "Bob".to_string(),
+                "Initech".to_string(),
+                "[]".to_string(),
+                false
+            ),
+        ]
+    );
+
+    // the rows are deleted by their ids as they are typed
+    let mut members = cder::sqlite::SqliteTable::new(&mut connection, "members");
+    members.delete(&RefValue::Int(1)).await?;
+    let row = sqlx::query("SELECT COUNT(*) FROM members")
+        .fetch_one(&mut connection)
+        .await?;
+    assert_eq!(row.get::<i64, _>(0), 1);
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+extern crate cder;
+
+use anyhow::Result;
+use cder::{DatabaseSeeder, FixtureDumper, RefValue, SeedState};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Company {
+    #[serde(default)]
+    id: i64,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct User {
+    #[serde(default)]
+    id: i64,
+    name: String,
+    company_id: i64,
+    manager_id: Option<i64>,
+}
+
+#[test]
+fn test_fixture_dumper_dump() -> Result<()> {
+    let companies = vec![
+        Company {
+            id: 10,
+            name: "MassiveSoft".to_string(),
+        },
+        Company {
+            id: 20,
+            name: "BuggyTech".to_string(),
+        },
+    ];
+    let users = vec![
+        User {
+            id: 3,
+            name: "Alice".to_string(),
+            company_id: 20,
+            manager_id: None,
+        },
+        User {
+            id: 4,
+            name: "Bob".to_string(),
+            company_id: 10,
+            manager_id: Some(7),
+        },
+    ];
+
+    let mut dumper = FixtureDumper::new();
+    let companies_yaml = dumper.dump("companies", &companies, &[])?;
+    assert_eq!(dumper.get_label("companies", 20), Some("companies_2"));
+
+    // the manager has not been dumped
+    let references = [("company_id", "companies"), ("manager_id", "users")];
+    let results = dumper.dump("users", &users, &references);
+    let err = results.unwrap_err().to_string();
+    assert!(err.contains("users.manager_id (7)"));
+    assert_eq!(dumper.get_label("users", 3), None);
+
+    // the manager that is labeled elsewhere
+    dumper.set_label("users", 7, "Manager");
+    let users_yaml = dumper.dump("users", &users, &references)?;
+    assert_eq!(
+        users_yaml,
+        r#"users_1:
+  name: Alice
+  company_id: ${{ REF(companies_2) }}
+  manager_id: null
+users_2:
+  name: Bob
+  company_id: ${{ REF(companies_1) }}
+  manager_id: ${{ REF(Manager) }}
+"#
+    );
+
+    // the dumped fixtures can be populated again, with different ids
+    let base_dir = std::env::temp_dir().join("cder_test_dump");
+    fs::create_dir_all(&base_dir)?;
+    fs::write(base_dir.join("companies.yml"), companies_yaml)?;
+    fs::write(base_dir.join("users.yml"), users_yaml)?;
+
+    let mut seeder = DatabaseSeeder::new();
+    seeder.set_dir(base_dir.to_str().unwrap());
+    seeder.populate("companies.yml", |company: Company| {
+        Ok(if company.name == "MassiveSoft" {
+            100
+        } else {
+            200
+        })
+    })?;
+    // the manager is populated by another seeder
+    let mut state = SeedState::default();
+    state.ids.insert("Manager".to_string(), RefValue::Int(7));
+    seeder.restore_state(state);
+    let mut inserted_users = Vec::new();
+    seeder.populate("users.yml", |user: User| {
+        inserted_users.push(user);
+        Ok(inserted_users.len() as i64)
+    })?;
+    fs::remove_dir_all(&base_dir)?;
+
+    assert_eq!(seeder.get_id("companies_2"), Some(&RefValue::Int(200)));
+    assert_eq!(inserted_users[0].company_id, 200);
+    assert_eq!(inserted_users[1].company_id, 100);
+    assert_eq!(inserted_users[1].manager_id, Some(7));
+
+    Ok(())
+}
@@ -4,7 +4,7 @@ extern crate cder;
 
 use anyhow::Result;
 use cder::{Dict, StructLoader};
-use std::env;
+use std::{collections::hash_map::DefaultHasher, hash::BuildHasherDefault};
 
 #[test]
 fn test_struct_loader_new() {
@@ -77,8 +77,8 @@ fn test_struct_loader_load_customers() -> Result<()> {
     {
         // when ENV var is specified
 
-        env::set_var("DEV_EMAIL", "johndoo@dev.example.com");
         let mut loader = StructLoader::<Customer>::new("customers.yml", &base_dir);
+        loader.set_env("DEV_EMAIL", "johndoo@dev.example.com");
         loader.load(&empty_dict)?;
 
         let customer = loader.get("Alice")?;
@@ -108,9 +108,6 @@ fn test_struct_loader_load_customers() -> Result<()> {
         assert_eq!(customer.emails[0], "johndoo@dev.example.com");
         assert_eq!(customer.plan, Plan::Standard);
         assert_eq!(customer.country_code, Some(44));
-
-        // teardown
-        env::remove_var("DEV_EMAIL");
     }
 
     {
@@ -213,5 +210,181 @@ fn test_struct_loader_load_orders() -> Result<()> {
         assert_eq!(order.purchased_at, parse_datetime("2021-03-11 11:55:44")?);
     }
 
+    {
+        // when the dependencies are hashed with another hasher
+        let mapping = [("Alice", 1), ("Bob", 2), ("Dev", 3)]
+            .into_iter()
+            .chain([
+                ("Melon", 100),
+                ("Orange", 101),
+                ("Apple", 102),
+                ("Carrot", 103),
+            ])
+            .map(|(name, id)| (name.to_string(), id))
+            .collect::<Dict<i64, BuildHasherDefault<DefaultHasher>>>();
+
+        let mut loader = StructLoader::<Order>::new("orders.yml", &base_dir);
+        assert!(loader.validate(&mapping).is_valid());
+        loader.load(&mapping)?;
+
+        let order = loader.get("Order1")?;
+        assert_eq!(order.customer_id, 1);
+        assert_eq!(order.item_id, 102);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_struct_loader_validate() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mut dependencies = Dict::<i64>::new();
+    dependencies.insert("Alice".to_string(), 1);
+
+    // every record is checked against the type
+    let loader = StructLoader::<Order>::new("invalid/orders.yml", &base_dir);
+    let report = loader.validate(&dependencies);
+    let file = &report.files[0];
+    assert_eq!(file.stats.records, 2);
+    assert_eq!(file.stats.refs, 2);
+    assert_eq!(
+        file.errors
+            .iter()
+            .map(|error| (error.label.as_deref(), error.code))
+            .collect::<Vec<(Option<&str>, Option<&str>)>>(),
+        vec![
+            (Some("Order1"), Some("CDER001")),
+            (Some("Order2"), Some("CDER001")),
+        ]
+    );
+
+    dependencies.insert("Banana".to_string(), 2);
+    let report = loader.validate(&dependencies);
+    let file = &report.files[0];
+    assert_eq!(file.errors.len(), 1);
+    assert_eq!(file.errors[0].label.as_deref(), Some("Order2"));
+    assert_eq!(file.errors[0].line, Some(7));
+    assert_eq!(file.warnings[0].rule, "defaults_used");
+    assert_eq!(file.warnings[0].label.as_deref(), Some("Order1"));
+
+    let loader = StructLoader::<Item>::new("items.yml", &base_dir);
+    assert!(loader.validate(&Dict::<i64>::new()).is_valid());
+
+    Ok(())
+}
+
+#[test]
+fn test_struct_loader_django_fixtures() -> Result<()> {
+    let base_dir = format!("{}/django", get_test_base_dir());
+
+    let mut loader = StructLoader::<Item>::new("items.json", &base_dir);
+    loader.django_fixtures(true).load(&Dict::<i64>::new())?;
+    assert_eq!(loader.get("1")?.name, "melon");
+    assert_eq!(loader.get("2")?.price, 200.0);
+
+    let mut mapping = Dict::<i64>::new();
+    mapping.insert("Alice".to_string(), 1);
+    mapping.insert("2".to_string(), 20);
+
+    let mut loader = StructLoader::<Order>::new("orders.json", &base_dir);
+    loader.django_fixtures(true).load(&mapping)?;
+    let order = loader.get("1200")?;
+    assert_eq!(order.customer_id, 1);
+    assert_eq!(order.item_id, 20);
+    assert_eq!(order.quantity, 2);
+
+    Ok(())
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_struct_loader_to_csv() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let mut loader = StructLoader::<Customer>::new("customers.yml", &base_dir);
+    loader.set_env("DEV_EMAIL", "dev@example.com");
+    loader.load(&Dict::<String>::new())?;
+
+    let mut csv = Vec::new();
+    loader.to_csv(&mut csv)?;
+    assert_eq!(
+        String::from_utf8(csv)?,
+        "label,name,emails,plan,country_code\n\
+         Alice,Alice,\"[\"\"alice@example.com\"\"]\",Premium,\n\
+         Bob,Bob,\"[\"\"bob@example.com\"\",\"\"bob.doe@example.co.jp\"\"]\",\"{\"\"Family\"\":{\"\"shared_membership\"\":4}}\",81\n\
+         Dev,Developer,\"[\"\"dev@example.com\"\"]\",Standard,44\n"
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_struct_loader_to_parquet() -> Result<()> {
+    use arrow_array::{Array, Int64Array, StringArray};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    let base_dir = get_test_base_dir();
+    let mut loader = StructLoader::<Customer>::new("customers.yml", &base_dir);
+    loader.set_env("DEV_EMAIL", "dev@example.com");
+    loader.load(&Dict::<String>::new())?;
+
+    let path = std::env::temp_dir().join("cder_test_to_parquet.parquet");
+    loader.to_parquet(std::fs::File::create(&path)?)?;
+
+    let batches = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path)?)?
+        .build()?
+        .collect::<Result<Vec<_>, _>>()?;
+    std::fs::remove_file(&path)?;
+    let batch = &batches[0];
+    let schema = batch.schema();
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| field.name().as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        columns,
+        vec!["label", "name", "emails", "plan", "country_code"]
+    );
+
+    let string_column = |name: &str| {
+        batch
+            .column_by_name(name)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap()
+            .iter()
+            .map(|value| value.map(str::to_string))
+            .collect::<Vec<Option<String>>>()
+    };
+    assert_eq!(
+        string_column("label"),
+        vec![
+            Some("Alice".to_string()),
+            Some("Bob".to_string()),
+            Some("Dev".to_string())
+        ]
+    );
+    assert_eq!(
+        string_column("plan"),
+        vec![
+            Some("Premium".to_string()),
+            Some(r#"{"Family":{"shared_membership":4}}"#.to_string()),
+            Some("Standard".to_string())
+        ]
+    );
+
+    // the integers are typed, with the missing country code of Alice as null
+    let country_codes = batch
+        .column_by_name("country_code")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert!(country_codes.is_null(0));
+    assert_eq!(country_codes.value(1), 81);
+    assert_eq!(country_codes.value(2), 44);
+
     Ok(())
 }
@@ -1,12 +1,12 @@
 use chrono::NaiveDateTime;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Item {
     pub name: String,
     pub price: f64,
 }
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Customer {
     pub name: String,
     pub emails: Vec<String>,
@@ -14,7 +14,7 @@ pub struct Customer {
     pub country_code: Option<u8>,
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum Plan {
     Premium,
     Family { shared_membership: u8 },
@@ -0,0 +1,187 @@
+mod test_utils;
+use test_utils::{get_test_base_dir, parse_datetime, Customer, Item, Order, Plan};
+extern crate cder;
+
+use anyhow::Result;
+use cder::{
+    testing::{assert_matches_fixture, assert_snapshot, FixtureBuilder, Fixtures, InMemorySeeder},
+    RefValue,
+};
+use std::env;
+
+#[test]
+fn test_fixtures() -> Result<()> {
+    let base_dir = get_test_base_dir();
+    let fixtures = Fixtures::load(&base_dir, &["items.yml", "customers.yml", "orders.yml"])?;
+    assert_eq!(fixtures.labels().count(), 11);
+    assert_eq!(fixtures.id("Bob"), Some(&RefValue::Int(2)));
+
+    let alice = fixtures.get::<Customer>("Alice")?;
+    assert_eq!(alice.plan, Plan::Premium);
+    assert_eq!(alice.emails, vec!["alice@example.com"]);
+    assert_eq!(fixtures.find::<Customer>("dev")?.country_code, Some(44));
+    assert_eq!(fixtures.find::<Item>("ORANGE")?.price, 200.0);
+
+    // the ids are assigned sequentially in each file
+    let order = fixtures.find::<Order>("order_4")?;
+    assert_eq!(order.customer_id, 3);
+    assert_eq!(order.item_id, 1);
+    assert_eq!(order.quantity, 2);
+    assert_eq!(order.purchased_at, parse_datetime("2021-03-11 11:55:44")?);
+
+    // the labels have to be exact with `get`
+    assert!(fixtures.get::<Item>("orange").is_err());
+    assert!(fixtures.find::<Item>("banana").is_err());
+    // the record of the other type cannot be deserialized
+    assert!(fixtures.find::<Customer>("melon").is_err());
+    Ok(())
+}
+
+#[cfg(feature = "macros")]
+#[cder::fixtures("items.yml", "customers.yml", "orders.yml")]
+#[test]
+fn test_fixtures_macro(melon: Item, dev: Customer, order_4: Order) {
+    assert_eq!(melon.price, 500.0);
+    assert_eq!(dev.name, "Developer");
+    assert_eq!(order_4.customer_id, 3);
+}
+
+#[cfg(feature = "macros")]
+#[cder::fixtures("items.yml", dir = "tests/fixtures")]
+#[tokio::test]
+async fn test_fixtures_macro_async(mut carrot: Item) -> Result<()> {
+    carrot.price += 1.0;
+    assert_eq!(carrot.price, 151.0);
+    Ok(())
+}
+
+#[test]
+fn test_fixtures_render() -> Result<()> {
+    let fixtures = Fixtures::load(&get_test_base_dir(), &["items.yml", "customers.yml"])?;
+
+    // the environment does not change the rendered document
+    env::set_var("DEV_EMAIL", "dev@example.org");
+    assert_snapshot("customers", &fixtures.render("customers.yml")?);
+    assert_snapshot("orders", &fixtures.render("orders.yml")?);
+
+    assert!(fixtures.render("invalid/orders.yml").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_assert_matches_fixture() -> Result<()> {
+    let fixtures = Fixtures::load(&get_test_base_dir(), &["items.yml"])?;
+    let items = ["Melon", "Orange", "Apple", "Carrot"]
+        .map(|label| fixtures.get::<Item>(label))
+        .into_iter()
+        .collect::<Result<Vec<Item>>>()?;
+    assert_matches_fixture(&items, "items.yml");
+
+    let result = std::panic::catch_unwind(|| assert_matches_fixture(&items[1..], "items.yml"));
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_in_memory_seeder() -> Result<()> {
+    let base_dir = get_test_base_dir();
+
+    // customers are not populated without the profile, so orders cannot be resolved
+    let mut seeder = InMemorySeeder::from_manifest(&format!("{}/seeds.yml", base_dir))?;
+    assert!(seeder.populate_manifest(None).is_err());
+    assert_eq!(seeder.files(), vec!["items.yml"]);
+
+    let mut seeder = InMemorySeeder::from_manifest(&format!("{}/seeds.yml", base_dir))?;
+    seeder.populate_manifest(Some("development"))?;
+    assert_eq!(
+        seeder.files(),
+        vec!["items.yml", "customers.yml", "orders.yml"]
+    );
+    assert_eq!(seeder.tables(), vec!["products", "customers", "orders"]);
+    assert_eq!(seeder.table("products").map(|table| table.len()), Some(4));
+    assert!(seeder.table("items").is_none());
+
+    let orders = seeder.records::<Order>("orders")?;
+    assert_eq!(orders.len(), 4);
+    assert_eq!(orders[1].customer_id, 2);
+    assert_eq!(orders[1].item_id, 1);
+    assert_eq!(seeder.get::<Customer>("Bob")?.name, "Bob");
+    assert_matches_fixture(seeder.records::<Item>("products")?, "items.yml");
+
+    // the ids are assigned per table
+    assert_eq!(
+        seeder.populate_into("items.yml", "orders")?,
+        vec![5, 6, 7, 8]
+    );
+    assert_eq!(seeder.get::<Item>("Melon")?.price, 500.0);
+    Ok(())
+}
+
+#[test]
+fn test_fixture_builder() -> Result<()> {
+    // the items are built at runtime
+    let prices = [("Melon", 500.0), ("Apple", 100.0)];
+    let items = FixtureBuilder::new().records(prices.map(|(label, price)| {
+        let item = Item {
+            name: label.to_lowercase(),
+            price,
+        };
+        (label, item)
+    }));
+    assert_eq!(items.len(), 2);
+
+    let file = items.write_temp("items.yml")?;
+    let base_dir = file.base_dir().to_string();
+    let mut seeder = file.seeder();
+    let ids = seeder.populate("items.yml", |item: Item| Ok(item.price as i64))?;
+    assert_eq!(ids, vec![500, 100]);
+
+    let orders = FixtureBuilder::new().record(
+        "Order1",
+        serde_json::json!({
+            "id": 1,
+            "customer_id": 1,
+            "item_id": "${{ REF(Apple) }}",
+            "quantity": 2,
+            "purchased_at": "2021-03-01T15:15:44",
+        }),
+    );
+    orders.write(&base_dir, "orders.yml")?;
+    let ids = seeder.populate("orders.yml", |order: Order| Ok(order.item_id))?;
+    assert_eq!(ids, vec![100]);
+
+    // the directory is removed along with the file
+    let mut loader = file.loader::<Item>();
+    loader.load(&cder::Dict::<i64>::new())?;
+    assert_eq!(loader.get("Apple")?.name, "apple");
+    drop(file);
+    assert!(!std::path::Path::new(&base_dir).exists());
+    Ok(())
+}
+
+#[cfg(feature = "proptest")]
+mod generated {
+    use cder::testing::{fixture_builder, labeled_records, round_trip, MockAdapter};
+    use proptest::prelude::*;
+
+    type Record = (String, i64, Option<bool>, Vec<u32>);
+
+    proptest! {
+        #[test]
+        fn test_round_trip(records in labeled_records::<Record>(0..8)) {
+            prop_assert_eq!(round_trip(&records).unwrap(), records);
+        }
+
+        #[test]
+        fn test_populate_generated(builder in fixture_builder::<(String, i64)>(1..8)) {
+            let file = builder.write_temp("records.yml").unwrap();
+            let records = MockAdapter::new();
+            let ids = file
+                .seeder()
+                .populate(file.filename(), |record: (String, i64)| records.push(record))
+                .unwrap();
+            prop_assert_eq!(ids.len(), builder.len());
+            prop_assert_eq!(records.len(), builder.len());
+        }
+    }
+}